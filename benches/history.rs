@@ -0,0 +1,161 @@
+//! Benchmarks of the clipboard read path and the history operations the overlay and
+//! CLI hit most, on a 10 000 item history: recording a copy (text and image), merging a
+//! repeat copy through the content hash, searching and serializing a history page.
+//!
+//! Run with `cargo bench`; criterion keeps the previous results in `target/criterion`
+//! and reports changes against them.
+
+// The daemon's modules are compiled into the benchmark as they are; most of their API
+// is unused here
+#![allow(dead_code)]
+
+#[path = "../src/backend/mod.rs"]
+mod backend;
+#[path = "../src/shared/mod.rs"]
+mod shared;
+
+use backend::backend_state::BackendState;
+use backend::sync::content_hash;
+use bytes::Bytes;
+use criterion::{BatchSize, Criterion, criterion_group, criterion_main};
+use image::{ImageFormat, Rgba, RgbaImage};
+use indexmap::IndexMap;
+use shared::{
+    BackendMessage, BackendResponse, HISTORY_PAGE_SIZE, HistoryOrder, HistoryView, RetentionPolicy,
+};
+use std::hint::black_box;
+use std::io::Cursor;
+
+const HISTORY_LEN: usize = 10_000;
+
+fn text_item(text: &str) -> IndexMap<String, Bytes> {
+    let payload = Bytes::from(text.to_string());
+    ["text/plain;charset=utf-8", "text/plain", "UTF8_STRING"]
+        .into_iter()
+        .map(|mime| (mime.to_string(), payload.clone()))
+        .collect()
+}
+
+/// Varied text like real copies: words, URLs, code and longer paragraphs.
+fn sample_text(index: usize) -> String {
+    match index % 4 {
+        0 => format!("meeting notes {index}: move the release to thursday"),
+        1 => format!("https://example.org/issues/{index}?tab=comments"),
+        2 => format!("fn handler_{index}(state: &mut State) -> Result<(), Error> {{ Ok(()) }}"),
+        _ => format!("{index} lorem ipsum dolor sit amet ").repeat(40),
+    }
+}
+
+fn png_item() -> IndexMap<String, Bytes> {
+    let image = RgbaImage::from_fn(512, 512, |x, y| {
+        Rgba([(x % 256) as u8, (y % 256) as u8, 128, 255])
+    });
+    let mut png = Vec::new();
+    image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .expect("encoding a PNG in memory");
+    IndexMap::from([("image/png".to_string(), Bytes::from(png))])
+}
+
+/// A daemon state holding `len` text items, without persistence. Retention keeps it at
+/// `len` items while a benchmark adds more.
+fn filled_state(len: usize) -> BackendState {
+    // Demo mode never opens the history database
+    let mut state = BackendState::new(false, true);
+    state.set_retention_policy(RetentionPolicy {
+        max_items: len,
+        max_total_bytes: 0,
+        max_item_bytes: 0,
+        max_age_days: 0,
+    });
+    state.clear_history();
+    for index in 0..len {
+        state.add_clipboard_item_from_mime_map(text_item(&sample_text(index)), None);
+    }
+    state
+}
+
+fn read_path(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read_path");
+    let mut state = filled_state(HISTORY_LEN);
+    let mut counter = HISTORY_LEN;
+    group.bench_function("add_text_item", |b| {
+        b.iter(|| {
+            counter += 1;
+            black_box(
+                state.add_clipboard_item_from_mime_map(text_item(&sample_text(counter)), None),
+            )
+        })
+    });
+    let image = png_item();
+    group.bench_function("add_image_item", |b| {
+        b.iter_batched(
+            || image.clone(),
+            |image| black_box(state.add_clipboard_item_from_mime_map(image, None)),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+fn dedup(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dedup");
+    let repeated = text_item(&sample_text(HISTORY_LEN / 2));
+    group.bench_function("content_hash", |b| {
+        b.iter(|| black_box(content_hash(black_box(&repeated))))
+    });
+    // A repeat copy of an item deep in the history is found and moved to the top
+    let mut state = filled_state(HISTORY_LEN);
+    group.bench_function("repeat_copy", |b| {
+        b.iter_batched(
+            || repeated.clone(),
+            |item| black_box(state.add_clipboard_item_from_mime_map(item, None)),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+fn search(c: &mut Criterion) {
+    let mut group = c.benchmark_group("search");
+    let mut state = filled_state(HISTORY_LEN);
+    for query in ["release", "hndlr", "type:url example", "re:issues/9\\d+"] {
+        group.bench_function(query, |b| {
+            b.iter(|| black_box(state.search_history(query, HistoryView::All, 20)))
+        });
+    }
+    group.finish();
+}
+
+fn history_pages(c: &mut Criterion) {
+    let mut group = c.benchmark_group("history_page");
+    let mut state = filled_state(HISTORY_LEN);
+    for order in [HistoryOrder::Recent, HistoryOrder::Frecency] {
+        group.bench_function(format!("get_{order:?}"), |b| {
+            b.iter(|| {
+                black_box(state.get_history_page(0, HISTORY_PAGE_SIZE, HistoryView::All, order))
+            })
+        });
+    }
+    let page = state.get_history_page(
+        HISTORY_LEN / 2,
+        HISTORY_PAGE_SIZE,
+        HistoryView::All,
+        HistoryOrder::Recent,
+    );
+    let response = BackendResponse {
+        revision: Some(state.history_revision),
+        message: BackendMessage::HistoryPage { page },
+    };
+    group.bench_function("serialize", |b| {
+        b.iter(|| black_box(serde_json::to_string(black_box(&response))))
+    });
+    let json = serde_json::to_string(&response).expect("serializing a page");
+    group.bench_function("deserialize", |b| {
+        b.iter(|| black_box(BackendResponse::from_json(black_box(&json))))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, read_path, dedup, search, history_pages);
+criterion_main!(benches);
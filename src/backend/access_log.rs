@@ -0,0 +1,135 @@
+use crate::shared::config_dir::home_dir;
+use log::{debug, warn};
+use serde::Serialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: u64 = 86400;
+
+/// One audit record per IPC command. Deliberately carries no clipboard payloads.
+#[derive(Debug, Serialize)]
+struct AccessLogEntry<'a> {
+    timestamp: u64,
+    pid: Option<i32>,
+    command: &'a str,
+    item_id: Option<u64>,
+    ok: bool,
+}
+
+/// Opt-in JSON-lines audit log of IPC commands, rotated daily
+/// (`access-YYYY-MM-DD.log`) with old files pruned after `retention_days`.
+pub struct AccessLog {
+    dir: PathBuf,
+    retention_days: u64,
+    // Day number (days since epoch) of the open file, and the file itself
+    current: Mutex<Option<(u64, File)>>,
+}
+
+impl AccessLog {
+    pub fn open_default(retention_days: u64) -> Self {
+        Self {
+            dir: access_log_dir(),
+            retention_days,
+            current: Mutex::new(None),
+        }
+    }
+
+    pub fn record(&self, pid: Option<i32>, command: &str, item_id: Option<u64>, ok: bool) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let entry = AccessLogEntry {
+            timestamp,
+            pid,
+            command,
+            item_id,
+            ok,
+        };
+        let Ok(mut line) = serde_json::to_string(&entry) else {
+            return;
+        };
+        line.push('\n');
+
+        let day = timestamp / SECONDS_PER_DAY;
+        let mut current = self.current.lock().unwrap();
+        if current
+            .as_ref()
+            .is_none_or(|(open_day, _)| *open_day != day)
+        {
+            *current = self.open_for_day(day).map(|file| (day, file));
+            self.prune(day);
+        }
+
+        if let Some((_, file)) = current.as_mut()
+            && let Err(e) = file.write_all(line.as_bytes())
+        {
+            warn!("Failed to write IPC access log entry: {e}");
+        }
+    }
+
+    fn open_for_day(&self, day: u64) -> Option<File> {
+        if let Err(e) = fs::create_dir_all(&self.dir) {
+            warn!("Failed to create access log directory: {e}");
+            return None;
+        }
+        let path = self.dir.join(log_file_name(day));
+        debug!("Opening IPC access log {}", path.display());
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(0o600)
+            .open(&path)
+            .inspect_err(|e| warn!("Failed to open access log {}: {e}", path.display()))
+            .ok()
+    }
+
+    /// Remove log files that fell out of the retention window.
+    fn prune(&self, today: u64) {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return;
+        };
+        let oldest_kept = log_file_name(today.saturating_sub(self.retention_days));
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            // ISO dates in the file name compare correctly as strings
+            if name.starts_with("access-")
+                && name.ends_with(".log")
+                && name < oldest_kept
+                && let Err(e) = fs::remove_file(entry.path())
+            {
+                warn!("Failed to remove old access log {name}: {e}");
+            }
+        }
+    }
+}
+
+fn access_log_dir() -> PathBuf {
+    let state_home = std::env::var("XDG_STATE_HOME")
+        .map_or_else(|_| home_dir().join(".local").join("state"), PathBuf::from);
+    state_home.join("cursor-clip").join("access-log")
+}
+
+fn log_file_name(day: u64) -> String {
+    let (year, month, day) = civil_from_days(day);
+    format!("access-{year:04}-{month:02}-{day:02}.log")
+}
+
+/// Convert days since the Unix epoch to a (year, month, day) UTC date.
+/// Howard Hinnant's `civil_from_days` algorithm.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
@@ -1,16 +1,49 @@
+use crate::backend::classifier::Classifier;
+use crate::backend::clipboard_portal::{PORTAL_PROTOCOL_NAME, PortalSelection};
+use crate::backend::crash_backup::{self, SelectionBackup};
+use crate::backend::demo::demo_items;
+use crate::backend::file_list::{URI_LIST_MIME, parse_uri_list, paths_payload};
+use crate::backend::foreign_toplevel::{ToplevelTracker, app_id_matches};
+use crate::backend::frecency::frecency_score;
+use crate::backend::fuzzy::{fuzzy_match, highlight_ranges, merge_ranges};
+use crate::backend::history_export::{export_history, read_export};
+use crate::backend::idle::IdleTracker;
+use crate::backend::image_pipeline::{CANONICAL_IMAGE_MIME, image_info, normalize_image_payload};
+use crate::backend::language_detect::detect_language;
+use crate::backend::metrics::record_item_captured;
+use crate::backend::payload_compression::{
+    compress_payloads, decompress_item, decompressed_payloads,
+};
 use crate::backend::persistence::{
-    ClipboardPersistence, db_has_persisted_items, generate_and_store_db_password,
-    load_persistence_enabled_from_config, read_db_password_from_keyring_once,
+    BackendConfig, ClipboardPersistence, db_has_persisted_items, generate_and_store_db_password,
+    load_backend_config, read_db_password_from_keyring_once, save_daemon_settings, save_snippets,
     warn_persistence_sync_error,
 };
+use crate::backend::plain_text::{
+    HTML_MIME, PLAIN_TEXT_MIME, SYNTHESIZED_TEXT_MIMES, needs_synthesized_text, plain_text_payload,
+    strip_html, text_mime_content, with_synthesized_text,
+};
+use crate::backend::power::PowerState;
+use crate::backend::search_filter::parse_search_query;
+use crate::backend::snippets::expand_placeholders;
+use crate::backend::sync::content_hash;
+use crate::backend::text_transform::apply_transform;
 use crate::backend::virtual_keyboard::paste_via_virtual_keyboard_shortcut;
 use crate::backend::wayland_clipboard::MutexBackendState; // for QueueHandle type
+use crate::backend::x11_clipboard::{X11_PROTOCOL_NAME, X11Selection};
 use fast_image_resize as fir;
 use fast_image_resize::images::Image;
+use gtk4::glib;
 use image::{ImageFormat, RgbaImage};
-use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
 use std::io::Cursor;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::{Notify, broadcast};
 use wayland_client::Proxy;
 use wayland_client::backend::ObjectId;
 use wayland_client::protocol::wl_seat;
@@ -18,19 +51,61 @@ use wayland_client::{Connection, QueueHandle};
 use wayland_protocols::ext::data_control::v1::client::{
     ext_data_control_device_v1::ExtDataControlDeviceV1,
     ext_data_control_manager_v1::ExtDataControlManagerV1,
+    ext_data_control_offer_v1::ExtDataControlOfferV1,
     ext_data_control_source_v1::ExtDataControlSourceV1,
 };
 use wayland_protocols_wlr::data_control::v1::client::{
     zwlr_data_control_device_v1::ZwlrDataControlDeviceV1,
     zwlr_data_control_manager_v1::ZwlrDataControlManagerV1,
+    zwlr_data_control_offer_v1::ZwlrDataControlOfferV1,
     zwlr_data_control_source_v1::ZwlrDataControlSourceV1,
 };
 
-use crate::shared::{ClipboardContentType, ClipboardItem, ClipboardItemPreview};
+use crate::shared::quirks::{Quirks, active_quirks};
+use crate::shared::{
+    BackendMessage, ClipboardContentType, ClipboardItem, ClipboardItemPreview, CursorClipError,
+    CursorClipResult, DaemonSettings, DaemonStatus, ExportFormat, HistoryDelta, HistoryOrder,
+    HistoryPage, HistoryStats, HistoryView, ImageInfo, ItemDetails, MergeSeparator, MimeFilter,
+    MonitorState, PowerStatus, RetentionPolicy, SearchResult, Snippet, TextTransform,
+};
 use bytes::Bytes;
 use indexmap::IndexMap;
 use log::{debug, info, warn};
 
+// Emoji remembered for the overlay's Emoji tab
+const MAX_RECENT_EMOJI: usize = 24;
+// Revisions whose changes are kept for `GetHistorySince`; clients further
+// behind get the whole history
+const REVISION_LOG_LEN: usize = 256;
+
+// Deletions and Clear Alls that can be undone at once; older ones are dropped early
+const TRASH_OPERATIONS: usize = 20;
+
+/// Items removed by one delete or Clear All, kept for `UndoLastOperation`
+#[derive(Debug)]
+struct TrashedItems {
+    deleted_at: Instant,
+    /// Each item with its index in `history` before the removal, ascending
+    items: Vec<(usize, ClipboardItem)>,
+}
+
+/// What becomes of an item offered to the history (`BackendState::admit_item`)
+enum Admission {
+    /// Over `max_item_bytes`
+    TooLarge,
+    /// The same content is already in the history, at this index
+    Duplicate(usize),
+    New,
+}
+
+/// Items one history revision added or changed, and removed
+#[derive(Debug)]
+struct RevisionChanges {
+    revision: u64,
+    changed: Vec<u64>,
+    removed: Vec<u64>,
+}
+
 #[derive(Debug, Clone)]
 pub enum DataControlManager {
     Wlr(ZwlrDataControlManagerV1),
@@ -49,7 +124,36 @@ pub enum DataControlSource {
     Ext(ExtDataControlSourceV1),
 }
 
+#[derive(Debug, Clone)]
+pub enum DataControlOffer {
+    Wlr(ZwlrDataControlOfferV1),
+    Ext(ExtDataControlOfferV1),
+}
+
 impl DataControlManager {
+    pub fn protocol_name(&self) -> &'static str {
+        match self {
+            Self::Wlr(_) => "wlr-data-control-unstable-v1",
+            Self::Ext(_) => "ext-data-control-v1",
+        }
+    }
+
+    /// Protocol version the manager was bound at
+    pub fn version(&self) -> u32 {
+        match self {
+            Self::Wlr(manager) => manager.version(),
+            Self::Ext(manager) => manager.version(),
+        }
+    }
+
+    /// wlr-data-control only gained the primary selection in version 2
+    pub fn supports_primary_selection(&self) -> bool {
+        match self {
+            Self::Wlr(manager) => manager.version() >= 2,
+            Self::Ext(_) => true,
+        }
+    }
+
     pub fn destroy(self) {
         match self {
             Self::Wlr(manager) => manager.destroy(),
@@ -64,14 +168,21 @@ impl DataControlManager {
         }
     }
 
+    /// Create a data device for `seat`; `seat_key` is attached as user data so device
+    /// events can be attributed to the originating seat.
     pub fn get_data_device(
         &self,
         seat: &wl_seat::WlSeat,
+        seat_key: u32,
         qh: &QueueHandle<MutexBackendState>,
     ) -> DataControlDevice {
         match self {
-            Self::Wlr(manager) => DataControlDevice::Wlr(manager.get_data_device(seat, qh, ())),
-            Self::Ext(manager) => DataControlDevice::Ext(manager.get_data_device(seat, qh, ())),
+            Self::Wlr(manager) => {
+                DataControlDevice::Wlr(manager.get_data_device(seat, qh, seat_key))
+            }
+            Self::Ext(manager) => {
+                DataControlDevice::Ext(manager.get_data_device(seat, qh, seat_key))
+            }
         }
     }
 }
@@ -97,6 +208,20 @@ impl DataControlDevice {
             _ => warn!("Mismatched data control protocol between device and source"),
         }
     }
+
+    pub fn set_primary_selection(&self, source: Option<&DataControlSource>) {
+        match (self, source) {
+            (Self::Wlr(device), Some(DataControlSource::Wlr(source))) => {
+                device.set_primary_selection(Some(source))
+            }
+            (Self::Wlr(device), None) => device.set_primary_selection(None),
+            (Self::Ext(device), Some(DataControlSource::Ext(source))) => {
+                device.set_primary_selection(Some(source))
+            }
+            (Self::Ext(device), None) => device.set_primary_selection(None),
+            _ => warn!("Mismatched data control protocol between device and source"),
+        }
+    }
 }
 
 impl DataControlSource {
@@ -122,6 +247,140 @@ impl DataControlSource {
     }
 }
 
+impl DataControlOffer {
+    pub fn destroy(&self) {
+        match self {
+            Self::Wlr(offer) => offer.destroy(),
+            Self::Ext(offer) => offer.destroy(),
+        }
+    }
+
+    pub fn id(&self) -> ObjectId {
+        match self {
+            Self::Wlr(offer) => offer.id(),
+            Self::Ext(offer) => offer.id(),
+        }
+    }
+
+    /// Ask the source to write `mime_type` into `fd`.
+    pub fn receive(&self, mime_type: String, fd: std::os::fd::BorrowedFd<'_>) {
+        match self {
+            Self::Wlr(offer) => offer.receive(mime_type, fd),
+            Self::Ext(offer) => offer.receive(mime_type, fd),
+        }
+    }
+}
+
+/// Clipboard bookkeeping for a single `wl_seat`; every seat has its own selection.
+#[derive(Debug)]
+pub struct SeatState {
+    pub name: String,
+    pub seat: wl_seat::WlSeat,
+    pub data_control_device: Option<DataControlDevice>,
+    // Currently selected offer id (if any)
+    pub current_data_offer: Option<ObjectId>,
+    pub current_source_object: Option<DataControlSource>,
+    pub current_source_entry_id: Option<u64>,
+    // Payload served instead of the entry's MIME data (e.g. paste as plain text)
+    pub current_source_payload: Option<IndexMap<String, Bytes>>,
+    // When we programmatically set the selection, the compositor will echo it
+    // back as a new offer/selection. If we immediately try to read that offer
+    // inside the dispatch callback, we deadlock because the Send event for our
+    // own ZwlrDataControlSourceV1 cannot be processed until we return to the
+    // event loop. This flag suppresses reading the very next selection so we
+    // avoid blocking on our own source.
+    pub suppress_next_selection_read: bool,
+    // History entry recorded from the current external selection, re-offered by
+    // `persist_on_exit` when that selection is cleared
+    pub last_recorded_entry_id: Option<u64>,
+    // Selection read but not yet recorded while `selection_debounce_ms` runs
+    pub pending_selection: Option<PendingSelection>,
+    // Formats of the recorded history entry that were left with the source app
+    // (`lazy_mime_fetch`), fetched only when a paste asks for them
+    pub lazy_offer: Option<(u64, LazyOffer)>,
+    // Source we offer as the primary selection (middle-click paste) and its entry
+    pub primary_source_object: Option<DataControlSource>,
+    pub primary_source_entry_id: Option<u64>,
+}
+
+/// A read selection waiting out the debounce window; a newer one replaces it.
+#[derive(Debug)]
+pub struct PendingSelection {
+    pub offer_id: ObjectId,
+    pub mime_map: IndexMap<String, Bytes>,
+    pub seat_name: Option<String>,
+    pub lazy_offer: Option<LazyOffer>,
+}
+
+/// An external offer kept alive for the MIME types not read at copy time.
+/// The offer is destroyed once this is dropped.
+#[derive(Debug)]
+pub struct LazyOffer {
+    pub offer: DataControlOffer,
+    pub mime_types: Vec<String>,
+}
+
+impl LazyOffer {
+    pub fn provides(&self, mime_type: &str) -> bool {
+        self.mime_types.iter().any(|mime| mime == mime_type)
+    }
+}
+
+impl Drop for LazyOffer {
+    fn drop(&mut self) {
+        self.offer.destroy();
+    }
+}
+
+impl SeatState {
+    pub fn new(global_name: u32, seat: wl_seat::WlSeat) -> Self {
+        Self {
+            // Replaced by the compositor-provided name once wl_seat.name arrives
+            name: format!("seat-{global_name}"),
+            seat,
+            data_control_device: None,
+            current_data_offer: None,
+            current_source_object: None,
+            current_source_entry_id: None,
+            current_source_payload: None,
+            suppress_next_selection_read: false,
+            last_recorded_entry_id: None,
+            pending_selection: None,
+            lazy_offer: None,
+            primary_source_object: None,
+            primary_source_entry_id: None,
+        }
+    }
+
+    /// Drop the selection source we own on this seat (if any) and re-enable reads.
+    pub fn release_source(&mut self) {
+        if let Some(prev) = self.current_source_object.take() {
+            prev.destroy();
+        }
+        self.current_source_entry_id = None;
+        self.current_source_payload = None;
+        // We explicitly destroyed our own source, so the expected Cancelled
+        // event may no longer arrive to clear this flag.
+        self.suppress_next_selection_read = false;
+        self.release_primary_source();
+    }
+
+    /// Drop the primary selection source we own on this seat (if any).
+    pub fn release_primary_source(&mut self) {
+        if let Some(prev) = self.primary_source_object.take() {
+            prev.destroy();
+        }
+        self.primary_source_entry_id = None;
+    }
+
+    /// Whether `source_id` is our primary selection source rather than the clipboard's.
+    pub fn is_primary_source(&self, source_id: &ObjectId) -> bool {
+        self.primary_source_object
+            .as_ref()
+            .is_some_and(|source| &source.id() == source_id)
+    }
+}
+
 #[derive(Debug)]
 pub struct BackendState {
     // Clipboard history and management
@@ -130,44 +389,146 @@ pub struct BackendState {
 
     // Wayland objects for clipboard operations
     pub data_control_manager: Option<DataControlManager>,
-    pub data_control_device: Option<DataControlDevice>,
+    // wl_registry name of the bound manager, to notice when the compositor withdraws it
+    pub data_control_global: Option<u32>,
+    pub monitor_state: MonitorState,
     pub qh: Option<QueueHandle<MutexBackendState>>,
-    pub seat: Option<wl_seat::WlSeat>,
+    // All advertised seats keyed by their wl_registry global name
+    pub seats: IndexMap<u32, SeatState>,
     pub connection: Option<Connection>,
 
     // Current clipboard data
     // Mapping of offer ObjectId -> list of MIME types provided by that offer
     pub mime_type_offers: HashMap<ObjectId, Vec<String>>,
-    // Currently selected offer id (if any)
-    pub current_data_offer: Option<ObjectId>,
-    pub current_source_object: Option<DataControlSource>,
-    pub current_source_entry_id: Option<u64>,
-    // When we programmatically set the selection, the compositor will echo it
-    // back as a new offer/selection. If we immediately try to read that offer
-    // inside the dispatch callback, we deadlock because the Send event for our
-    // own ZwlrDataControlSourceV1 cannot be processed until we return to the
-    // event loop. This flag suppresses reading the very next selection so we
-    // avoid blocking on our own source.
-    pub suppress_next_selection_read: bool,
     // If true, we only monitor external selections and DO NOT immediately
     // re-set (take ownership of) the newly received selection.
     // If false (default), after reading an external selection we immediately
     // set it ourselves so it persists even if the source app exits.
     pub monitor_only: bool,
+    // False while recording is paused: new selections are neither read nor stored.
+    // Not persisted; a restarted daemon records again
+    pub recording_enabled: bool,
+    // Settings from config.toml (read once at startup)
+    pub config: BackendConfig,
     pub persistence_enabled: bool,
     pub persistence: Option<ClipboardPersistence>,
     pub db_password: Option<String>,
+    // Serving fixture data (--demo): never read from or write to the real history database
+    pub demo_mode: bool,
+    // Fan-out of backend events (e.g. NewItem) to subscribers such as the D-Bus service
+    pub events: broadcast::Sender<BackendMessage>,
+    // Bumped on every change to `history`; sent with each IPC reply and `HistoryChanged`
+    pub history_revision: u64,
+    // First change not yet written to the history database, and the signal that wakes
+    // the batch writer for it
+    history_dirty_since: Option<Instant>,
+    pub persistence_wakeup: Arc<Notify>,
+    // Written to the history database but not yet flushed to disk
+    history_unsynced: bool,
+    // What each recent revision changed, oldest first, and the preview fingerprint of
+    // every item as of `history_revision` to find the next one's changes
+    revision_log: VecDeque<RevisionChanges>,
+    preview_fingerprints: HashMap<u64, u64>,
+    // Bumped whenever we set the selection, so a delayed instant paste can tell that
+    // another client set a different item meanwhile
+    pub selection_serial: Arc<AtomicU64>,
+    // Focused-window tracking for the `exclude_apps` deny list
+    pub toplevel_tracker: ToplevelTracker,
+    // Session idle state (ext-idle-notify) for throttling background work
+    pub idle_tracker: IdleTracker,
+    // Power source and profile, for battery saving
+    pub power: PowerState,
+    // Compositor-specific defaults (protocol preference, takeover delay)
+    pub quirks: Quirks,
+    // Selection restored from a crash backup, offered to the user until used or dismissed
+    pub recovered_item: Option<u64>,
+    // Compiled `[[classifier_rules]]`, applied to new items before the built-in heuristics
+    pub classifier: Classifier,
+    // Stack mode: items still to be pasted, in order. The front one is the selection and
+    // each paste of it moves on to the next
+    pub paste_queue: VecDeque<u64>,
+    // A paste of the queue's front was seen; the switch to the next item is scheduled
+    pub paste_queue_advancing: bool,
+    // Recently deleted or cleared items, newest operation last; only held in memory and
+    // emptied after `trash_minutes`
+    trash: Vec<TrashedItems>,
+    // Emoji copied or picked this session, most recent first (for the overlay's Emoji tab)
+    pub recent_emoji: VecDeque<String>,
+    // For `Status`: when the daemon started and the most recent failure (Unix time, message)
+    pub started_at: Instant,
+    pub last_error: Option<(u64, String)>,
+    // CLIPBOARD ownership when monitoring X11 instead of Wayland data-control
+    pub x11_selection: Option<X11Selection>,
+    // Clipboard ownership through xdg-desktop-portal when sandboxed without data-control
+    pub portal_selection: Option<PortalSelection>,
+}
+
+/// Built-in classification of a text item: the type from its preview and the language
+/// of code in its text. Text that clearly is code in a known language becomes Code.
+fn classify_text(preview: &str, text: Option<&[u8]>) -> (ClipboardContentType, Option<String>) {
+    let content_type = ClipboardContentType::type_from_preview(preview);
+    let language = matches!(
+        content_type,
+        ClipboardContentType::Code | ClipboardContentType::Text
+    )
+    .then_some(text)
+    .flatten()
+    .and_then(|text| detect_language(&String::from_utf8_lossy(text)))
+    .map(str::to_string);
+    let content_type = match content_type {
+        ClipboardContentType::Text if language.is_some() => ClipboardContentType::Code,
+        content_type => content_type,
+    };
+    (content_type, language)
+}
+
+/// Type from the first `[[classifier_rules]]` rule matching a text item's text, if any.
+fn rule_type(classifier: &Classifier, item: &ClipboardItem) -> Option<ClipboardContentType> {
+    if item.content_type == ClipboardContentType::Image || !item.files.is_empty() {
+        return None;
+    }
+    let text = plain_text_payload(&decompressed_payloads(item))
+        .and_then(|payload| payload.get(PLAIN_TEXT_MIME).cloned())
+        .map(|text| String::from_utf8_lossy(&text).into_owned())
+        .unwrap_or_else(|| item.content_preview.clone());
+    classifier.classify(&text)
+}
+
+/// Whether `existing` holds the same content as the new `copy`, whose text or image
+/// payload hashes to `hash`. The preview is compared first so only likely matches are
+/// decompressed; content without such a payload (e.g. only a custom MIME type) is
+/// matched by its preview.
+fn is_same_content(existing: &ClipboardItem, copy: &ClipboardItem, hash: Option<&str>) -> bool {
+    if existing.content_preview != copy.content_preview {
+        return false;
+    }
+    match hash {
+        Some(hash) => content_hash(&decompressed_payloads(existing)).as_deref() == Some(hash),
+        None => true,
+    }
 }
 
 impl Default for BackendState {
     fn default() -> Self {
-        Self::new(false)
+        Self::new(false, false)
     }
 }
 
 impl BackendState {
-    pub fn new(monitor_only: bool) -> Self {
-        let persistence_enabled = load_persistence_enabled_from_config();
+    pub fn new(monitor_only: bool, demo_mode: bool) -> Self {
+        if demo_mode {
+            let history = demo_items();
+            let mut state = Self::with_history(monitor_only, None, load_backend_config());
+            state.id_for_next_entry = history.len() as u64 + 1;
+            state.history = history;
+            state.demo_mode = true;
+            info!("Demo mode: serving {} fixture items", state.history.len());
+            return state;
+        }
+
+        let config = load_backend_config();
+        let monitor_only = monitor_only || config.monitor_only;
+        let persistence_enabled = config.persistence_enabled;
         let db_password = match read_db_password_from_keyring_once() {
             Ok(password) => password,
             Err(e) => {
@@ -176,48 +537,447 @@ impl BackendState {
             }
         };
 
-        let mut state = Self {
+        let mut state = Self::with_history(monitor_only, db_password, config);
+        if let Err(e) = state.set_persistence_enabled(persistence_enabled) {
+            warn!("Failed to initialize persistence from config: {e}");
+        }
+        if state.config.crash_backup {
+            state.restore_crash_backups();
+        }
+
+        state
+    }
+
+    /// Put selections a previous daemon still owned when it died back into the history.
+    fn restore_crash_backups(&mut self) {
+        for backup in crash_backup::take_leftovers() {
+            let Some(item) = Self::build_item(
+                self.id_for_next_entry,
+                backup.mime_data(),
+                backup.timestamp,
+                backup.seat.clone(),
+                true,
+            ) else {
+                continue;
+            };
+            if let Some(id) = self.insert_item(item) {
+                info!(
+                    "Recovered selection from an unclean exit as item {id} (seat {:?})",
+                    backup.seat
+                );
+                self.recovered_item = Some(id);
+            }
+        }
+    }
+
+    fn with_history(
+        monitor_only: bool,
+        db_password: Option<String>,
+        config: BackendConfig,
+    ) -> Self {
+        Self {
             history: Vec::new(),
             mime_type_offers: HashMap::new(),
             id_for_next_entry: 1,
             data_control_manager: None,
-            data_control_device: None,
-            seat: None,
-            current_data_offer: None,
-            current_source_object: None,
-            current_source_entry_id: None,
+            data_control_global: None,
+            monitor_state: MonitorState::Degraded,
+            seats: IndexMap::new(),
             qh: None,
-            suppress_next_selection_read: false,
             connection: None,
             monitor_only,
+            recording_enabled: true,
+            classifier: Classifier::new(&config.classifier_rules),
+            config,
             persistence_enabled: false,
             persistence: None,
             db_password,
+            demo_mode: false,
+            events: broadcast::channel(64).0,
+            history_revision: 0,
+            history_dirty_since: None,
+            persistence_wakeup: Arc::new(Notify::new()),
+            history_unsynced: false,
+            revision_log: VecDeque::new(),
+            preview_fingerprints: HashMap::new(),
+            selection_serial: Arc::new(AtomicU64::new(0)),
+            toplevel_tracker: ToplevelTracker::default(),
+            idle_tracker: IdleTracker::default(),
+            power: PowerState::default(),
+            quirks: active_quirks().1,
+            recovered_item: None,
+            paste_queue: VecDeque::new(),
+            paste_queue_advancing: false,
+            trash: Vec::new(),
+            recent_emoji: VecDeque::new(),
+            started_at: Instant::now(),
+            last_error: None,
+            x11_selection: None,
+            portal_selection: None,
+        }
+    }
+
+    pub fn add_clipboard_item_from_mime_map(
+        &mut self,
+        mut mime_content: IndexMap<String, Bytes>,
+        source_seat: Option<String>,
+    ) -> Option<u64> {
+        // A selection read before recording was paused is dropped too
+        if !self.recording_enabled {
+            debug!("Recording paused; dropping selection");
+            return None;
+        }
+        normalize_image_payload(&mut mime_content, self.config.max_image_bytes);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let mut item = Self::build_item(
+            self.id_for_next_entry,
+            mime_content,
+            timestamp,
+            source_seat,
+            !self.battery_saving(),
+        )?;
+        // Offers don't name their source; the focused app is the best guess
+        item.source_app = self.toplevel_tracker.focused_app_id().map(str::to_string);
+        let new_id = self.insert_item(item)?;
+        record_item_captured();
+        Some(new_id)
+    }
+
+    /// Record content handed to the daemon directly (`AddItem`) rather than copied.
+    /// Unlike a copy it is kept while recording is paused. With `pin` it goes to the
+    /// pinned items, with `select` it also becomes the selection. Returns the new item ID.
+    pub fn add_item(
+        &mut self,
+        mut mime_content: IndexMap<String, Bytes>,
+        pin: bool,
+        select: bool,
+    ) -> CursorClipResult<u64> {
+        normalize_image_payload(&mut mime_content, self.config.max_image_bytes);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let item = Self::build_item(
+            self.id_for_next_entry,
+            mime_content,
+            timestamp,
+            None,
+            !self.battery_saving(),
+        )
+        .ok_or_else(|| {
+            CursorClipError::UnsupportedContent("No payload the history can hold".into())
+        })?;
+        let new_id = self.insert_item(item).ok_or_else(|| {
+            CursorClipError::UnsupportedContent("Item exceeds max_item_bytes".into())
+        })?;
+        if pin {
+            self.set_pinned(new_id, true)?;
+        }
+        if select {
+            self.set_clipboard_by_id(new_id, false, None, None)?;
+        }
+        Ok(new_id)
+    }
+
+    /// Record an item received from a sync peer, keeping the copy time it had there.
+    pub fn add_synced_item(
+        &mut self,
+        mime_content: IndexMap<String, Bytes>,
+        timestamp: u64,
+    ) -> Option<u64> {
+        let item = Self::build_item(
+            self.id_for_next_entry,
+            mime_content,
+            timestamp,
+            None,
+            !self.battery_saving(),
+        )?;
+        self.insert_item(item)
+    }
+
+    /// Put a freshly built item (carrying `id_for_next_entry`) into the unpinned history
+    /// by timestamp (the top for local copies), enforce retention and announce it.
+    /// Returns the new item id.
+    fn insert_item(&mut self, mut item: ClipboardItem) -> Option<u64> {
+        let timestamp = item.timestamp;
+        match self.admit_item(&mut item) {
+            Admission::TooLarge => return None,
+            Admission::Duplicate(index) => return Some(self.merge_repeat_copy(index, item)),
+            Admission::New => {}
+        }
+
+        let insert_index = self.unpinned_insert_index(timestamp);
+        let preview = self.place_new_item(insert_index, item);
+        self.prune_history(timestamp);
+
+        let new_id = self.id_for_next_entry;
+        self.id_for_next_entry += 1;
+        self.history_changed();
+        if let Some(emoji) = emojis::get(preview.content_preview.trim()) {
+            self.note_emoji_use(emoji.as_str());
+        }
+        // Nobody listening is fine; send only fails without subscribers
+        let _ = self.events.send(BackendMessage::NewItem { item: preview });
+        Some(new_id)
+    }
+
+    /// Checks every new entry goes through: the size limit, the classifier rules, and
+    /// whether the same content is already in the history.
+    fn admit_item(&self, item: &mut ClipboardItem) -> Admission {
+        let max_item_bytes = self.config.retention.max_item_bytes;
+        if max_item_bytes != 0 && item.payload_size() > max_item_bytes {
+            info!(
+                "Skipping clipboard item of {} bytes (max_item_bytes is {max_item_bytes})",
+                item.payload_size()
+            );
+            return Admission::TooLarge;
+        }
+        if let Some(content_type) = rule_type(&self.classifier, item) {
+            item.content_type = content_type;
+        }
+        let hash = content_hash(&item.mime_data);
+        match self
+            .history
+            .iter()
+            .position(|existing| is_same_content(existing, item, hash.as_deref()))
+        {
+            Some(index) => Admission::Duplicate(index),
+            None => Admission::New,
+        }
+    }
+
+    /// Store an admitted item at `index`, its payloads compressed; returns its preview.
+    fn place_new_item(&mut self, index: usize, mut item: ClipboardItem) -> ClipboardItemPreview {
+        let preview = ClipboardItemPreview::from(&item);
+        compress_payloads(&mut item, self.config.compress_payloads_over_bytes);
+        self.history.insert(index, item);
+        preview
+    }
+
+    /// Fold a copy of content already in the history into the existing entry at `index`:
+    /// count it, move the entry to the top (pinned entries stay in place) and take over
+    /// where and from which app it was copied, and the formats it was offered in. Returns
+    /// the existing entry's ID.
+    fn merge_repeat_copy(&mut self, index: usize, copy: ClipboardItem) -> u64 {
+        let mut existing = self.history.remove(index);
+        existing.times_copied = existing.times_copied.saturating_add(1);
+        existing.last_copied = Some(copy.timestamp.max(existing.last_copied_at()));
+        existing.seat = copy.seat.or(existing.seat);
+        existing.source_app = copy.source_app.or(existing.source_app);
+        // The same text copied from a browser brings text/html a terminal copy lacked:
+        // the newer payloads win and formats only the older copy had are kept
+        for (mime, payload) in copy.mime_data {
+            existing.compressed.shift_remove(&mime);
+            existing.mime_data.insert(mime, payload);
+        }
+        compress_payloads(&mut existing, self.config.compress_payloads_over_bytes);
+        let entry_id = existing.item_id;
+        debug!("Item {entry_id} copied again ({}×)", existing.times_copied);
+
+        let preview = ClipboardItemPreview::from(&existing);
+        let insert_index = if existing.pinned {
+            index
+        } else {
+            self.unpinned_insert_index(existing.last_copied_at())
         };
+        let last_copied = existing.last_copied_at();
+        self.history.insert(insert_index, existing);
+        // The added formats count against `max_total_bytes`
+        self.prune_history(last_copied);
+        self.history_changed();
+        let _ = self.events.send(BackendMessage::NewItem { item: preview });
+        entry_id
+    }
 
-        if let Err(e) = state.set_persistence_enabled(persistence_enabled) {
-            warn!("Failed to initialize persistence from config: {e}");
+    /// Where an unpinned entry last copied at `timestamp` goes: after the pinned items and
+    /// anything copied later.
+    fn unpinned_insert_index(&self, timestamp: u64) -> usize {
+        self.history
+            .iter()
+            .position(|existing| !existing.pinned && existing.last_copied_at() <= timestamp)
+            .unwrap_or(self.history.len())
+    }
+
+    /// Apply a text transform to an item, record the result as a new history entry
+    /// linked to the original and set it as the selection.
+    pub fn transform_and_set(
+        &mut self,
+        entry_id: u64,
+        transform: TextTransform,
+        instant_paste: bool,
+        seat: Option<&str>,
+    ) -> CursorClipResult<ClipboardItemPreview> {
+        let item = self
+            .get_item_by_id(entry_id)
+            .ok_or(CursorClipError::ItemNotFound { id: entry_id })?;
+        let text = plain_text_payload(&item.mime_data)
+            .and_then(|payload| payload.get(PLAIN_TEXT_MIME).cloned())
+            .ok_or_else(|| {
+                CursorClipError::UnsupportedContent(format!(
+                    "Clipboard item {entry_id} has no text content"
+                ))
+            })?;
+        let text = String::from_utf8_lossy(&text);
+        let transformed =
+            apply_transform(transform, &text).map_err(CursorClipError::UnsupportedContent)?;
+
+        // Nothing changed; reuse the original instead of adding a duplicate
+        if transformed == text {
+            self.set_clipboard_by_id(entry_id, instant_paste, seat, None)?;
+            return Ok(ClipboardItemPreview::from(&item));
         }
 
-        state
+        let mime_content = text_mime_content(&transformed);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let mut derived = Self::build_item(
+            self.id_for_next_entry,
+            mime_content,
+            timestamp,
+            item.seat,
+            false,
+        )
+        .ok_or_else(|| CursorClipError::UnsupportedContent("Transformed text is empty".into()))?;
+        derived.derived_from = Some(entry_id);
+        let preview = ClipboardItemPreview::from(&derived);
+        let new_id = self.insert_item(derived).ok_or_else(|| {
+            CursorClipError::UnsupportedContent("Transformed item exceeds max_item_bytes".into())
+        })?;
+        info!("Transformed item {entry_id} ({transform:?}) into item {new_id}");
+
+        self.set_clipboard_by_id(new_id, instant_paste, seat, None)?;
+        Ok(preview)
     }
 
-    pub fn add_clipboard_item_from_mime_map(
+    /// Join the text of `ids` (in that order) with `separator`, record the result as a new
+    /// history entry and set it as the selection.
+    pub fn merge_and_set(
         &mut self,
+        ids: &[u64],
+        separator: MergeSeparator,
+        instant_paste: bool,
+        seat: Option<&str>,
+    ) -> CursorClipResult<ClipboardItemPreview> {
+        if ids.len() < 2 {
+            return Err(CursorClipError::InvalidRequest(
+                "Merging needs at least two items".into(),
+            ));
+        }
+        let mut parts = Vec::with_capacity(ids.len());
+        for &id in ids {
+            let item = self
+                .get_item_by_id(id)
+                .ok_or(CursorClipError::ItemNotFound { id })?;
+            let text = plain_text_payload(&item.mime_data)
+                .and_then(|payload| payload.get(PLAIN_TEXT_MIME).cloned())
+                .ok_or_else(|| {
+                    CursorClipError::UnsupportedContent(format!(
+                        "Clipboard item {id} has no text content"
+                    ))
+                })?;
+            parts.push(String::from_utf8_lossy(&text).into_owned());
+        }
+        let merged = parts.join(separator.as_str());
+
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let item = Self::build_item(
+            self.id_for_next_entry,
+            text_mime_content(&merged),
+            timestamp,
+            seat.map(str::to_string),
+            false,
+        )
+        .ok_or_else(|| CursorClipError::UnsupportedContent("Merged text is empty".into()))?;
+        let preview = ClipboardItemPreview::from(&item);
+        let new_id = self.insert_item(item).ok_or_else(|| {
+            CursorClipError::UnsupportedContent("Merged item exceeds max_item_bytes".into())
+        })?;
+        info!("Merged items {ids:?} into item {new_id}");
+
+        self.set_clipboard_by_id(new_id, instant_paste, seat, None)?;
+        Ok(preview)
+    }
+
+    /// Classify every text item again with the current built-in heuristics and
+    /// `[[classifier_rules]]`. Returns how many items changed type.
+    pub fn reclassify_history(&mut self) -> usize {
+        let mut changed = 0;
+        for item in &mut self.history {
+            if item.content_type == ClipboardContentType::Image || !item.files.is_empty() {
+                continue;
+            }
+            let (builtin_type, language) = {
+                let payloads = decompressed_payloads(item);
+                classify_text(
+                    &item.content_preview,
+                    payloads.get(PLAIN_TEXT_MIME).map(|text| text.as_ref()),
+                )
+            };
+            let content_type = rule_type(&self.classifier, item).unwrap_or(builtin_type);
+            if content_type != item.content_type {
+                changed += 1;
+            }
+            item.content_type = content_type;
+            item.language = language;
+        }
+        if changed > 0 {
+            self.history_changed();
+        }
+        info!("Reclassified history: {changed} items changed type");
+        changed
+    }
+
+    /// Classify a MIME map into a history entry (preview text, content type, thumbnail).
+    /// Without `with_thumbnail` image thumbnails are left for [`Self::get_history`] to create.
+    pub fn build_item(
+        item_id: u64,
         mut mime_content: IndexMap<String, Bytes>,
-    ) -> Option<u64> {
+        timestamp: u64,
+        seat: Option<String>,
+        with_thumbnail: bool,
+    ) -> Option<ClipboardItem> {
         if mime_content.is_empty() {
             return None;
         }
 
-        // If we have image/png, prefer showing mime_type + bytes and set type to Image
-        let (content_preview, content_type, thumbnail) = if let Some(png_bytes) =
-            mime_content.get("image/png")
+        let files = mime_content
+            .get(URI_LIST_MIME)
+            .map(|uri_list| parse_uri_list(uri_list))
+            .unwrap_or_default();
+
+        // If we have image/png, describe it by dimensions and size and set type to Image
+        let image_info = mime_content
+            .get(CANONICAL_IMAGE_MIME)
+            .and_then(|png_bytes| image_info(CANONICAL_IMAGE_MIME, png_bytes));
+        let (content_preview, content_type, thumbnail, language) = if let Some(png_bytes) =
+            mime_content.get(CANONICAL_IMAGE_MIME)
         {
             (
-                format!("<image/png {} bytes>", png_bytes.len()),
+                image_info.as_ref().map_or_else(
+                    || format!("<image/png {} bytes>", png_bytes.len()),
+                    ImageInfo::describe,
+                ),
                 ClipboardContentType::Image,
-                Self::scale_image(png_bytes),
+                with_thumbnail
+                    .then(|| Self::scale_image(png_bytes))
+                    .flatten(),
+                None,
+            )
+        } else if !files.is_empty() {
+            // File manager copies: list the paths, the overlay shows names and icons
+            (
+                files.join("\n").chars().take(200).collect(),
+                ClipboardContentType::File,
+                None,
+                None,
             )
         } else {
             // Otherwise, if we have text/plain;charset=utf-8, show up to first 200 chars and infer type
@@ -227,6 +987,12 @@ impl BackendState {
                         Ok(s) => s.chars().take(200).collect(),
                         Err(_) => format!("<text/plain;charset=utf-8 {} bytes>", txt_bytes.len()),
                     }
+                } else if let Some(html) = mime_content.get(HTML_MIME) {
+                    // HTML-only copies (e.g. from some web apps) preview as their text
+                    strip_html(&String::from_utf8_lossy(html))
+                        .chars()
+                        .take(200)
+                        .collect()
                 } else {
                     // Fallback: show placeholder using first mime entry
                     let (mime_name, len) = mime_content
@@ -236,40 +1002,32 @@ impl BackendState {
                         .unwrap();
                     format!("<{mime_name} {len} bytes>")
                 };
-            let content_type = ClipboardContentType::type_from_preview(&preview);
-            (preview, content_type, None)
+            let (content_type, language) = classify_text(
+                &preview,
+                mime_content.get(PLAIN_TEXT_MIME).map(|text| text.as_ref()),
+            );
+            (preview, content_type, None, language)
         };
 
-        let item = ClipboardItem {
-            item_id: self.id_for_next_entry,
+        Some(ClipboardItem {
+            item_id,
             content_type,
             content_preview,
-            timestamp: SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            timestamp,
             pinned: false,
             mime_data: mime_content.drain(..).collect(),
             thumbnail,
-        };
-
-        // remove duplicates (todo change to more robust solution -> hashes)
-        self.history
-            .retain(|existing| existing.content_preview != item.content_preview);
-        let insert_index = self
-            .history
-            .iter()
-            .position(|existing| !existing.pinned)
-            .unwrap_or(self.history.len());
-        self.history.insert(insert_index, item);
-        if self.history.len() > 100 {
-            self.history.truncate(100);
-        }
-
-        let new_id = self.id_for_next_entry;
-        self.id_for_next_entry += 1;
-        self.persist_history_if_enabled();
-        Some(new_id)
+            seat,
+            image_info,
+            derived_from: None,
+            files,
+            paste_count: 0,
+            compressed: IndexMap::new(),
+            language,
+            source_app: None,
+            times_copied: 1,
+            last_copied: None,
+        })
     }
 
     fn scale_image(img_bytes: &Bytes) -> Option<Bytes> {
@@ -318,98 +1076,1212 @@ impl BackendState {
         }
     }
 
-    #[cfg(debug_assertions)]
-    pub fn add_clipboard_item_from_text(&mut self, text: &str) -> Option<u64> {
-        let mut mime_content = IndexMap::new();
-        mime_content.insert(
-            "text/plain;charset=utf-8".to_string(),
-            Bytes::copy_from_slice(text.as_bytes()),
-        );
-        self.add_clipboard_item_from_mime_map(mime_content)
-    }
-
-    pub fn get_history(&self) -> Vec<ClipboardItemPreview> {
+    pub fn get_history(&mut self) -> Vec<ClipboardItemPreview> {
+        let all: Vec<usize> = (0..self.history.len()).collect();
+        self.fill_missing_thumbnails(&all);
         self.history
             .iter()
             .map(ClipboardItemPreview::from)
             .collect()
     }
 
-    pub fn get_item_by_id(&self, id: u64) -> Option<ClipboardItem> {
-        self.history.iter().find(|i| i.item_id == id).cloned()
-    }
-
-    pub fn clear_history(&mut self) {
-        self.history.clear();
-
-        // If we clear history while owning a selection source, drop it and
-        // re-enable selection reads so external copies keep being tracked.
-        if let Some(prev) = self.current_source_object.take() {
-            prev.destroy();
+    /// The changes to the history since `since_revision`, or None if the daemon no longer
+    /// knows them (or never had that revision) or they touch most of the history, in
+    /// which case the whole history is cheaper to send.
+    pub fn history_since(&mut self, since_revision: u64) -> Option<HistoryDelta> {
+        if since_revision > self.history_revision {
+            return None;
+        }
+        if since_revision == self.history_revision {
+            return Some(HistoryDelta {
+                revision: self.history_revision,
+                ..HistoryDelta::default()
+            });
+        }
+        // The log must reach back to the revision right after the client's
+        let first_logged = self.revision_log.front()?.revision;
+        if first_logged > since_revision + 1 {
+            return None;
         }
-        self.current_source_entry_id = None;
-        self.suppress_next_selection_read = false;
-
-        self.persist_history_if_enabled();
-    }
 
-    pub fn delete_item_by_id(&mut self, entry_id: u64) -> Result<(), String> {
+        let mut changed = HashSet::new();
+        let mut removed = HashSet::new();
+        for revision in self
+            .revision_log
+            .iter()
+            .filter(|revision| revision.revision > since_revision)
+        {
+            changed.extend(revision.changed.iter().copied());
+            removed.extend(revision.removed.iter().copied());
+        }
+        if changed.len() + removed.len() > self.history.len() / 2 {
+            return None;
+        }
+
+        let indices: Vec<usize> = self
+            .history
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| changed.contains(&item.item_id))
+            .map(|(index, _)| index)
+            .collect();
+        // May start a new revision; the delta includes it and is stamped after it
+        self.fill_missing_thumbnails(&indices);
+        let present: HashSet<u64> = self.history.iter().map(|item| item.item_id).collect();
+        Some(HistoryDelta {
+            revision: self.history_revision,
+            changed: indices
+                .into_iter()
+                .map(|index| (index, ClipboardItemPreview::from(&self.history[index])))
+                .collect(),
+            removed: removed
+                .into_iter()
+                .filter(|id| !present.contains(id))
+                .collect(),
+        })
+    }
+
+    /// Up to `limit` items of `view`, skipping the first `offset` of them.
+    pub fn get_history_page(
+        &mut self,
+        offset: usize,
+        limit: usize,
+        view: HistoryView,
+        order: HistoryOrder,
+    ) -> HistoryPage {
+        let mut matching: Vec<usize> = self
+            .history
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| view.includes(item.content_type, item.pinned))
+            .map(|(index, _)| index)
+            .collect();
+        if order == HistoryOrder::Frecency {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs();
+            // Stable, so equal scores keep the most recent first; pinned items stay on top
+            matching.sort_by_key(|index| {
+                let item = &self.history[*index];
+                let score = (!item.pinned).then(|| frecency_score(item, now));
+                (!item.pinned, std::cmp::Reverse(score))
+            });
+        }
+        let total = matching.len();
+        let start = offset.min(total);
+        let end = start.saturating_add(limit).min(total);
+        let page = &matching[start..end];
+        self.fill_missing_thumbnails(page);
+        HistoryPage {
+            items: page
+                .iter()
+                .map(|index| ClipboardItemPreview::from(&self.history[*index]))
+                .collect(),
+            offset: start,
+            total,
+        }
+    }
+
+    /// Fuzzy-search the previews of `view`, best first. Items whose preview does not
+    /// match but whose full text, file paths or type name contain the query are listed
+    /// too, with a low score and no highlights. Recent and pinned items get a bonus.
+    /// Filter words (`type:`, `mime:`, `re:`, `since:`, `"phrases"` and `prefix*`, see
+    /// [`parse_search_query`]) narrow the results. `limit` 0 returns every match.
+    pub fn search_history(
+        &mut self,
+        query: &str,
+        view: HistoryView,
+        limit: usize,
+    ) -> CursorClipResult<Vec<SearchResult>> {
+        const SCORE_CONTAINS: i64 = 8;
+        const BONUS_PINNED: i64 = 10;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let query = parse_search_query(query, now).map_err(CursorClipError::InvalidRequest)?;
+        if query.is_empty() {
+            return Ok(Vec::new());
+        }
+        let text = query.text.as_str();
+        let lowered = text.to_lowercase();
+
+        let mut matches: Vec<(i64, usize, Vec<(usize, usize)>)> = Vec::new();
+        for (index, item) in self.history.iter().enumerate() {
+            if !view.includes(item.content_type, item.pinned) || !query.accepts(item) {
+                continue;
+            }
+            // Passwords are masked in previews; don't let a query probe their text
+            let full_text = (item.content_type != ClipboardContentType::Password)
+                .then(|| plain_text_payload(&decompressed_payloads(item)))
+                .flatten()
+                .and_then(|payload| payload.get(PLAIN_TEXT_MIME).cloned())
+                .map(|text| String::from_utf8_lossy(&text).into_owned());
+            let searchable = full_text.as_deref().unwrap_or(&item.content_preview);
+            if !query.patterns.iter().all(|pattern| {
+                pattern.is_match(searchable) || item.files.iter().any(|path| pattern.is_match(path))
+            }) {
+                continue;
+            }
+
+            let (score, mut highlights) = if query.is_filter_only() {
+                (0, Vec::new())
+            } else if let Some(found) = fuzzy_match(&item.content_preview, text) {
+                let highlights = highlight_ranges(&item.content_preview, &found.positions);
+                (found.score, highlights)
+            } else if item.content_type.as_str().to_lowercase().contains(&lowered)
+                || item
+                    .files
+                    .iter()
+                    .any(|path| path.to_lowercase().contains(&lowered))
+                || full_text
+                    .as_ref()
+                    .is_some_and(|full_text| full_text.to_lowercase().contains(&lowered))
+            {
+                (SCORE_CONTAINS, Vec::new())
+            } else {
+                continue;
+            };
+            for pattern in &query.patterns {
+                highlights.extend(
+                    pattern
+                        .find_iter(&item.content_preview)
+                        .filter(|found| !found.is_empty())
+                        .map(|found| (found.start(), found.end())),
+                );
+            }
+            let highlights = merge_ranges(highlights);
+
+            let age = now.saturating_sub(item.last_copied_at());
+            let recency = match age {
+                0..3_600 => 12,
+                3_600..86_400 => 8,
+                86_400..604_800 => 4,
+                _ => 0,
+            };
+            let pinned = if item.pinned { BONUS_PINNED } else { 0 };
+            matches.push((score + recency + pinned, index, highlights));
+        }
+
+        // Stable: equal scores keep history order
+        matches.sort_by_key(|(score, _, _)| std::cmp::Reverse(*score));
+        if limit != 0 {
+            matches.truncate(limit);
+        }
+        let indices: Vec<usize> = matches.iter().map(|(_, index, _)| *index).collect();
+        self.fill_missing_thumbnails(&indices);
+        Ok(matches
+            .into_iter()
+            .map(|(score, index, highlights)| SearchResult {
+                item: ClipboardItemPreview::from(&self.history[index]),
+                score,
+                highlights,
+            })
+            .collect())
+    }
+
+    fn fill_missing_thumbnails(&mut self, indices: &[usize]) {
+        let mut filled = false;
+        for index in indices {
+            let item = &mut self.history[*index];
+            if item.content_type == ClipboardContentType::Image
+                && item.thumbnail.is_none()
+                && let Some(png_bytes) = item.mime_data.get(CANONICAL_IMAGE_MIME)
+            {
+                item.thumbnail = Self::scale_image(png_bytes);
+                filled |= item.thumbnail.is_some();
+            }
+        }
+        if filled {
+            self.history_changed();
+        }
+    }
+
+    /// Full text and MIME overview of one item for the overlay's detail view.
+    pub fn item_details(&self, entry_id: u64) -> CursorClipResult<ItemDetails> {
+        let item = self
+            .history
+            .iter()
+            .find(|item| item.item_id == entry_id)
+            .ok_or(CursorClipError::ItemNotFound { id: entry_id })?;
+        let full_text = plain_text_payload(&decompressed_payloads(item))
+            .and_then(|payload| payload.get(PLAIN_TEXT_MIME).cloned())
+            .map(|text| String::from_utf8_lossy(&text).into_owned());
+        Ok(ItemDetails {
+            item: ClipboardItemPreview::from(item),
+            full_text,
+            mime_types: item
+                .mime_data
+                .iter()
+                .map(|(mime, bytes)| {
+                    // Sizes as copied, not as held compressed in memory
+                    let size = item.compressed.get(mime).copied();
+                    (mime.clone(), size.unwrap_or(bytes.len() as u64))
+                })
+                .collect(),
+        })
+    }
+
+    /// The first `max_bytes` of an item's text (cut at a character boundary) and whether
+    /// the text goes on, for showing more than the preview without sending it all.
+    pub fn item_full_preview(
+        &self,
+        entry_id: u64,
+        max_bytes: usize,
+    ) -> CursorClipResult<(String, bool)> {
+        let item = self
+            .history
+            .iter()
+            .find(|item| item.item_id == entry_id)
+            .ok_or(CursorClipError::ItemNotFound { id: entry_id })?;
+        let text = plain_text_payload(&decompressed_payloads(item))
+            .and_then(|payload| payload.get(PLAIN_TEXT_MIME).cloned())
+            .ok_or_else(|| {
+                CursorClipError::UnsupportedContent(format!("item {entry_id} has no text"))
+            })?;
+        if text.len() <= max_bytes {
+            return Ok((String::from_utf8_lossy(&text).into_owned(), false));
+        }
+        let head = &text[..max_bytes];
+        // Don't end on the first bytes of a character that was cut in half
+        let end = match std::str::from_utf8(head) {
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            _ => max_bytes,
+        };
+        Ok((String::from_utf8_lossy(&head[..end]).into_owned(), true))
+    }
+
+    /// The payload of one item in `mime_type`, decompressed; plain text is converted from
+    /// HTML for items that have none of their own, as when the item is pasted.
+    pub fn item_data(&self, entry_id: u64, mime_type: &str) -> CursorClipResult<Bytes> {
+        let item = self
+            .history
+            .iter()
+            .find(|item| item.item_id == entry_id)
+            .ok_or(CursorClipError::ItemNotFound { id: entry_id })?;
+        with_synthesized_text(decompressed_payloads(item).into_owned())
+            .swap_remove(mime_type)
+            .ok_or_else(|| {
+                CursorClipError::UnsupportedContent(format!(
+                    "item {entry_id} has no {mime_type} payload"
+                ))
+            })
+    }
+
+    /// Whether background work is currently reduced (on battery or power-saver profile).
+    pub fn battery_saving(&self) -> bool {
+        self.config.battery_saver && self.power.saving()
+    }
+
+    /// The item with its payloads decompressed.
+    pub fn get_item_by_id(&self, id: u64) -> Option<ClipboardItem> {
+        self.history
+            .iter()
+            .find(|i| i.item_id == id)
+            .map(|item| decompress_item(item).into_owned())
+    }
+
+    pub fn clear_history(&mut self) {
+        let cleared = std::mem::take(&mut self.history);
+        self.move_to_trash(cleared.into_iter().enumerate().collect());
+        self.paste_queue.clear();
+
+        // If we clear history while owning a selection source, drop it and
+        // re-enable selection reads so external copies keep being tracked.
+        for seat in self.seats.values_mut() {
+            seat.release_source();
+        }
+
+        self.history_changed();
+        let _ = self.events.send(BackendMessage::HistoryCleared);
+    }
+
+    /// Delete an item, keeping it in the trash for `UndoLastOperation`.
+    pub fn delete_item_by_id(&mut self, entry_id: u64) -> CursorClipResult<()> {
+        let (index, item) = self.remove_item_by_id(entry_id)?;
+        self.move_to_trash(vec![(index, item)]);
+        Ok(())
+    }
+
+    fn remove_item_by_id(&mut self, entry_id: u64) -> CursorClipResult<(usize, ClipboardItem)> {
         let index = self
             .history
             .iter()
             .position(|item| item.item_id == entry_id)
-            .ok_or_else(|| format!("No clipboard item found with ID: {entry_id}"))?;
+            .ok_or(CursorClipError::ItemNotFound { id: entry_id })?;
 
-        self.history.remove(index);
+        let item = self.history.remove(index);
+        self.paste_queue.retain(|id| *id != entry_id);
 
-        if self.current_source_entry_id == Some(entry_id) {
-            if let Some(prev) = self.current_source_object.take() {
-                prev.destroy();
+        for seat in self.seats.values_mut() {
+            if seat.current_source_entry_id == Some(entry_id) {
+                seat.release_source();
             }
-            self.current_source_entry_id = None;
-            // We explicitly destroyed our own source, so the expected Cancelled
-            // event may no longer arrive to clear this flag.
-            self.suppress_next_selection_read = false;
         }
 
-        self.persist_history_if_enabled();
+        self.history_changed();
+        let _ = self
+            .events
+            .send(BackendMessage::ItemDeleted { id: entry_id });
 
-        Ok(())
+        Ok((index, item))
+    }
+
+    fn move_to_trash(&mut self, items: Vec<(usize, ClipboardItem)>) {
+        self.empty_expired_trash();
+        if self.config.trash_minutes == 0 || items.is_empty() {
+            return;
+        }
+        if self.trash.len() == TRASH_OPERATIONS {
+            self.trash.remove(0);
+        }
+        self.trash.push(TrashedItems {
+            deleted_at: Instant::now(),
+            items,
+        });
+    }
+
+    /// Drop trashed items older than `trash_minutes`. Called periodically by the daemon.
+    pub fn empty_expired_trash(&mut self) {
+        let keep_for = std::time::Duration::from_secs(self.config.trash_minutes.saturating_mul(60));
+        self.trash
+            .retain(|trashed| trashed.deleted_at.elapsed() < keep_for);
+    }
+
+    /// Put back the items of the most recent delete or Clear All still in the trash:
+    /// pinned items at their old places, the others where their last copy puts them.
+    /// Items that are back in the history meanwhile (re-imported, or the same content
+    /// copied again) are skipped, and the retention policy applies as to new copies.
+    /// Returns how many items were restored.
+    pub fn undo_last_operation(&mut self) -> CursorClipResult<usize> {
+        self.empty_expired_trash();
+        let trashed = self.trash.pop().ok_or_else(|| {
+            CursorClipError::InvalidRequest("nothing to undo; the trash is empty".to_string())
+        })?;
+
+        let mut put_back = Vec::new();
+        for (index, item) in trashed.items {
+            let hash = content_hash(&decompressed_payloads(&item));
+            let present = self.history.iter().any(|existing| {
+                existing.item_id == item.item_id
+                    || is_same_content(existing, &item, hash.as_deref())
+            });
+            if present {
+                continue;
+            }
+            let index = if item.pinned {
+                let pinned = self
+                    .history
+                    .iter()
+                    .take_while(|existing| existing.pinned)
+                    .count();
+                index.min(pinned)
+            } else {
+                self.unpinned_insert_index(item.last_copied_at())
+            };
+            put_back.push(item.item_id);
+            self.history.insert(index, item);
+        }
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.prune_history(now);
+        let present: HashSet<u64> = self.history.iter().map(|item| item.item_id).collect();
+        let restored = put_back.iter().filter(|id| present.contains(id)).count();
+        if !put_back.is_empty() {
+            self.history_changed();
+        }
+        info!("Restored {restored} items from the trash");
+        let _ = self
+            .events
+            .send(BackendMessage::ItemsRestored { count: restored });
+        Ok(restored)
+    }
+
+    /// The session was locked: clear the selection and/or delete passwords as set in
+    /// `[on_lock]`.
+    pub fn wipe_on_lock(&mut self) {
+        let on_lock = self.config.on_lock.clone();
+        if on_lock.purge_sensitive {
+            let passwords: Vec<u64> = self
+                .history
+                .iter()
+                .filter(|item| item.content_type == ClipboardContentType::Password)
+                .map(|item| item.item_id)
+                .collect();
+            for id in &passwords {
+                // Found just above; not kept in the trash
+                let _ = self.remove_item_by_id(*id);
+            }
+            for trashed in &mut self.trash {
+                trashed
+                    .items
+                    .retain(|(_, item)| item.content_type != ClipboardContentType::Password);
+            }
+            // Undo restores the latest operation that still has items
+            self.trash.retain(|trashed| !trashed.items.is_empty());
+            info!("Session locked: deleted {} password items", passwords.len());
+        }
+        if on_lock.clear_selection {
+            self.clear_selection();
+            info!("Session locked: cleared the selection");
+        }
     }
 
+    /// Empty the selection on every seat, whoever owns it, without re-offering the last copy.
+    fn clear_selection(&mut self) {
+        for seat in self.seats.values_mut() {
+            seat.release_source();
+            seat.pending_selection = None;
+            seat.last_recorded_entry_id = None;
+            if let Some(device) = &seat.data_control_device {
+                device.set_selection(None);
+            }
+        }
+        if let Some(conn) = &self.connection
+            && let Err(e) = conn.flush()
+        {
+            warn!("Failed to flush Wayland connection after clearing the selection: {e}");
+        }
+        if let Some(selection) = &self.x11_selection
+            && let Err(e) = selection.clear_selection()
+        {
+            warn!("Failed to clear the X11 clipboard: {e}");
+        }
+        if let Some(selection) = &self.portal_selection
+            && let Err(e) = selection.clear_selection()
+        {
+            warn!("Failed to clear the clipboard through the portal: {e}");
+        }
+    }
+
+    /// Write the history to `path`, an absolute path chosen by the client.
+    /// Returns how many items were exported.
+    pub fn export_history(
+        &self,
+        path: &str,
+        format: ExportFormat,
+        include_sensitive: bool,
+    ) -> CursorClipResult<usize> {
+        let path = export_path(path)?;
+        let history: Vec<ClipboardItem> = self
+            .history
+            .iter()
+            .map(|item| decompress_item(item).into_owned())
+            .collect();
+        let count = export_history(&history, path, format, include_sensitive)
+            .map_err(CursorClipError::ImportExport)?;
+        info!("Exported {count} items to {}", path.display());
+        Ok(count)
+    }
+
+    /// Merge an export into the history, each item at the place its timestamp puts it
+    /// and with its pinned state. Imported items go through the same checks as copies,
+    /// except that content already in the history is skipped rather than merged, as are
+    /// items over `max_item_bytes`. Returns (imported, skipped).
+    pub fn import_history(&mut self, path: &str) -> CursorClipResult<(usize, usize)> {
+        let path = export_path(path)?;
+        let items = read_export(path).map_err(CursorClipError::ImportExport)?;
+        let total = items.len();
+        let with_thumbnail = !self.battery_saving();
+
+        let mut imported = Vec::new();
+        for item in items {
+            let Some(mut entry) = Self::build_item(
+                self.id_for_next_entry,
+                item.mime_data,
+                item.timestamp,
+                item.seat,
+                with_thumbnail,
+            ) else {
+                continue;
+            };
+            if !matches!(self.admit_item(&mut entry), Admission::New) {
+                continue;
+            }
+            entry.pinned = item.pinned;
+            let index = self
+                .history
+                .iter()
+                .position(|existing| {
+                    if entry.pinned {
+                        !existing.pinned || existing.last_copied_at() <= entry.timestamp
+                    } else {
+                        !existing.pinned && existing.last_copied_at() <= entry.timestamp
+                    }
+                })
+                .unwrap_or(self.history.len());
+            imported.push(self.place_new_item(index, entry));
+            self.id_for_next_entry += 1;
+        }
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        self.prune_history(now);
+        self.history_changed();
+        // Announced like copies, except those the retention policy dropped right away
+        let present: HashSet<u64> = self.history.iter().map(|item| item.item_id).collect();
+        let count = imported.len();
+        for preview in imported {
+            if present.contains(&preview.item_id) {
+                let _ = self.events.send(BackendMessage::NewItem { item: preview });
+            }
+        }
+        info!("Imported {count} of {total} items from {}", path.display());
+        Ok((count, total - count))
+    }
+
+    /// App id of the focused window when it is on the `exclude_apps` deny list.
+    pub fn excluded_focused_app(&self) -> Option<String> {
+        let app_id = self.toplevel_tracker.focused_app_id()?;
+        app_id_matches(&self.config.exclude_apps, app_id).then(|| app_id.to_string())
+    }
+
+    /// Replace the retention policy and apply it right away. Returns the number of pruned items.
+    pub fn set_retention_policy(&mut self, policy: RetentionPolicy) -> usize {
+        info!("Retention policy changed to {policy:?}");
+        self.config.retention = policy;
+        self.enforce_retention()
+    }
+
+    /// Remember a failure for `Status`, so users can see what went wrong last.
+    pub fn record_error(&mut self, message: impl Into<String>) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.last_error = Some((timestamp, message.into()));
+    }
+
+    pub fn status(&self) -> DaemonStatus {
+        DaemonStatus {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            pid: std::process::id(),
+            uptime_secs: self.started_at.elapsed().as_secs(),
+            protocol: self
+                .data_control_manager
+                .as_ref()
+                .map(|manager| manager.protocol_name().to_string())
+                .or_else(|| {
+                    self.x11_selection
+                        .as_ref()
+                        .map(|_| X11_PROTOCOL_NAME.to_string())
+                })
+                .or_else(|| {
+                    self.portal_selection
+                        .as_ref()
+                        .map(|_| PORTAL_PROTOCOL_NAME.to_string())
+                }),
+            protocol_version: self
+                .data_control_manager
+                .as_ref()
+                .map(DataControlManager::version),
+            monitor_state: self.monitor_state,
+            monitor_only: self.monitor_only,
+            recording_enabled: self.recording_enabled,
+            persistence_enabled: self.persistence_enabled,
+            seats: self.seats.values().map(|seat| seat.name.clone()).collect(),
+            stats: self.stats(),
+            last_error: self.last_error.clone(),
+            power: PowerStatus {
+                on_battery: self.power.on_battery,
+                profile: self.power.profile.clone(),
+                saving: self.battery_saving(),
+            },
+        }
+    }
+
+    /// `GetStats`: history counts plus copies per local hour of the day.
+    pub fn stats(&self) -> HistoryStats {
+        let mut stats = HistoryStats::from_history(&self.history);
+        for item in &self.history {
+            let hour = glib::DateTime::from_unix_local(item.timestamp as i64)
+                .map(|time| time.hour() as usize);
+            if let Ok(hour) = hour
+                && let Some(copies) = stats.copies_by_hour.get_mut(hour)
+            {
+                *copies += 1;
+            }
+        }
+        stats
+    }
+
+    pub fn daemon_settings(&self) -> DaemonSettings {
+        DaemonSettings {
+            monitor_only: self.monitor_only,
+            persist_on_exit: self.config.persist_on_exit,
+            retention: self.config.retention,
+            exclude_apps: self.config.exclude_apps.clone(),
+        }
+    }
+
+    /// Apply new runtime settings and write them to config.toml (except in demo mode).
+    pub fn apply_daemon_settings(&mut self, settings: DaemonSettings) -> CursorClipResult<()> {
+        if settings.monitor_only != self.monitor_only {
+            self.set_monitor_only(settings.monitor_only);
+        }
+        if settings.persist_on_exit != self.config.persist_on_exit {
+            self.set_persist_on_exit(settings.persist_on_exit);
+        }
+        if settings.exclude_apps != self.config.exclude_apps {
+            self.set_excluded_apps(settings.exclude_apps.clone());
+        }
+        if settings.retention != self.config.retention {
+            self.set_retention_policy(settings.retention);
+        }
+        if self.demo_mode {
+            return Ok(());
+        }
+        save_daemon_settings(&settings).map_err(CursorClipError::Config)
+    }
+
+    /// Switch between recording only and re-owning copied selections.
+    pub fn set_monitor_only(&mut self, enabled: bool) {
+        info!(
+            "Monitor-only mode {}",
+            if enabled { "enabled" } else { "disabled" }
+        );
+        self.monitor_only = enabled;
+    }
+
+    /// Pause or resume recording of new selections.
+    pub fn set_recording_enabled(&mut self, enabled: bool) {
+        info!("Recording {}", if enabled { "resumed" } else { "paused" });
+        self.recording_enabled = enabled;
+        // Nobody listening is fine; send only fails without subscribers
+        let _ = self.events.send(BackendMessage::RecordingState { enabled });
+    }
+
+    /// In monitor-only mode, re-offer a copy once the app that owned it goes away.
+    pub fn set_persist_on_exit(&mut self, enabled: bool) {
+        info!(
+            "Persist-on-exit {}",
+            if enabled { "enabled" } else { "disabled" }
+        );
+        self.config.persist_on_exit = enabled;
+    }
+
+    /// Replace the `exclude_apps` deny list; applies to the next copy.
+    pub fn set_excluded_apps(&mut self, apps: Vec<String>) {
+        info!("Excluded apps changed to {apps:?}");
+        self.config.exclude_apps = apps;
+    }
+
+    /// The snippet library, re-read from config.toml so hand edits apply without a restart.
+    pub fn snippets(&mut self) -> Vec<Snippet> {
+        self.config.snippets = load_backend_config().snippets;
+        self.config.snippets.clone()
+    }
+
+    pub fn add_snippet(&mut self, snippet: Snippet) -> CursorClipResult<Vec<Snippet>> {
+        if snippet.name.trim().is_empty() || snippet.text.is_empty() {
+            return Err(CursorClipError::InvalidRequest(
+                "Snippets need a name and text".into(),
+            ));
+        }
+        let mut snippets = self.snippets();
+        snippets.push(snippet);
+        self.store_snippets(snippets)
+    }
+
+    pub fn remove_snippet(&mut self, id: usize) -> CursorClipResult<Vec<Snippet>> {
+        let mut snippets = self.snippets();
+        if id >= snippets.len() {
+            return Err(CursorClipError::InvalidRequest(format!(
+                "No snippet at position {id}"
+            )));
+        }
+        snippets.remove(id);
+        self.store_snippets(snippets)
+    }
+
+    fn store_snippets(&mut self, snippets: Vec<Snippet>) -> CursorClipResult<Vec<Snippet>> {
+        if !self.demo_mode {
+            save_snippets(&snippets).map_err(CursorClipError::Config)?;
+        }
+        self.config.snippets = snippets;
+        Ok(self.config.snippets.clone())
+    }
+
+    /// Expand a snippet's placeholders, record the result as a history entry and set
+    /// it as the selection. `{clipboard}` is the text of the newest history item.
+    pub fn paste_snippet(
+        &mut self,
+        id: usize,
+        instant_paste: bool,
+        seat: Option<&str>,
+    ) -> CursorClipResult<()> {
+        let snippet = self.snippets().get(id).cloned().ok_or_else(|| {
+            CursorClipError::InvalidRequest(format!("No snippet at position {id}"))
+        })?;
+        let clipboard = self
+            .history
+            .iter()
+            .max_by_key(|item| item.last_copied_at())
+            .and_then(|item| plain_text_payload(&decompressed_payloads(item)))
+            .and_then(|payload| payload.get(PLAIN_TEXT_MIME).cloned())
+            .map(|text| String::from_utf8_lossy(&text).into_owned());
+        let text = expand_placeholders(&snippet.text, clipboard.as_deref());
+
+        let new_id = self.insert_text_item(&text, seat)?;
+        info!("Pasting snippet {:?} as item {new_id}", snippet.name);
+
+        self.set_clipboard_by_id(new_id, instant_paste, seat, None)
+    }
+
+    /// Record a picked emoji as a history entry and set it as the selection.
+    pub fn paste_emoji(
+        &mut self,
+        emoji: &str,
+        instant_paste: bool,
+        seat: Option<&str>,
+    ) -> CursorClipResult<()> {
+        if emojis::get(emoji).is_none() {
+            return Err(CursorClipError::InvalidRequest(format!(
+                "{emoji:?} is not an emoji"
+            )));
+        }
+        let new_id = self.insert_text_item(emoji, seat)?;
+        info!("Pasting emoji {emoji} as item {new_id}");
+
+        self.set_clipboard_by_id(new_id, instant_paste, seat, None)
+    }
+
+    /// Recently used emoji, most recent first: this session's, then single-emoji items
+    /// still in the history (e.g. from earlier sessions).
+    pub fn recent_emoji(&self) -> Vec<String> {
+        let mut history_emoji: Vec<(u64, &str)> = self
+            .history
+            .iter()
+            .filter_map(|item| {
+                let emoji = emojis::get(item.content_preview.trim())?;
+                Some((item.timestamp, emoji.as_str()))
+            })
+            .collect();
+        history_emoji.sort_by_key(|(timestamp, _)| std::cmp::Reverse(*timestamp));
+
+        let mut recent: Vec<String> = self.recent_emoji.iter().cloned().collect();
+        for (_, emoji) in history_emoji {
+            if recent.len() >= MAX_RECENT_EMOJI {
+                break;
+            }
+            if !recent.iter().any(|known| known == emoji) {
+                recent.push(emoji.to_string());
+            }
+        }
+        recent
+    }
+
+    fn note_emoji_use(&mut self, emoji: &str) {
+        self.recent_emoji.retain(|known| known != emoji);
+        self.recent_emoji.push_front(emoji.to_string());
+        self.recent_emoji.truncate(MAX_RECENT_EMOJI);
+    }
+
+    /// Record `text` as a new history entry, as if copied on `seat`. Returns its ID.
+    fn insert_text_item(&mut self, text: &str, seat: Option<&str>) -> CursorClipResult<u64> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let item = Self::build_item(
+            self.id_for_next_entry,
+            text_mime_content(text),
+            timestamp,
+            seat.map(str::to_string),
+            false,
+        )
+        .ok_or_else(|| CursorClipError::UnsupportedContent("Text is empty".into()))?;
+        self.insert_item(item).ok_or_else(|| {
+            CursorClipError::UnsupportedContent("Text exceeds max_item_bytes".into())
+        })
+    }
+
+    /// Prune the history according to the retention policy and persist the result.
+    /// Called periodically by the daemon so age limits apply without new copies.
+    pub fn enforce_retention(&mut self) -> usize {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let pruned = self.prune_history(now);
+        if pruned > 0 {
+            debug!("Retention policy pruned {pruned} items");
+            self.history_changed();
+        }
+        pruned
+    }
+
+    /// Drop unpinned items that violate the retention policy, oldest first.
+    /// Does not persist; callers do that once they are done mutating the history.
+    fn prune_history(&mut self, now: u64) -> usize {
+        let policy = self.config.retention;
+        let mut removed = Vec::new();
+
+        let max_age_secs = policy.max_age_days.saturating_mul(86400);
+        self.history.retain(|item| {
+            let expired =
+                max_age_secs != 0 && now.saturating_sub(item.last_copied_at()) > max_age_secs;
+            let oversized =
+                policy.max_item_bytes != 0 && item.payload_size() > policy.max_item_bytes;
+            let keep = item.pinned || !(expired || oversized);
+            if !keep {
+                removed.push(item.item_id);
+            }
+            keep
+        });
+
+        // Unpinned items are ordered newest first, so the last unpinned one is the oldest
+        let mut total_bytes: u64 = self.history.iter().map(ClipboardItem::payload_size).sum();
+        loop {
+            let over_count = policy.max_items != 0 && self.history.len() > policy.max_items;
+            let over_bytes = policy.max_total_bytes != 0 && total_bytes > policy.max_total_bytes;
+            if !(over_count || over_bytes) {
+                break;
+            }
+            let Some(index) = self.history.iter().rposition(|item| !item.pinned) else {
+                break;
+            };
+            let item = self.history.remove(index);
+            total_bytes -= item.payload_size();
+            removed.push(item.item_id);
+        }
+
+        for id in &removed {
+            for seat in self.seats.values_mut() {
+                if seat.current_source_entry_id == Some(*id) {
+                    seat.release_source();
+                }
+            }
+            let _ = self.events.send(BackendMessage::ItemDeleted { id: *id });
+        }
+        removed.len()
+    }
+
+    /// Set the selection to a history entry. Without an explicit `seat` name the
+    /// item's originating seat is used, falling back to the first known seat.
     pub fn set_clipboard_by_id(
         &mut self,
         entry_id: u64,
         instant_paste: bool,
-    ) -> Result<(), String> {
+        seat: Option<&str>,
+        mime_filter: Option<MimeFilter>,
+    ) -> CursorClipResult<()> {
+        let item = self
+            .get_item_by_id(entry_id)
+            .ok_or(CursorClipError::ItemNotFound { id: entry_id })?;
+        let item_seat = item.seat.clone();
+
+        // Without Wayland data-control the X11 clipboard is the only one we can set
+        if self.seats.is_empty()
+            && let Some(selection) = &self.x11_selection
+        {
+            let payload = filtered_payload(&item, mime_filter)?
+                .unwrap_or_else(|| with_synthesized_text(item.mime_data));
+            selection
+                .set_selection(payload)
+                .map_err(CursorClipError::SeatUnavailable)?;
+            info!("Set X11 clipboard content by ID {entry_id}");
+            self.record_paste(entry_id);
+            if instant_paste {
+                warn!("Instant paste needs a Wayland virtual keyboard; paste manually on X11");
+            }
+            return Ok(());
+        }
+        // Likewise the portal's clipboard inside a sandbox
+        if self.seats.is_empty()
+            && let Some(selection) = &self.portal_selection
+        {
+            let payload = filtered_payload(&item, mime_filter)?
+                .unwrap_or_else(|| with_synthesized_text(item.mime_data));
+            selection
+                .set_selection(payload)
+                .map_err(CursorClipError::SeatUnavailable)?;
+            info!("Set clipboard content through the portal by ID {entry_id}");
+            self.record_paste(entry_id);
+            if instant_paste {
+                warn!("Instant paste needs a Wayland virtual keyboard; paste manually");
+            }
+            return Ok(());
+        }
+
+        let seat_key = match seat {
+            Some(name) => self
+                .seat_key_by_name(name)
+                .ok_or_else(|| CursorClipError::SeatUnavailable(format!("Unknown seat: {name}")))?,
+            None => item_seat
+                .as_deref()
+                .and_then(|name| self.seat_key_by_name(name))
+                .or_else(|| self.seats.keys().next().copied())
+                .ok_or_else(|| {
+                    CursorClipError::SeatUnavailable("No Wayland seat available yet".into())
+                })?,
+        };
+
+        self.set_selection_on_seat(entry_id, seat_key, mime_filter)?;
+        self.record_paste(entry_id);
+        let serial = self.selection_serial.load(Ordering::SeqCst);
+
+        if instant_paste {
+            info!("Instant paste via virtual keyboard shortcut for ID {entry_id}");
+            let selection_serial = self.selection_serial.clone();
+            std::thread::spawn(move || {
+                // Give the overlay a brief moment to close so shortcut targets the previous app.
+                std::thread::sleep(std::time::Duration::from_millis(150));
+                // Another client set a different item meanwhile; pasting now would paste that
+                if selection_serial.load(Ordering::SeqCst) != serial {
+                    info!("Skipping instant paste of ID {entry_id}; the selection changed since");
+                    return;
+                }
+                if let Err(e) = paste_via_virtual_keyboard_shortcut() {
+                    warn!("Instant paste failed: {e}");
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Offer a history entry as the primary selection (pasted with a middle click) on
+    /// `seat`, or on the seat it was copied on. Wayland only.
+    pub fn set_primary_by_id(&mut self, entry_id: u64, seat: Option<&str>) -> CursorClipResult<()> {
         let item = self
             .get_item_by_id(entry_id)
-            .ok_or_else(|| format!("No clipboard item found with ID: {entry_id}"))?;
+            .ok_or(CursorClipError::ItemNotFound { id: entry_id })?;
+        let seat_key = match seat {
+            Some(name) => self
+                .seat_key_by_name(name)
+                .ok_or_else(|| CursorClipError::SeatUnavailable(format!("Unknown seat: {name}")))?,
+            None => item
+                .seat
+                .as_deref()
+                .and_then(|name| self.seat_key_by_name(name))
+                .or_else(|| self.seats.keys().next().copied())
+                .ok_or_else(|| {
+                    CursorClipError::SeatUnavailable(
+                        "The primary selection needs a Wayland seat".into(),
+                    )
+                })?,
+        };
 
-        info!("Setting clipboard content by ID {entry_id}");
+        let (Some(manager), Some(qh)) = (&self.data_control_manager, &self.qh) else {
+            return Err(CursorClipError::SeatUnavailable(
+                "Wayland clipboard objects not available yet".into(),
+            ));
+        };
+        if !manager.supports_primary_selection() {
+            return Err(CursorClipError::SeatUnavailable(format!(
+                "The compositor offers {} v{}, which has no primary selection",
+                manager.protocol_name(),
+                manager.version()
+            )));
+        }
+        let seat = self.seats.get_mut(&seat_key).ok_or_else(|| {
+            CursorClipError::SeatUnavailable(format!("Seat {seat_key} is no longer available"))
+        })?;
+        let Some(device) = &seat.data_control_device else {
+            return Err(CursorClipError::SeatUnavailable(format!(
+                "No data control device for seat {}",
+                seat.name
+            )));
+        };
 
-        let (Some(manager), Some(device), Some(qh)) = (
-            &self.data_control_manager,
-            &self.data_control_device,
-            &self.qh,
-        ) else {
-            return Err("Wayland clipboard objects not available yet".into());
+        info!(
+            "Setting primary selection by ID {entry_id} on {}",
+            seat.name
+        );
+        let source = manager.create_data_source(qh);
+        for mime in item.mime_data.keys() {
+            source.offer(mime.clone());
+        }
+        if needs_synthesized_text(&item.mime_data) {
+            for mime in SYNTHESIZED_TEXT_MIMES {
+                source.offer(mime.to_string());
+            }
+        }
+        device.set_primary_selection(Some(&source));
+        seat.release_primary_source();
+        seat.primary_source_object = Some(source);
+        seat.primary_source_entry_id = Some(entry_id);
+        if let Some(conn) = &self.connection
+            && let Err(e) = conn.flush()
+        {
+            warn!("Failed to flush Wayland connection after setting primary selection: {e}");
+        }
+        self.record_paste(entry_id);
+        Ok(())
+    }
+
+    /// Append items to the paste queue (stack mode). If the queue was empty, its new
+    /// front becomes the selection. Returns the queue.
+    pub fn push_paste_queue(
+        &mut self,
+        ids: &[u64],
+        seat: Option<&str>,
+    ) -> CursorClipResult<Vec<u64>> {
+        if let Some(&id) = ids
+            .iter()
+            .find(|id| !self.history.iter().any(|item| item.item_id == **id))
+        {
+            return Err(CursorClipError::ItemNotFound { id });
+        }
+        let was_empty = self.paste_queue.is_empty();
+        self.paste_queue.extend(ids);
+        if was_empty {
+            self.select_paste_queue_front(false, seat)?;
+        }
+        info!("Paste queue holds {} items", self.paste_queue.len());
+        Ok(self.paste_queue.iter().copied().collect())
+    }
+
+    /// Drop the queue's front without pasting it and make the next item the selection,
+    /// pasting it right away with `instant_paste`. Returns the remaining queue.
+    pub fn pop_paste_queue(
+        &mut self,
+        instant_paste: bool,
+        seat: Option<&str>,
+    ) -> CursorClipResult<Vec<u64>> {
+        if let Some(id) = self.paste_queue.pop_front() {
+            debug!("Skipped item {id} in the paste queue");
+        }
+        self.select_paste_queue_front(instant_paste, seat)?;
+        Ok(self.paste_queue.iter().copied().collect())
+    }
+
+    /// Called shortly after the queue's front was pasted: move on to the next item.
+    pub fn advance_paste_queue(&mut self, pasted_id: u64, seat_key: u32) {
+        self.paste_queue_advancing = false;
+        if self.paste_queue.front() != Some(&pasted_id) {
+            return;
+        }
+        self.paste_queue.pop_front();
+        let seat = self.seats.get(&seat_key).map(|seat| seat.name.clone());
+        match self.select_paste_queue_front(false, seat.as_deref()) {
+            Ok(()) => info!(
+                "Pasted queued item {pasted_id}; {} left",
+                self.paste_queue.len()
+            ),
+            Err(e) => warn!("Failed to select the next queued item: {e}"),
+        }
+    }
+
+    /// Set the queue's front as the selection, dropping items deleted in the meantime.
+    fn select_paste_queue_front(
+        &mut self,
+        instant_paste: bool,
+        seat: Option<&str>,
+    ) -> CursorClipResult<()> {
+        while let Some(&id) = self.paste_queue.front() {
+            match self.set_clipboard_by_id(id, instant_paste, seat, None) {
+                Err(CursorClipError::ItemNotFound { .. }) => {
+                    self.paste_queue.pop_front();
+                }
+                result => return result,
+            }
+        }
+        Ok(())
+    }
+
+    /// Count a paste from the history for `GetStats` and the frecency order.
+    fn record_paste(&mut self, entry_id: u64) {
+        if let Some(item) = self
+            .history
+            .iter_mut()
+            .find(|item| item.item_id == entry_id)
+        {
+            item.paste_count = item.paste_count.saturating_add(1);
+            item.last_pasted = Some(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs(),
+            );
+            self.history_changed();
+        }
+    }
+
+    /// Offer a history entry as the selection of one specific seat.
+    pub fn set_selection_on_seat(
+        &mut self,
+        entry_id: u64,
+        seat_key: u32,
+        mime_filter: Option<MimeFilter>,
+    ) -> CursorClipResult<()> {
+        let item = self
+            .get_item_by_id(entry_id)
+            .ok_or(CursorClipError::ItemNotFound { id: entry_id })?;
+        let payload = filtered_payload(&item, mime_filter)?;
+
+        let (Some(manager), Some(qh)) = (&self.data_control_manager, &self.qh) else {
+            return Err(CursorClipError::SeatUnavailable(
+                "Wayland clipboard objects not available yet".into(),
+            ));
         };
+        let Some(seat) = self.seats.get_mut(&seat_key) else {
+            return Err(CursorClipError::SeatUnavailable(format!(
+                "Seat {seat_key} is no longer available"
+            )));
+        };
+        let Some(device) = &seat.data_control_device else {
+            return Err(CursorClipError::SeatUnavailable(format!(
+                "No data control device for seat {}",
+                seat.name
+            )));
+        };
+
+        info!(
+            "Setting clipboard content by ID {entry_id} on {}",
+            seat.name
+        );
 
         // Clean up any previously set source that we own
-        if let Some(prev) = self.current_source_object.take() {
+        if let Some(prev) = seat.current_source_object.take() {
             prev.destroy();
         }
 
+        // From here on we are the only holder of this data
+        if self.config.crash_backup && !self.demo_mode {
+            let backup = SelectionBackup::new(
+                Some(seat.name.clone()),
+                item.timestamp,
+                payload.as_ref().unwrap_or(&item.mime_data),
+            );
+            if let Err(e) = crash_backup::save(&seat.name, &backup) {
+                warn!("Failed to write selection backup: {e}");
+            }
+        }
+        self.recovered_item = None;
+
         let source = manager.create_data_source(qh);
-        for mime in item.mime_data.keys() {
+        for mime in payload.as_ref().unwrap_or(&item.mime_data).keys() {
             source.offer(mime.clone());
         }
+        // Converted from the HTML when a paste target asks for plain text
+        if payload.is_none() && needs_synthesized_text(&item.mime_data) {
+            for mime in SYNTHESIZED_TEXT_MIMES {
+                source.offer(mime.to_string());
+            }
+        }
+        // Formats the source app still holds for this entry, forwarded on paste
+        if payload.is_none()
+            && let Some((lazy_entry_id, lazy_offer)) = &seat.lazy_offer
+            && *lazy_entry_id == entry_id
+        {
+            for mime in &lazy_offer.mime_types {
+                source.offer(mime.clone());
+            }
+        }
         device.set_selection(Some(&source));
-        self.current_source_object = Some(source);
-        self.current_source_entry_id = Some(entry_id);
+        seat.current_source_object = Some(source);
+        seat.current_source_entry_id = Some(entry_id);
+        seat.current_source_payload = payload;
         // Prevent reading back our own just-set selection (would deadlock due to event queue handling)
-        self.suppress_next_selection_read = true;
+        seat.suppress_next_selection_read = true;
         // Flush the Wayland connection so the compositor sees our selection (very important)
         if let Some(conn) = &self.connection
             && let Err(e) = conn.flush()
@@ -417,27 +2289,147 @@ impl BackendState {
             warn!("Failed to flush Wayland connection after setting selection: {e}");
         }
         debug!("Created clipboard source and set selection (id {entry_id})");
+        self.selection_serial.fetch_add(1, Ordering::SeqCst);
 
-        if instant_paste {
-            info!("Instant paste via virtual keyboard shortcut for ID {entry_id}");
-            std::thread::spawn(move || {
-                // Give the overlay a brief moment to close so shortcut targets the previous app.
-                std::thread::sleep(std::time::Duration::from_millis(150));
-                if let Err(e) = paste_via_virtual_keyboard_shortcut() {
-                    warn!("Instant paste failed: {e}");
-                }
-            });
+        Ok(())
+    }
+
+    /// Install a (re-)bound data-control manager and create a device for every seat.
+    pub fn attach_data_control_manager(&mut self, manager: DataControlManager, global_name: u32) {
+        self.detach_data_control_manager();
+        if let Some(qh) = &self.qh {
+            for (seat_key, seat) in &mut self.seats {
+                seat.data_control_device = Some(manager.get_data_device(&seat.seat, *seat_key, qh));
+            }
+        }
+        self.data_control_manager = Some(manager);
+        self.data_control_global = Some(global_name);
+        self.update_monitor_state();
+    }
+
+    /// Tear down the data-control manager together with every device and source created from it.
+    pub fn detach_data_control_manager(&mut self) {
+        for seat in self.seats.values_mut() {
+            seat.release_source();
+            seat.current_data_offer = None;
+            if let Some(device) = seat.data_control_device.take() {
+                device.destroy();
+            }
+        }
+        if let Some(manager) = self.data_control_manager.take() {
+            manager.destroy();
         }
+        self.data_control_global = None;
+        self.mime_type_offers.clear();
+    }
 
-        Ok(())
+    /// Drop every Wayland object after the compositor connection was lost; the monitor
+    /// creates them again when it reconnects. Returns the item the clipboard was offering.
+    pub fn release_wayland_connection(&mut self) -> Option<u64> {
+        let offered_entry_id = self
+            .seats
+            .values()
+            .find_map(|seat| seat.current_source_entry_id);
+        self.detach_data_control_manager();
+        self.seats.clear(); // wl_seat proxies of a dead connection need no release
+        self.toplevel_tracker = ToplevelTracker::default();
+        self.idle_tracker = IdleTracker::default();
+        self.qh = None;
+        self.connection = None;
+        self.update_monitor_state();
+        offered_entry_id
+    }
+
+    pub fn add_seat(&mut self, global_name: u32, seat: wl_seat::WlSeat) {
+        let mut seat_state = SeatState::new(global_name, seat);
+        if let (Some(manager), Some(qh)) = (&self.data_control_manager, &self.qh) {
+            seat_state.data_control_device =
+                Some(manager.get_data_device(&seat_state.seat, global_name, qh));
+        }
+        self.seats.insert(global_name, seat_state);
+        self.update_monitor_state();
+    }
+
+    /// Forget a seat the compositor removed. Returns false if `global_name` was not a seat.
+    pub fn remove_seat(&mut self, global_name: u32) -> bool {
+        let Some(mut seat) = self.seats.shift_remove(&global_name) else {
+            return false;
+        };
+        seat.release_source();
+        if let Some(device) = seat.data_control_device.take() {
+            device.destroy();
+        }
+        if seat.seat.version() >= 5 {
+            seat.seat.release();
+        }
+        self.update_monitor_state();
+        true
     }
 
-    pub fn set_pinned(&mut self, entry_id: u64, pinned: bool) -> Result<(), String> {
+    /// Recompute whether monitoring is functional and broadcast transitions.
+    pub fn update_monitor_state(&mut self) {
+        let (monitor_state, reason) = if self.connection.is_none() {
+            (
+                MonitorState::Degraded,
+                "Lost the connection to the Wayland compositor; reconnecting",
+            )
+        } else if self.data_control_manager.is_none() {
+            (
+                MonitorState::Degraded,
+                "Compositor does not currently advertise a data-control manager",
+            )
+        } else if self.seats.is_empty() {
+            (
+                MonitorState::Degraded,
+                "Compositor does not currently advertise a wl_seat",
+            )
+        } else {
+            (MonitorState::Active, "Clipboard monitoring active")
+        };
+
+        if monitor_state == self.monitor_state {
+            return;
+        }
+        match monitor_state {
+            MonitorState::Active => info!("{reason}"),
+            MonitorState::Degraded => {
+                warn!("Clipboard monitoring degraded: {reason}");
+                self.record_error(format!("Clipboard monitoring degraded: {reason}"));
+            }
+        }
+        self.monitor_state = monitor_state;
+        let _ = self.events.send(BackendMessage::MonitorStateChanged {
+            state: monitor_state,
+            reason: reason.to_string(),
+        });
+    }
+
+    pub fn seat_key_by_name(&self, name: &str) -> Option<u32> {
+        self.seats
+            .iter()
+            .find(|(_, seat)| seat.name == name)
+            .map(|(key, _)| *key)
+    }
+
+    /// Find the seat whose currently owned selection source has the given id.
+    pub fn seat_key_for_source(&self, source_id: &ObjectId) -> Option<u32> {
+        self.seats
+            .iter()
+            .find(|(_, seat)| {
+                seat.current_source_object
+                    .as_ref()
+                    .is_some_and(|source| &source.id() == source_id)
+                    || seat.is_primary_source(source_id)
+            })
+            .map(|(key, _)| *key)
+    }
+
+    pub fn set_pinned(&mut self, entry_id: u64, pinned: bool) -> CursorClipResult<()> {
         let index = self
             .history
             .iter()
             .position(|item| item.item_id == entry_id)
-            .ok_or_else(|| format!("No clipboard item found with ID: {entry_id}"))?;
+            .ok_or(CursorClipError::ItemNotFound { id: entry_id })?;
 
         let mut item = self.history.remove(index);
         item.pinned = pinned;
@@ -452,11 +2444,25 @@ impl BackendState {
         };
 
         self.history.insert(insert_index, item);
-        self.persist_history_if_enabled();
+        self.history_changed();
+        let _ = self.events.send(BackendMessage::ItemPinned {
+            id: entry_id,
+            pinned,
+        });
         Ok(())
     }
 
-    pub fn set_persistence_enabled(&mut self, enabled: bool) -> Result<(), String> {
+    pub fn set_persistence_enabled(&mut self, enabled: bool) -> CursorClipResult<()> {
+        if enabled && self.demo_mode {
+            return Err(CursorClipError::InvalidRequest(
+                "Persistent history is unavailable in demo mode".to_string(),
+            ));
+        }
+        self.switch_persistence(enabled)
+            .map_err(CursorClipError::Persistence)
+    }
+
+    fn switch_persistence(&mut self, enabled: bool) -> Result<(), String> {
         if enabled {
             if self.persistence.is_none() {
                 if self.db_password.is_none() {
@@ -491,12 +2497,23 @@ impl BackendState {
                         .max()
                         .unwrap_or(0)
                         .saturating_add(1);
-                    self.history = loaded;
+                    let threshold = self.config.compress_payloads_over_bytes;
+                    self.history = loaded
+                        .into_iter()
+                        .map(|mut item| {
+                            compress_payloads(&mut item, threshold);
+                            item
+                        })
+                        .collect();
+                    self.bump_history_revision();
                 }
             } else {
                 self.persist_history_if_enabled();
             }
         } else {
+            // Leave the database as current as it would have been without batching
+            self.flush_history();
+            self.sync_history();
             self.persistence_enabled = false;
             self.persistence = None;
         }
@@ -504,15 +2521,354 @@ impl BackendState {
         Ok(())
     }
 
-    fn persist_history_if_enabled(&self) {
+    /// Record a change to `history`: bump the revision, tell subscribers to reload and
+    /// save the history. Removed items are written out right away so they don't linger
+    /// on disk; other changes are batched (`persistence_write_delay_ms`).
+    fn history_changed(&mut self) {
+        if self.bump_history_revision() {
+            self.write_history_if_enabled();
+        } else {
+            self.persist_history_if_enabled();
+        }
+    }
+
+    /// Returns whether the new revision removed items.
+    fn bump_history_revision(&mut self) -> bool {
+        self.history_revision += 1;
+
+        let fingerprints: HashMap<u64, u64> = self
+            .history
+            .iter()
+            .map(|item| (item.item_id, preview_fingerprint(item)))
+            .collect();
+        let changed = self
+            .history
+            .iter()
+            .map(|item| item.item_id)
+            .filter(|id| self.preview_fingerprints.get(id) != fingerprints.get(id))
+            .collect();
+        let removed: Vec<u64> = self
+            .preview_fingerprints
+            .keys()
+            .filter(|id| !fingerprints.contains_key(id))
+            .copied()
+            .collect();
+        let removed_items = !removed.is_empty();
+        self.preview_fingerprints = fingerprints;
+        self.revision_log.push_back(RevisionChanges {
+            revision: self.history_revision,
+            changed,
+            removed,
+        });
+        if self.revision_log.len() > REVISION_LOG_LEN {
+            self.revision_log.pop_front();
+        }
+
+        let _ = self.events.send(BackendMessage::HistoryChanged {
+            revision: self.history_revision,
+        });
+        removed_items
+    }
+
+    /// Save the history, or with a write delay mark it for the next batched write.
+    fn persist_history_if_enabled(&mut self) {
         if !self.persistence_enabled {
             return;
         }
+        if self.config.persistence_write_delay_ms == 0 {
+            self.write_history_if_enabled();
+        } else if self.history_dirty_since.is_none() {
+            self.history_dirty_since = Some(Instant::now());
+            self.persistence_wakeup.notify_one();
+        }
+    }
+
+    /// How long the first unsaved change waits for more before they are written together.
+    pub fn persistence_write_delay(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.config.persistence_write_delay_ms)
+    }
+
+    /// Write pending batched changes now (on the batch timer and at shutdown).
+    pub fn flush_history(&mut self) {
+        if self.history_dirty_since.is_some() {
+            self.write_history_if_enabled();
+        }
+    }
 
+    /// How often written changes are flushed to disk (`persistence_sync_interval_secs`).
+    pub fn persistence_sync_interval(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(self.config.persistence_sync_interval_secs.max(1))
+    }
+
+    /// Flush written changes to disk now (periodically and at shutdown).
+    pub fn sync_history(&mut self) {
+        if !std::mem::take(&mut self.history_unsynced) {
+            return;
+        }
         if let Some(persistence) = &self.persistence
-            && let Err(e) = persistence.save_history(&self.history)
+            && let Err(e) = persistence.sync_to_disk()
         {
+            warn_persistence_sync_error("sync", &e);
+        }
+    }
+
+    fn write_history_if_enabled(&mut self) {
+        self.history_dirty_since = None;
+        if !self.persistence_enabled {
+            return;
+        }
+
+        // The database keeps plain payloads; text deltas are computed on them
+        let history: Vec<ClipboardItem> = self
+            .history
+            .iter()
+            .map(|item| decompress_item(item).into_owned())
+            .collect();
+        let Some(persistence) = &self.persistence else {
+            return;
+        };
+        if let Err(e) = persistence.save_history(&history) {
             warn_persistence_sync_error("save", &e);
+            return;
+        }
+        self.history_unsynced = true;
+        if self.config.persistence_sync_interval_secs == 0 {
+            self.sync_history();
+        }
+    }
+}
+
+/// Hash of what clients are shown of an item; it differs once any of that changed.
+fn preview_fingerprint(item: &ClipboardItem) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    (
+        &item.content_preview,
+        item.content_type.as_str(),
+        item.timestamp,
+        item.pinned,
+        item.thumbnail.is_some(),
+        &item.files,
+        &item.language,
+        item.times_copied,
+        item.last_copied,
+        item.paste_count,
+        item.last_pasted,
+    )
+        .hash(&mut hasher);
+    hasher.finish()
+}
+
+/// The daemon does not share the client's working directory, so paths must be absolute.
+fn export_path(path: &str) -> CursorClipResult<&Path> {
+    let path = Path::new(path);
+    if path.is_absolute() {
+        Ok(path)
+    } else {
+        Err(CursorClipError::InvalidRequest(format!(
+            "Export path must be absolute: {}",
+            path.display()
+        )))
+    }
+}
+
+/// The payload `mime_filter` asks for, or None to offer the item as copied.
+fn filtered_payload(
+    item: &ClipboardItem,
+    mime_filter: Option<MimeFilter>,
+) -> CursorClipResult<Option<IndexMap<String, Bytes>>> {
+    let entry_id = item.item_id;
+    Ok(match mime_filter {
+        Some(MimeFilter::PlainText) => {
+            Some(plain_text_payload(&item.mime_data).ok_or_else(|| {
+                CursorClipError::UnsupportedContent(format!(
+                    "Clipboard item {entry_id} has no text content"
+                ))
+            })?)
+        }
+        Some(MimeFilter::PathsAsText) => Some(paths_payload(&item.files).ok_or_else(|| {
+            CursorClipError::UnsupportedContent(format!(
+                "Clipboard item {entry_id} is not a file copy"
+            ))
+        })?),
+        None => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::persistence::BackendConfig;
+
+    fn test_state() -> BackendState {
+        BackendState::with_history(false, None, BackendConfig::default())
+    }
+
+    fn text(text: &str) -> IndexMap<String, Bytes> {
+        IndexMap::from([(PLAIN_TEXT_MIME.to_string(), Bytes::from(text.to_string()))])
+    }
+
+    fn unix_now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs()
+    }
+
+    fn history_texts(state: &BackendState) -> Vec<String> {
+        state
+            .history
+            .iter()
+            .map(|item| {
+                let payloads = decompressed_payloads(item);
+                String::from_utf8(payloads[PLAIN_TEXT_MIME].to_vec()).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn import_places_items_by_time_and_skips_content_already_present() {
+        // Longer than a preview, so only the endings tell the texts apart
+        let opening = "Shared opening line of a long note. ".repeat(8);
+        let (older, old, newer) = (
+            format!("{opening}older"),
+            format!("{opening}old"),
+            format!("{opening}newer"),
+        );
+        let now = unix_now();
+
+        let mut source = test_state();
+        source.add_synced_item(text(&older), now - 300).unwrap();
+        source.add_synced_item(text(&old), now - 200).unwrap();
+        let export = std::env::temp_dir().join(format!(
+            "cursor-clip-import-test-{}.json",
+            std::process::id()
+        ));
+        let export = export.to_str().unwrap();
+        source
+            .export_history(export, ExportFormat::Json, true)
+            .unwrap();
+
+        let mut target = test_state();
+        target.add_synced_item(text(&older), now - 400).unwrap();
+        target.add_synced_item(text(&newer), now - 100).unwrap();
+        let mut events = target.events.subscribe();
+        let result = target.import_history(export);
+        let _ = std::fs::remove_file(export);
+
+        assert_eq!(result.unwrap(), (1, 1));
+        assert_eq!(
+            history_texts(&target),
+            [newer.as_str(), old.as_str(), older.as_str()]
+        );
+        let announced = std::iter::from_fn(|| events.try_recv().ok())
+            .filter_map(|event| match event {
+                BackendMessage::NewItem { item } => Some(item.item_id),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(announced, [target.history[1].item_id]);
+    }
+
+    #[test]
+    fn details_report_payload_sizes_as_copied() {
+        let mut state = test_state();
+        state.config.compress_payloads_over_bytes = 1024;
+        let long_text = "a line that compresses well\n".repeat(1000);
+        let id = state.add_synced_item(text(&long_text), unix_now()).unwrap();
+        assert!(!state.history[0].compressed.is_empty());
+
+        let details = state.item_details(id).unwrap();
+        let size = details
+            .mime_types
+            .iter()
+            .find(|(mime, _)| mime == PLAIN_TEXT_MIME)
+            .map(|(_, size)| *size);
+        assert_eq!(size, Some(long_text.len() as u64));
+    }
+
+    #[test]
+    fn imported_payloads_are_compressed() {
+        let long_text = "an exported line that compresses well\n".repeat(1000);
+        let mut source = test_state();
+        source.config.compress_payloads_over_bytes = 0;
+        source
+            .add_synced_item(text(&long_text), unix_now())
+            .unwrap();
+        let export = std::env::temp_dir().join(format!(
+            "cursor-clip-import-compress-test-{}.json",
+            std::process::id()
+        ));
+        let export = export.to_str().unwrap();
+        source
+            .export_history(export, ExportFormat::Json, true)
+            .unwrap();
+
+        let mut target = test_state();
+        target.config.compress_payloads_over_bytes = 1024;
+        let result = target.import_history(export);
+        let _ = std::fs::remove_file(export);
+
+        assert_eq!(result.unwrap(), (1, 0));
+        assert!(target.history[0].compressed.contains_key(PLAIN_TEXT_MIME));
+        assert_eq!(history_texts(&target), [long_text.as_str()]);
+    }
+
+    #[test]
+    fn import_applies_max_items() {
+        let now = unix_now();
+        let mut source = test_state();
+        for age in 1..=3 {
+            source
+                .add_synced_item(text(&format!("exported {age}")), now - age)
+                .unwrap();
         }
+        let export = std::env::temp_dir().join(format!(
+            "cursor-clip-import-limit-test-{}.json",
+            std::process::id()
+        ));
+        let export = export.to_str().unwrap();
+        source
+            .export_history(export, ExportFormat::Json, true)
+            .unwrap();
+
+        let mut target = test_state();
+        target.config.retention.max_items = 2;
+        let result = target.import_history(export);
+        let _ = std::fs::remove_file(export);
+
+        assert_eq!(result.unwrap(), (3, 0));
+        assert_eq!(history_texts(&target), ["exported 1", "exported 2"]);
+    }
+
+    #[test]
+    fn phrase_and_prefix_queries_search_the_full_text() {
+        // The phrase is past the end of the preview
+        let opening = "Shared opening line of a long note. ".repeat(8);
+        let now = unix_now();
+        let mut state = test_state();
+        let staging = state
+            .add_synced_item(text(&format!("{opening}deploy to staging")), now - 200)
+            .unwrap();
+        state
+            .add_synced_item(text(&format!("{opening}staging deploy")), now - 100)
+            .unwrap();
+        let keygen = state
+            .add_synced_item(text("ssh-keygen -t ed25519"), now)
+            .unwrap();
+
+        let found = |state: &mut BackendState, query: &str| -> Vec<u64> {
+            state
+                .search_history(query, HistoryView::All, 0)
+                .unwrap()
+                .iter()
+                .map(|result| result.item.item_id)
+                .collect()
+        };
+        assert_eq!(found(&mut state, r#""Deploy to staging""#), [staging]);
+        assert_eq!(found(&mut state, "ssh* ed*"), [keygen]);
+        assert!(found(&mut state, "gen*").is_empty());
+
+        let results = state.search_history("ssh*", HistoryView::All, 0).unwrap();
+        assert_eq!(results[0].highlights, [(0, 3)]);
     }
 }
@@ -0,0 +1,53 @@
+//! User rules for content types (`[[classifier_rules]]` in config.toml). They are tried
+//! in order before the built-in heuristics, e.g. to mark ticket numbers as Code or
+//! internal hostnames as URLs.
+
+use crate::backend::search_filter::parse_content_type;
+use crate::shared::ClipboardContentType;
+use log::warn;
+use regex::Regex;
+use serde::Deserialize;
+
+/// One `[[classifier_rules]]` entry
+#[derive(Clone, Debug, Deserialize)]
+pub struct ClassifierRule {
+    /// Regex matched against the item's text
+    pub pattern: String,
+    /// Type given to matching items, by its `type:` filter name (e.g. "code", "url")
+    #[serde(rename = "type")]
+    pub content_type: String,
+}
+
+/// Compiled `[[classifier_rules]]`
+#[derive(Debug, Default)]
+pub struct Classifier {
+    rules: Vec<(Regex, ClipboardContentType)>,
+}
+
+impl Classifier {
+    /// Compile the rules; invalid ones are logged and skipped.
+    pub fn new(rules: &[ClassifierRule]) -> Self {
+        let rules = rules
+            .iter()
+            .filter_map(|rule| {
+                let compiled = Regex::new(&rule.pattern)
+                    .map_err(|e| e.to_string())
+                    .and_then(|regex| Ok((regex, parse_content_type(&rule.content_type)?)));
+                compiled
+                    .inspect_err(|e| {
+                        warn!("Ignoring classifier rule {:?}: {e}", rule.pattern);
+                    })
+                    .ok()
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// Type of the first rule matching `text`, if any.
+    pub fn classify(&self, text: &str) -> Option<ClipboardContentType> {
+        self.rules
+            .iter()
+            .find(|(pattern, _)| pattern.is_match(text))
+            .map(|(_, content_type)| *content_type)
+    }
+}
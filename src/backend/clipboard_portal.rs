@@ -0,0 +1,424 @@
+//! Clipboard access through xdg-desktop-portal for sandboxes the compositor keeps away
+//! from data-control (e.g. Flatpak with wp_security_context_v1). The Clipboard portal
+//! only works within a RemoteDesktop session, so one is started first; the portal asks
+//! the user once per daemon start. Copies are read when `SelectionOwnerChanged` reports
+//! a new owner, and history items are offered back with `SetSelection`, answering each
+//! `SelectionTransfer` with the payload.
+
+use crate::backend::backend_state::BackendState;
+use crate::backend::wayland_clipboard::select_target_mimes;
+use bytes::Bytes;
+use gtk4::gio;
+use gtk4::glib::{self, ToVariant};
+use gtk4::prelude::*;
+use indexmap::IndexMap;
+use log::{debug, info, warn};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Reported as the data-control protocol in `Status`
+pub const PORTAL_PROTOCOL_NAME: &str = "xdg-desktop-portal-clipboard";
+
+const PORTAL_NAME: &str = "org.freedesktop.portal.Desktop";
+const PORTAL_PATH: &str = "/org/freedesktop/portal/desktop";
+const REMOTE_DESKTOP_INTERFACE: &str = "org.freedesktop.portal.RemoteDesktop";
+const CLIPBOARD_INTERFACE: &str = "org.freedesktop.portal.Clipboard";
+const REQUEST_INTERFACE: &str = "org.freedesktop.portal.Request";
+
+// Portal calls that return right away; `Start` waits for the user through its Request
+const CALL_TIMEOUT_MS: i32 = 5_000;
+
+/// Tells apart the Request objects of our portal calls
+static NEXT_TOKEN: AtomicU32 = AtomicU32::new(0);
+
+/// Our ownership of the clipboard through the portal session, shared by the signal
+/// handlers (which serve transfers) and `BackendState` (which sets history items).
+pub struct PortalSelection {
+    connection: gio::DBusConnection,
+    session: glib::variant::ObjectPath,
+    owned: Arc<Mutex<Option<IndexMap<String, Bytes>>>>,
+}
+
+impl PortalSelection {
+    /// Become the clipboard owner, serving `payload` until another client takes over.
+    pub fn set_selection(&self, payload: IndexMap<String, Bytes>) -> Result<(), String> {
+        let mime_types: Vec<String> = payload.keys().cloned().collect();
+        *self.owned.lock().unwrap() = Some(payload);
+        self.announce(mime_types)
+    }
+
+    /// Stop offering anything; the portal has no way to empty another app's clipboard.
+    pub fn clear_selection(&self) -> Result<(), String> {
+        *self.owned.lock().unwrap() = None;
+        self.announce(Vec::new())
+    }
+
+    fn announce(&self, mime_types: Vec<String>) -> Result<(), String> {
+        let options = glib::VariantDict::new(None);
+        options.insert_value("mime_types", &mime_types.to_variant());
+        let parameters = glib::Variant::tuple_from_iter([self.session.to_variant(), options.end()]);
+        self.connection
+            .call_sync(
+                Some(PORTAL_NAME),
+                PORTAL_PATH,
+                CLIPBOARD_INTERFACE,
+                "SetSelection",
+                Some(&parameters),
+                None,
+                gio::DBusCallFlags::NONE,
+                CALL_TIMEOUT_MS,
+                gio::Cancellable::NONE,
+            )
+            .map(|_| ())
+            .map_err(portal_error)
+    }
+}
+
+pub struct ClipboardPortalMonitor {
+    backend_state: Arc<Mutex<BackendState>>,
+}
+
+impl ClipboardPortalMonitor {
+    pub const fn new(backend_state: Arc<Mutex<BackendState>>) -> Self {
+        Self { backend_state }
+    }
+
+    /// Start a RemoteDesktop session with clipboard access and follow the clipboard from
+    /// a dedicated GLib main loop. Blocks for the lifetime of the session.
+    pub fn start_monitoring(&self) -> Result<(), String> {
+        let context = glib::MainContext::new();
+        context
+            .with_thread_default(|| self.run(&context))
+            .map_err(|e| format!("Failed to acquire a GLib main context: {e}"))?
+    }
+
+    fn run(&self, context: &glib::MainContext) -> Result<(), String> {
+        let connection = gio::bus_get_sync(gio::BusType::Session, gio::Cancellable::NONE)
+            .map_err(|e| format!("Session bus unavailable: {e}"))?;
+
+        let options = glib::VariantDict::new(None);
+        options.insert_value("session_handle_token", &next_token().to_variant());
+        let results = portal_request(
+            &connection,
+            context,
+            REMOTE_DESKTOP_INTERFACE,
+            "CreateSession",
+            |token| glib::Variant::tuple_from_iter([with_token(&options, token)]),
+        )?;
+        let session = results
+            .lookup::<String>("session_handle")
+            .ok()
+            .flatten()
+            .and_then(|path| glib::variant::ObjectPath::try_from(path).ok())
+            .ok_or("The portal created no RemoteDesktop session")?;
+        debug!("Portal session {}", session.as_str());
+
+        // Clipboard access has to be requested before the session starts
+        let empty = glib::VariantDict::new(None).end();
+        connection
+            .call_sync(
+                Some(PORTAL_NAME),
+                PORTAL_PATH,
+                CLIPBOARD_INTERFACE,
+                "RequestClipboard",
+                Some(&glib::Variant::tuple_from_iter([
+                    session.to_variant(),
+                    empty,
+                ])),
+                None,
+                gio::DBusCallFlags::NONE,
+                CALL_TIMEOUT_MS,
+                gio::Cancellable::NONE,
+            )
+            .map_err(portal_error)?;
+
+        let options = glib::VariantDict::new(None);
+        let results = portal_request(
+            &connection,
+            context,
+            REMOTE_DESKTOP_INTERFACE,
+            "Start",
+            |token| {
+                glib::Variant::tuple_from_iter([
+                    session.to_variant(),
+                    "".to_variant(),
+                    with_token(&options, token),
+                ])
+            },
+        )?;
+        if !results
+            .lookup::<bool>("clipboard_enabled")
+            .ok()
+            .flatten()
+            .unwrap_or(false)
+        {
+            return Err("The portal did not grant clipboard access".to_string());
+        }
+
+        let owned = Arc::new(Mutex::new(None));
+        let selection = PortalSelection {
+            connection: connection.clone(),
+            session: session.clone(),
+            owned: owned.clone(),
+        };
+        self.backend_state.lock().unwrap().portal_selection = Some(selection);
+        info!("Clipboard portal monitor initialized, monitoring changes...");
+
+        let clipboard = gio::DBusProxy::new_sync(
+            &connection,
+            gio::DBusProxyFlags::DO_NOT_LOAD_PROPERTIES,
+            None,
+            Some(PORTAL_NAME),
+            PORTAL_PATH,
+            CLIPBOARD_INTERFACE,
+            gio::Cancellable::NONE,
+        )
+        .map_err(portal_error)?;
+        let backend_state = self.backend_state.clone();
+        clipboard.connect_g_signal(None, move |_, _, signal, parameters| {
+            let result = match signal {
+                "SelectionOwnerChanged" => {
+                    on_owner_changed(&connection, &session, &backend_state, parameters)
+                }
+                "SelectionTransfer" => on_transfer(&connection, &session, &owned, parameters),
+                _ => Ok(()),
+            };
+            if let Err(e) = result {
+                warn!("Clipboard portal: {e}");
+            }
+        });
+
+        // The proxy stays alive for the lifetime of the loop
+        let _clipboard = clipboard;
+        glib::MainLoop::new(Some(context), false).run();
+        Ok(())
+    }
+}
+
+/// Another app took the clipboard: read the formats we record and add them to the history.
+fn on_owner_changed(
+    connection: &gio::DBusConnection,
+    session: &glib::variant::ObjectPath,
+    backend_state: &Arc<Mutex<BackendState>>,
+    parameters: &glib::Variant,
+) -> Result<(), String> {
+    let Some((_, options)) = parameters.get::<(glib::variant::ObjectPath, glib::VariantDict)>()
+    else {
+        return Err("malformed SelectionOwnerChanged".to_string());
+    };
+    if options
+        .lookup::<bool>("session_is_owner")
+        .ok()
+        .flatten()
+        .unwrap_or(false)
+    {
+        return Ok(());
+    }
+    let mime_types: Vec<String> = options
+        .lookup("mime_types")
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+
+    let mut received = IndexMap::new();
+    for mime in select_target_mimes(&mime_types) {
+        let parameters =
+            glib::Variant::tuple_from_iter([session.to_variant(), mime.as_str().to_variant()]);
+        let mut file = call_for_fd(connection, "SelectionRead", &parameters)?;
+        let mut data = Vec::new();
+        if let Err(e) = file.read_to_end(&mut data) {
+            debug!("Failed to read {mime} from the clipboard portal: {e}");
+            continue;
+        }
+        if !data.is_empty() {
+            received.insert(mime, Bytes::from(data));
+        }
+    }
+    if received.is_empty() {
+        debug!("Clipboard portal offers no formats we record");
+        return Ok(());
+    }
+
+    let mut state = backend_state.lock().unwrap();
+    let Some(new_id) = state.add_clipboard_item_from_mime_map(received, None) else {
+        return Ok(());
+    };
+    // Keep the contents available after the copying app exits, as on Wayland
+    if !state.monitor_only
+        && !state.idle_tracker.is_idle()
+        && let Some(item) = state.get_item_by_id(new_id)
+        && let Some(selection) = &state.portal_selection
+        && let Err(e) = selection.set_selection(item.mime_data)
+    {
+        warn!("Failed to take ownership of the clipboard through the portal: {e}");
+    }
+    Ok(())
+}
+
+/// A paste from our selection: write the requested format into the portal's pipe.
+fn on_transfer(
+    connection: &gio::DBusConnection,
+    session: &glib::variant::ObjectPath,
+    owned: &Mutex<Option<IndexMap<String, Bytes>>>,
+    parameters: &glib::Variant,
+) -> Result<(), String> {
+    let Some((_, mime, serial)) = parameters.get::<(glib::variant::ObjectPath, String, u32)>()
+    else {
+        return Err("malformed SelectionTransfer".to_string());
+    };
+    let data = owned
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|payload| payload.get(&mime).cloned());
+
+    let success = match data {
+        Some(data) => {
+            let parameters =
+                glib::Variant::tuple_from_iter([session.to_variant(), serial.to_variant()]);
+            let mut file = call_for_fd(connection, "SelectionWrite", &parameters)?;
+            match file.write_all(&data) {
+                Ok(()) => true,
+                Err(e) => {
+                    debug!("Failed to serve {mime} through the clipboard portal: {e}");
+                    false
+                }
+            }
+        }
+        None => {
+            debug!("Clipboard portal asked for unavailable format {mime}");
+            false
+        }
+    };
+    let parameters = glib::Variant::tuple_from_iter([
+        session.to_variant(),
+        serial.to_variant(),
+        success.to_variant(),
+    ]);
+    connection
+        .call_sync(
+            Some(PORTAL_NAME),
+            PORTAL_PATH,
+            CLIPBOARD_INTERFACE,
+            "SelectionWriteDone",
+            Some(&parameters),
+            None,
+            gio::DBusCallFlags::NONE,
+            CALL_TIMEOUT_MS,
+            gio::Cancellable::NONE,
+        )
+        .map(|_| ())
+        .map_err(portal_error)
+}
+
+/// Call a Clipboard method that answers with a file descriptor.
+fn call_for_fd(
+    connection: &gio::DBusConnection,
+    method: &str,
+    parameters: &glib::Variant,
+) -> Result<File, String> {
+    let (reply, fds) = connection
+        .call_with_unix_fd_list_sync(
+            Some(PORTAL_NAME),
+            PORTAL_PATH,
+            CLIPBOARD_INTERFACE,
+            method,
+            Some(parameters),
+            None,
+            gio::DBusCallFlags::NONE,
+            CALL_TIMEOUT_MS,
+            gio::UnixFDList::NONE,
+            gio::Cancellable::NONE,
+        )
+        .map_err(portal_error)?;
+    let (handle,) = reply
+        .get::<(glib::variant::Handle,)>()
+        .ok_or_else(|| format!("{method} returned no file descriptor"))?;
+    let fd = fds
+        .ok_or_else(|| format!("{method} returned no file descriptor"))?
+        .get(handle.0)
+        .map_err(portal_error)?;
+    Ok(File::from(fd))
+}
+
+/// Call a portal method that answers through a Request object and wait for its
+/// `Response`. `parameters` builds the arguments around the request's handle token.
+fn portal_request(
+    connection: &gio::DBusConnection,
+    context: &glib::MainContext,
+    interface: &str,
+    method: &str,
+    parameters: impl FnOnce(&str) -> glib::Variant,
+) -> Result<glib::VariantDict, String> {
+    let token = next_token();
+    // The Request object path is known in advance, so its Response can't be missed
+    let sender = connection
+        .unique_name()
+        .ok_or("Not connected to the session bus")?;
+    let request_path = format!(
+        "{PORTAL_PATH}/request/{}/{token}",
+        sender.trim_start_matches(':').replace('.', "_")
+    );
+    let request = gio::DBusProxy::new_sync(
+        connection,
+        gio::DBusProxyFlags::DO_NOT_LOAD_PROPERTIES,
+        None,
+        Some(PORTAL_NAME),
+        &request_path,
+        REQUEST_INTERFACE,
+        gio::Cancellable::NONE,
+    )
+    .map_err(portal_error)?;
+    let response = Arc::new(Mutex::new(None));
+    let response_for_signal = response.clone();
+    request.connect_g_signal(None, move |_, _, signal, parameters| {
+        if signal == "Response" {
+            *response_for_signal.lock().unwrap() = parameters.get::<(u32, glib::VariantDict)>();
+        }
+    });
+
+    connection
+        .call_sync(
+            Some(PORTAL_NAME),
+            PORTAL_PATH,
+            interface,
+            method,
+            Some(&parameters(&token)),
+            None,
+            gio::DBusCallFlags::NONE,
+            CALL_TIMEOUT_MS,
+            gio::Cancellable::NONE,
+        )
+        .map_err(portal_error)?;
+    loop {
+        if let Some((code, results)) = response.lock().unwrap().take() {
+            // 1: cancelled by the user, 2: failed otherwise
+            return match code {
+                0 => Ok(results),
+                1 => Err(format!("{method} was cancelled")),
+                _ => Err(format!("{method} failed")),
+            };
+        }
+        context.iteration(true);
+    }
+}
+
+/// `options` with the `handle_token` of a request added.
+fn with_token(options: &glib::VariantDict, token: &str) -> glib::Variant {
+    options.insert_value("handle_token", &token.to_variant());
+    options.end()
+}
+
+fn next_token() -> String {
+    format!(
+        "cursor_clip_{}_{}",
+        std::process::id(),
+        NEXT_TOKEN.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+fn portal_error(error: impl std::fmt::Display) -> String {
+    format!("Portal request failed: {error}")
+}
@@ -0,0 +1,135 @@
+use crate::shared::runtime_dir::{ensure_private_runtime_dir, runtime_dir};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use bytes::Bytes;
+use indexmap::IndexMap;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+
+const BACKUP_PREFIX: &str = "selection-backup-";
+
+/// Copy of a selection the daemon currently owns. While we own a selection we are the
+/// only holder of its data, so it is written to the runtime directory (tmpfs, private to
+/// the user) and removed again once another client takes the selection over. A backup
+/// that is still present on startup means the daemon went away while owning it.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SelectionBackup {
+    pub seat: Option<String>,
+    pub timestamp: u64,
+    /// MIME type -> base64 payload
+    formats: IndexMap<String, String>,
+}
+
+impl SelectionBackup {
+    pub fn new(seat: Option<String>, timestamp: u64, mime_data: &IndexMap<String, Bytes>) -> Self {
+        Self {
+            seat,
+            timestamp,
+            formats: mime_data
+                .iter()
+                .map(|(mime, payload)| (mime.clone(), BASE64.encode(payload)))
+                .collect(),
+        }
+    }
+
+    pub fn mime_data(&self) -> IndexMap<String, Bytes> {
+        self.formats
+            .iter()
+            .filter_map(|(mime, encoded)| {
+                BASE64
+                    .decode(encoded)
+                    .ok()
+                    .map(|payload| (mime.clone(), Bytes::from(payload)))
+            })
+            .collect()
+    }
+}
+
+/// Write the backup for `seat_name`, replacing the previous one atomically.
+pub fn save(seat_name: &str, backup: &SelectionBackup) -> Result<(), String> {
+    let dir = ensure_private_runtime_dir()?;
+    let path = backup_path(&dir, seat_name);
+    let tmp_path = path.with_extension("tmp");
+    let contents = serde_json::to_vec(backup)
+        .map_err(|e| format!("Failed to encode selection backup: {e}"))?;
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(&tmp_path)
+        .map_err(|e| format!("Failed to open {}: {e}", tmp_path.display()))?;
+    file.write_all(&contents)
+        .and_then(|()| file.sync_all())
+        .map_err(|e| format!("Failed to write {}: {e}", tmp_path.display()))?;
+    fs::rename(&tmp_path, &path)
+        .map_err(|e| format!("Failed to move selection backup into place: {e}"))
+}
+
+/// Drop the backup for `seat_name`; its selection is no longer ours to lose.
+pub fn clear(seat_name: &str) {
+    let Some(dir) = runtime_dir() else {
+        return;
+    };
+    let path = backup_path(&dir, seat_name);
+    match fs::remove_file(&path) {
+        Ok(()) => debug!("Removed selection backup for {seat_name}"),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => warn!("Failed to remove {}: {e}", path.display()),
+    }
+}
+
+/// Read and remove all backups left behind by a previous daemon, oldest first.
+pub fn take_leftovers() -> Vec<SelectionBackup> {
+    let Some(dir) = runtime_dir() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+
+    let mut backups: Vec<SelectionBackup> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension().is_some_and(|ext| ext == "json")
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with(BACKUP_PREFIX))
+        })
+        .filter_map(|path| {
+            let backup = fs::read(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|contents| {
+                    serde_json::from_slice::<SelectionBackup>(&contents).map_err(|e| e.to_string())
+                });
+            if let Err(e) = fs::remove_file(&path) {
+                warn!("Failed to remove {}: {e}", path.display());
+            }
+            backup
+                .inspect_err(|e| {
+                    warn!(
+                        "Ignoring unreadable selection backup {}: {e}",
+                        path.display()
+                    )
+                })
+                .ok()
+        })
+        .collect();
+    backups.sort_by_key(|backup| backup.timestamp);
+    backups
+}
+
+fn backup_path(dir: &std::path::Path, seat_name: &str) -> PathBuf {
+    let seat: String = seat_name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    dir.join(format!("{BACKUP_PREFIX}{seat}.json"))
+}
@@ -0,0 +1,207 @@
+use std::sync::{Arc, Mutex};
+
+use gtk4::gio;
+use gtk4::glib::{self, ToVariant};
+use log::{debug, info, warn};
+
+use super::backend_state::BackendState;
+use crate::shared::{BackendMessage, ClipboardItemPreview, CursorClipError, CursorClipResult};
+
+pub const DBUS_NAME: &str = "org.cursorclip.Daemon";
+pub const DBUS_PATH: &str = "/org/cursorclip/Daemon";
+pub const DBUS_INTERFACE: &str = "org.cursorclip.Daemon";
+
+// History entries are exposed as (id, preview, content type, timestamp, pinned).
+const INTERFACE_XML: &str = r#"
+<node>
+  <interface name="org.cursorclip.Daemon">
+    <method name="GetHistory">
+      <arg type="a(tsstb)" name="items" direction="out"/>
+    </method>
+    <method name="SetClipboardById">
+      <arg type="t" name="id" direction="in"/>
+      <arg type="b" name="instant_paste" direction="in"/>
+    </method>
+    <method name="GetPowerState">
+      <arg type="b" name="on_battery" direction="out"/>
+      <arg type="s" name="profile" direction="out"/>
+      <arg type="b" name="saving" direction="out"/>
+    </method>
+    <method name="Clear"/>
+    <method name="Delete">
+      <arg type="t" name="id" direction="in"/>
+    </method>
+    <signal name="NewItem">
+      <arg type="t" name="id"/>
+      <arg type="s" name="preview"/>
+      <arg type="s" name="content_type"/>
+      <arg type="t" name="timestamp"/>
+    </signal>
+  </interface>
+</node>
+"#;
+
+type DbusHistoryEntry = (u64, String, String, u64, bool);
+
+/// Start the D-Bus service on its own thread with a dedicated GLib main loop,
+/// so it neither blocks the tokio runtime nor the Wayland dispatch thread.
+pub fn spawn_dbus_service(state: Arc<Mutex<BackendState>>) {
+    if let Err(e) = std::thread::Builder::new()
+        .name("cursor-clip-dbus".to_string())
+        .spawn(move || run_dbus_service(state))
+    {
+        warn!("Failed to spawn D-Bus service thread: {e}");
+    }
+}
+
+fn run_dbus_service(state: Arc<Mutex<BackendState>>) {
+    let context = glib::MainContext::new();
+    let result = context.with_thread_default(|| {
+        let node_info = match gio::DBusNodeInfo::for_xml(INTERFACE_XML) {
+            Ok(info) => info,
+            Err(e) => {
+                warn!("Invalid D-Bus interface description: {e}");
+                return;
+            }
+        };
+        let Some(interface_info) = node_info.lookup_interface(DBUS_INTERFACE) else {
+            warn!("D-Bus interface {DBUS_INTERFACE} missing from introspection data");
+            return;
+        };
+
+        let state_for_bus = state.clone();
+        let _owner_id = gio::bus_own_name(
+            gio::BusType::Session,
+            DBUS_NAME,
+            gio::BusNameOwnerFlags::NONE,
+            move |connection, _| {
+                register_daemon_object(&connection, &interface_info, state_for_bus.clone());
+                spawn_signal_forwarder(connection, &state_for_bus);
+            },
+            |_, name| info!("Acquired D-Bus name {name}"),
+            |_, name| warn!("Lost or could not acquire D-Bus name {name}"),
+        );
+
+        glib::MainLoop::new(Some(&context), false).run();
+    });
+
+    if let Err(e) = result {
+        warn!("Failed to acquire GLib main context for D-Bus service: {e}");
+    }
+}
+
+fn register_daemon_object(
+    connection: &gio::DBusConnection,
+    interface_info: &gio::DBusInterfaceInfo,
+    state: Arc<Mutex<BackendState>>,
+) {
+    let registration = connection
+        .register_object(DBUS_PATH, interface_info)
+        .method_call(move |_, sender, _, _, method, params, invocation| {
+            debug!("D-Bus call {method} from {}", sender.unwrap_or("<unknown>"));
+            handle_method_call(&state, method, &params, invocation);
+        })
+        .build();
+
+    if let Err(e) = registration {
+        warn!("Failed to register D-Bus object at {DBUS_PATH}: {e}");
+    }
+}
+
+fn handle_method_call(
+    state: &Arc<Mutex<BackendState>>,
+    method: &str,
+    params: &glib::Variant,
+    invocation: gio::DBusMethodInvocation,
+) {
+    let result: CursorClipResult<Option<glib::Variant>> = match method {
+        "GetHistory" => {
+            let items: Vec<DbusHistoryEntry> = state
+                .lock()
+                .unwrap()
+                .get_history()
+                .iter()
+                .map(history_entry)
+                .collect();
+            Ok(Some((items,).to_variant()))
+        }
+        "SetClipboardById" => match params.get::<(u64, bool)>() {
+            Some((id, instant_paste)) => state
+                .lock()
+                .unwrap()
+                .set_clipboard_by_id(id, instant_paste, None, None)
+                .map(|()| None),
+            None => Err(CursorClipError::InvalidRequest(
+                "Expected (tb) arguments".to_string(),
+            )),
+        },
+        "GetPowerState" => {
+            let state = state.lock().unwrap();
+            Ok(Some(
+                (
+                    state.power.on_battery,
+                    state.power.profile.clone().unwrap_or_default(),
+                    state.battery_saving(),
+                )
+                    .to_variant(),
+            ))
+        }
+        "Clear" => {
+            state.lock().unwrap().clear_history();
+            Ok(None)
+        }
+        "Delete" => match params.get::<(u64,)>() {
+            Some((id,)) => state.lock().unwrap().delete_item_by_id(id).map(|()| None),
+            None => Err(CursorClipError::InvalidRequest(
+                "Expected (t) arguments".to_string(),
+            )),
+        },
+        _ => Err(CursorClipError::InvalidRequest(format!(
+            "Unknown method {method}"
+        ))),
+    };
+
+    match result {
+        Ok(value) => invocation.return_value(value.as_ref()),
+        Err(error) => invocation.return_dbus_error(error.dbus_name(), &error.to_string()),
+    }
+}
+
+/// Forward `NewItem` backend events to D-Bus subscribers as signals.
+fn spawn_signal_forwarder(connection: gio::DBusConnection, state: &Arc<Mutex<BackendState>>) {
+    let mut events = state.lock().unwrap().events.subscribe();
+    std::thread::spawn(move || {
+        use tokio::sync::broadcast::error::RecvError;
+        loop {
+            match events.blocking_recv() {
+                Ok(BackendMessage::NewItem { item }) => {
+                    let (id, preview, content_type, timestamp, _) = history_entry(&item);
+                    if let Err(e) = connection.emit_signal(
+                        None,
+                        DBUS_PATH,
+                        DBUS_INTERFACE,
+                        "NewItem",
+                        Some(&(id, preview, content_type, timestamp).to_variant()),
+                    ) {
+                        warn!("Failed to emit D-Bus NewItem signal: {e}");
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(skipped)) => {
+                    debug!("D-Bus signal forwarder skipped {skipped} events");
+                }
+                Err(RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+fn history_entry(item: &ClipboardItemPreview) -> DbusHistoryEntry {
+    (
+        item.item_id,
+        item.content_preview.clone(),
+        item.content_type.as_str().to_string(),
+        item.timestamp,
+        item.pinned,
+    )
+}
@@ -0,0 +1,120 @@
+use crate::backend::backend_state::BackendState;
+use crate::shared::ClipboardItem;
+use bytes::Bytes;
+use image::{ImageFormat, Rgba, RgbaImage};
+use indexmap::IndexMap;
+use std::io::Cursor;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A fixture entry: age in seconds relative to startup, pinned flag and its MIME payloads.
+struct DemoFixture {
+    age_secs: u64,
+    pinned: bool,
+    mime_data: Vec<(&'static str, Bytes)>,
+}
+
+/// Deterministic fake history used by `--demo` (screenshots, UI development, manual QA).
+/// Ids and content are fixed; timestamps are fixed offsets from now so relative
+/// labels ("5 minutes ago") always render the same.
+pub fn demo_items() -> Vec<ClipboardItem> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    demo_fixtures()
+        .into_iter()
+        .zip(1u64..)
+        .filter_map(|(fixture, item_id)| {
+            let mime_data: IndexMap<String, Bytes> = fixture
+                .mime_data
+                .into_iter()
+                .map(|(mime, bytes)| (mime.to_string(), bytes))
+                .collect();
+            let mut item = BackendState::build_item(
+                item_id,
+                mime_data,
+                now.saturating_sub(fixture.age_secs),
+                None,
+                true,
+            )?;
+            item.pinned = fixture.pinned;
+            Some(item)
+        })
+        .collect()
+}
+
+fn demo_fixtures() -> Vec<DemoFixture> {
+    vec![
+        DemoFixture {
+            age_secs: 2 * 86400,
+            pinned: true,
+            mime_data: vec![text(
+                "ssh -L 8080:localhost:80 demo@build-server.example.org",
+            )],
+        },
+        DemoFixture {
+            age_secs: 10,
+            pinned: false,
+            mime_data: vec![text("Hello, world Cursor-Clip!")],
+        },
+        DemoFixture {
+            age_secs: 5 * 60,
+            pinned: false,
+            mime_data: vec![text("https://github.com/Sirulex/cursor-clip")],
+        },
+        DemoFixture {
+            age_secs: 12 * 60,
+            pinned: false,
+            mime_data: vec![("image/png", gradient_png(640, 360))],
+        },
+        DemoFixture {
+            age_secs: 3600,
+            pinned: false,
+            mime_data: vec![text(
+                "impl Display for MyStruct {\n    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {\n        write!(f, \"MyStruct\")\n    }\n}",
+            )],
+        },
+        DemoFixture {
+            age_secs: 3 * 3600,
+            pinned: false,
+            mime_data: vec![
+                (
+                    "text/uri-list",
+                    Bytes::from_static(b"file:///home/demo/Documents/quarterly-report.pdf\r\n"),
+                ),
+                text("/home/demo/Documents/quarterly-report.pdf"),
+            ],
+        },
+        DemoFixture {
+            age_secs: 5 * 3600,
+            pinned: false,
+            mime_data: vec![text("Password4234!Cursor-Clip")],
+        },
+        DemoFixture {
+            age_secs: 26 * 3600,
+            pinned: false,
+            mime_data: vec![text(
+                "Sample clipboard content for testing the clipboard manager. It is long enough to wrap over multiple lines in the overlay so ellipsizing can be checked as well.",
+            )],
+        },
+    ]
+}
+
+fn text(value: &'static str) -> (&'static str, Bytes) {
+    (
+        "text/plain;charset=utf-8",
+        Bytes::from_static(value.as_bytes()),
+    )
+}
+
+fn gradient_png(width: u32, height: u32) -> Bytes {
+    let image = RgbaImage::from_fn(width, height, |x, y| {
+        Rgba([(x * 255 / width) as u8, (y * 255 / height) as u8, 200, 255])
+    });
+    let mut buffer = Cursor::new(Vec::new());
+    image::DynamicImage::ImageRgba8(image)
+        .write_to(&mut buffer, ImageFormat::Png)
+        .expect("encoding an in-memory PNG cannot fail");
+    Bytes::from(buffer.into_inner())
+}
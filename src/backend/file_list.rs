@@ -0,0 +1,36 @@
+use bytes::Bytes;
+use gtk4::gio;
+use gtk4::prelude::*;
+use indexmap::IndexMap;
+
+use crate::backend::plain_text::PLAIN_TEXT_MIME;
+
+/// MIME type file managers use for copied files (RFC 2483)
+pub const URI_LIST_MIME: &str = "text/uri-list";
+
+/// Local paths listed in a `text/uri-list` payload. Comment lines and non-`file://`
+/// URIs are skipped.
+pub fn parse_uri_list(payload: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(payload)
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter(|uri| uri.starts_with("file:"))
+        .filter_map(|uri| gio::File::for_uri(uri).path())
+        .map(|path| path.to_string_lossy().into_owned())
+        .collect()
+}
+
+/// The paths of a file copy as plain text, one per line.
+pub fn paths_payload(files: &[String]) -> Option<IndexMap<String, Bytes>> {
+    if files.is_empty() {
+        return None;
+    }
+    let text = Bytes::from(files.join("\n"));
+    Some(
+        [PLAIN_TEXT_MIME, "text/plain", "UTF8_STRING"]
+            .into_iter()
+            .map(|mime| (mime.to_string(), text.clone()))
+            .collect(),
+    )
+}
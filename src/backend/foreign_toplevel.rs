@@ -0,0 +1,135 @@
+use crate::backend::wayland_clipboard::MutexBackendState;
+use log::{debug, info};
+use std::collections::HashMap;
+use std::sync::Arc;
+use wayland_client::backend::ObjectId;
+use wayland_client::globals::GlobalList;
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle};
+use wayland_protocols_wlr::foreign_toplevel::v1::client::{
+    zwlr_foreign_toplevel_handle_v1::{self, ZwlrForeignToplevelHandleV1},
+    zwlr_foreign_toplevel_manager_v1::{self, ZwlrForeignToplevelManagerV1},
+};
+
+#[derive(Debug, Default)]
+struct ToplevelInfo {
+    app_id: Option<String>,
+    activated: bool,
+}
+
+/// Tracks open windows through wlr-foreign-toplevel-management so a selection can be
+/// attributed to the focused app. Data-control offers carry no information about
+/// their source, so the focused window at copy time is the best available guess.
+/// (ext-foreign-toplevel-list exposes app ids but no focus state, so it cannot be used here.)
+#[derive(Debug, Default)]
+pub struct ToplevelTracker {
+    manager: Option<ZwlrForeignToplevelManagerV1>,
+    toplevels: HashMap<ObjectId, ToplevelInfo>,
+}
+
+impl ToplevelTracker {
+    pub fn bind(&mut self, globals: &GlobalList, qh: &QueueHandle<MutexBackendState>) {
+        if self.manager.is_some() {
+            return;
+        }
+        match globals.bind::<ZwlrForeignToplevelManagerV1, _, _>(qh, 1..=3, ()) {
+            Ok(manager) => {
+                debug!("Bound zwlr_foreign_toplevel_manager_v1");
+                self.manager = Some(manager);
+            }
+            Err(_) => info!(
+                "Compositor lacks zwlr_foreign_toplevel_manager_v1; the app exclude list cannot be applied"
+            ),
+        }
+    }
+
+    /// App id of the currently activated (focused) toplevel, if known.
+    pub fn focused_app_id(&self) -> Option<&str> {
+        self.toplevels
+            .values()
+            .find(|toplevel| toplevel.activated)
+            .and_then(|toplevel| toplevel.app_id.as_deref())
+    }
+}
+
+/// Whether `app_id` matches an exclude list entry. Matching is case-insensitive;
+/// a trailing `*` matches any suffix (e.g. `org.keepassxc.*`).
+pub fn app_id_matches(patterns: &[String], app_id: &str) -> bool {
+    let app_id = app_id.to_lowercase();
+    patterns.iter().any(|pattern| {
+        let pattern = pattern.to_lowercase();
+        pattern
+            .strip_suffix('*')
+            .map_or(pattern == app_id, |prefix| app_id.starts_with(prefix))
+    })
+}
+
+impl Dispatch<ZwlrForeignToplevelManagerV1, ()> for MutexBackendState {
+    fn event(
+        wrapper: &mut Self,
+        _: &ZwlrForeignToplevelManagerV1,
+        event: zwlr_foreign_toplevel_manager_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let mut state = wrapper.backend_state.lock().unwrap();
+        match event {
+            zwlr_foreign_toplevel_manager_v1::Event::Toplevel { toplevel } => {
+                state
+                    .toplevel_tracker
+                    .toplevels
+                    .insert(toplevel.id(), ToplevelInfo::default());
+            }
+            zwlr_foreign_toplevel_manager_v1::Event::Finished => {
+                info!("Compositor stopped sending foreign toplevel events");
+                state.toplevel_tracker.manager = None;
+                state.toplevel_tracker.toplevels.clear();
+            }
+            _ => {}
+        }
+    }
+
+    fn event_created_child(
+        opcode: u16,
+        qhandle: &QueueHandle<Self>,
+    ) -> Arc<dyn wayland_client::backend::ObjectData> {
+        match opcode {
+            zwlr_foreign_toplevel_manager_v1::EVT_TOPLEVEL_OPCODE => {
+                qhandle.make_data::<ZwlrForeignToplevelHandleV1, ()>(())
+            }
+            _ => panic!("Unknown child object for opcode {opcode}"),
+        }
+    }
+}
+
+impl Dispatch<ZwlrForeignToplevelHandleV1, ()> for MutexBackendState {
+    fn event(
+        wrapper: &mut Self,
+        handle: &ZwlrForeignToplevelHandleV1,
+        event: zwlr_foreign_toplevel_handle_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let mut state = wrapper.backend_state.lock().unwrap();
+        let toplevels = &mut state.toplevel_tracker.toplevels;
+        match event {
+            zwlr_foreign_toplevel_handle_v1::Event::AppId { app_id } => {
+                toplevels.entry(handle.id()).or_default().app_id = Some(app_id);
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::State { state } => {
+                // Array of native-endian u32 state values
+                let activated = state.chunks_exact(4).any(|chunk| {
+                    u32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]])
+                        == zwlr_foreign_toplevel_handle_v1::State::Activated as u32
+                });
+                toplevels.entry(handle.id()).or_default().activated = activated;
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::Closed => {
+                toplevels.remove(&handle.id());
+                handle.destroy();
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,30 @@
+//! Frecency: how often and how recently an item was used, for `HistoryOrder::Frecency`.
+//! Every paste and every repeat copy counts as a use; the score decays in steps as the
+//! item's last use gets older, so a snippet pasted daily stays above yesterday's one-off
+//! copy while something pasted often last year sinks below fresh items.
+
+use crate::shared::ClipboardItem;
+
+/// Weight of the last use's age, newest first: (maximum age in seconds, weight)
+const RECENCY_WEIGHTS: [(u64, u64); 4] = [
+    (3_600, 100),
+    (86_400, 70),
+    (7 * 86_400, 50),
+    (30 * 86_400, 30),
+];
+/// Weight of anything last used longer ago
+const OLD_WEIGHT: u64 = 10;
+/// A paste says more about an item's value than copying it again
+const PASTE_USES: u64 = 2;
+
+/// Frecency score of `item` at `now` (Unix seconds); higher sorts first.
+pub fn frecency_score(item: &ClipboardItem, now: u64) -> u64 {
+    let uses = u64::from(item.times_copied.max(1))
+        + u64::from(item.paste_count).saturating_mul(PASTE_USES);
+    let age = now.saturating_sub(item.last_used_at());
+    let weight = RECENCY_WEIGHTS
+        .iter()
+        .find(|(max_age, _)| age < *max_age)
+        .map_or(OLD_WEIGHT, |(_, weight)| *weight);
+    uses.saturating_mul(weight)
+}
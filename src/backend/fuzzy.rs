@@ -0,0 +1,130 @@
+//! Fuzzy matching for `SearchHistory`, in the style of Sublime Text / skim: the query's
+//! characters must appear in order, and matches score higher when they are consecutive,
+//! start a word or sit near the beginning. Smart case: a query with an uppercase letter
+//! matches case-sensitively.
+
+const SCORE_MATCH: i64 = 16;
+const BONUS_CONSECUTIVE: i64 = 15;
+const BONUS_WORD_START: i64 = 10;
+const BONUS_FIRST_CHAR: i64 = 8;
+const PENALTY_GAP: i64 = 1;
+const PENALTY_LEADING_MAX: i64 = 10;
+
+/// A successful match: its score and the byte offsets of the matched characters.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    pub positions: Vec<usize>,
+}
+
+/// Match `query` against `haystack`. Whitespace in the query is ignored. Every start
+/// position of the first query character is tried and the best greedy match is kept.
+pub fn fuzzy_match(haystack: &str, query: &str) -> Option<FuzzyMatch> {
+    let case_sensitive = query.chars().any(char::is_uppercase);
+    let fold = |c: char| {
+        if case_sensitive {
+            c
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    };
+    let needle: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(fold)
+        .collect();
+    let first = *needle.first()?;
+    let chars: Vec<(usize, char)> = haystack.char_indices().collect();
+
+    let mut best: Option<FuzzyMatch> = None;
+    for start in 0..chars.len() {
+        if fold(chars[start].1) != first {
+            continue;
+        }
+        let Some(candidate) = match_from(&chars, &needle, start, fold) else {
+            // Later starts cannot fit the rest of the query either
+            break;
+        };
+        if best
+            .as_ref()
+            .is_none_or(|best| candidate.score > best.score)
+        {
+            best = Some(candidate);
+        }
+    }
+    best
+}
+
+fn match_from(
+    chars: &[(usize, char)],
+    needle: &[char],
+    start: usize,
+    fold: impl Fn(char) -> char,
+) -> Option<FuzzyMatch> {
+    let mut positions = Vec::with_capacity(needle.len());
+    let mut score = -(start as i64).min(PENALTY_LEADING_MAX);
+    let mut previous: Option<usize> = None;
+    let mut index = start;
+
+    for wanted in needle {
+        while index < chars.len() && fold(chars[index].1) != *wanted {
+            index += 1;
+        }
+        let (offset, c) = *chars.get(index)?;
+
+        score += SCORE_MATCH;
+        match previous {
+            Some(previous) if previous + 1 == index => score += BONUS_CONSECUTIVE,
+            Some(previous) => score -= PENALTY_GAP * (index - previous - 1) as i64,
+            None => {}
+        }
+        if index == 0 {
+            score += BONUS_FIRST_CHAR;
+        }
+        let before = index.checked_sub(1).map(|i| chars[i].1);
+        let word_start = match before {
+            None => true,
+            Some(before) => {
+                !before.is_alphanumeric() || (before.is_lowercase() && c.is_uppercase())
+            }
+        };
+        if word_start {
+            score += BONUS_WORD_START;
+        }
+
+        positions.push(offset);
+        previous = Some(index);
+        index += 1;
+    }
+
+    Some(FuzzyMatch { score, positions })
+}
+
+/// Merge matched character offsets into `(start, end)` byte ranges of `haystack`.
+pub fn highlight_ranges(haystack: &str, positions: &[usize]) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &position in positions {
+        let Some(c) = haystack[position..].chars().next() else {
+            continue;
+        };
+        let end = position + c.len_utf8();
+        match ranges.last_mut() {
+            Some(last) if last.1 == position => last.1 = end,
+            _ => ranges.push((position, end)),
+        }
+    }
+    ranges
+}
+
+/// Sort byte ranges and merge the ones that overlap or touch.
+pub fn merge_ranges(mut ranges: Vec<(usize, usize)>) -> Vec<(usize, usize)> {
+    ranges.sort_unstable();
+    let mut merged: Vec<(usize, usize)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
@@ -0,0 +1,276 @@
+//! `ExportHistory` / `ImportHistory`: dump the history to a file another machine can
+//! restore. A JSON export holds every item with its payloads base64 encoded; a tarball
+//! holds the same manifest (`history.json`) with the payloads as separate files, which
+//! keeps large images out of the JSON.
+
+use crate::backend::file_list::URI_LIST_MIME;
+use crate::backend::image_pipeline::CANONICAL_IMAGE_MIME;
+use crate::backend::plain_text::PLAIN_TEXT_MIME;
+use crate::shared::{ClipboardContentType, ClipboardItem, ExportFormat};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use bytes::Bytes;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const EXPORT_VERSION: u32 = 1;
+const MANIFEST_NAME: &str = "history.json";
+const TAR_BLOCK: usize = 512;
+
+// Payloads worth restoring elsewhere; app-private and legacy X11 targets are dropped
+const EXPORTED_MIMES: &[&str] = &[
+    PLAIN_TEXT_MIME,
+    "text/plain",
+    "UTF8_STRING",
+    "text/html",
+    URI_LIST_MIME,
+    CANONICAL_IMAGE_MIME,
+];
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ExportManifest {
+    version: u32,
+    exported_at: u64,
+    items: Vec<ExportedItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ExportedItem {
+    content_preview: String,
+    content_type: ClipboardContentType,
+    timestamp: u64,
+    #[serde(default)]
+    pinned: bool,
+    #[serde(default)]
+    seat: Option<String>,
+    /// MIME type -> base64 payload (JSON exports)
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    mime_data: IndexMap<String, String>,
+    /// MIME type -> file name inside the tarball (tar exports)
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    payload_files: IndexMap<String, String>,
+}
+
+/// An item read back from an export, ready to be rebuilt into a history entry.
+#[derive(Debug)]
+pub struct ImportedItem {
+    pub timestamp: u64,
+    pub pinned: bool,
+    pub seat: Option<String>,
+    pub mime_data: IndexMap<String, Bytes>,
+}
+
+/// Write `history` to `path`. Passwords are left out unless `include_sensitive`.
+/// Returns how many items were written.
+pub fn export_history(
+    history: &[ClipboardItem],
+    path: &Path,
+    format: ExportFormat,
+    include_sensitive: bool,
+) -> Result<usize, String> {
+    let exported_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let items: Vec<&ClipboardItem> = history
+        .iter()
+        .filter(|item| include_sensitive || item.content_type != ClipboardContentType::Password)
+        .collect();
+
+    let mut payloads: Vec<(String, &Bytes)> = Vec::new();
+    let manifest = ExportManifest {
+        version: EXPORT_VERSION,
+        exported_at,
+        items: items
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                let mut exported = ExportedItem {
+                    content_preview: item.content_preview.clone(),
+                    content_type: item.content_type,
+                    timestamp: item.timestamp,
+                    pinned: item.pinned,
+                    seat: item.seat.clone(),
+                    mime_data: IndexMap::new(),
+                    payload_files: IndexMap::new(),
+                };
+                let entries = item
+                    .mime_data
+                    .iter()
+                    .filter(|(mime, _)| EXPORTED_MIMES.contains(&mime.as_str()));
+                for (payload_index, (mime, bytes)) in entries.enumerate() {
+                    match format {
+                        ExportFormat::Json => {
+                            exported
+                                .mime_data
+                                .insert(mime.clone(), BASE64.encode(bytes));
+                        }
+                        ExportFormat::Tar => {
+                            let name = format!("payloads/{index}-{payload_index}.bin");
+                            exported.payload_files.insert(mime.clone(), name.clone());
+                            payloads.push((name, bytes));
+                        }
+                    }
+                }
+                exported
+            })
+            .collect(),
+    };
+    let manifest_json = serde_json::to_vec_pretty(&manifest)
+        .map_err(|e| format!("Failed to encode export: {e}"))?;
+
+    let contents = match format {
+        ExportFormat::Json => manifest_json,
+        ExportFormat::Tar => {
+            let mut tar = Vec::new();
+            append_tar_entry(&mut tar, MANIFEST_NAME, &manifest_json, exported_at)?;
+            for (name, bytes) in payloads {
+                append_tar_entry(&mut tar, &name, bytes, exported_at)?;
+            }
+            // End of archive: two empty blocks
+            tar.resize(tar.len() + 2 * TAR_BLOCK, 0);
+            tar
+        }
+    };
+
+    // Exports hold clipboard contents; keep them private like the history database
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .map_err(|e| format!("Failed to create {}: {e}", path.display()))?;
+    file.write_all(&contents)
+        .map_err(|e| format!("Failed to write {}: {e}", path.display()))?;
+    Ok(items.len())
+}
+
+/// Read an export written by [`export_history`]; the format is detected from the content.
+pub fn read_export(path: &Path) -> Result<Vec<ImportedItem>, String> {
+    let contents = fs::read(path).map_err(|e| format!("Failed to read {}: {e}", path.display()))?;
+
+    let (manifest, files) = if contents.first() == Some(&b'{') {
+        (contents, IndexMap::new())
+    } else {
+        let mut files = read_tar_entries(&contents)?;
+        let manifest = files
+            .shift_remove(MANIFEST_NAME)
+            .ok_or_else(|| format!("{} has no {MANIFEST_NAME}", path.display()))?;
+        (manifest.to_vec(), files)
+    };
+    let manifest: ExportManifest = serde_json::from_slice(&manifest)
+        .map_err(|e| format!("{} is not a cursor-clip export: {e}", path.display()))?;
+    if manifest.version > EXPORT_VERSION {
+        return Err(format!(
+            "{} was written by a newer cursor-clip (export version {})",
+            path.display(),
+            manifest.version
+        ));
+    }
+
+    manifest
+        .items
+        .into_iter()
+        .map(|item| {
+            let mut mime_data = IndexMap::new();
+            for (mime, encoded) in item.mime_data {
+                let payload = BASE64
+                    .decode(encoded)
+                    .map_err(|e| format!("Invalid {mime} payload: {e}"))?;
+                mime_data.insert(mime, Bytes::from(payload));
+            }
+            for (mime, name) in item.payload_files {
+                let payload = files
+                    .get(&name)
+                    .ok_or_else(|| format!("Missing payload file {name}"))?;
+                mime_data.insert(mime, payload.clone());
+            }
+            Ok(ImportedItem {
+                timestamp: item.timestamp,
+                pinned: item.pinned,
+                seat: item.seat,
+                mime_data,
+            })
+        })
+        .collect()
+}
+
+/// Append one regular file to a ustar archive.
+fn append_tar_entry(tar: &mut Vec<u8>, name: &str, data: &[u8], mtime: u64) -> Result<(), String> {
+    if name.len() >= 100 {
+        return Err(format!("Archive entry name too long: {name}"));
+    }
+    let mut header = [0u8; TAR_BLOCK];
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    write_octal(&mut header[100..108], 0o600);
+    write_octal(&mut header[108..116], 0);
+    write_octal(&mut header[116..124], 0);
+    write_octal(&mut header[124..136], data.len() as u64);
+    write_octal(&mut header[136..148], mtime);
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+
+    // The checksum is computed with its own field filled with spaces
+    header[148..156].fill(b' ');
+    let checksum: u64 = header.iter().map(|byte| u64::from(*byte)).sum();
+    write_octal(&mut header[148..155], checksum);
+
+    tar.extend_from_slice(&header);
+    tar.extend_from_slice(data);
+    tar.resize(tar.len().next_multiple_of(TAR_BLOCK), 0);
+    Ok(())
+}
+
+/// Zero-padded octal digits followed by a NUL, filling `field`.
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = field.len() - 1;
+    let text = format!("{value:0digits$o}");
+    field[..digits].copy_from_slice(&text.as_bytes()[text.len() - digits..]);
+    field[digits] = 0;
+}
+
+/// Regular files of a ustar archive by name.
+fn read_tar_entries(tar: &[u8]) -> Result<IndexMap<String, Bytes>, String> {
+    let mut entries = IndexMap::new();
+    let mut offset = 0;
+    while offset + TAR_BLOCK <= tar.len() {
+        let header = &tar[offset..offset + TAR_BLOCK];
+        if header.iter().all(|byte| *byte == 0) {
+            break;
+        }
+        if &header[257..262] != b"ustar" {
+            return Err("Not a cursor-clip export (unknown file format)".to_string());
+        }
+        let name_end = header[..100]
+            .iter()
+            .position(|byte| *byte == 0)
+            .unwrap_or(100);
+        let name = String::from_utf8_lossy(&header[..name_end]).into_owned();
+        let size = parse_octal(&header[124..136])
+            .ok_or_else(|| format!("Invalid size for archive entry {name}"))?;
+        let data_start = offset + TAR_BLOCK;
+        let data_end = data_start
+            .checked_add(size)
+            .filter(|end| *end <= tar.len())
+            .ok_or_else(|| format!("Archive entry {name} is truncated"))?;
+        // Only regular files carry data we wrote; skip directories and the like
+        if matches!(header[156], b'0' | 0) {
+            entries.insert(name, Bytes::copy_from_slice(&tar[data_start..data_end]));
+        }
+        offset = data_start + size.next_multiple_of(TAR_BLOCK);
+    }
+    Ok(entries)
+}
+
+fn parse_octal(field: &[u8]) -> Option<usize> {
+    let text = std::str::from_utf8(field).ok()?;
+    let text = text.trim_matches(|c: char| c == '\0' || c == ' ');
+    usize::from_str_radix(text, 8).ok()
+}
@@ -0,0 +1,368 @@
+//! Versioned layout of the history database. The version lives in the `schema_version`
+//! table; a database written by an older release is copied aside and then brought up to
+//! date one step at a time when it is opened. `cursor-clip config migrate --dry-run`
+//! reports what that would change.
+//!
+//! To change the layout, bump `SCHEMA_VERSION` and append a step to `MIGRATIONS` that
+//! takes a database from the previous version to the new one.
+
+use crate::backend::persistence::{
+    ENCRYPTED_PAYLOAD_PREFIX, encrypt_payload, history_db_path, open_database,
+};
+use aes_gcm::Aes256Gcm;
+use log::info;
+use std::fmt;
+use std::fs;
+use std::os::unix::fs::DirBuilderExt;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use stoolap::Database;
+
+/// Layout written by this release
+pub const SCHEMA_VERSION: u32 = 2;
+/// Databases of releases before the version table; they recorded no version
+const UNVERSIONED_SCHEMA: u32 = 1;
+
+/// A step from version `from` to `from + 1`
+struct Migration {
+    from: u32,
+    description: &'static str,
+    /// Rows the step would rewrite
+    pending_rows: fn(&Database) -> Result<usize, String>,
+    /// Rewrite the rows, returning how many were changed
+    apply: fn(&Database, &Aes256Gcm) -> Result<usize, String>,
+}
+
+const MIGRATIONS: &[Migration] = &[Migration {
+    from: 1,
+    description: "encrypt rows stored in plain text by releases before history encryption",
+    pending_rows: count_plain_rows,
+    apply: encrypt_plain_rows,
+}];
+
+/// What opening the history database would change.
+#[derive(Debug)]
+pub struct MigrationPlan {
+    pub db_path: PathBuf,
+    pub from: u32,
+    /// Steps that would run, with the rows each would rewrite
+    pub steps: Vec<(u32, &'static str, usize)>,
+}
+
+impl MigrationPlan {
+    pub fn is_current(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+impl fmt::Display for MigrationPlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_current() {
+            return write!(
+                f,
+                "History database {} is up to date (schema version {SCHEMA_VERSION})",
+                self.db_path.display()
+            );
+        }
+        writeln!(
+            f,
+            "History database {} has schema version {}; this release uses version {SCHEMA_VERSION}",
+            self.db_path.display(),
+            self.from
+        )?;
+        write!(
+            f,
+            "Migrating first copies it to {}",
+            backup_path(&self.db_path, self.from).display()
+        )?;
+        for (from, description, rows) in &self.steps {
+            write!(
+                f,
+                "\n  v{from} -> v{}: {description} ({rows} rows)",
+                from + 1
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Plan for the default history database, or None if there is none yet.
+pub fn plan_default() -> Result<Option<MigrationPlan>, String> {
+    let db_path = history_db_path();
+    if !db_path.exists() {
+        return Ok(None);
+    }
+    let db = open_database(&db_path)?;
+    plan(&db, db_path).map(Some)
+}
+
+fn plan(db: &Database, db_path: PathBuf) -> Result<MigrationPlan, String> {
+    let from = stored_version(db)?;
+    if from > SCHEMA_VERSION {
+        return Err(format!(
+            "History database {} has schema version {from}, written by a newer cursor-clip; \
+             this release only reads up to version {SCHEMA_VERSION}",
+            db_path.display()
+        ));
+    }
+    let steps = MIGRATIONS
+        .iter()
+        .filter(|step| step.from >= from)
+        .map(|step| Ok((step.from, step.description, (step.pending_rows)(db)?)))
+        .collect::<Result<_, String>>()?;
+    Ok(MigrationPlan {
+        db_path,
+        from,
+        steps,
+    })
+}
+
+/// Open the history database at `db_path`, backing it up and migrating it first if an
+/// older release wrote it.
+pub fn open_migrated(db_path: &Path, cipher: &Aes256Gcm) -> Result<Database, String> {
+    let db = open_database(db_path)?;
+    let plan = plan(&db, db_path.to_path_buf())?;
+    if plan.is_current() {
+        if recorded_version(&db) != Some(SCHEMA_VERSION) {
+            record_version(&db, SCHEMA_VERSION)?;
+        }
+        return Ok(db);
+    }
+
+    // Copied while closed, so the backup holds no half-written pages
+    drop(db);
+    let backup = backup_path(db_path, plan.from);
+    copy_recursively(db_path, &backup)
+        .map_err(|e| format!("Failed to back up history database before migrating: {e}"))?;
+    info!(
+        "Backed up history database (schema version {}) to {}",
+        plan.from,
+        backup.display()
+    );
+
+    let db = open_database(db_path)?;
+    for step in MIGRATIONS.iter().filter(|step| step.from >= plan.from) {
+        let rows = (step.apply)(&db, cipher).map_err(|e| {
+            format!(
+                "Failed to migrate history database to schema version {} (backup at {}): {e}",
+                step.from + 1,
+                backup.display()
+            )
+        })?;
+        record_version(&db, step.from + 1)?;
+        info!(
+            "Migrated history database to schema version {}: {} ({rows} rows)",
+            step.from + 1,
+            step.description
+        );
+    }
+    Ok(db)
+}
+
+/// Version of the database's layout. A fresh database has no rows and takes the current
+/// version.
+fn stored_version(db: &Database) -> Result<u32, String> {
+    if let Some(version) = recorded_version(db) {
+        return Ok(version);
+    }
+    let rows: i64 = db
+        .query_one("SELECT COUNT(*) FROM clipboard_history", ())
+        .map_err(|e| format!("Failed to count persisted items: {e}"))?;
+    Ok(if rows > 0 {
+        UNVERSIONED_SCHEMA
+    } else {
+        SCHEMA_VERSION
+    })
+}
+
+/// The version in `schema_version`; None while the table is missing or empty, so that
+/// a dry run leaves the database untouched
+fn recorded_version(db: &Database) -> Option<u32> {
+    let version: i64 = db
+        .query_one("SELECT COALESCE(MAX(version), 0) FROM schema_version", ())
+        .ok()?;
+    u32::try_from(version).ok().filter(|version| *version > 0)
+}
+
+fn record_version(db: &Database, version: u32) -> Result<(), String> {
+    let record_error = |e| format!("Failed to record history schema version {version}: {e}");
+    db.execute(
+        "CREATE TABLE IF NOT EXISTS schema_version (version BIGINT NOT NULL)",
+        (),
+    )
+    .map_err(record_error)?;
+    db.execute("DELETE FROM schema_version", ())
+        .map_err(record_error)?;
+    db.execute(
+        "INSERT INTO schema_version (version) VALUES ($1)",
+        (i64::from(version),),
+    )
+    .map_err(record_error)?;
+    Ok(())
+}
+
+/// `history.stoolap.db.v1-<unix time>.bak` next to the database, so earlier backups
+/// are never overwritten
+fn backup_path(db_path: &Path, version: u32) -> PathBuf {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    let mut name = db_path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".v{version}-{now}.bak"));
+    db_path.with_file_name(name)
+}
+
+/// Stoolap keeps a database in a directory (data files and write-ahead log)
+fn copy_recursively(from: &Path, to: &Path) -> std::io::Result<()> {
+    if from.is_dir() {
+        // The history may be unencrypted before migrating; keep the copy private
+        fs::DirBuilder::new()
+            .recursive(true)
+            .mode(0o700)
+            .create(to)?;
+        for entry in fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursively(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        fs::copy(from, to).map(drop)
+    }
+}
+
+fn plain_rows(db: &Database) -> Result<Vec<(i64, String)>, String> {
+    let rows = db
+        .query("SELECT item_id, item_json FROM clipboard_history", ())
+        .map_err(|e| format!("Failed to query persisted history: {e}"))?;
+    let mut plain = Vec::new();
+    for row in rows {
+        let row = row.map_err(|e| format!("Failed to read persisted row: {e}"))?;
+        let payload = row
+            .get::<String>(1)
+            .map_err(|e| format!("Failed to parse persisted row payload: {e}"))?;
+        if !payload.starts_with(ENCRYPTED_PAYLOAD_PREFIX) {
+            let item_id = row
+                .get::<i64>(0)
+                .map_err(|e| format!("Failed to parse persisted row id: {e}"))?;
+            plain.push((item_id, payload));
+        }
+    }
+    Ok(plain)
+}
+
+fn count_plain_rows(db: &Database) -> Result<usize, String> {
+    plain_rows(db).map(|rows| rows.len())
+}
+
+fn encrypt_plain_rows(db: &Database, cipher: &Aes256Gcm) -> Result<usize, String> {
+    let rows = plain_rows(db)?;
+    for (item_id, payload) in &rows {
+        let encrypted = encrypt_payload(cipher, payload)
+            .map_err(|e| format!("Failed to encrypt persisted item {item_id}: {e}"))?;
+        db.execute(
+            "UPDATE clipboard_history SET item_json = $1 WHERE item_id = $2",
+            (encrypted, *item_id),
+        )
+        .map_err(|e| format!("Failed to rewrite persisted item {item_id}: {e}"))?;
+    }
+    Ok(rows.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::persistence::derive_cipher;
+
+    /// A database path of its own for each test, removed with its backups when dropped
+    struct TempDb(PathBuf);
+
+    impl TempDb {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!(
+                "cursor-clip-migration-{name}-{}.db",
+                std::process::id()
+            ));
+            let db = Self(path);
+            db.remove();
+            db
+        }
+
+        fn backups(&self) -> Vec<PathBuf> {
+            let name = self.0.file_name().unwrap().to_string_lossy().into_owned();
+            fs::read_dir(self.0.parent().unwrap())
+                .unwrap()
+                .filter_map(|entry| Some(entry.ok()?.path()))
+                .filter(|path| {
+                    path.file_name().is_some_and(|file| {
+                        file.to_string_lossy().starts_with(&format!("{name}.v"))
+                    })
+                })
+                .collect()
+        }
+
+        fn remove(&self) {
+            for path in self.backups().into_iter().chain([self.0.clone()]) {
+                let _ = fs::remove_dir_all(&path);
+                let _ = fs::remove_file(&path);
+            }
+        }
+    }
+
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            self.remove();
+        }
+    }
+
+    fn insert_plain_row(db: &Database, item_id: i64) {
+        db.execute(
+            "INSERT INTO clipboard_history (item_id, item_json, created_ts, pinned) VALUES ($1, $2, $3, $4)",
+            (item_id, format!("{{\"item_id\":{item_id}}}"), item_id, false),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn fresh_database_is_current() {
+        let temp = TempDb::new("fresh");
+        let db = open_database(&temp.0).unwrap();
+        let plan = plan(&db, temp.0.clone()).unwrap();
+        assert!(plan.is_current());
+        assert_eq!(plan.from, SCHEMA_VERSION);
+    }
+
+    #[test]
+    fn plain_rows_are_backed_up_then_encrypted() {
+        let temp = TempDb::new("plain");
+        let db = open_database(&temp.0).unwrap();
+        insert_plain_row(&db, 1);
+        insert_plain_row(&db, 2);
+
+        let plan = plan(&db, temp.0.clone()).unwrap();
+        assert_eq!(plan.from, UNVERSIONED_SCHEMA);
+        assert_eq!(plan.steps.len(), 1);
+        assert_eq!(plan.steps[0].2, 2);
+        // A dry run leaves the database as it was
+        assert_eq!(recorded_version(&db), None);
+        drop(db);
+
+        let db = open_migrated(&temp.0, &derive_cipher("test password")).unwrap();
+        assert_eq!(recorded_version(&db), Some(SCHEMA_VERSION));
+        assert_eq!(count_plain_rows(&db).unwrap(), 0);
+        assert_eq!(temp.backups().len(), 1);
+        drop(db);
+
+        // Opening it again changes nothing more
+        let db = open_migrated(&temp.0, &derive_cipher("test password")).unwrap();
+        assert_eq!(recorded_version(&db), Some(SCHEMA_VERSION));
+        assert_eq!(temp.backups().len(), 1);
+    }
+
+    #[test]
+    fn database_of_a_newer_release_is_refused() {
+        let temp = TempDb::new("newer");
+        let db = open_database(&temp.0).unwrap();
+        record_version(&db, SCHEMA_VERSION + 1).unwrap();
+        assert!(plan(&db, temp.0.clone()).is_err());
+    }
+}
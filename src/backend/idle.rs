@@ -0,0 +1,81 @@
+use crate::backend::wayland_clipboard::MutexBackendState;
+use log::{debug, info};
+use wayland_client::globals::GlobalList;
+use wayland_client::protocol::wl_seat::WlSeat;
+use wayland_client::{Connection, Dispatch, QueueHandle};
+use wayland_protocols::ext::idle_notify::v1::client::{
+    ext_idle_notification_v1::{self, ExtIdleNotificationV1},
+    ext_idle_notifier_v1::ExtIdleNotifierV1,
+};
+
+/// Tracks whether the session is idle through ext-idle-notify. While idle the daemon
+/// skips periodic maintenance and does not re-own selections, which are then most
+/// likely set by background jobs rather than the user.
+#[derive(Debug, Default)]
+pub struct IdleTracker {
+    notification: Option<ExtIdleNotificationV1>,
+    idle: bool,
+}
+
+impl IdleTracker {
+    pub fn bind(
+        &mut self,
+        globals: &GlobalList,
+        qh: &QueueHandle<MutexBackendState>,
+        seat: &WlSeat,
+        timeout_secs: u64,
+    ) {
+        if self.notification.is_some() {
+            return;
+        }
+        match globals.bind::<ExtIdleNotifierV1, _, _>(qh, 1..=1, ()) {
+            Ok(notifier) => {
+                let timeout_ms =
+                    u32::try_from(timeout_secs.saturating_mul(1000)).unwrap_or(u32::MAX);
+                self.notification = Some(notifier.get_idle_notification(timeout_ms, seat, qh, ()));
+                debug!("Watching for {timeout_secs} s of inactivity via ext_idle_notifier_v1");
+            }
+            Err(_) => info!("Compositor lacks ext_idle_notifier_v1; idle throttling is disabled"),
+        }
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.idle
+    }
+}
+
+impl Dispatch<ExtIdleNotifierV1, ()> for MutexBackendState {
+    fn event(
+        _: &mut Self,
+        _: &ExtIdleNotifierV1,
+        _: <ExtIdleNotifierV1 as wayland_client::Proxy>::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ExtIdleNotificationV1, ()> for MutexBackendState {
+    fn event(
+        wrapper: &mut Self,
+        _: &ExtIdleNotificationV1,
+        event: ext_idle_notification_v1::Event,
+        _: &(),
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        let mut state = wrapper.backend_state.lock().unwrap();
+        match event {
+            ext_idle_notification_v1::Event::Idled => {
+                debug!("Session idle; pausing maintenance and selection takeover");
+                state.idle_tracker.idle = true;
+            }
+            ext_idle_notification_v1::Event::Resumed => {
+                debug!("Session active again");
+                state.idle_tracker.idle = false;
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use bytes::Bytes;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat, ImageReader};
+use indexmap::IndexMap;
+use log::{debug, warn};
+use std::io::Cursor;
+
+use crate::shared::ImageInfo;
+
+pub const CANONICAL_IMAGE_MIME: &str = "image/png";
+
+// Image MIME types we can decode and convert to PNG, in order of preference
+// (lossless formats first so conversion does not compound compression artifacts).
+const CONVERTIBLE_IMAGE_MIMES: &[&str] = &[
+    "image/png",
+    "image/bmp",
+    "image/x-bmp",
+    "image/tiff",
+    "image/webp",
+    "image/gif",
+    "image/jpeg",
+    "image/jpg",
+];
+
+// Downscale attempts before giving up on reaching the byte limit.
+const MAX_DOWNSCALE_PASSES: u32 = 4;
+
+/// Pick the single image MIME type worth reading from an offer. Compositors and
+/// screenshot tools often advertise the same picture in many formats; reading
+/// them all would multiply huge payloads for no benefit.
+pub fn preferred_image_mime(available_mimes: &[String]) -> Option<&str> {
+    CONVERTIBLE_IMAGE_MIMES
+        .iter()
+        .find_map(|preferred| {
+            available_mimes
+                .iter()
+                .find(|m| m.as_str() == *preferred)
+                .map(String::as_str)
+        })
+        .or_else(|| {
+            available_mimes
+                .iter()
+                .find(|m| m.starts_with("image/"))
+                .map(String::as_str)
+        })
+}
+
+/// Normalize the image payload of a freshly read selection in place:
+/// convert it to PNG and, when it exceeds `max_bytes` (0 = unlimited), downscale
+/// until it fits. Non-image MIME entries are left untouched.
+pub fn normalize_image_payload(mime_map: &mut IndexMap<String, Bytes>, max_bytes: u64) {
+    let Some((index, mime, bytes)) = mime_map
+        .iter()
+        .enumerate()
+        .find(|(_, (mime, _))| mime.starts_with("image/"))
+        .map(|(index, (mime, bytes))| (index, mime.clone(), bytes.clone()))
+    else {
+        return;
+    };
+
+    let within_limit = max_bytes == 0 || bytes.len() as u64 <= max_bytes;
+    if mime == CANONICAL_IMAGE_MIME && within_limit {
+        return;
+    }
+
+    let png = match image::load_from_memory(&bytes) {
+        Ok(decoded) => encode_png_within_limit(decoded, max_bytes),
+        Err(e) => {
+            warn!(
+                "Could not decode {mime} clipboard image ({} bytes): {e}",
+                bytes.len()
+            );
+            return;
+        }
+    };
+
+    let Some(png) = png else {
+        warn!("Failed to convert {mime} clipboard image to PNG");
+        return;
+    };
+
+    debug!(
+        "Normalized {mime} image ({} bytes) to PNG ({} bytes)",
+        bytes.len(),
+        png.len()
+    );
+    mime_map.shift_remove(&mime);
+    mime_map.shift_insert(index, CANONICAL_IMAGE_MIME.to_string(), png);
+}
+
+fn encode_png_within_limit(mut image: DynamicImage, max_bytes: u64) -> Option<Bytes> {
+    let mut encoded = encode_png(&image)?;
+
+    for _ in 0..MAX_DOWNSCALE_PASSES {
+        if max_bytes == 0 || encoded.len() as u64 <= max_bytes {
+            break;
+        }
+
+        // PNG size scales roughly with pixel count, so shrink both sides by sqrt(ratio)
+        // (with a little headroom so we usually converge in one pass).
+        let ratio = (max_bytes as f64 / encoded.len() as f64).sqrt() * 0.9;
+        let width = ((image.width() as f64 * ratio) as u32).max(1);
+        let height = ((image.height() as f64 * ratio) as u32).max(1);
+        debug!(
+            "PNG is {} bytes (limit {max_bytes}); downscaling {}x{} -> {width}x{height}",
+            encoded.len(),
+            image.width(),
+            image.height()
+        );
+        image = image.resize_exact(width, height, FilterType::Triangle);
+        encoded = encode_png(&image)?;
+    }
+
+    if max_bytes != 0 && encoded.len() as u64 > max_bytes {
+        warn!(
+            "Clipboard image still exceeds {max_bytes} bytes after downscaling ({} bytes)",
+            encoded.len()
+        );
+    }
+    Some(encoded)
+}
+
+fn encode_png(image: &DynamicImage) -> Option<Bytes> {
+    let mut buffer = Cursor::new(Vec::new());
+    image.write_to(&mut buffer, ImageFormat::Png).ok()?;
+    Some(Bytes::from(buffer.into_inner()))
+}
+
+/// Read dimensions from the image header without decoding the pixel data.
+pub fn image_info(mime: &str, bytes: &Bytes) -> Option<ImageInfo> {
+    let (width, height) = ImageReader::new(Cursor::new(bytes.as_ref()))
+        .with_guessed_format()
+        .ok()?
+        .into_dimensions()
+        .ok()?;
+
+    Some(ImageInfo {
+        width,
+        height,
+        format: mime
+            .strip_prefix("image/")
+            .unwrap_or(mime)
+            .to_ascii_uppercase(),
+        byte_size: bytes.len() as u64,
+    })
+}
@@ -1,93 +1,404 @@
 use std::sync::{Arc, Mutex};
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
 use tokio::net::{UnixListener, UnixStream};
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::sync::broadcast;
 
+use super::access_log::AccessLog;
 use super::backend_state::BackendState;
-use super::wayland_clipboard::WaylandClipboardMonitor;
-use crate::shared::{BackendMessage, FrontendMessage};
-use log::{error, info};
+use super::clipboard_portal::ClipboardPortalMonitor;
+use super::dbus_service::spawn_dbus_service;
+use super::metrics::{record_ipc_latency, spawn_metrics_server};
+use super::power::spawn_power_monitor;
+use super::session_lock::spawn_lock_monitor;
+use super::sync::spawn_sync_service;
+use super::wayland_clipboard::{WaylandClipboardMonitor, data_control_available};
+use super::x11_clipboard::X11ClipboardMonitor;
+use crate::shared::runtime_dir::{
+    LEGACY_SOCKET_NAME, SOCKET_NAME, current_uid, ensure_private_runtime_dir, frontend_socket_path,
+};
+use crate::shared::sandbox::in_flatpak;
+use crate::shared::{
+    BackendMessage, BackendResponse, CursorClipError, CursorClipResult, FULL_PREVIEW_BYTES,
+    FrontendMessage, MAX_FULL_PREVIEW_BYTES,
+};
+use log::{debug, error, info, warn};
+use std::os::unix::fs::PermissionsExt;
 
-pub async fn run_backend(monitor_only: bool) -> Result<(), Box<dyn std::error::Error>> {
-    let xdg_runtime_dir = std::env::var("XDG_RUNTIME_DIR")?;
-    let socket_dir = format!("{xdg_runtime_dir}/cursor-clip");
-    std::fs::create_dir_all(&socket_dir)?;
+/// Byte stream a client speaks the IPC protocol over: an accepted Unix socket, or an
+/// in-memory `tokio::io::duplex` stream when exercising the protocol without a daemon
+pub trait IpcStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> IpcStream for T {}
+
+/// Longest request line accepted; `AddItem` payloads of large images stay well below it.
+/// Longer lines are skipped up to their newline and answered with an error.
+const MAX_REQUEST_BYTES: usize = 64 * 1024 * 1024;
+/// A connection that sends no complete request for this long is closed; the overlay
+/// reconnects on its next action
+const IDLE_CLIENT_TIMEOUT: Duration = Duration::from_secs(300);
+/// Requests a connection may send in a burst, refilled at `REQUESTS_PER_SECOND`; beyond
+/// that the connection is throttled rather than answered with errors
+const REQUEST_BURST: f64 = 100.0;
+const REQUESTS_PER_SECOND: f64 = 50.0;
+
+const RETENTION_INTERVAL: Duration = Duration::from_secs(60);
+const RETENTION_INTERVAL_BATTERY: Duration = Duration::from_secs(600);
+
+pub async fn run_backend(
+    monitor_only: bool,
+    persist_on_exit: bool,
+    demo: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Only the owner may enter the directory, so nobody else can reach the socket
+    let socket_dir = ensure_private_runtime_dir()?;
 
     // Remove existing socket if it exists
-    let socket_path = format!("{socket_dir}/cursor-clip.sock");
+    let socket_path = socket_dir.join(SOCKET_NAME);
     let _ = std::fs::remove_file(&socket_path);
 
     // Create Unix socket for IPC
     let listener = UnixListener::bind(&socket_path)?;
-    info!("Clipboard backend listening on {socket_path}");
+    std::fs::set_permissions(&socket_path, std::fs::Permissions::from_mode(0o600))?;
+    info!("Clipboard backend listening on {}", socket_path.display());
 
-    let state = Arc::new(Mutex::new(BackendState::new(monitor_only)));
+    let state = Arc::new(Mutex::new(BackendState::new(monitor_only, demo)));
+    if persist_on_exit {
+        state.lock().unwrap().config.persist_on_exit = true;
+    }
 
-    // Start Wayland clipboard monitoring in a separate task
+    // Clients of earlier releases connect to the old socket name
+    let legacy_socket_path = socket_dir.join(LEGACY_SOCKET_NAME);
+    let _ = std::fs::remove_file(&legacy_socket_path);
+    if state.lock().unwrap().config.legacy_socket_path {
+        match std::os::unix::fs::symlink(SOCKET_NAME, &legacy_socket_path) {
+            Ok(()) => info!("Also serving IPC on {}", legacy_socket_path.display()),
+            Err(e) => warn!("Failed to create legacy socket link: {e}"),
+        }
+    }
+
+    // Opt-in audit trail of IPC commands for multi-user/kiosk deployments
+    let access_log = {
+        let state = state.lock().unwrap();
+        state.config.ipc_access_log.then(|| {
+            info!("IPC access log enabled");
+            Arc::new(AccessLog::open_default(
+                state.config.ipc_access_log_retention_days,
+            ))
+        })
+    };
+
+    // Start clipboard monitoring in a separate thread. Without Wayland data-control fall
+    // back to the clipboard portal inside a Flatpak sandbox (the compositor may hide the
+    // protocol from sandboxed clients), else to the X11 clipboard (plain X11 sessions,
+    // compositors lacking the protocol). The monitors block for the daemon's lifetime;
+    // as runtime tasks they would keep the runtime from shutting down on SIGTERM.
     let wayland_state = state.clone();
+    let runtime = tokio::runtime::Handle::current();
+    std::thread::Builder::new()
+        .name("cursor-clip-monitor".to_string())
+        .spawn(move || {
+            // The Wayland monitor runs its payload reads and writes on the runtime
+            let _runtime = runtime.enter();
+            if !data_control_available() && in_flatpak() {
+                info!(
+                    "Wayland data-control unavailable in the sandbox; using the clipboard portal"
+                );
+                let monitor = ClipboardPortalMonitor::new(wayland_state);
+                if let Err(e) = monitor.start_monitoring() {
+                    error!("Clipboard portal monitoring error: {e}");
+                }
+                return;
+            }
+            if !data_control_available() && std::env::var_os("DISPLAY").is_some() {
+                info!("Wayland data-control unavailable; monitoring the X11 clipboard instead");
+                let monitor = X11ClipboardMonitor::new(wayland_state);
+                if let Err(e) = monitor.start_monitoring() {
+                    error!("X11 clipboard monitoring error: {e}");
+                }
+                return;
+            }
+            let monitor = WaylandClipboardMonitor::new(wayland_state);
+            if let Err(e) = monitor.start_monitoring() {
+                error!("Wayland clipboard monitoring error: {e}");
+            }
+        })?;
+
+    let metrics_address = {
+        let state = state.lock().unwrap();
+        state
+            .config
+            .metrics_enabled
+            .then(|| state.config.metrics_address.clone())
+    };
+    if let Some(address) = metrics_address {
+        spawn_metrics_server(&address, state.clone());
+    }
+
+    // Enforce age limits even when nothing new is copied; the first tick also prunes
+    // a freshly loaded persistent history. Trashed items expire on the same tick.
+    let retention_state = state.clone();
+    tokio::spawn(async move {
+        loop {
+            let period = {
+                let mut state = retention_state.lock().unwrap();
+                state.empty_expired_trash();
+                // Nothing is being copied while idle; catch up on the first tick after resume
+                if !state.idle_tracker.is_idle() {
+                    state.enforce_retention();
+                }
+                if state.battery_saving() {
+                    RETENTION_INTERVAL_BATTERY
+                } else {
+                    RETENTION_INTERVAL
+                }
+            };
+            tokio::time::sleep(period).await;
+        }
+    });
+
+    // Write batched history changes once `persistence_write_delay_ms` has passed since
+    // the first of them
+    let persistence_state = state.clone();
+    let persistence_wakeup = state.lock().unwrap().persistence_wakeup.clone();
     tokio::spawn(async move {
-        let monitor = WaylandClipboardMonitor::new(wayland_state);
-        if let Err(e) = monitor.start_monitoring() {
-            error!("Wayland clipboard monitoring error: {e}");
+        loop {
+            persistence_wakeup.notified().await;
+            let delay = persistence_state.lock().unwrap().persistence_write_delay();
+            tokio::time::sleep(delay).await;
+            persistence_state.lock().unwrap().flush_history();
         }
     });
 
-    // Add some sample data only in debug builds (helps during development without polluting release)
-    #[cfg(debug_assertions)]
-    {
-        let mut state_lock = state.lock().unwrap();
-        for sample in [
-            "Hello, world Cursor-Clip!",
-            "https://github.com/rust-lang/rust",
-            "Sample clipboard content for testing the clipboard manager",
-            "impl Display for MyStruct {\n    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {\n        write!(f, \"MyStruct\")\n    }\n}",
-            "Password4234!Cursor-Clip",
-        ] {
-            let _ = state_lock.add_clipboard_item_from_text(sample);
+    // Flush written changes to disk every `persistence_sync_interval_secs`
+    let sync_state = state.clone();
+    tokio::spawn(async move {
+        loop {
+            let period = sync_state.lock().unwrap().persistence_sync_interval();
+            tokio::time::sleep(period).await;
+            sync_state.lock().unwrap().sync_history();
         }
+    });
+
+    // Reduce background work on battery; follows UPower on the system bus
+    if state.lock().unwrap().config.battery_saver {
+        spawn_power_monitor(state.clone());
+    }
+
+    // Clear the selection and/or passwords when the session locks; follows logind
+    if state.lock().unwrap().config.on_lock.enabled() {
+        spawn_lock_monitor(state.clone());
     }
 
-    // Handle IPC connections
+    // Expose the same operations over D-Bus for desktop integrations and scripts
+    spawn_dbus_service(state.clone());
+
+    // Replicate history with the peers in `[sync]`; fixture data stays local
+    if !demo {
+        spawn_sync_service(state.clone());
+    }
+
+    let mut terminate = signal(SignalKind::terminate())?;
+    let mut interrupt = signal(SignalKind::interrupt())?;
+
+    // Handle IPC connections until asked to stop
     loop {
-        let (stream, _addr) = listener.accept().await?;
+        let (stream, _addr) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            _ = terminate.recv() => break,
+            _ = interrupt.recv() => break,
+        };
         let state_clone = state.clone();
+        let access_log = access_log.clone();
 
         tokio::spawn(async move {
-            if let Err(e) = handle_client(stream, state_clone).await {
+            if let Err(e) = handle_client(stream, state_clone, access_log).await {
                 error!("Client error: {e}");
             }
         });
     }
+
+    // Batched history changes must not be lost when the session ends
+    info!("Shutting down; writing pending history changes");
+    let mut state = state.lock().unwrap();
+    state.flush_history();
+    state.sync_history();
+    Ok(())
 }
 
 async fn handle_client(
     stream: UnixStream,
     state: Arc<Mutex<BackendState>>,
+    access_log: Option<Arc<AccessLog>>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let (reader, mut writer) = stream.into_split();
-    let mut lines = BufReader::new(reader).lines();
+    let peer_cred = stream.peer_cred()?;
+    // The directory permissions already keep other users out; this also covers
+    // sockets reached through a leaked file descriptor or a loosened directory
+    if peer_cred.uid() != current_uid() {
+        warn!(
+            "Rejecting IPC client with uid {} (pid {:?})",
+            peer_cred.uid(),
+            peer_cred.pid()
+        );
+        return Ok(());
+    }
+    serve_connection(stream, peer_cred.pid(), state, access_log).await
+}
 
-    while let Some(line) = lines.next_line().await? {
-        let message: FrontendMessage = serde_json::from_str(&line)?;
+/// Answer the newline-delimited JSON requests of one client until it disconnects,
+/// independent of the transport so the protocol handling runs over any `IpcStream`.
+pub(crate) async fn serve_connection(
+    stream: impl IpcStream,
+    client_pid: Option<i32>,
+    state: Arc<Mutex<BackendState>>,
+    access_log: Option<Arc<AccessLog>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (reader, mut writer) = tokio::io::split(stream);
+    let mut reader = BufReader::new(reader);
+    let mut line = Vec::new();
+    let mut rate_limit = RateLimit::new();
+
+    loop {
+        let read = tokio::time::timeout(
+            IDLE_CLIENT_TIMEOUT,
+            read_request_line(&mut reader, &mut line, MAX_REQUEST_BYTES),
+        )
+        .await;
+        let complete = match read {
+            Ok(Ok(Some(complete))) => complete,
+            Ok(Ok(None)) => break,
+            Ok(Err(e)) => return Err(e.into()),
+            Err(_) => {
+                debug!("Closing IPC connection idle for {IDLE_CLIENT_TIMEOUT:?}");
+                break;
+            }
+        };
+        rate_limit.acquire().await;
+
+        // Answer instead of dropping the connection so the client sees what was wrong
+        let parsed = if complete {
+            serde_json::from_slice::<FrontendMessage>(&line)
+                .map_err(|e| CursorClipError::InvalidRequest(e.to_string()))
+        } else {
+            warn!("Rejecting IPC request over {MAX_REQUEST_BYTES} bytes");
+            Err(CursorClipError::InvalidRequest(format!(
+                "request exceeds {MAX_REQUEST_BYTES} bytes"
+            )))
+        };
+        let message = match parsed {
+            Ok(message) => message,
+            Err(error) => {
+                let revision = state.lock().unwrap().history_revision;
+                write_response(&mut writer, BackendMessage::Error { error }, revision).await?;
+                continue;
+            }
+        };
+        let (command, item_id) = (message.name(), message.item_id());
+        let started = Instant::now();
+        let mut events = None;
 
         let response = match message {
+            FrontendMessage::Subscribe => {
+                // Subscribe before answering so no event in between is missed
+                events = Some(state.lock().unwrap().events.subscribe());
+                BackendMessage::Subscribed
+            }
             FrontendMessage::GetHistory => {
-                let state = state.lock().unwrap();
+                let mut state = state.lock().unwrap();
                 BackendMessage::History {
                     items: state.get_history(),
                 }
             }
-            FrontendMessage::SetClipboardById { id, instant_paste } => {
+            FrontendMessage::GetHistorySince { since_revision } => {
                 let mut state = state.lock().unwrap();
-                match state.set_clipboard_by_id(id, instant_paste) {
+                match state.history_since(since_revision) {
+                    Some(delta) => BackendMessage::HistoryDelta { delta },
+                    None => BackendMessage::History {
+                        items: state.get_history(),
+                    },
+                }
+            }
+            FrontendMessage::GetHistoryPage {
+                offset,
+                limit,
+                view,
+                order,
+            } => {
+                let mut state = state.lock().unwrap();
+                BackendMessage::HistoryPage {
+                    page: state.get_history_page(offset, limit, view, order),
+                }
+            }
+            FrontendMessage::GetStats => {
+                let state = state.lock().unwrap();
+                BackendMessage::Stats {
+                    stats: state.stats(),
+                }
+            }
+            FrontendMessage::GetItemDetails { id } => {
+                let state = state.lock().unwrap();
+                match state.item_details(id) {
+                    Ok(details) => BackendMessage::ItemDetails { details },
+                    Err(error) => BackendMessage::Error { error },
+                }
+            }
+            FrontendMessage::GetItemData { id, mime_type } => {
+                let state = state.lock().unwrap();
+                match state.item_data(id, &mime_type) {
+                    Ok(data) => BackendMessage::ItemData { mime_type, data },
+                    Err(error) => BackendMessage::Error { error },
+                }
+            }
+            FrontendMessage::GetItemFullPreview { id, max_bytes } => {
+                let max_bytes = max_bytes
+                    .unwrap_or(FULL_PREVIEW_BYTES)
+                    .min(MAX_FULL_PREVIEW_BYTES);
+                let state = state.lock().unwrap();
+                match state.item_full_preview(id, max_bytes) {
+                    Ok((text, truncated)) => BackendMessage::ItemFullPreview {
+                        id,
+                        text,
+                        truncated,
+                    },
+                    Err(error) => BackendMessage::Error { error },
+                }
+            }
+            FrontendMessage::GetRecoveredItem => {
+                let state = state.lock().unwrap();
+                BackendMessage::RecoveredItem {
+                    id: state.recovered_item,
+                }
+            }
+            FrontendMessage::DismissRecoveredItem => {
+                let mut state = state.lock().unwrap();
+                state.recovered_item = None;
+                BackendMessage::RecoveredItem { id: None }
+            }
+            FrontendMessage::SetClipboardById {
+                id,
+                instant_paste,
+                seat,
+                mime_filter,
+            } => {
+                let mut state = state.lock().unwrap();
+                match state.set_clipboard_by_id(id, instant_paste, seat.as_deref(), mime_filter) {
+                    Ok(()) => BackendMessage::ClipboardSet,
+                    Err(error) => BackendMessage::Error { error },
+                }
+            }
+            FrontendMessage::SetPrimaryById { id, seat } => {
+                let mut state = state.lock().unwrap();
+                match state.set_primary_by_id(id, seat.as_deref()) {
                     Ok(()) => BackendMessage::ClipboardSet,
-                    Err(e) => BackendMessage::Error { message: e },
+                    Err(error) => BackendMessage::Error { error },
                 }
             }
             FrontendMessage::SetPinned { id, pinned } => {
                 let mut state = state.lock().unwrap();
                 match state.set_pinned(id, pinned) {
                     Ok(()) => BackendMessage::ItemPinned { id, pinned },
-                    Err(e) => BackendMessage::Error { message: e },
+                    Err(error) => BackendMessage::Error { error },
                 }
             }
             FrontendMessage::ClearHistory => {
@@ -95,28 +406,500 @@ async fn handle_client(
                 state.clear_history();
                 BackendMessage::HistoryCleared
             }
+            FrontendMessage::UndoLastOperation => {
+                let mut state = state.lock().unwrap();
+                match state.undo_last_operation() {
+                    Ok(count) => BackendMessage::ItemsRestored { count },
+                    Err(error) => BackendMessage::Error { error },
+                }
+            }
             FrontendMessage::SetHistoryPersistence { enabled } => {
                 let mut state = state.lock().unwrap();
                 match state.set_persistence_enabled(enabled) {
                     Ok(()) => BackendMessage::PersistenceState {
                         enabled: state.persistence_enabled,
                     },
-                    Err(e) => BackendMessage::Error { message: e },
+                    Err(error) => BackendMessage::Error { error },
+                }
+            }
+            FrontendMessage::SetRetentionPolicy { policy } => {
+                let mut state = state.lock().unwrap();
+                let pruned = state.set_retention_policy(policy);
+                BackendMessage::RetentionPolicyUpdated { policy, pruned }
+            }
+            FrontendMessage::Status => {
+                let state = state.lock().unwrap();
+                BackendMessage::Status {
+                    status: state.status(),
+                }
+            }
+            FrontendMessage::GetConfig => {
+                let state = state.lock().unwrap();
+                BackendMessage::Config {
+                    settings: state.daemon_settings(),
+                }
+            }
+            FrontendMessage::SetConfig { settings } => {
+                let mut state = state.lock().unwrap();
+                match state.apply_daemon_settings(settings) {
+                    Ok(()) => BackendMessage::Config {
+                        settings: state.daemon_settings(),
+                    },
+                    Err(error) => BackendMessage::Error { error },
+                }
+            }
+            FrontendMessage::SetMonitorOnly { enabled } => {
+                let mut state = state.lock().unwrap();
+                state.set_monitor_only(enabled);
+                BackendMessage::MonitorOnlyState { enabled }
+            }
+            FrontendMessage::SetRecordingEnabled { enabled } => {
+                let mut state = state.lock().unwrap();
+                state.set_recording_enabled(enabled);
+                BackendMessage::RecordingState { enabled }
+            }
+            FrontendMessage::GetRecordingState => {
+                let state = state.lock().unwrap();
+                BackendMessage::RecordingState {
+                    enabled: state.recording_enabled,
+                }
+            }
+            FrontendMessage::SetExcludedApps { apps } => {
+                let mut state = state.lock().unwrap();
+                state.set_excluded_apps(apps.clone());
+                BackendMessage::ExcludedAppsUpdated { apps }
+            }
+            FrontendMessage::TransformAndSet {
+                id,
+                transform,
+                instant_paste,
+                seat,
+            } => {
+                let mut state = state.lock().unwrap();
+                match state.transform_and_set(id, transform, instant_paste, seat.as_deref()) {
+                    Ok(item) => BackendMessage::ItemTransformed {
+                        source_id: id,
+                        item,
+                    },
+                    Err(error) => BackendMessage::Error { error },
+                }
+            }
+            FrontendMessage::MergeAndSet {
+                ids,
+                separator,
+                instant_paste,
+                seat,
+            } => {
+                let mut state = state.lock().unwrap();
+                match state.merge_and_set(&ids, separator, instant_paste, seat.as_deref()) {
+                    Ok(item) => BackendMessage::ItemsMerged {
+                        source_ids: ids,
+                        item,
+                    },
+                    Err(error) => BackendMessage::Error { error },
+                }
+            }
+            FrontendMessage::PushPasteQueue { ids, seat } => {
+                let mut state = state.lock().unwrap();
+                match state.push_paste_queue(&ids, seat.as_deref()) {
+                    Ok(ids) => BackendMessage::PasteQueue { ids },
+                    Err(error) => BackendMessage::Error { error },
+                }
+            }
+            FrontendMessage::PopPasteQueue {
+                instant_paste,
+                seat,
+            } => {
+                let mut state = state.lock().unwrap();
+                match state.pop_paste_queue(instant_paste, seat.as_deref()) {
+                    Ok(ids) => BackendMessage::PasteQueue { ids },
+                    Err(error) => BackendMessage::Error { error },
+                }
+            }
+            FrontendMessage::GetPasteQueue => {
+                let state = state.lock().unwrap();
+                BackendMessage::PasteQueue {
+                    ids: state.paste_queue.iter().copied().collect(),
+                }
+            }
+            FrontendMessage::ClearPasteQueue => {
+                let mut state = state.lock().unwrap();
+                state.paste_queue.clear();
+                BackendMessage::PasteQueue { ids: Vec::new() }
+            }
+            FrontendMessage::ReclassifyHistory => {
+                let mut state = state.lock().unwrap();
+                BackendMessage::HistoryReclassified {
+                    changed: state.reclassify_history(),
                 }
             }
             FrontendMessage::DeleteItemById { id } => {
                 let mut state = state.lock().unwrap();
                 match state.delete_item_by_id(id) {
                     Ok(()) => BackendMessage::ItemDeleted { id },
-                    Err(e) => BackendMessage::Error { message: e },
+                    Err(error) => BackendMessage::Error { error },
+                }
+            }
+            FrontendMessage::ExportHistory {
+                path,
+                format,
+                include_sensitive,
+            } => {
+                let state = state.lock().unwrap();
+                match state.export_history(&path, format, include_sensitive) {
+                    Ok(count) => BackendMessage::HistoryExported { path, count },
+                    Err(error) => BackendMessage::Error { error },
+                }
+            }
+            FrontendMessage::SearchHistory { query, view, limit } => {
+                let mut state = state.lock().unwrap();
+                match state.search_history(&query, view, limit) {
+                    Ok(results) => BackendMessage::SearchResults { results },
+                    Err(error) => BackendMessage::Error { error },
+                }
+            }
+            FrontendMessage::GetSnippets => {
+                let mut state = state.lock().unwrap();
+                BackendMessage::Snippets {
+                    snippets: state.snippets(),
+                }
+            }
+            FrontendMessage::AddSnippet { snippet } => {
+                let mut state = state.lock().unwrap();
+                match state.add_snippet(snippet) {
+                    Ok(snippets) => BackendMessage::Snippets { snippets },
+                    Err(error) => BackendMessage::Error { error },
+                }
+            }
+            FrontendMessage::RemoveSnippet { id } => {
+                let mut state = state.lock().unwrap();
+                match state.remove_snippet(id) {
+                    Ok(snippets) => BackendMessage::Snippets { snippets },
+                    Err(error) => BackendMessage::Error { error },
+                }
+            }
+            FrontendMessage::PasteSnippet {
+                id,
+                instant_paste,
+                seat,
+            } => {
+                let mut state = state.lock().unwrap();
+                match state.paste_snippet(id, instant_paste, seat.as_deref()) {
+                    Ok(()) => BackendMessage::ClipboardSet,
+                    Err(error) => BackendMessage::Error { error },
+                }
+            }
+            FrontendMessage::GetRecentEmoji => {
+                let state = state.lock().unwrap();
+                BackendMessage::RecentEmoji {
+                    emoji: state.recent_emoji(),
+                }
+            }
+            FrontendMessage::PasteEmoji {
+                emoji,
+                instant_paste,
+                seat,
+            } => {
+                let mut state = state.lock().unwrap();
+                match state.paste_emoji(&emoji, instant_paste, seat.as_deref()) {
+                    Ok(()) => BackendMessage::ClipboardSet,
+                    Err(error) => BackendMessage::Error { error },
+                }
+            }
+            FrontendMessage::ImportHistory { path } => {
+                let mut state = state.lock().unwrap();
+                match state.import_history(&path) {
+                    Ok((imported, skipped)) => {
+                        BackendMessage::HistoryImported { imported, skipped }
+                    }
+                    Err(error) => BackendMessage::Error { error },
+                }
+            }
+            FrontendMessage::AddItem {
+                mime_data,
+                pin,
+                select,
+            } => {
+                let mut state = state.lock().unwrap();
+                match state.add_item(mime_data, pin, select) {
+                    Ok(id) => BackendMessage::ItemAdded { id },
+                    Err(error) => BackendMessage::Error { error },
+                }
+            }
+            message @ (FrontendMessage::ShowAt { .. } | FrontendMessage::Close) => {
+                match relay_to_frontend(&message).await {
+                    Ok(response) => response,
+                    Err(error) => BackendMessage::Error { error },
                 }
             }
         };
 
-        let response_json = serde_json::to_string(&response)?;
-        writer.write_all(response_json.as_bytes()).await?;
-        writer.write_all(b"\n").await?;
+        record_ipc_latency(started.elapsed());
+        if let BackendMessage::Error { error } = &response {
+            state
+                .lock()
+                .unwrap()
+                .record_error(format!("{command}: {error}"));
+        }
+
+        if let Some(log) = &access_log {
+            let ok = !matches!(response, BackendMessage::Error { .. });
+            log.record(client_pid, command, item_id, ok);
+        }
+
+        let revision = state.lock().unwrap().history_revision;
+        write_response(&mut writer, response, revision).await?;
+
+        if let Some(events) = events {
+            forward_events(&mut writer, events).await;
+            return Ok(());
+        }
     }
 
     Ok(())
 }
+
+/// Write a reply line: `message` with the history revision it reflects.
+async fn write_response(
+    writer: &mut (impl AsyncWrite + Unpin),
+    message: BackendMessage,
+    revision: u64,
+) -> CursorClipResult<()> {
+    let response = BackendResponse {
+        revision: Some(revision),
+        message,
+    };
+    let response_json = serde_json::to_string(&response)?;
+    writer.write_all(response_json.as_bytes()).await?;
+    writer.write_all(b"\n").await?;
+    Ok(())
+}
+
+/// Read one request into `line` without its newline. Returns None at the end of the
+/// stream, and `Some(false)` if the line was longer than `max_bytes`: the rest of it is
+/// skipped so the next request starts in step, and `line` holds only its start.
+async fn read_request_line(
+    reader: &mut (impl AsyncBufRead + Unpin),
+    line: &mut Vec<u8>,
+    max_bytes: usize,
+) -> std::io::Result<Option<bool>> {
+    line.clear();
+    let mut complete = true;
+    let mut read_any = false;
+    loop {
+        let buffer = reader.fill_buf().await?;
+        if buffer.is_empty() {
+            // A last request without a newline still counts
+            return Ok(read_any.then_some(complete));
+        }
+        read_any = true;
+        let (chunk, found_newline) = match buffer.iter().position(|&byte| byte == b'\n') {
+            Some(end) => (&buffer[..end], true),
+            None => (buffer, false),
+        };
+        if complete && line.len() + chunk.len() <= max_bytes {
+            line.extend_from_slice(chunk);
+        } else {
+            complete = false;
+        }
+        let consumed = chunk.len() + usize::from(found_newline);
+        reader.consume(consumed);
+        if found_newline {
+            return Ok(Some(complete));
+        }
+    }
+}
+
+/// Token bucket limiting how fast one connection's requests are handled.
+struct RateLimit {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimit {
+    fn new() -> Self {
+        Self {
+            tokens: REQUEST_BURST,
+            refilled_at: Instant::now(),
+        }
+    }
+
+    /// Take a token, waiting for the bucket to refill if it is empty.
+    async fn acquire(&mut self) {
+        let now = Instant::now();
+        self.tokens = (self.tokens
+            + now.duration_since(self.refilled_at).as_secs_f64() * REQUESTS_PER_SECOND)
+            .min(REQUEST_BURST);
+        self.refilled_at = now;
+        if self.tokens < 1.0 {
+            let wait = Duration::from_secs_f64((1.0 - self.tokens) / REQUESTS_PER_SECOND);
+            tokio::time::sleep(wait).await;
+            self.tokens = 1.0;
+            self.refilled_at = Instant::now();
+        }
+        self.tokens -= 1.0;
+    }
+}
+
+/// Pass an overlay command on to the resident frontend and return its answer.
+async fn relay_to_frontend(message: &FrontendMessage) -> CursorClipResult<BackendMessage> {
+    let path = frontend_socket_path()
+        .ok_or_else(|| CursorClipError::Config("XDG_RUNTIME_DIR is not set".into()))?;
+    let stream = UnixStream::connect(&path).await.map_err(|_| {
+        CursorClipError::InvalidRequest(
+            "no resident frontend is running; start one with `cursor-clip resident`".into(),
+        )
+    })?;
+    let (reader, mut writer) = stream.into_split();
+    let message_json = serde_json::to_string(message)?;
+    writer.write_all(message_json.as_bytes()).await?;
+    writer.write_all(b"\n").await?;
+
+    let mut line = String::new();
+    if BufReader::new(reader).read_line(&mut line).await? == 0 {
+        return Err(CursorClipError::Io(
+            "The resident frontend closed the connection".into(),
+        ));
+    }
+    Ok(serde_json::from_str(line.trim())?)
+}
+
+/// Write backend events to a subscribed client, one JSON line each, until it disconnects.
+async fn forward_events(
+    writer: &mut (impl AsyncWrite + Unpin),
+    mut events: broadcast::Receiver<BackendMessage>,
+) {
+    use broadcast::error::RecvError;
+    loop {
+        let event = match events.recv().await {
+            Ok(event) => event,
+            Err(RecvError::Lagged(skipped)) => {
+                debug!("IPC subscriber skipped {skipped} events");
+                continue;
+            }
+            Err(RecvError::Closed) => return,
+        };
+        let Ok(event_json) = serde_json::to_string(&event) else {
+            continue;
+        };
+        if writer.write_all(event_json.as_bytes()).await.is_err()
+            || writer.write_all(b"\n").await.is_err()
+        {
+            debug!("IPC subscriber disconnected");
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::DuplexStream;
+
+    /// Serve a demo-mode backend over an in-memory stream; returns the client's end
+    fn serve_demo() -> (DuplexStream, tokio::task::JoinHandle<()>) {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let state = Arc::new(Mutex::new(BackendState::new(false, true)));
+        let served = tokio::spawn(async move {
+            serve_connection(server, None, state, None)
+                .await
+                .expect("connection served without I/O errors");
+        });
+        (client, served)
+    }
+
+    async fn reply(reader: &mut (impl AsyncBufRead + Unpin)) -> BackendMessage {
+        let mut line = String::new();
+        reader.read_line(&mut line).await.expect("reply line");
+        BackendResponse::from_json(&line)
+            .expect("reply parses")
+            .message
+    }
+
+    fn assert_invalid_request(message: BackendMessage) {
+        match message {
+            BackendMessage::Error {
+                error: CursorClipError::InvalidRequest(_),
+            } => {}
+            other => panic!("expected an invalid request error, got {other:?}"),
+        }
+    }
+
+    /// After an error the connection must still answer the next request in step
+    async fn assert_still_serving(
+        reader: &mut (impl AsyncBufRead + Unpin),
+        writer: &mut (impl AsyncWrite + Unpin),
+    ) {
+        writer.write_all(b"\"GetStats\"\n").await.unwrap();
+        match reply(reader).await {
+            BackendMessage::Stats { .. } => {}
+            other => panic!("expected stats, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn malformed_json_is_answered_with_an_error() {
+        let (client, served) = serve_demo();
+        let (reader, mut writer) = tokio::io::split(client);
+        let mut reader = BufReader::new(reader);
+
+        writer.write_all(b"{\"GetStats\n").await.unwrap();
+        assert_invalid_request(reply(&mut reader).await);
+        assert_still_serving(&mut reader, &mut writer).await;
+
+        writer.shutdown().await.unwrap();
+        served.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn oversized_request_is_skipped_and_rejected() {
+        let (client, served) = serve_demo();
+        let (reader, mut writer) = tokio::io::split(client);
+        let mut reader = BufReader::new(reader);
+
+        let sending = tokio::spawn(async move {
+            let chunk = vec![b'x'; 1024 * 1024];
+            for _ in 0..=MAX_REQUEST_BYTES / chunk.len() {
+                writer.write_all(&chunk).await.unwrap();
+            }
+            writer.write_all(b"\n").await.unwrap();
+            writer
+        });
+        match reply(&mut reader).await {
+            BackendMessage::Error {
+                error: CursorClipError::InvalidRequest(reason),
+            } => assert!(reason.contains("exceeds"), "{reason}"),
+            other => panic!("expected the request to be rejected, got {other:?}"),
+        }
+        let mut writer = sending.await.unwrap();
+        assert_still_serving(&mut reader, &mut writer).await;
+
+        writer.shutdown().await.unwrap();
+        served.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn request_from_a_newer_client_is_answered_with_an_error() {
+        let (client, served) = serve_demo();
+        let (reader, mut writer) = tokio::io::split(client);
+        let mut reader = BufReader::new(reader);
+
+        // A command this daemon does not know, as a client of a later release sends it
+        writer
+            .write_all(b"{\"GetFutureThing\":{\"since\":1}}\n")
+            .await
+            .unwrap();
+        assert_invalid_request(reply(&mut reader).await);
+        assert_still_serving(&mut reader, &mut writer).await;
+
+        writer.shutdown().await.unwrap();
+        served.await.unwrap();
+    }
+
+    #[test]
+    fn bare_reply_of_an_older_daemon_is_accepted() {
+        let response = BackendResponse::from_json("\"ClipboardSet\"").unwrap();
+        assert_eq!(response.revision, None);
+        assert!(matches!(response.message, BackendMessage::ClipboardSet));
+    }
+}
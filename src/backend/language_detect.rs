@@ -0,0 +1,183 @@
+// Bytes of a copy looked at; enough to recognize a language, cheap on huge pastes
+const SAMPLE_BYTES: usize = 4096;
+// Weighted hits needed before text counts as code in a language
+const MIN_SCORE: u32 = 4;
+
+/// Telltale fragments per language, with weights. Languages are named by the file
+/// extension syntax highlighters look them up with.
+const SIGNALS: &[(&str, &[(&str, u32)])] = &[
+    (
+        "rs",
+        &[
+            ("fn ", 2),
+            ("let mut ", 3),
+            ("impl ", 2),
+            ("pub fn ", 3),
+            ("#[derive(", 4),
+            ("println!(", 3),
+            ("use std::", 4),
+            ("&mut ", 2),
+            ("Option<", 2),
+            ("-> ", 1),
+        ],
+    ),
+    (
+        "py",
+        &[
+            ("def ", 2),
+            ("import ", 1),
+            ("from ", 1),
+            ("self.", 2),
+            ("elif ", 3),
+            ("print(", 1),
+            ("__init__", 4),
+            ("None", 1),
+            ("    return ", 1),
+        ],
+    ),
+    (
+        "js",
+        &[
+            ("function ", 2),
+            ("const ", 2),
+            ("=> ", 2),
+            ("console.log(", 4),
+            ("===", 3),
+            ("require(", 3),
+            ("document.", 3),
+            ("export default", 4),
+        ],
+    ),
+    (
+        "go",
+        &[
+            ("func ", 3),
+            ("package ", 2),
+            (" := ", 3),
+            ("fmt.", 3),
+            ("err != nil", 4),
+        ],
+    ),
+    (
+        "c",
+        &[
+            ("#include <", 4),
+            ("int main(", 4),
+            ("printf(", 2),
+            ("malloc(", 3),
+            ("NULL", 1),
+        ],
+    ),
+    (
+        "cpp",
+        &[
+            ("std::", 3),
+            ("#include <", 2),
+            ("template<", 4),
+            ("nullptr", 3),
+            ("cout <<", 4),
+        ],
+    ),
+    (
+        "java",
+        &[
+            ("public class ", 4),
+            ("System.out.", 4),
+            ("private ", 1),
+            ("public static void", 4),
+            ("@Override", 4),
+        ],
+    ),
+    (
+        "sh",
+        &[
+            ("echo ", 2),
+            ("$(", 2),
+            ("\nfi", 3),
+            ("; then", 3),
+            ("sudo ", 2),
+            ("export ", 1),
+            (" | grep", 2),
+            ("${", 1),
+        ],
+    ),
+    (
+        "html",
+        &[
+            ("<!DOCTYPE", 4),
+            ("<html", 4),
+            ("<div", 2),
+            ("</", 1),
+            ("<span", 2),
+            ("href=\"", 2),
+        ],
+    ),
+    (
+        "css",
+        &[
+            ("px;", 2),
+            ("color:", 2),
+            ("margin:", 2),
+            ("padding:", 2),
+            ("display:", 2),
+            ("@media", 3),
+        ],
+    ),
+    (
+        "sql",
+        &[
+            ("SELECT ", 2),
+            (" FROM ", 2),
+            (" WHERE ", 2),
+            ("INSERT INTO", 4),
+            ("CREATE TABLE", 4),
+            ("JOIN ", 2),
+        ],
+    ),
+];
+
+/// Guess the programming language of copied text. Returns a file extension (e.g. "rs")
+/// or `None` when the text doesn't look like code.
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let mut end = text.len().min(SAMPLE_BYTES);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let sample = text[..end].trim();
+
+    if let Some(first_line) = sample.lines().next()
+        && let Some(interpreter) = first_line.strip_prefix("#!")
+    {
+        let interpreter = interpreter.rsplit(['/', ' ']).next().unwrap_or_default();
+        return match interpreter {
+            "sh" | "bash" | "zsh" => Some("sh"),
+            name if name.starts_with("python") => Some("py"),
+            "node" => Some("js"),
+            _ => None,
+        };
+    }
+    if (sample.starts_with('{') || sample.starts_with('['))
+        && sample.len() > 2
+        && serde_json::from_str::<serde_json::Value>(sample).is_ok()
+    {
+        return Some("json");
+    }
+
+    SIGNALS
+        .iter()
+        .map(|(language, signals)| {
+            let score: u32 = signals
+                .iter()
+                .filter(|(fragment, _)| sample.contains(fragment))
+                .map(|(_, weight)| weight)
+                .sum();
+            (*language, score)
+        })
+        .filter(|(_, score)| *score >= MIN_SCORE)
+        // First listed wins a tie
+        .fold(None, |best: Option<(&str, u32)>, candidate| match best {
+            Some((_, best_score)) if best_score >= candidate.1 => best,
+            _ => Some(candidate),
+        })
+        .map(|(language, _)| language)
+}
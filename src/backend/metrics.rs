@@ -0,0 +1,187 @@
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use log::{debug, error, info, warn};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use super::backend_state::BackendState;
+use crate::shared::ClipboardItem;
+
+// Upper bounds (in seconds) of the IPC latency histogram buckets
+const IPC_LATENCY_BUCKETS: [f64; 11] = [
+    0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0,
+];
+
+/// Process-wide counters, updated from the Wayland thread and the IPC tasks.
+/// Kept lock-free so recording costs nothing when the endpoint is disabled.
+struct Metrics {
+    items_captured: AtomicU64,
+    wayland_reconnects: AtomicU64,
+    ipc_requests: AtomicU64,
+    ipc_latency_micros_sum: AtomicU64,
+    ipc_latency_buckets: [AtomicU64; IPC_LATENCY_BUCKETS.len()],
+}
+
+static METRICS: Metrics = Metrics {
+    items_captured: AtomicU64::new(0),
+    wayland_reconnects: AtomicU64::new(0),
+    ipc_requests: AtomicU64::new(0),
+    ipc_latency_micros_sum: AtomicU64::new(0),
+    ipc_latency_buckets: [const { AtomicU64::new(0) }; IPC_LATENCY_BUCKETS.len()],
+};
+
+pub fn record_item_captured() {
+    METRICS.items_captured.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn record_wayland_reconnect() {
+    METRICS.wayland_reconnects.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn record_ipc_latency(elapsed: Duration) {
+    METRICS.ipc_requests.fetch_add(1, Ordering::Relaxed);
+    METRICS
+        .ipc_latency_micros_sum
+        .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    let seconds = elapsed.as_secs_f64();
+    // Buckets are stored non-cumulatively and summed up when rendering
+    if let Some(index) = IPC_LATENCY_BUCKETS.iter().position(|le| seconds <= *le) {
+        METRICS.ipc_latency_buckets[index].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Render all metrics in the Prometheus text exposition format.
+fn render(state: &BackendState) -> String {
+    let history = &state.history;
+    let stored_bytes: u64 = history.iter().map(ClipboardItem::payload_size).sum();
+
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "# HELP cursor_clip_items_captured_total Clipboard selections recorded since startup.\n\
+         # TYPE cursor_clip_items_captured_total counter\n\
+         cursor_clip_items_captured_total {}",
+        METRICS.items_captured.load(Ordering::Relaxed)
+    );
+    let _ = writeln!(
+        out,
+        "# HELP cursor_clip_history_items Items currently held in history.\n\
+         # TYPE cursor_clip_history_items gauge\n\
+         cursor_clip_history_items {}",
+        history.len()
+    );
+    let _ = writeln!(
+        out,
+        "# HELP cursor_clip_history_bytes Bytes of MIME payloads currently held in history.\n\
+         # TYPE cursor_clip_history_bytes gauge\n\
+         cursor_clip_history_bytes {stored_bytes}"
+    );
+    let _ = writeln!(
+        out,
+        "# HELP cursor_clip_battery_saving Whether background work is reduced to save power.\n\
+         # TYPE cursor_clip_battery_saving gauge\n\
+         cursor_clip_battery_saving {}",
+        u8::from(state.battery_saving())
+    );
+    let _ = writeln!(
+        out,
+        "# HELP cursor_clip_wayland_reconnects_total Data-control managers re-bound after the compositor withdrew one or the connection to it was re-established.\n\
+         # TYPE cursor_clip_wayland_reconnects_total counter\n\
+         cursor_clip_wayland_reconnects_total {}",
+        METRICS.wayland_reconnects.load(Ordering::Relaxed)
+    );
+
+    let _ = writeln!(
+        out,
+        "# HELP cursor_clip_ipc_request_duration_seconds Time spent handling IPC requests.\n\
+         # TYPE cursor_clip_ipc_request_duration_seconds histogram"
+    );
+    let mut cumulative = 0;
+    for (le, bucket) in IPC_LATENCY_BUCKETS.iter().zip(&METRICS.ipc_latency_buckets) {
+        cumulative += bucket.load(Ordering::Relaxed);
+        let _ = writeln!(
+            out,
+            "cursor_clip_ipc_request_duration_seconds_bucket{{le=\"{le}\"}} {cumulative}"
+        );
+    }
+    let count = METRICS.ipc_requests.load(Ordering::Relaxed);
+    let sum = METRICS.ipc_latency_micros_sum.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+    let _ = writeln!(
+        out,
+        "cursor_clip_ipc_request_duration_seconds_bucket{{le=\"+Inf\"}} {count}\n\
+         cursor_clip_ipc_request_duration_seconds_sum {sum}\n\
+         cursor_clip_ipc_request_duration_seconds_count {count}"
+    );
+    out
+}
+
+/// Serve `/metrics` on `address`. Only loopback addresses are accepted, since the
+/// endpoint is unauthenticated.
+pub fn spawn_metrics_server(address: &str, state: Arc<Mutex<BackendState>>) {
+    let address: SocketAddr = match address.parse() {
+        Ok(address) => address,
+        Err(e) => {
+            error!("Invalid metrics_address {address:?}: {e}");
+            return;
+        }
+    };
+    if !address.ip().is_loopback() {
+        error!("Refusing to expose metrics on non-loopback address {address}");
+        return;
+    }
+
+    tokio::spawn(async move {
+        let listener = match TcpListener::bind(address).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("Failed to bind metrics endpoint on {address}: {e}");
+                return;
+            }
+        };
+        info!("Prometheus metrics available at http://{address}/metrics");
+
+        loop {
+            let stream = match listener.accept().await {
+                Ok((stream, _)) => stream,
+                Err(e) => {
+                    warn!("Metrics endpoint accept failed: {e}");
+                    continue;
+                }
+            };
+            let state = state.clone();
+            tokio::spawn(async move {
+                if let Err(e) = handle_scrape(stream, state).await {
+                    debug!("Metrics scrape failed: {e}");
+                }
+            });
+        }
+    });
+}
+
+async fn handle_scrape(
+    mut stream: TcpStream,
+    state: Arc<Mutex<BackendState>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Only the request line matters; scrapers send small GET requests
+    let mut buffer = [0u8; 1024];
+    let read = stream.read(&mut buffer).await?;
+    let request = String::from_utf8_lossy(&buffer[..read]);
+    let path = request.split_whitespace().nth(1).unwrap_or_default();
+
+    let response = if request.starts_with("GET ") && (path == "/metrics" || path == "/") {
+        let body = render(&state.lock().unwrap());
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+    } else {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    };
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
@@ -0,0 +1,65 @@
+//! Which offered MIME types the monitor reads (`[mime_policy]` section of config.toml).
+//! Apps often offer a dozen private `application/x-*` variants of one copy; reading only
+//! the common ones keeps memory use and read times down.
+
+use log::debug;
+use serde::Deserialize;
+
+/// `[mime_policy]` section of config.toml
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct MimePolicy {
+    /// Read every offered type, ignoring `allow` and `deny`
+    pub record_all: bool,
+    /// Types to read; a trailing `*` matches a prefix (`text/*`)
+    pub allow: Vec<String>,
+    /// Types never read, even when allowed
+    pub deny: Vec<String>,
+}
+
+impl Default for MimePolicy {
+    fn default() -> Self {
+        Self {
+            record_all: false,
+            allow: ["text/*", "image/png", "image/jpeg", "text/uri-list"]
+                .map(String::from)
+                .to_vec(),
+            deny: Vec::new(),
+        }
+    }
+}
+
+impl MimePolicy {
+    /// Whether `mime` should be read.
+    pub fn allows(&self, mime: &str) -> bool {
+        if self.record_all {
+            return true;
+        }
+        self.allow.iter().any(|pattern| mime_matches(pattern, mime))
+            && !self.deny.iter().any(|pattern| mime_matches(pattern, mime))
+    }
+
+    /// Keep the offered types this policy allows, in their original order.
+    pub fn filter(&self, offered: Vec<String>) -> Vec<String> {
+        if self.record_all {
+            return offered;
+        }
+        let (kept, skipped): (Vec<String>, Vec<String>) =
+            offered.into_iter().partition(|mime| self.allows(mime));
+        if !skipped.is_empty() {
+            debug!("MIME policy skips {}", skipped.join(", "));
+        }
+        kept
+    }
+}
+
+/// `pattern` is a MIME type, or a prefix when it ends in `*`. Parameters such as
+/// `;charset=utf-8` are ignored when comparing exact types.
+fn mime_matches(pattern: &str, mime: &str) -> bool {
+    let mime = mime.to_ascii_lowercase();
+    let pattern = pattern.to_ascii_lowercase();
+    match pattern.strip_suffix('*') {
+        Some(prefix) => mime.starts_with(prefix),
+        None => mime.split(';').next().unwrap_or_default().trim() == pattern,
+    }
+}
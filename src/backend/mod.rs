@@ -1,7 +1,34 @@
+pub mod access_log;
 pub mod backend_state;
+pub mod classifier;
+pub mod clipboard_portal;
+pub mod crash_backup;
+pub mod dbus_service;
+pub mod demo;
+pub mod file_list;
+pub mod foreign_toplevel;
+pub mod frecency;
+pub mod fuzzy;
+pub mod history_export;
+pub mod history_migration;
+pub mod idle;
+pub mod image_pipeline;
 pub mod ipc_server;
+pub mod language_detect;
+pub mod metrics;
+pub mod mime_policy;
+pub mod payload_compression;
 pub mod persistence;
+pub mod plain_text;
+pub mod power;
+pub mod search_filter;
+pub mod session_lock;
+pub mod snippets;
+pub mod sync;
+pub mod text_delta;
+pub mod text_transform;
 pub mod virtual_keyboard;
 pub mod wayland_clipboard;
+pub mod x11_clipboard;
 
 pub use ipc_server::*;
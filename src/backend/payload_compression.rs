@@ -0,0 +1,179 @@
+//! Transparent zstd compression of large payloads held in memory. Compressed MIME
+//! types are listed in `ClipboardItem::compressed` with their original size; anything
+//! handing data out of the daemon (paste, persistence, export, sync) reads through
+//! [`decompressed_payloads`] or [`decompress_item`].
+
+use crate::shared::ClipboardItem;
+use bytes::Bytes;
+use indexmap::IndexMap;
+use log::{debug, warn};
+use std::borrow::Cow;
+
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Compress every payload of `item` larger than `threshold` bytes; 0 disables.
+/// Payloads that are compressed already or barely shrink are kept as they are.
+pub fn compress_payloads(item: &mut ClipboardItem, threshold: u64) {
+    if threshold == 0 {
+        return;
+    }
+    for (mime, bytes) in &mut item.mime_data {
+        if bytes.len() as u64 <= threshold
+            || item.compressed.contains_key(mime)
+            || is_precompressed(mime)
+        {
+            continue;
+        }
+        let compressed = match zstd::bulk::compress(bytes, COMPRESSION_LEVEL) {
+            Ok(compressed) => compressed,
+            Err(e) => {
+                warn!("Failed to compress {mime} payload: {e}");
+                continue;
+            }
+        };
+        // Not worth the decompression on every paste below 1/8 saved
+        if compressed.len() > bytes.len() - bytes.len() / 8 {
+            continue;
+        }
+        debug!(
+            "Compressed {mime} payload of item {} from {} to {} bytes",
+            item.item_id,
+            bytes.len(),
+            compressed.len()
+        );
+        item.compressed.insert(mime.clone(), bytes.len() as u64);
+        *bytes = Bytes::from(compressed);
+    }
+}
+
+/// The payloads of `item` as they were copied.
+pub fn decompressed_payloads(item: &ClipboardItem) -> Cow<'_, IndexMap<String, Bytes>> {
+    if item.compressed.is_empty() {
+        return Cow::Borrowed(&item.mime_data);
+    }
+    Cow::Owned(
+        item.mime_data
+            .iter()
+            .filter_map(|(mime, bytes)| {
+                let Some(original_size) = item.compressed.get(mime) else {
+                    return Some((mime.clone(), bytes.clone()));
+                };
+                match decompress_payload(bytes, *original_size) {
+                    Ok(original) => Some((mime.clone(), original)),
+                    Err(e) => {
+                        warn!(
+                            "Failed to decompress {mime} payload of item {}: {e}",
+                            item.item_id
+                        );
+                        None
+                    }
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Decompress one payload listed in `ClipboardItem::compressed`.
+pub fn decompress_payload(bytes: &Bytes, original_size: u64) -> std::io::Result<Bytes> {
+    zstd::bulk::decompress(bytes, original_size as usize).map(Bytes::from)
+}
+
+/// `item` with all payloads decompressed.
+pub fn decompress_item(item: &ClipboardItem) -> Cow<'_, ClipboardItem> {
+    if item.compressed.is_empty() {
+        return Cow::Borrowed(item);
+    }
+    let mut decompressed = item.clone();
+    decompressed.mime_data = decompressed_payloads(item).into_owned();
+    decompressed.compressed.clear();
+    Cow::Owned(decompressed)
+}
+
+/// Formats that zstd cannot shrink any further.
+fn is_precompressed(mime: &str) -> bool {
+    matches!(
+        mime,
+        "image/png"
+            | "image/jpeg"
+            | "image/gif"
+            | "image/webp"
+            | "image/avif"
+            | "application/zip"
+            | "application/gzip"
+            | "application/zstd"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item_with(payloads: &[(&str, Vec<u8>)]) -> ClipboardItem {
+        let mut item = ClipboardItem::plain_text(1, "");
+        item.mime_data = payloads
+            .iter()
+            .map(|(mime, bytes)| (mime.to_string(), Bytes::from(bytes.clone())))
+            .collect();
+        item
+    }
+
+    #[test]
+    fn large_payloads_round_trip_through_compression() {
+        let text = "compressible text\n".repeat(1000).into_bytes();
+        let mut item = item_with(&[
+            ("text/plain", text.clone()),
+            ("text/x-small", b"tiny".to_vec()),
+        ]);
+        let original = item.mime_data.clone();
+
+        compress_payloads(&mut item, 1024);
+        assert_eq!(
+            item.compressed.get("text/plain"),
+            Some(&(text.len() as u64))
+        );
+        assert!(item.mime_data["text/plain"].len() < text.len());
+        // At or below the threshold stays as it is
+        assert!(!item.compressed.contains_key("text/x-small"));
+
+        assert_eq!(*decompressed_payloads(&item), original);
+        let decompressed = decompress_item(&item);
+        assert_eq!(decompressed.mime_data, original);
+        assert!(decompressed.compressed.is_empty());
+    }
+
+    #[test]
+    fn compressing_twice_changes_nothing() {
+        let mut item = item_with(&[("text/plain", "again and again\n".repeat(1000).into_bytes())]);
+        compress_payloads(&mut item, 1024);
+        let once = item.clone();
+        compress_payloads(&mut item, 1024);
+        assert_eq!(item.mime_data, once.mime_data);
+        assert_eq!(item.compressed, once.compressed);
+    }
+
+    #[test]
+    fn precompressed_incompressible_or_disabled_payloads_are_kept() {
+        let repetitive = vec![b'x'; 4096];
+        // xorshift output; zstd finds nothing to shrink
+        let mut state = 0x2545_f491_u32;
+        let noise: Vec<u8> = (0..4096)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            })
+            .collect();
+
+        let mut item = item_with(&[
+            ("image/png", repetitive.clone()),
+            ("application/octet-stream", noise),
+        ]);
+        compress_payloads(&mut item, 1024);
+        assert!(item.compressed.is_empty());
+
+        let mut item = item_with(&[("text/plain", repetitive)]);
+        compress_payloads(&mut item, 0);
+        assert!(item.compressed.is_empty());
+    }
+}
@@ -1,33 +1,173 @@
-use crate::shared::ClipboardItem;
+use crate::backend::classifier::ClassifierRule;
+use crate::backend::history_migration::open_migrated;
+use crate::backend::mime_policy::MimePolicy;
+use crate::backend::session_lock::LockConfig;
+use crate::backend::sync::SyncConfig;
+use crate::backend::text_delta::{TextDelta, apply_text_deltas, encode_text_deltas};
+use crate::shared::config_dir::{config_dir, config_path};
+use crate::shared::{ClipboardItem, DaemonSettings, RetentionPolicy, Snippet};
 use aes_gcm::aead::{Aead, KeyInit};
 use aes_gcm::{Aes256Gcm, Nonce};
 use base64::Engine;
 use base64::engine::general_purpose::STANDARD as BASE64;
+use indexmap::IndexMap;
 use keyring::Entry;
 use log::warn;
 use rand::Rng;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use stoolap::Database;
 
-#[derive(Debug, Default, Deserialize)]
+/// Backend view of `config.toml`; the frontend owns the file and keys it doesn't know are preserved.
+#[derive(Debug, Clone, Deserialize)]
 #[serde(default)]
-struct BackendConfig {
+pub struct BackendConfig {
     #[serde(alias = "persistent_history")]
-    persistence_enabled: bool,
+    pub persistence_enabled: bool,
+    /// Collect history changes this long (ms) before writing them to the database in one
+    /// go; 0 writes every change immediately. Removals are always written immediately.
+    pub persistence_write_delay_ms: u64,
+    /// Flush written changes to disk (fsync) at most this many seconds after a write, so
+    /// they survive a crash or power loss; 0 flushes after every write. Pending changes
+    /// are always flushed when the daemon stops.
+    pub persistence_sync_interval_secs: u64,
+    /// Keep deleted and cleared items this many minutes for `UndoLastOperation`; 0
+    /// deletes them for good right away
+    pub trash_minutes: u64,
+    /// Images larger than this (in bytes) are downscaled after PNG conversion; 0 disables the limit
+    pub max_image_bytes: u64,
+    /// History limits (`max_items`, `max_total_bytes`, `max_item_bytes`, `max_age_days`)
+    #[serde(flatten)]
+    pub retention: RetentionPolicy,
+    /// Record every IPC command (client pid, command, item id; no payloads) to a daily log
+    pub ipc_access_log: bool,
+    /// Days of access log files to keep before they are deleted
+    pub ipc_access_log_retention_days: u64,
+    /// Hold-off (ms) before re-owning an external selection; 0 takes ownership immediately.
+    /// Defaults to the compositor quirk when unset.
+    pub ownership_takeover_delay_ms: Option<u64>,
+    /// App ids (e.g. `org.keepassxc.KeePassXC`, `kitty`) whose copies are never recorded
+    pub exclude_apps: Vec<String>,
+    /// Serve Prometheus metrics on `metrics_address`
+    pub metrics_enabled: bool,
+    /// Seconds without input after which the session counts as idle; 0 disables idle throttling
+    pub idle_timeout_secs: u64,
+    /// Reduce background work on battery or with the power-saver profile
+    pub battery_saver: bool,
+    /// Loopback address for the metrics endpoint
+    pub metrics_address: String,
+    /// Keep a copy of owned selections in the runtime dir so a crash never loses them
+    pub crash_backup: bool,
+    /// Record copies without re-owning the selection, as if started with `--monitor-only`
+    pub monitor_only: bool,
+    /// Wait this long for a selection to settle before recording it; 0 records every one
+    pub selection_debounce_ms: u64,
+    /// Skip a MIME type whose data takes longer than this to read; 0 waits indefinitely
+    pub mime_read_timeout_ms: u64,
+    /// Stop reading further MIME types of a selection after this long; 0 disables the deadline
+    pub selection_read_deadline_ms: u64,
+    /// Hold payloads larger than this zstd-compressed in memory; 0 disables compression
+    pub compress_payloads_over_bytes: u64,
+    /// Read only plain text, images and file lists at copy time; other formats are
+    /// fetched from the source app when a paste asks for them
+    pub lazy_mime_fetch: bool,
+    /// Which offered MIME types are read (`[mime_policy]` section)
+    pub mime_policy: MimePolicy,
+    /// In monitor-only mode, take over a selection only once the app that copied it exits
+    pub persist_on_exit: bool,
+    /// Also serve IPC on the socket name used by earlier releases (`cursor-clip.sock`)
+    pub legacy_socket_path: bool,
+    /// Replicate history with other devices (`[sync]` section)
+    pub sync: SyncConfig,
+    /// Permanent text templates shown above the history (`[[snippets]]`)
+    pub snippets: Vec<Snippet>,
+    /// Regex -> content type rules tried before the built-in heuristics (`[[classifier_rules]]`)
+    pub classifier_rules: Vec<ClassifierRule>,
+    /// What to wipe when the session locks (`[on_lock]` section)
+    pub on_lock: LockConfig,
 }
 
-pub fn load_persistence_enabled_from_config() -> bool {
+impl Default for BackendConfig {
+    fn default() -> Self {
+        Self {
+            persistence_enabled: false,
+            persistence_write_delay_ms: 1_000,
+            persistence_sync_interval_secs: 30,
+            trash_minutes: 5,
+            max_image_bytes: 10 * 1024 * 1024,
+            retention: RetentionPolicy::default(),
+            ipc_access_log: false,
+            ipc_access_log_retention_days: 14,
+            ownership_takeover_delay_ms: None,
+            exclude_apps: Vec::new(),
+            metrics_enabled: false,
+            metrics_address: "127.0.0.1:9464".to_string(),
+            idle_timeout_secs: 300,
+            battery_saver: true,
+            crash_backup: true,
+            monitor_only: false,
+            selection_debounce_ms: 200,
+            mime_read_timeout_ms: 5_000,
+            selection_read_deadline_ms: 15_000,
+            compress_payloads_over_bytes: 64 * 1024,
+            lazy_mime_fetch: false,
+            mime_policy: MimePolicy::default(),
+            persist_on_exit: false,
+            legacy_socket_path: false,
+            sync: SyncConfig::default(),
+            snippets: Vec::new(),
+            classifier_rules: Vec::new(),
+            on_lock: LockConfig::default(),
+        }
+    }
+}
+
+pub fn load_backend_config() -> BackendConfig {
     let path = config_path();
     let Ok(contents) = fs::read_to_string(path) else {
-        return false;
+        return BackendConfig::default();
+    };
+
+    toml::from_str::<BackendConfig>(&contents).unwrap_or_else(|e| {
+        warn!("Failed to parse backend config, using defaults: {e}");
+        BackendConfig::default()
+    })
+}
+
+/// Write runtime-changed settings back to config.toml, keeping every other key
+/// (frontend options, quirk overrides, comments are not preserved).
+pub fn save_daemon_settings(settings: &DaemonSettings) -> Result<(), String> {
+    let toml::Value::Table(updates) = toml::Value::try_from(settings)
+        .map_err(|e| format!("Failed to serialize settings: {e}"))?
+    else {
+        return Err("Settings did not serialize to a table".to_string());
+    };
+    update_config_file(updates)
+}
+
+/// Replace the `[[snippets]]` list in config.toml, keeping every other key.
+pub fn save_snippets(snippets: &[Snippet]) -> Result<(), String> {
+    let snippets = toml::Value::try_from(snippets)
+        .map_err(|e| format!("Failed to serialize snippets: {e}"))?;
+    update_config_file(toml::Table::from_iter([("snippets".to_string(), snippets)]))
+}
+
+fn update_config_file(updates: toml::Table) -> Result<(), String> {
+    let path = config_path();
+    let mut table = match fs::read_to_string(&path) {
+        Ok(contents) => toml::from_str::<toml::Table>(&contents)
+            .map_err(|e| format!("Refusing to overwrite unparsable {}: {e}", path.display()))?,
+        Err(_) => toml::Table::new(),
     };
+    table.extend(updates);
 
-    toml::from_str::<BackendConfig>(&contents)
-        .map(|cfg| cfg.persistence_enabled)
-        .unwrap_or(false)
+    fs::create_dir_all(config_dir()).map_err(|e| format!("Failed to create config dir: {e}"))?;
+    let contents =
+        toml::to_string_pretty(&table).map_err(|e| format!("Failed to serialize config: {e}"))?;
+    fs::write(&path, contents).map_err(|e| format!("Failed to write {}: {e}", path.display()))
 }
 
 pub fn history_db_path() -> PathBuf {
@@ -78,6 +218,22 @@ pub fn db_has_persisted_items() -> Result<bool, String> {
         return Ok(false);
     }
 
+    let db = open_database(&db_path)?;
+
+    let count: i64 = db
+        .query_one("SELECT COUNT(*) FROM clipboard_history", ())
+        .map_err(|e| format!("Failed to count persisted items: {e}"))?;
+
+    Ok(count > 0)
+}
+
+/// Open the history database at `db_path`, creating it and its table if needed.
+pub(crate) fn open_database(db_path: &Path) -> Result<Database, String> {
+    if let Some(parent) = db_path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create persistence directory: {e}"))?;
+    }
+
     let dsn = format!("file://{}", db_path.display());
     let db = Database::open(&dsn).map_err(|e| {
         format!(
@@ -97,15 +253,12 @@ pub fn db_has_persisted_items() -> Result<bool, String> {
     )
     .map_err(|e| format!("Failed to initialize persistence schema: {e}"))?;
 
-    let count: i64 = db
-        .query_one("SELECT COUNT(*) FROM clipboard_history", ())
-        .map_err(|e| format!("Failed to count persisted items: {e}"))?;
-
-    Ok(count > 0)
+    Ok(db)
 }
 
 pub struct ClipboardPersistence {
     db: Database,
+    db_path: PathBuf,
     cipher: Aes256Gcm,
 }
 
@@ -118,38 +271,29 @@ impl std::fmt::Debug for ClipboardPersistence {
 
 impl ClipboardPersistence {
     pub fn open_default(password: &str) -> Result<Self, String> {
+        let cipher = derive_cipher(password);
         let db_path = history_db_path();
-        if let Some(parent) = db_path.parent() {
-            fs::create_dir_all(parent)
-                .map_err(|e| format!("Failed to create persistence directory: {e}"))?;
-        }
+        let db = open_migrated(&db_path, &cipher)?;
 
-        let dsn = format!("file://{}", db_path.display());
-        let db = Database::open(&dsn).map_err(|e| {
+        Ok(Self {
+            db,
+            db_path,
+            cipher,
+        })
+    }
+
+    /// Flush what was written to the database's files out to disk.
+    pub fn sync_to_disk(&self) -> Result<(), String> {
+        sync_recursively(&self.db_path).map_err(|e| {
             format!(
-                "Failed to open Stoolap database at {}: {e}",
-                db_path.display()
+                "Failed to flush history database {} to disk: {e}",
+                self.db_path.display()
             )
-        })?;
-
-        db.execute(
-            "CREATE TABLE IF NOT EXISTS clipboard_history (
-                item_id BIGINT PRIMARY KEY,
-                item_json TEXT NOT NULL,
-                created_ts BIGINT NOT NULL,
-                pinned BOOLEAN NOT NULL
-            )",
-            (),
-        )
-        .map_err(|e| format!("Failed to initialize persistence schema: {e}"))?;
-
-        let cipher = derive_cipher(password);
-
-        Ok(Self { db, cipher })
+        })
     }
 
     pub fn load_history(&self) -> Result<Vec<ClipboardItem>, String> {
-        let mut items = Vec::new();
+        let mut rows_in_order = Vec::new();
         let rows = self
             .db
             .query(
@@ -167,19 +311,27 @@ impl ClipboardPersistence {
             // Backward-compatible load:
             // - encrypted rows must decrypt successfully
             // - plain rows are accepted for legacy migrations
-            let item_json = if stored_payload.starts_with("enc:v1:") {
+            let item_json = if stored_payload.starts_with(ENCRYPTED_PAYLOAD_PREFIX) {
                 decrypt_payload(&self.cipher, &stored_payload)?
             } else {
                 stored_payload
             };
 
-            let item = serde_json::from_str::<ClipboardItem>(&item_json).map_err(|e| {
-                format!("Failed to deserialize persisted clipboard item payload: {e}")
-            })?;
-            items.push(item);
+            let stored = if let Some(delta_json) = item_json.strip_prefix(DELTA_ROW_PREFIX) {
+                let row = serde_json::from_str::<DeltaRow>(delta_json).map_err(|e| {
+                    format!("Failed to deserialize persisted clipboard delta payload: {e}")
+                })?;
+                StoredItem::Delta(row)
+            } else {
+                let item = serde_json::from_str::<ClipboardItem>(&item_json).map_err(|e| {
+                    format!("Failed to deserialize persisted clipboard item payload: {e}")
+                })?;
+                StoredItem::Full(item)
+            };
+            rows_in_order.push(stored);
         }
 
-        Ok(items)
+        Ok(resolve_delta_rows(rows_in_order))
     }
 
     pub fn save_history(&self, history: &[ClipboardItem]) -> Result<(), String> {
@@ -187,13 +339,32 @@ impl ClipboardPersistence {
             .execute("DELETE FROM clipboard_history", ())
             .map_err(|e| format!("Failed to clear persisted history: {e}"))?;
 
-        for item in history {
-            let item_json = serde_json::to_string(item)
+        // In the order `load_history` reads the rows back, so each delta row's base is
+        // the row after it
+        let mut rows: Vec<&ClipboardItem> = history.iter().collect();
+        rows.sort_by_key(|item| {
+            std::cmp::Reverse((item.pinned, item.last_copied_at(), item.item_id))
+        });
+
+        // Oldest first, so every delta row's base is already written; a save cut short
+        // leaves the oldest items loadable
+        for (index, item) in rows.iter().copied().enumerate().rev() {
+            // Near-duplicate text is stored as a delta against the next older item
+            let item_json = rows
+                .get(index + 1)
+                .and_then(|base| delta_row(base, item))
+                .map_or_else(
+                    || serde_json::to_string(item),
+                    |row| {
+                        serde_json::to_string(&row).map(|json| format!("{DELTA_ROW_PREFIX}{json}"))
+                    },
+                )
                 .map_err(|e| format!("Failed to serialize clipboard item {}: {e}", item.item_id))?;
             let encrypted_payload = encrypt_payload(&self.cipher, &item_json)
                 .map_err(|e| format!("Failed to encrypt clipboard item {}: {e}", item.item_id))?;
             let item_id = u64_to_i64(item.item_id)?;
-            let created_ts = u64_to_i64(item.timestamp)?;
+            // Ordered by the last copy, so entries copied again load at their new place
+            let created_ts = u64_to_i64(item.last_copied_at())?;
 
             self.db
                 .execute(
@@ -207,27 +378,107 @@ impl ClipboardPersistence {
     }
 }
 
-fn config_dir() -> PathBuf {
-    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-    PathBuf::from(home).join(".config").join("cursor-clip")
+/// fsync a file, or a directory with everything in it; Stoolap keeps a database in a
+/// directory. Directories are synced too, so files created since are found after a crash.
+fn sync_recursively(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        for entry in fs::read_dir(path)? {
+            sync_recursively(&entry?.path())?;
+        }
+    }
+    match fs::File::open(path) {
+        Ok(file) => file.sync_all(),
+        // Removed by a checkpoint since the directory was listed
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+pub(crate) const ENCRYPTED_PAYLOAD_PREFIX: &str = "enc:v1:";
+const DELTA_ROW_PREFIX: &str = "delta:v1:";
+
+/// A persisted item whose text payloads are deltas against another persisted item.
+/// The delta-encoded entries of `item.mime_data` are stored empty.
+#[derive(Debug, Serialize, Deserialize)]
+struct DeltaRow {
+    base_id: u64,
+    deltas: IndexMap<String, TextDelta>,
+    item: ClipboardItem,
+}
+
+enum StoredItem {
+    Full(ClipboardItem),
+    Delta(DeltaRow),
+}
+
+fn delta_row(base: &ClipboardItem, item: &ClipboardItem) -> Option<DeltaRow> {
+    let mut stored = item.clone();
+    let deltas = encode_text_deltas(&base.mime_data, &mut stored.mime_data);
+    (!deltas.is_empty()).then_some(DeltaRow {
+        base_id: base.item_id,
+        deltas,
+        item: stored,
+    })
 }
 
-fn config_path() -> PathBuf {
-    config_dir().join("config.toml")
+/// Rebuild full items from the loaded rows, keeping their order. A delta row's base is
+/// the next older item, loaded after it, so one pass from the oldest row resolves
+/// chains of deltas. Rows whose base is missing (a save cut short by a crash) are
+/// dropped rather than failing the whole load.
+fn resolve_delta_rows(rows: Vec<StoredItem>) -> Vec<ClipboardItem> {
+    let index_by_id: HashMap<u64, usize> = rows
+        .iter()
+        .enumerate()
+        .map(|(index, row)| match row {
+            StoredItem::Full(item) => (item.item_id, index),
+            StoredItem::Delta(row) => (row.item.item_id, index),
+        })
+        .collect();
+    let mut resolved: Vec<Option<ClipboardItem>> = Vec::with_capacity(rows.len());
+    let mut pending = Vec::new();
+    for (index, row) in rows.into_iter().enumerate() {
+        match row {
+            StoredItem::Full(item) => resolved.push(Some(item)),
+            StoredItem::Delta(row) => {
+                resolved.push(None);
+                pending.push((index, row));
+            }
+        }
+    }
+
+    for (index, mut row) in pending.into_iter().rev() {
+        let item_id = row.item.item_id;
+        let Some(base) = index_by_id
+            .get(&row.base_id)
+            .and_then(|base_index| resolved[*base_index].as_ref())
+        else {
+            warn!(
+                "Dropping persisted clipboard item {item_id}: its delta base {} is missing",
+                row.base_id
+            );
+            continue;
+        };
+        match apply_text_deltas(&base.mime_data, &mut row.item.mime_data, &row.deltas) {
+            Ok(()) => resolved[index] = Some(row.item),
+            Err(e) => warn!("Dropping persisted clipboard item {item_id}: {e}"),
+        }
+    }
+
+    resolved.into_iter().flatten().collect()
 }
 
 fn u64_to_i64(value: u64) -> Result<i64, String> {
     i64::try_from(value).map_err(|_| format!("Value {value} exceeds i64 range"))
 }
 
-fn derive_cipher(password: &str) -> Aes256Gcm {
+pub(crate) fn derive_cipher(password: &str) -> Aes256Gcm {
     let mut hasher = Sha256::new();
     hasher.update(password.as_bytes());
     let key = hasher.finalize();
     Aes256Gcm::new_from_slice(&key).expect("SHA-256 output must be 32 bytes")
 }
 
-fn encrypt_payload(cipher: &Aes256Gcm, plaintext: &str) -> Result<String, String> {
+pub(crate) fn encrypt_payload(cipher: &Aes256Gcm, plaintext: &str) -> Result<String, String> {
     let mut nonce_bytes = [0u8; 12];
     rand::rng().fill_bytes(&mut nonce_bytes);
     let nonce = Nonce::from_slice(&nonce_bytes);
@@ -237,14 +488,14 @@ fn encrypt_payload(cipher: &Aes256Gcm, plaintext: &str) -> Result<String, String
         .map_err(|e| format!("Encryption failed: {e}"))?;
 
     Ok(format!(
-        "enc:v1:{}:{}",
+        "{ENCRYPTED_PAYLOAD_PREFIX}{}:{}",
         BASE64.encode(nonce_bytes),
         BASE64.encode(ciphertext)
     ))
 }
 
 fn decrypt_payload(cipher: &Aes256Gcm, payload: &str) -> Result<String, String> {
-    let Some(rest) = payload.strip_prefix("enc:v1:") else {
+    let Some(rest) = payload.strip_prefix(ENCRYPTED_PAYLOAD_PREFIX) else {
         return Err("Payload is not encrypted".to_string());
     };
 
@@ -278,3 +529,96 @@ fn decrypt_payload(cipher: &Aes256Gcm, payload: &str) -> Result<String, String>
 pub fn warn_persistence_sync_error(context: &str, err: &str) {
     warn!("Persistence {context} failed: {err}");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn near_duplicates() -> Vec<ClipboardItem> {
+        let base = "fn main() {\n    println!(\"hello\");\n}\n".repeat(8);
+        // Newest first, as in the history; each one a small edit of the next older one
+        vec![
+            ClipboardItem::plain_text(3, &format!("{base}// third edit\n")),
+            ClipboardItem::plain_text(2, &format!("{base}// second\n")),
+            ClipboardItem::plain_text(1, &base),
+        ]
+    }
+
+    fn text(item: &ClipboardItem) -> &[u8] {
+        &item.mime_data["text/plain"]
+    }
+
+    /// Rows as `save_history` stores them: each a delta against the next one if it can be
+    fn stored_rows(history: &[ClipboardItem]) -> Vec<StoredItem> {
+        history
+            .iter()
+            .enumerate()
+            .map(|(index, item)| {
+                match history
+                    .get(index + 1)
+                    .and_then(|base| delta_row(base, item))
+                {
+                    Some(row) => StoredItem::Delta(row),
+                    None => StoredItem::Full(item.clone()),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn chained_delta_rows_resolve_to_the_full_items() {
+        let history = near_duplicates();
+        let rows = stored_rows(&history);
+        assert!(matches!(rows[0], StoredItem::Delta(_)));
+        assert!(matches!(rows[1], StoredItem::Delta(_)));
+
+        let resolved = resolve_delta_rows(rows);
+        assert_eq!(resolved.len(), history.len());
+        for (resolved, original) in resolved.iter().zip(&history) {
+            assert_eq!(resolved.item_id, original.item_id);
+            assert_eq!(text(resolved), text(original));
+        }
+    }
+
+    #[test]
+    fn delta_rows_without_their_base_are_dropped() {
+        let history = near_duplicates();
+        let mut rows = stored_rows(&history);
+        // The middle row was never written; the newest one depends on it
+        rows.remove(1);
+
+        let resolved = resolve_delta_rows(rows);
+        let ids: Vec<u64> = resolved.iter().map(|item| item.item_id).collect();
+        assert_eq!(ids, [1]);
+    }
+
+    #[test]
+    fn saved_history_loads_back_in_order() {
+        let db_path = std::env::temp_dir().join(format!(
+            "cursor-clip-persistence-test-{}.db",
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&db_path);
+        let persistence = ClipboardPersistence {
+            db: open_database(&db_path).unwrap(),
+            db_path: db_path.clone(),
+            cipher: derive_cipher("test password"),
+        };
+
+        let mut history = near_duplicates();
+        // A pinned item is read back first whatever its age
+        history[2].pinned = true;
+        history.rotate_right(1);
+        persistence.save_history(&history).unwrap();
+        let loaded = persistence.load_history().unwrap();
+        drop(persistence);
+        let _ = fs::remove_dir_all(&db_path);
+
+        let ids: Vec<u64> = loaded.iter().map(|item| item.item_id).collect();
+        assert_eq!(ids, [1, 3, 2]);
+        for (loaded, original) in loaded.iter().zip(&history) {
+            assert_eq!(text(loaded), text(original));
+            assert_eq!(loaded.pinned, original.pinned);
+        }
+    }
+}
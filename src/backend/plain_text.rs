@@ -0,0 +1,177 @@
+use bytes::Bytes;
+use indexmap::IndexMap;
+
+pub const PLAIN_TEXT_MIME: &str = "text/plain;charset=utf-8";
+pub const HTML_MIME: &str = "text/html";
+
+// Offered for text the daemon creates or converts itself
+pub const SYNTHESIZED_TEXT_MIMES: [&str; 3] = [PLAIN_TEXT_MIME, "text/plain", "UTF8_STRING"];
+
+// Plain text flavours, best first
+const PLAIN_TEXT_MIMES: &[&str] = &[
+    PLAIN_TEXT_MIME,
+    "text/plain",
+    "UTF8_STRING",
+    "STRING",
+    "TEXT",
+];
+
+/// Reduce an entry to a single `text/plain;charset=utf-8` payload: the stored plain
+/// text when there is one, otherwise its HTML with the markup stripped.
+pub fn plain_text_payload(mime_data: &IndexMap<String, Bytes>) -> Option<IndexMap<String, Bytes>> {
+    let text = PLAIN_TEXT_MIMES
+        .iter()
+        .find_map(|mime| mime_data.get(*mime))
+        .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+        .or_else(|| {
+            mime_data
+                .get(HTML_MIME)
+                .map(|html| strip_html(&String::from_utf8_lossy(html)))
+        })?;
+
+    let mut payload = IndexMap::new();
+    payload.insert(PLAIN_TEXT_MIME.to_string(), Bytes::from(text));
+    Some(payload)
+}
+
+/// Whether an entry has HTML but no plain text, so re-owning it should also offer
+/// [`SYNTHESIZED_TEXT_MIMES`] for paste targets that only accept plain text.
+pub fn needs_synthesized_text(mime_data: &IndexMap<String, Bytes>) -> bool {
+    mime_data.contains_key(HTML_MIME)
+        && !PLAIN_TEXT_MIMES
+            .iter()
+            .any(|mime| mime_data.contains_key(*mime))
+}
+
+/// `mime_data` plus plain text converted from its HTML, if it has no plain text of its own.
+pub fn with_synthesized_text(mut mime_data: IndexMap<String, Bytes>) -> IndexMap<String, Bytes> {
+    if needs_synthesized_text(&mime_data) {
+        let text = strip_html(&String::from_utf8_lossy(&mime_data[HTML_MIME]));
+        mime_data.extend(text_mime_content(&text));
+    }
+    mime_data
+}
+
+/// MIME map for text the daemon creates itself (transforms, snippets, merges).
+pub fn text_mime_content(text: &str) -> IndexMap<String, Bytes> {
+    let mut mime_content = IndexMap::new();
+    for mime in SYNTHESIZED_TEXT_MIMES {
+        mime_content.insert(mime.to_string(), Bytes::from(text.to_string()));
+    }
+    mime_content
+}
+
+/// Minimal HTML to text conversion: drops tags, comments, `<script>`/`<style>` bodies,
+/// collapses source whitespace (except inside `<pre>`), turns block-level elements into
+/// line breaks and decodes common and numeric entities.
+pub fn strip_html(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut rest = html;
+    let mut in_pre = false;
+
+    while let Some(start) = rest.find('<') {
+        push_text(&mut text, &rest[..start], in_pre);
+        rest = &rest[start..];
+
+        if let Some(after_comment) = rest.strip_prefix("<!--") {
+            rest = after_comment
+                .find("-->")
+                .map_or("", |end| &after_comment[end + 3..]);
+            continue;
+        }
+
+        let Some(end) = rest.find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = rest[1..end].trim().to_ascii_lowercase();
+        rest = &rest[end + 1..];
+
+        let closing_tag = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default();
+        if !closing_tag && matches!(name, "script" | "style") {
+            let closing = format!("</{name}");
+            rest = rest
+                .to_ascii_lowercase()
+                .find(&closing)
+                .and_then(|index| rest[index..].find('>').map(|end| &rest[index + end + 1..]))
+                .unwrap_or("");
+            continue;
+        }
+        if name == "pre" {
+            in_pre = !closing_tag;
+        }
+        if matches!(
+            name,
+            "br" | "p" | "div" | "li" | "tr" | "pre" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6"
+        ) && !text.ends_with('\n')
+            && !text.is_empty()
+        {
+            text.truncate(text.trim_end_matches(' ').len());
+            text.push('\n');
+        }
+    }
+    push_text(&mut text, rest, in_pre);
+
+    decode_entities(text.trim())
+}
+
+/// Append text between tags; outside `<pre>` runs of whitespace become one space.
+fn push_text(text: &mut String, segment: &str, in_pre: bool) {
+    if in_pre {
+        text.push_str(segment);
+        return;
+    }
+    for c in segment.chars() {
+        if !c.is_whitespace() {
+            text.push(c);
+        } else if !text.is_empty() && !text.ends_with([' ', '\n']) {
+            text.push(' ');
+        }
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    decode_numeric_entities(text)
+        .replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&apos;", "'")
+        // Last, so "&amp;lt;" becomes "&lt;" rather than "<"
+        .replace("&amp;", "&")
+}
+
+/// Decode `&#NNN;` and `&#xHH;`; invalid references are kept as they are.
+fn decode_numeric_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("&#") {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let reference = rest[2..].split_once(';').and_then(|(number, _)| {
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            Some((char::from_u32(code)?, number.len() + 3))
+        });
+        match reference {
+            Some((c, len)) => {
+                decoded.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push_str("&#");
+                rest = &rest[2..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
@@ -0,0 +1,127 @@
+use std::sync::{Arc, Mutex};
+
+use gtk4::gio;
+use gtk4::glib;
+use gtk4::prelude::*;
+use log::{debug, info, warn};
+
+use super::backend_state::BackendState;
+
+const UPOWER_NAME: &str = "org.freedesktop.UPower";
+const UPOWER_PATH: &str = "/org/freedesktop/UPower";
+const PROFILES_NAME: &str = "net.hadess.PowerProfiles";
+const PROFILES_PATH: &str = "/net/hadess/PowerProfiles";
+
+/// Power source and profile as reported by UPower and power-profiles-daemon.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PowerState {
+    pub on_battery: bool,
+    /// Active power profile, e.g. "balanced" or "power-saver"
+    pub profile: Option<String>,
+}
+
+impl PowerState {
+    /// Whether background work should be reduced.
+    pub fn saving(&self) -> bool {
+        self.on_battery || self.profile.as_deref() == Some("power-saver")
+    }
+}
+
+/// Follow UPower and power-profiles-daemon on the system bus from a dedicated GLib
+/// main loop. Either service may be missing; the state then keeps its defaults.
+pub fn spawn_power_monitor(state: Arc<Mutex<BackendState>>) {
+    if let Err(e) = std::thread::Builder::new()
+        .name("cursor-clip-power".to_string())
+        .spawn(move || run_power_monitor(state))
+    {
+        warn!("Failed to spawn power monitor thread: {e}");
+    }
+}
+
+fn run_power_monitor(state: Arc<Mutex<BackendState>>) {
+    let context = glib::MainContext::new();
+    let result = context.with_thread_default(|| {
+        let upower = watch_property(
+            &state,
+            UPOWER_NAME,
+            UPOWER_PATH,
+            "OnBattery",
+            |power, value| {
+                power.on_battery = value.get::<bool>().unwrap_or(false);
+            },
+        );
+        let profiles = watch_property(
+            &state,
+            PROFILES_NAME,
+            PROFILES_PATH,
+            "ActiveProfile",
+            |power, value| power.profile = value.get::<String>(),
+        );
+        if upower.is_none() && profiles.is_none() {
+            info!(
+                "Neither UPower nor power-profiles-daemon is reachable; battery saving is disabled"
+            );
+            return;
+        }
+
+        // Proxies stay alive for the lifetime of the loop
+        let _proxies = (upower, profiles);
+        glib::MainLoop::new(Some(&context), false).run();
+    });
+
+    if let Err(e) = result {
+        warn!("Failed to acquire GLib main context for power monitor: {e}");
+    }
+}
+
+/// Apply `property` of the service to the backend state now and whenever it changes.
+fn watch_property(
+    state: &Arc<Mutex<BackendState>>,
+    name: &str,
+    path: &str,
+    property: &'static str,
+    apply: fn(&mut PowerState, &glib::Variant),
+) -> Option<gio::DBusProxy> {
+    let proxy = match gio::DBusProxy::for_bus_sync(
+        gio::BusType::System,
+        gio::DBusProxyFlags::NONE,
+        None,
+        name,
+        path,
+        name,
+        gio::Cancellable::NONE,
+    ) {
+        Ok(proxy) => proxy,
+        Err(e) => {
+            debug!("{name} unavailable: {e}");
+            return None;
+        }
+    };
+    // A proxy is created even when the service is not running; it just has no properties
+    let Some(value) = proxy.cached_property(property) else {
+        debug!("{name} does not provide {property}");
+        return None;
+    };
+    update_power_state(state, |power| apply(power, &value));
+
+    let state = state.clone();
+    proxy.connect_g_properties_changed(move |proxy, _, _| {
+        if let Some(value) = proxy.cached_property(property) {
+            update_power_state(&state, |power| apply(power, &value));
+        }
+    });
+    Some(proxy)
+}
+
+fn update_power_state(state: &Arc<Mutex<BackendState>>, update: impl FnOnce(&mut PowerState)) {
+    let mut state = state.lock().unwrap();
+    let was_saving = state.power.saving();
+    update(&mut state.power);
+    if state.power.saving() != was_saving && state.config.battery_saver {
+        if state.power.saving() {
+            info!("Battery saving enabled ({:?})", state.power);
+        } else {
+            info!("Battery saving disabled ({:?})", state.power);
+        }
+    }
+}
@@ -0,0 +1,274 @@
+//! Filter prefixes in `SearchHistory` queries. Words like `type:url`, `mime:text/html`,
+//! `app:firefox`, `since:today` and `re:\d+\.\d+\.\d+\.\d+` narrow the results, as do
+//! `"quoted phrases"` and `prefix*` words; the remaining words are the fuzzy query.
+
+use crate::shared::{ClipboardContentType, ClipboardItem};
+use gtk4::glib;
+use regex::Regex;
+
+/// A parsed search query: the fuzzy text plus every filter given.
+#[derive(Debug, Default)]
+pub struct SearchQuery {
+    /// Words without a filter prefix, joined by spaces
+    pub text: String,
+    /// Any of these content types (`type:`); empty allows every type
+    pub types: Vec<ClipboardContentType>,
+    /// Any of these MIME types, `image/*` style wildcards allowed (`mime:`)
+    pub mimes: Vec<String>,
+    /// Any of these, as a case-insensitive part of the source app id (`app:`)
+    pub apps: Vec<String>,
+    /// All of these must match the item's text (`re:`, quoted phrases and `prefix*` words)
+    pub patterns: Vec<Regex>,
+    /// Copied at or after this Unix time (`since:`)
+    pub since: Option<u64>,
+}
+
+impl SearchQuery {
+    /// Whether the query has neither text nor filters.
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+            && self.types.is_empty()
+            && self.mimes.is_empty()
+            && self.apps.is_empty()
+            && self.patterns.is_empty()
+            && self.since.is_none()
+    }
+
+    /// Whether the query has only filters and nothing to rank by.
+    pub fn is_filter_only(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Whether `item` passes the type, MIME, app and time filters. The regular expressions
+    /// are checked separately since they need the item's text.
+    pub fn accepts(&self, item: &ClipboardItem) -> bool {
+        (self.types.is_empty() || self.types.contains(&item.content_type))
+            && (self.mimes.is_empty()
+                || item
+                    .mime_data
+                    .keys()
+                    .any(|mime| self.mimes.iter().any(|pattern| mime_matches(pattern, mime))))
+            && (self.apps.is_empty()
+                || item.source_app.as_ref().is_some_and(|app_id| {
+                    let app_id = app_id.to_ascii_lowercase();
+                    self.apps.iter().any(|app| app_id.contains(app.as_str()))
+                }))
+            && self.since.is_none_or(|since| item.timestamp >= since)
+    }
+}
+
+/// Split `query` into filters and fuzzy text. Unknown prefixes (e.g. `http:` in a URL)
+/// are left in the text. `now` is the current Unix time.
+pub fn parse_search_query(query: &str, now: u64) -> Result<SearchQuery, String> {
+    let mut parsed = SearchQuery::default();
+    let mut words = Vec::new();
+
+    let mut tokens = query.split_whitespace();
+    while let Some(word) = tokens.next() {
+        if let Some(mut part) = word.strip_prefix('"') {
+            // Up to the closing quote, or the end of the query while it is still being typed
+            let mut phrase = Vec::new();
+            loop {
+                if let Some(last) = part.strip_suffix('"') {
+                    phrase.push(last);
+                    break;
+                }
+                phrase.push(part);
+                match tokens.next() {
+                    Some(next) => part = next,
+                    None => break,
+                }
+            }
+            if let Some(pattern) = phrase_pattern(&phrase.join(" "))? {
+                parsed.patterns.push(pattern);
+            }
+            continue;
+        }
+        let Some((prefix, value)) = word.split_once(':') else {
+            match word.strip_suffix('*').filter(|prefix| !prefix.is_empty()) {
+                Some(prefix) => parsed.patterns.push(prefix_pattern(prefix)?),
+                None => words.push(word),
+            }
+            continue;
+        };
+        match prefix.to_ascii_lowercase().as_str() {
+            "type" => parsed.types.push(parse_content_type(value)?),
+            "mime" => parsed.mimes.push(value.to_ascii_lowercase()),
+            "app" => parsed.apps.push(value.to_ascii_lowercase()),
+            "re" => parsed.patterns.push(
+                Regex::new(value)
+                    .map_err(|e| format!("Invalid regular expression {value:?}: {e}"))?,
+            ),
+            "since" => parsed.since = Some(parse_since(value, now)?),
+            _ => words.push(word),
+        }
+    }
+
+    parsed.text = words.join(" ");
+    Ok(parsed)
+}
+
+/// Case-insensitive match of `phrase`'s words in order, with any whitespace between them.
+/// None for an empty phrase.
+fn phrase_pattern(phrase: &str) -> Result<Option<Regex>, String> {
+    let words: Vec<String> = phrase.split_whitespace().map(regex::escape).collect();
+    if words.is_empty() {
+        return Ok(None);
+    }
+    Regex::new(&format!("(?i){}", words.join(r"\s+")))
+        .map(Some)
+        .map_err(|e| format!("Invalid phrase {phrase:?}: {e}"))
+}
+
+/// Case-insensitive match of a word starting with `prefix`.
+fn prefix_pattern(prefix: &str) -> Result<Regex, String> {
+    // `\b` only holds before a word character, so `#tag*` matches `#tags` too
+    let boundary = if prefix.starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+        r"\b"
+    } else {
+        ""
+    };
+    Regex::new(&format!("(?i){boundary}{}", regex::escape(prefix)))
+        .map_err(|e| format!("Invalid prefix {prefix:?}: {e}"))
+}
+
+/// Content type by name, as in `type:` filters and `[[classifier_rules]]`.
+pub fn parse_content_type(value: &str) -> Result<ClipboardContentType, String> {
+    let value = value.to_ascii_lowercase();
+    // Accept the plural too, as in the overlay's tab names
+    let value = value.strip_suffix('s').unwrap_or(&value);
+    match value {
+        "text" => Ok(ClipboardContentType::Text),
+        "url" | "link" => Ok(ClipboardContentType::Url),
+        "code" => Ok(ClipboardContentType::Code),
+        "password" => Ok(ClipboardContentType::Password),
+        "file" => Ok(ClipboardContentType::File),
+        "image" => Ok(ClipboardContentType::Image),
+        "other" => Ok(ClipboardContentType::Other),
+        "email" | "mail" => Ok(ClipboardContentType::Email),
+        "ip" => Ok(ClipboardContentType::IpAddress),
+        "uuid" => Ok(ClipboardContentType::Uuid),
+        _ => Err(format!(
+            "Unknown type {value:?}; expected text, url, code, password, file, image, email, ip, uuid or other"
+        )),
+    }
+}
+
+/// `today`, `yesterday` (local midnight) or an age such as `30m`, `2h`, `7d`, `4w`.
+fn parse_since(value: &str, now: u64) -> Result<u64, String> {
+    match value.to_ascii_lowercase().as_str() {
+        "today" => return local_midnight(0),
+        "yesterday" => return local_midnight(1),
+        _ => {}
+    }
+
+    let invalid = || format!("Invalid since:{value}; expected today, yesterday or e.g. 2h, 7d");
+    let unit_start = value
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(invalid)?;
+    let amount: u64 = value[..unit_start].parse().map_err(|_| invalid())?;
+    let unit = match &value[unit_start..] {
+        "m" => 60,
+        "h" => 3_600,
+        "d" => 86_400,
+        "w" => 604_800,
+        _ => return Err(invalid()),
+    };
+    Ok(now.saturating_sub(amount.saturating_mul(unit)))
+}
+
+/// Unix time of the local midnight `days_ago` days back.
+fn local_midnight(days_ago: i32) -> Result<u64, String> {
+    let now = glib::DateTime::now_local().map_err(|e| format!("Local time unavailable: {e}"))?;
+    let midnight =
+        glib::DateTime::from_local(now.year(), now.month(), now.day_of_month(), 0, 0, 0.0)
+            .and_then(|midnight| midnight.add_days(-days_ago))
+            .map_err(|e| format!("Local time unavailable: {e}"))?;
+    Ok(midnight.to_unix().max(0) as u64)
+}
+
+/// `pattern` is a MIME type, or a prefix when it ends in `*` (`image/*`).
+fn mime_matches(pattern: &str, mime: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => mime.to_ascii_lowercase().starts_with(prefix),
+        None => mime.eq_ignore_ascii_case(pattern),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(query: &str) -> SearchQuery {
+        parse_search_query(query, 1_700_000_000).unwrap()
+    }
+
+    fn matching<'a>(query: &SearchQuery, texts: &[&'a str]) -> Vec<&'a str> {
+        texts
+            .iter()
+            .copied()
+            .filter(|text| query.patterns.iter().all(|pattern| pattern.is_match(text)))
+            .collect()
+    }
+
+    #[test]
+    fn quoted_words_match_as_a_phrase() {
+        let query = parse(r#"deploy "Merge  Request" now"#);
+        assert_eq!(query.text, "deploy now");
+        assert_eq!(
+            matching(
+                &query,
+                &[
+                    "open merge request 12",
+                    "merge\trequest",
+                    "request merge",
+                    "merge-request"
+                ]
+            ),
+            ["open merge request 12", "merge\trequest"]
+        );
+    }
+
+    #[test]
+    fn unclosed_quote_runs_to_the_end_of_the_query() {
+        let query = parse(r#""git push"#);
+        assert!(query.is_filter_only());
+        assert_eq!(
+            matching(&query, &["git push origin", "git pull"]),
+            ["git push origin"]
+        );
+        // Quotes alone are no filter
+        assert!(parse(r#""""#).is_empty());
+        assert!(parse(r#"" ""#).is_empty());
+    }
+
+    #[test]
+    fn starred_words_match_word_starts() {
+        let query = parse("ssh* #to*");
+        assert!(query.is_filter_only());
+        assert_eq!(
+            matching(
+                &query,
+                &[
+                    "SSH-keygen #todo",
+                    "openssh #todo",
+                    "ssh #notodo",
+                    "ssh_config #TOMORROW"
+                ]
+            ),
+            ["SSH-keygen #todo", "ssh_config #TOMORROW"]
+        );
+    }
+
+    #[test]
+    fn phrase_and_prefix_characters_are_literal() {
+        assert_eq!(
+            matching(&parse(r#""1.2 (a)""#), &["v1.2 (a)", "1x2 a"]),
+            ["v1.2 (a)"]
+        );
+        assert_eq!(matching(&parse("c++*"), &["c++20", "c20"]), ["c++20"]);
+        // A lone star and filter values stay as they were
+        assert_eq!(parse("*").text, "*");
+        assert!(parse("re:a*").patterns[0].is_match("b"));
+    }
+}
@@ -0,0 +1,97 @@
+use std::sync::{Arc, Mutex};
+
+use gtk4::gio;
+use gtk4::glib;
+use gtk4::prelude::*;
+use log::{debug, info, warn};
+use serde::Deserialize;
+
+use super::backend_state::BackendState;
+
+const LOGIND_NAME: &str = "org.freedesktop.login1";
+// logind resolves "auto" to the caller's session, or the user's graphical session
+const SESSION_PATH: &str = "/org/freedesktop/login1/session/auto";
+const SESSION_INTERFACE: &str = "org.freedesktop.login1.Session";
+
+/// `[on_lock]` section of config.toml: what to wipe when the session locks
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct LockConfig {
+    /// Clear the current selection, so nothing can be pasted from the locked session
+    pub clear_selection: bool,
+    /// Delete password items from the history, pinned ones included
+    pub purge_sensitive: bool,
+}
+
+impl LockConfig {
+    pub fn enabled(&self) -> bool {
+        self.clear_selection || self.purge_sensitive
+    }
+}
+
+/// Follow logind's lock state on the system bus from a dedicated GLib main loop.
+/// Lockers speaking ext-session-lock can't be observed by other clients, but
+/// `loginctl lock-session` (as used by swayidle, hypridle and desktop environments)
+/// emits `Lock`, and many lockers set `LockedHint`.
+pub fn spawn_lock_monitor(state: Arc<Mutex<BackendState>>) {
+    if let Err(e) = std::thread::Builder::new()
+        .name("cursor-clip-lock".to_string())
+        .spawn(move || run_lock_monitor(state))
+    {
+        warn!("Failed to spawn session lock monitor thread: {e}");
+    }
+}
+
+fn run_lock_monitor(state: Arc<Mutex<BackendState>>) {
+    let context = glib::MainContext::new();
+    let result = context.with_thread_default(|| {
+        let proxy = match gio::DBusProxy::for_bus_sync(
+            gio::BusType::System,
+            gio::DBusProxyFlags::NONE,
+            None,
+            LOGIND_NAME,
+            SESSION_PATH,
+            SESSION_INTERFACE,
+            gio::Cancellable::NONE,
+        ) {
+            Ok(proxy) => proxy,
+            Err(e) => {
+                info!("logind unavailable; [on_lock] settings have no effect: {e}");
+                return;
+            }
+        };
+        if proxy.cached_property("LockedHint").is_none() {
+            info!("No logind session found; [on_lock] settings have no effect");
+            return;
+        }
+
+        let state_for_signal = state.clone();
+        proxy.connect_g_signal(None, move |_, _, signal, _| {
+            if signal == "Lock" {
+                on_session_locked(&state_for_signal);
+            }
+        });
+        proxy.connect_g_properties_changed(move |proxy, changed, _| {
+            let locked = proxy
+                .cached_property("LockedHint")
+                .and_then(|value| value.get::<bool>())
+                .unwrap_or(false);
+            if locked && changed.lookup_value("LockedHint", None).is_some() {
+                on_session_locked(&state);
+            }
+        });
+
+        // The proxy stays alive for the lifetime of the loop
+        let _proxy = proxy;
+        glib::MainLoop::new(Some(&context), false).run();
+    });
+
+    if let Err(e) = result {
+        warn!("Failed to acquire GLib main context for session lock monitor: {e}");
+    }
+}
+
+fn on_session_locked(state: &Arc<Mutex<BackendState>>) {
+    debug!("Session locked");
+    state.lock().unwrap().wipe_on_lock();
+}
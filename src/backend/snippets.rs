@@ -0,0 +1,54 @@
+use gtk4::glib;
+
+/// Fill in the placeholders of a snippet: `{date}` (2024-05-31), `{time}` (14:05),
+/// `{datetime}` (2024-05-31 14:05) in local time, and `{clipboard}` with the text of
+/// the newest history item. `{{` and `}}` produce literal braces; unknown
+/// placeholders are kept as written.
+pub fn expand_placeholders(text: &str, clipboard: Option<&str>) -> String {
+    let now = glib::DateTime::now_local().ok();
+    let format = |pattern: &str| {
+        now.as_ref()
+            .and_then(|now| now.format(pattern).ok())
+            .map(|formatted| formatted.to_string())
+            .unwrap_or_default()
+    };
+
+    let mut expanded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(['{', '}']) {
+        expanded.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("{{") {
+            expanded.push('{');
+            rest = after;
+            continue;
+        }
+        if let Some(after) = rest.strip_prefix("}}") {
+            expanded.push('}');
+            rest = after;
+            continue;
+        }
+        let end = rest
+            .find('}')
+            .filter(|end| rest.starts_with('{') && !rest[1..*end].contains('{'));
+        let Some(end) = end else {
+            expanded.push_str(&rest[..1]);
+            rest = &rest[1..];
+            continue;
+        };
+        let value = match &rest[1..end] {
+            "date" => Some(format("%Y-%m-%d")),
+            "time" => Some(format("%H:%M")),
+            "datetime" => Some(format("%Y-%m-%d %H:%M")),
+            "clipboard" => Some(clipboard.unwrap_or_default().to_string()),
+            _ => None,
+        };
+        match value {
+            Some(value) => expanded.push_str(&value),
+            None => expanded.push_str(&rest[..=end]),
+        }
+        rest = &rest[end + 1..];
+    }
+    expanded.push_str(rest);
+    expanded
+}
@@ -0,0 +1,548 @@
+//! Opt-in history replication between devices. Peers share a key from `[sync]` in
+//! config.toml; every frame is sealed with AES-256-GCM under that key and each side
+//! proves it holds the key by echoing the other's random challenge, so recorded
+//! frames cannot be replayed into a new session.
+//!
+//! An exchange is one TCP connection: `Hello` both ways, then the connecting side
+//! sends its items newer than the previous exchange (`Sync`) and gets the peer's
+//! back (`Items`). Items are identified by a hash of their content, so merging is
+//! idempotent and both sides converge regardless of who connects first.
+
+use crate::backend::backend_state::BackendState;
+use crate::backend::image_pipeline::CANONICAL_IMAGE_MIME;
+use crate::backend::payload_compression::decompressed_payloads;
+use crate::backend::plain_text::{PLAIN_TEXT_MIME, plain_text_payload};
+use crate::shared::{BackendMessage, ClipboardContentType, ClipboardItem};
+use aes_gcm::aead::{Aead, KeyInit};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use bytes::Bytes;
+use indexmap::IndexMap;
+use log::{debug, error, info, warn};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::RecvError;
+
+const PROTOCOL_VERSION: u32 = 1;
+const MAX_FRAME_BYTES: usize = 32 * 1024 * 1024;
+const MIN_KEY_LEN: usize = 16;
+const IO_TIMEOUT: Duration = Duration::from_secs(10);
+// Items up to this much older than the last exchange are sent again, covering clock
+// skew between devices; duplicates are dropped by hash on the receiving side
+const CLOCK_SKEW_MARGIN_SECS: u64 = 300;
+
+/// `[sync]` section of config.toml
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct SyncConfig {
+    pub enabled: bool,
+    /// Shared secret, identical on every device (e.g. `openssl rand -base64 32`)
+    pub key: String,
+    /// Address to accept peers on; empty to only connect out
+    pub listen_address: String,
+    /// Name sent to peers in the handshake; defaults to the hostname
+    pub device_name: Option<String>,
+    /// Also replicate images (PNG); text, URLs and code are always included
+    pub include_images: bool,
+    /// Seconds between exchanges with every peer; new copies are pushed immediately
+    pub interval_secs: u64,
+    pub peers: Vec<SyncPeer>,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            key: String::new(),
+            listen_address: "0.0.0.0:47469".to_string(),
+            device_name: None,
+            include_images: false,
+            interval_secs: 60,
+            peers: Vec::new(),
+        }
+    }
+}
+
+// Keeps the shared key out of debug output
+impl std::fmt::Debug for SyncConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SyncConfig")
+            .field("enabled", &self.enabled)
+            .field("listen_address", &self.listen_address)
+            .field("device_name", &self.device_name)
+            .field("include_images", &self.include_images)
+            .field("interval_secs", &self.interval_secs)
+            .field("peers", &self.peers)
+            .finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SyncPeer {
+    pub name: String,
+    /// `host:port` of the peer's `listen_address`
+    pub address: String,
+}
+
+/// A history entry as sent to peers: only the payloads that make sense on another
+/// machine (plain text or the canonical PNG), base64 encoded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SyncItem {
+    hash: String,
+    timestamp: u64,
+    mime_data: IndexMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum SyncMessage {
+    Hello {
+        version: u32,
+        device: String,
+        challenge: String,
+    },
+    Sync {
+        since: u64,
+        items: Vec<SyncItem>,
+    },
+    Items {
+        items: Vec<SyncItem>,
+    },
+}
+
+/// Every frame after the first `Hello` carries the challenge the receiver sent.
+#[derive(Debug, Serialize, Deserialize)]
+struct SyncFrame {
+    session: String,
+    message: SyncMessage,
+}
+
+struct SyncContext {
+    state: Arc<Mutex<BackendState>>,
+    cipher: Aes256Gcm,
+    device: String,
+    include_images: bool,
+}
+
+/// Start the listener and the exchange loop if `[sync]` is enabled and usable.
+pub fn spawn_sync_service(state: Arc<Mutex<BackendState>>) {
+    let config = state.lock().unwrap().config.sync.clone();
+    if !config.enabled {
+        return;
+    }
+    if config.key.len() < MIN_KEY_LEN {
+        error!("Sync disabled: `key` in [sync] must be at least {MIN_KEY_LEN} characters");
+        return;
+    }
+
+    let context = Arc::new(SyncContext {
+        state,
+        cipher: derive_sync_cipher(&config.key),
+        device: config.device_name.clone().unwrap_or_else(hostname),
+        include_images: config.include_images,
+    });
+    info!(
+        "History sync enabled as {:?} with {} peer(s)",
+        context.device,
+        config.peers.len()
+    );
+
+    if !config.listen_address.is_empty() {
+        let context = context.clone();
+        let address = config.listen_address.clone();
+        tokio::spawn(async move { run_listener(&address, context).await });
+    }
+    if !config.peers.is_empty() {
+        let interval = Duration::from_secs(config.interval_secs.max(5));
+        tokio::spawn(async move { run_exchange_loop(config.peers, interval, context).await });
+    }
+}
+
+async fn run_listener(address: &str, context: Arc<SyncContext>) {
+    let listener = match TcpListener::bind(address).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("Failed to bind sync listener on {address}: {e}");
+            return;
+        }
+    };
+    info!("Accepting sync peers on {address}");
+
+    loop {
+        let (stream, peer_address) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!("Sync accept failed: {e}");
+                continue;
+            }
+        };
+        let context = context.clone();
+        tokio::spawn(async move {
+            match tokio::time::timeout(IO_TIMEOUT * 3, serve_peer(stream, &context)).await {
+                Ok(Ok((device, merged))) => {
+                    debug!("Sync from {device} ({peer_address}): merged {merged} item(s)")
+                }
+                Ok(Err(e)) => warn!("Sync with {peer_address} failed: {e}"),
+                Err(_) => warn!("Sync with {peer_address} timed out"),
+            }
+        });
+    }
+}
+
+/// Exchange with every peer on a timer and right after something new is recorded.
+async fn run_exchange_loop(peers: Vec<SyncPeer>, interval: Duration, context: Arc<SyncContext>) {
+    let mut events = context.state.lock().unwrap().events.subscribe();
+    // Per peer: Unix time of the last successful exchange
+    let mut last_exchange: HashMap<String, u64> = HashMap::new();
+
+    loop {
+        for peer in &peers {
+            let since = last_exchange
+                .get(&peer.name)
+                .map_or(0, |time| time.saturating_sub(CLOCK_SKEW_MARGIN_SECS));
+            let started = unix_now();
+            match tokio::time::timeout(IO_TIMEOUT * 3, exchange_with_peer(peer, since, &context))
+                .await
+            {
+                Ok(Ok(merged)) => {
+                    debug!("Synced with {}: merged {merged} item(s)", peer.name);
+                    last_exchange.insert(peer.name.clone(), started);
+                }
+                Ok(Err(e)) => debug!("Sync with {} ({}) failed: {e}", peer.name, peer.address),
+                Err(_) => debug!("Sync with {} ({}) timed out", peer.name, peer.address),
+            }
+        }
+
+        // Wait for the timer or a new copy; timer ticks while idle are skipped since
+        // nothing is being copied and the next copy triggers an exchange anyway
+        loop {
+            let new_item = async {
+                loop {
+                    match events.recv().await {
+                        Ok(BackendMessage::NewItem { .. }) | Err(RecvError::Lagged(_)) => {
+                            return true;
+                        }
+                        Ok(_) => continue,
+                        Err(RecvError::Closed) => return false,
+                    }
+                }
+            };
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {
+                    if !context.state.lock().unwrap().idle_tracker.is_idle() {
+                        break;
+                    }
+                }
+                running = new_item => {
+                    if !running {
+                        return;
+                    }
+                    break;
+                }
+            }
+        }
+    }
+}
+
+async fn exchange_with_peer(
+    peer: &SyncPeer,
+    since: u64,
+    context: &SyncContext,
+) -> Result<usize, String> {
+    let stream = tokio::time::timeout(IO_TIMEOUT, TcpStream::connect(&peer.address))
+        .await
+        .map_err(|_| "connect timed out".to_string())?
+        .map_err(|e| format!("connect failed: {e}"))?;
+    let mut connection = SyncConnection::new(stream, &context.cipher);
+
+    let challenge = random_token();
+    connection
+        .send(
+            "",
+            SyncMessage::Hello {
+                version: PROTOCOL_VERSION,
+                device: context.device.clone(),
+                challenge: challenge.clone(),
+            },
+        )
+        .await?;
+    let SyncMessage::Hello {
+        version,
+        challenge: peer_challenge,
+        ..
+    } = connection.recv(Some(&challenge)).await?
+    else {
+        return Err("expected Hello".to_string());
+    };
+    check_version(version)?;
+
+    let items = outgoing_items(context, since);
+    connection
+        .send(&peer_challenge, SyncMessage::Sync { since, items })
+        .await?;
+    let SyncMessage::Items { items } = connection.recv(Some(&challenge)).await? else {
+        return Err("expected Items".to_string());
+    };
+    Ok(merge_items(context, items))
+}
+
+async fn serve_peer(stream: TcpStream, context: &SyncContext) -> Result<(String, usize), String> {
+    let mut connection = SyncConnection::new(stream, &context.cipher);
+
+    // The opening Hello may be a replay; nothing is revealed until the peer echoes
+    // our fresh challenge in the next frame
+    let SyncMessage::Hello {
+        version,
+        device,
+        challenge: peer_challenge,
+    } = connection.recv(None).await?
+    else {
+        return Err("expected Hello".to_string());
+    };
+    check_version(version)?;
+
+    let challenge = random_token();
+    connection
+        .send(
+            &peer_challenge,
+            SyncMessage::Hello {
+                version: PROTOCOL_VERSION,
+                device: context.device.clone(),
+                challenge: challenge.clone(),
+            },
+        )
+        .await?;
+    let SyncMessage::Sync { since, items } = connection.recv(Some(&challenge)).await? else {
+        return Err("expected Sync".to_string());
+    };
+    let merged = merge_items(context, items);
+
+    let items = outgoing_items(context, since);
+    connection
+        .send(&peer_challenge, SyncMessage::Items { items })
+        .await?;
+    Ok((device, merged))
+}
+
+fn check_version(version: u32) -> Result<(), String> {
+    if version == PROTOCOL_VERSION {
+        Ok(())
+    } else {
+        Err(format!(
+            "peer speaks sync protocol {version}, this build speaks {PROTOCOL_VERSION}"
+        ))
+    }
+}
+
+/// Length-prefixed frames of `nonce || AES-256-GCM(JSON SyncFrame)`.
+struct SyncConnection<'a> {
+    stream: TcpStream,
+    cipher: &'a Aes256Gcm,
+}
+
+impl<'a> SyncConnection<'a> {
+    fn new(stream: TcpStream, cipher: &'a Aes256Gcm) -> Self {
+        Self { stream, cipher }
+    }
+
+    async fn send(&mut self, session: &str, message: SyncMessage) -> Result<(), String> {
+        let frame = SyncFrame {
+            session: session.to_string(),
+            message,
+        };
+        let plaintext =
+            serde_json::to_vec(&frame).map_err(|e| format!("failed to encode frame: {e}"))?;
+        let mut nonce_bytes = [0u8; 12];
+        rand::rng().fill_bytes(&mut nonce_bytes);
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce_bytes), plaintext.as_ref())
+            .map_err(|e| format!("encryption failed: {e}"))?;
+
+        let length = nonce_bytes.len() + ciphertext.len();
+        if length > MAX_FRAME_BYTES {
+            return Err(format!("frame of {length} bytes exceeds the sync limit"));
+        }
+        let mut buffer = Vec::with_capacity(4 + length);
+        buffer.extend_from_slice(&(length as u32).to_be_bytes());
+        buffer.extend_from_slice(&nonce_bytes);
+        buffer.extend_from_slice(&ciphertext);
+        tokio::time::timeout(IO_TIMEOUT, self.stream.write_all(&buffer))
+            .await
+            .map_err(|_| "write timed out".to_string())?
+            .map_err(|e| format!("write failed: {e}"))
+    }
+
+    /// Read and open the next frame. With `session`, the frame must carry that challenge.
+    async fn recv(&mut self, session: Option<&str>) -> Result<SyncMessage, String> {
+        let mut length = [0u8; 4];
+        tokio::time::timeout(IO_TIMEOUT, self.stream.read_exact(&mut length))
+            .await
+            .map_err(|_| "read timed out".to_string())?
+            .map_err(|e| format!("read failed: {e}"))?;
+        let length = u32::from_be_bytes(length) as usize;
+        if !(12..=MAX_FRAME_BYTES).contains(&length) {
+            return Err(format!("invalid frame length {length}"));
+        }
+        let mut buffer = vec![0u8; length];
+        tokio::time::timeout(IO_TIMEOUT, self.stream.read_exact(&mut buffer))
+            .await
+            .map_err(|_| "read timed out".to_string())?
+            .map_err(|e| format!("read failed: {e}"))?;
+
+        let (nonce, ciphertext) = buffer.split_at(12);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "frame failed to decrypt (peer uses a different key?)".to_string())?;
+        let frame: SyncFrame =
+            serde_json::from_slice(&plaintext).map_err(|e| format!("invalid frame: {e}"))?;
+        if let Some(expected) = session
+            && frame.session != expected
+        {
+            return Err("frame does not belong to this session".to_string());
+        }
+        Ok(frame.message)
+    }
+}
+
+fn outgoing_items(context: &SyncContext, since: u64) -> Vec<SyncItem> {
+    let state = context.state.lock().unwrap();
+    state
+        .history
+        .iter()
+        .filter(|item| item.timestamp >= since)
+        .filter_map(|item| sync_item(item, context.include_images))
+        .collect()
+}
+
+/// Insert items the local history does not have yet. Returns how many were added.
+fn merge_items(context: &SyncContext, items: Vec<SyncItem>) -> usize {
+    let mut state = context.state.lock().unwrap();
+    if state.demo_mode {
+        return 0;
+    }
+    let mut known: Vec<String> = state
+        .history
+        .iter()
+        .filter_map(|item| sync_item(item, true).map(|synced| synced.hash))
+        .collect();
+
+    let mut merged = 0;
+    for item in items {
+        let Some(mime_data) = decode_payloads(&item, context.include_images) else {
+            debug!("Dropping sync item with unexpected payloads");
+            continue;
+        };
+        // The sender's hash is not trusted; it only has to agree with the content
+        let Some(hash) = content_hash(&mime_data) else {
+            continue;
+        };
+        if known.contains(&hash) {
+            continue;
+        }
+        // Copies made in the future by a skewed clock would stick to the top
+        let timestamp = item.timestamp.min(unix_now());
+        if state.add_synced_item(mime_data, timestamp).is_some() {
+            known.push(hash);
+            merged += 1;
+        }
+    }
+    if merged > 0 {
+        info!("Merged {merged} item(s) from sync peers");
+    }
+    merged
+}
+
+/// The replicable part of a history entry; `None` for files, passwords and (unless
+/// enabled) images.
+fn sync_item(item: &ClipboardItem, include_images: bool) -> Option<SyncItem> {
+    let mime_data = decompressed_payloads(item);
+    let payload = match item.content_type {
+        ClipboardContentType::Text | ClipboardContentType::Url | ClipboardContentType::Code => {
+            plain_text_payload(&mime_data)?
+        }
+        ClipboardContentType::Image if include_images => {
+            let png = mime_data.get(CANONICAL_IMAGE_MIME)?;
+            IndexMap::from([(CANONICAL_IMAGE_MIME.to_string(), png.clone())])
+        }
+        _ => return None,
+    };
+    Some(SyncItem {
+        hash: content_hash(&payload)?,
+        timestamp: item.timestamp,
+        mime_data: payload
+            .iter()
+            .map(|(mime, bytes)| (mime.clone(), BASE64.encode(bytes)))
+            .collect(),
+    })
+}
+
+fn decode_payloads(item: &SyncItem, include_images: bool) -> Option<IndexMap<String, Bytes>> {
+    let (mime, encoded) = item.mime_data.first()?;
+    let allowed = mime == PLAIN_TEXT_MIME || (include_images && mime == CANONICAL_IMAGE_MIME);
+    if item.mime_data.len() != 1 || !allowed {
+        return None;
+    }
+    let bytes = BASE64.decode(encoded).ok()?;
+
+    let mut mime_data = IndexMap::new();
+    if mime == PLAIN_TEXT_MIME {
+        // Offer the usual text flavours, like items recorded locally
+        String::from_utf8(bytes.clone()).ok()?;
+        for text_mime in [PLAIN_TEXT_MIME, "text/plain", "UTF8_STRING"] {
+            mime_data.insert(text_mime.to_string(), Bytes::from(bytes.clone()));
+        }
+    } else {
+        mime_data.insert(mime.clone(), Bytes::from(bytes));
+    }
+    Some(mime_data)
+}
+
+/// Hash of the text (or PNG) payload, so the same copy matches across devices even
+/// when the source apps offered different MIME types.
+pub(crate) fn content_hash(mime_data: &IndexMap<String, Bytes>) -> Option<String> {
+    let (mime, bytes) = [PLAIN_TEXT_MIME, CANONICAL_IMAGE_MIME]
+        .into_iter()
+        .find_map(|mime| mime_data.get(mime).map(|bytes| (mime, bytes)))?;
+    let mut hasher = Sha256::new();
+    hasher.update(mime.as_bytes());
+    hasher.update([0]);
+    hasher.update(bytes);
+    Some(BASE64.encode(hasher.finalize()))
+}
+
+fn derive_sync_cipher(key: &str) -> Aes256Gcm {
+    let mut hasher = Sha256::new();
+    hasher.update(b"cursor-clip-sync-v1:");
+    hasher.update(key.as_bytes());
+    Aes256Gcm::new_from_slice(&hasher.finalize()).expect("SHA-256 output must be 32 bytes")
+}
+
+fn random_token() -> String {
+    let mut bytes = [0u8; 16];
+    rand::rng().fill_bytes(&mut bytes);
+    BASE64.encode(bytes)
+}
+
+fn hostname() -> String {
+    std::fs::read_to_string("/proc/sys/kernel/hostname")
+        .map(|name| name.trim().to_string())
+        .ok()
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "cursor-clip".to_string())
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
@@ -0,0 +1,191 @@
+use bytes::Bytes;
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+
+// Texts shorter than this are cheap enough to store in full
+const MIN_DELTA_TEXT_BYTES: usize = 128;
+
+/// A single edit window against a base text: keep `prefix_len` leading and
+/// `suffix_len` trailing bytes of the base and put `insert` in between.
+/// Covers the common incremental-edit case (typo fixes, appended lines) without
+/// a full diff algorithm.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextDelta {
+    base_len: usize,
+    prefix_len: usize,
+    suffix_len: usize,
+    insert: String,
+}
+
+impl TextDelta {
+    /// Compute a delta turning `base` into `target`, or `None` when the texts are too
+    /// different (or too short) for a delta to be worth it.
+    pub fn between(base: &str, target: &str) -> Option<Self> {
+        if target.len() < MIN_DELTA_TEXT_BYTES || base == target {
+            return None;
+        }
+
+        let mut prefix_len = base
+            .bytes()
+            .zip(target.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+        while !target.is_char_boundary(prefix_len) {
+            prefix_len -= 1;
+        }
+
+        let max_suffix = base.len().min(target.len()) - prefix_len;
+        let mut suffix_len = base
+            .bytes()
+            .rev()
+            .zip(target.bytes().rev())
+            .take(max_suffix)
+            .take_while(|(a, b)| a == b)
+            .count();
+        // Shared trailing bytes are identical, so a boundary in `target` is one in `base` too
+        while !target.is_char_boundary(target.len() - suffix_len) {
+            suffix_len -= 1;
+        }
+
+        let insert = &target[prefix_len..target.len() - suffix_len];
+        if insert.len() > target.len() / 2 {
+            return None;
+        }
+
+        Some(Self {
+            base_len: base.len(),
+            prefix_len,
+            suffix_len,
+            insert: insert.to_string(),
+        })
+    }
+
+    pub fn apply(&self, base: &str) -> Result<String, String> {
+        if base.len() != self.base_len
+            || self.prefix_len + self.suffix_len > base.len()
+            || !base.is_char_boundary(self.prefix_len)
+            || !base.is_char_boundary(base.len() - self.suffix_len)
+        {
+            return Err("Text delta does not match its base item".to_string());
+        }
+
+        let mut text = String::with_capacity(self.prefix_len + self.insert.len() + self.suffix_len);
+        text.push_str(&base[..self.prefix_len]);
+        text.push_str(&self.insert);
+        text.push_str(&base[base.len() - self.suffix_len..]);
+        Ok(text)
+    }
+}
+
+/// Delta-encode every text payload of `target` that is a near-duplicate of the same
+/// MIME type in `base`. Encoded entries are blanked in `target` and returned by MIME type.
+pub fn encode_text_deltas(
+    base: &IndexMap<String, Bytes>,
+    target: &mut IndexMap<String, Bytes>,
+) -> IndexMap<String, TextDelta> {
+    let mut deltas = IndexMap::new();
+    for (mime, bytes) in target.iter_mut() {
+        if !is_text_mime(mime) {
+            continue;
+        }
+        let Some(base_bytes) = base.get(mime) else {
+            continue;
+        };
+        let (Ok(base_text), Ok(text)) =
+            (std::str::from_utf8(base_bytes), std::str::from_utf8(bytes))
+        else {
+            continue;
+        };
+        if let Some(delta) = TextDelta::between(base_text, text) {
+            deltas.insert(mime.clone(), delta);
+            *bytes = Bytes::new();
+        }
+    }
+    deltas
+}
+
+/// Restore the payloads blanked by [`encode_text_deltas`] from the fully decoded base.
+pub fn apply_text_deltas(
+    base: &IndexMap<String, Bytes>,
+    target: &mut IndexMap<String, Bytes>,
+    deltas: &IndexMap<String, TextDelta>,
+) -> Result<(), String> {
+    for (mime, delta) in deltas {
+        let base_text = base
+            .get(mime)
+            .map(|bytes| std::str::from_utf8(bytes))
+            .ok_or_else(|| format!("Delta base is missing {mime} payload"))?
+            .map_err(|e| format!("Delta base {mime} payload is not UTF-8: {e}"))?;
+        target.insert(mime.clone(), Bytes::from(delta.apply(base_text)?));
+    }
+    Ok(())
+}
+
+fn is_text_mime(mime: &str) -> bool {
+    mime.starts_with("text/") || matches!(mime, "UTF8_STRING" | "STRING" | "TEXT")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn long_text() -> String {
+        "The quick brown fox jumps over the lazy dog. ".repeat(4)
+    }
+
+    #[test]
+    fn delta_of_an_edit_round_trips() {
+        let base = long_text();
+        let target = base.replacen("lazy", "sleepy", 1);
+        let delta = TextDelta::between(&base, &target).unwrap();
+        assert!(delta.insert.len() < 10, "{delta:?}");
+        assert_eq!(delta.apply(&base).unwrap(), target);
+    }
+
+    #[test]
+    fn delta_keeps_multibyte_characters_whole() {
+        let base = format!("{}é", long_text());
+        let target = format!("{}è", long_text());
+        let delta = TextDelta::between(&base, &target).unwrap();
+        assert_eq!(delta.apply(&base).unwrap(), target);
+    }
+
+    #[test]
+    fn short_equal_or_unrelated_texts_get_no_delta() {
+        let base = long_text();
+        assert!(TextDelta::between("short", "shorter").is_none());
+        assert!(TextDelta::between(&base, &base).is_none());
+        assert!(TextDelta::between(&base, &"0123456789".repeat(20)).is_none());
+    }
+
+    #[test]
+    fn delta_rejects_a_different_base() {
+        let base = long_text();
+        let delta = TextDelta::between(&base, &format!("{base}!")).unwrap();
+        assert!(delta.apply("another base").is_err());
+    }
+
+    #[test]
+    fn text_payloads_are_encoded_and_restored() {
+        let base = IndexMap::from([
+            ("text/plain".to_string(), Bytes::from(long_text())),
+            ("image/png".to_string(), Bytes::from_static(b"\x89PNG")),
+        ]);
+        let original = IndexMap::from([
+            (
+                "text/plain".to_string(),
+                Bytes::from(format!("{}.", long_text())),
+            ),
+            ("image/png".to_string(), Bytes::from_static(b"\x89PNG")),
+        ]);
+
+        let mut stored = original.clone();
+        let deltas = encode_text_deltas(&base, &mut stored);
+        assert_eq!(deltas.keys().collect::<Vec<_>>(), ["text/plain"]);
+        assert!(stored["text/plain"].is_empty());
+        assert_eq!(stored["image/png"], original["image/png"]);
+
+        apply_text_deltas(&base, &mut stored, &deltas).unwrap();
+        assert_eq!(stored, original);
+    }
+}
@@ -0,0 +1,66 @@
+use crate::shared::TextTransform;
+
+/// Apply `transform` to `text`. Fails when the input is not valid for the transform
+/// (e.g. malformed percent-encoding or JSON).
+pub fn apply_transform(transform: TextTransform, text: &str) -> Result<String, String> {
+    match transform {
+        TextTransform::Uppercase => Ok(text.to_uppercase()),
+        TextTransform::Lowercase => Ok(text.to_lowercase()),
+        TextTransform::TrimWhitespace => Ok(text
+            .trim()
+            .lines()
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n")),
+        TextTransform::StripNewlines => Ok(text.split_whitespace().collect::<Vec<_>>().join(" ")),
+        TextTransform::UrlDecode => url_decode(text),
+        TextTransform::UrlEncode => Ok(url_encode(text)),
+        TextTransform::JsonPretty => {
+            let value: serde_json::Value =
+                serde_json::from_str(text).map_err(|e| format!("Not valid JSON: {e}"))?;
+            serde_json::to_string_pretty(&value).map_err(|e| e.to_string())
+        }
+    }
+}
+
+// Percent-encode everything except RFC 3986 unreserved characters
+fn url_encode(text: &str) -> String {
+    let mut encoded = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+// Decodes `%XX` escapes and `+` as space (query-string style)
+fn url_decode(text: &str) -> Result<String, String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| format!("Invalid percent-encoding at byte {i}"))?;
+                decoded.push(hex);
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| "Decoded text is not valid UTF-8".to_string())
+}
@@ -1,9 +1,23 @@
-use crate::backend::backend_state::{BackendState, DataControlManager};
+use crate::backend::backend_state::{
+    BackendState, DataControlManager, DataControlOffer, LazyOffer, PendingSelection, SeatState,
+};
+use crate::backend::crash_backup;
+use crate::backend::file_list::URI_LIST_MIME;
+use crate::backend::image_pipeline::preferred_image_mime;
+use crate::backend::metrics::record_wayland_reconnect;
+use crate::backend::payload_compression::decompress_payload;
+use crate::backend::persistence::BackendConfig;
+use crate::backend::plain_text::{
+    HTML_MIME, PLAIN_TEXT_MIME, SYNTHESIZED_TEXT_MIMES, needs_synthesized_text, strip_html,
+};
+use crate::shared::ClipboardContentType;
+use crate::shared::quirks::DataControlProtocol;
 use std::sync::Arc as StdArc; // for event_created_child return type clarity
 use std::sync::{Arc, Mutex};
-use wayland_client::globals::{GlobalList, GlobalListContents, registry_queue_init};
+use std::time::Duration;
+use wayland_client::globals::{Global, GlobalList, GlobalListContents, registry_queue_init};
 use wayland_client::protocol::wl_registry;
-use wayland_client::protocol::wl_seat::WlSeat;
+use wayland_client::protocol::wl_seat::{self, WlSeat};
 use wayland_client::{Connection, Dispatch, EventQueue, Proxy, QueueHandle, delegate_noop};
 use wayland_protocols::ext::data_control::v1::client::{
     ext_data_control_device_v1::{self, ExtDataControlDeviceV1},
@@ -21,10 +35,59 @@ use wayland_protocols_wlr::data_control::v1::client::{
 use bytes::Bytes;
 use indexmap::IndexMap;
 use log::{debug, error, info, warn};
+use std::os::fd::OwnedFd;
+use tokio::sync::mpsc;
+
+// Stack mode waits this long after a paste before selecting the next queued item
+const PASTE_QUEUE_ADVANCE_DELAY: Duration = Duration::from_millis(300);
+// A pasting client that has not taken a payload after this long is given up on
+const PAYLOAD_WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+
+// Highest zwlr_data_control_manager_v1 version we bind; v2 added the primary selection
+const WLR_DATA_CONTROL_VERSION: u32 = 2;
+
+// Pauses before reconnecting after the compositor went away; the last one repeats
+const RECONNECT_BACKOFF: [Duration; 5] = [
+    Duration::from_millis(250),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_secs(2),
+    Duration::from_secs(5),
+];
 
 // Wrapper struct that holds the shared backend state for dispatch implementations
 pub struct MutexBackendState {
     pub backend_state: Arc<Mutex<BackendState>>,
+    /// Runtime for work off the dispatch thread (payload reads, paste queue advances)
+    runtime: tokio::runtime::Handle,
+    /// Finished payload reads, handed back to the task that records them
+    completed_reads: mpsc::UnboundedSender<CompletedRead>,
+}
+
+/// Payloads of one selection, read off the dispatch thread.
+struct CompletedRead {
+    seat_key: u32,
+    offer_id: wayland_client::backend::ObjectId,
+    seat_name: Option<String>,
+    mime_map: IndexMap<String, Bytes>,
+    lazy_offer: Option<LazyOffer>,
+}
+
+/// Time limits for reading one selection (`mime_read_timeout_ms`, `selection_read_deadline_ms`).
+#[derive(Clone, Copy)]
+struct ReadLimits {
+    per_mime: Option<Duration>,
+    total: Option<Duration>,
+}
+
+impl ReadLimits {
+    fn from_config(config: &BackendConfig) -> Self {
+        let limit = |ms: u64| (ms > 0).then(|| Duration::from_millis(ms));
+        Self {
+            per_mime: limit(config.mime_read_timeout_ms),
+            total: limit(config.selection_read_deadline_ms),
+        }
+    }
 }
 
 pub struct WaylandClipboardMonitor {
@@ -36,15 +99,68 @@ impl WaylandClipboardMonitor {
         Self { backend_state }
     }
 
+    /// Monitor the clipboard for as long as the daemon runs. When the compositor
+    /// connection is lost (e.g. the compositor restarted), reconnect with backoff,
+    /// bind the globals again and offer the item the clipboard held before. Returns
+    /// only if the first connection fails.
     pub fn start_monitoring(&self) -> Result<(), String> {
+        let mut reconnecting = false;
+        let mut reclaim_entry_id = None;
+        let mut retries = 0;
+        loop {
+            let error = match self.monitor_connection(reconnecting, reclaim_entry_id) {
+                Ok(()) => "the event loop ended".to_string(),
+                Err(e) => e,
+            };
+            let was_connected = self.backend_state.lock().unwrap().connection.is_some();
+            if was_connected {
+                warn!("Wayland connection lost ({error}); reconnecting");
+                let mut state = self.backend_state.lock().unwrap();
+                let offered = state.release_wayland_connection();
+                // Otherwise the newest item, as the compositor dropped every selection
+                reclaim_entry_id = offered.or_else(|| {
+                    (!state.monitor_only)
+                        .then(|| {
+                            state
+                                .history
+                                .iter()
+                                .max_by_key(|item| item.last_copied_at())
+                        })
+                        .flatten()
+                        .map(|item| item.item_id)
+                });
+                reconnecting = true;
+                retries = 0;
+            } else if !reconnecting {
+                return Err(error);
+            } else {
+                debug!("Reconnecting to Wayland failed: {error}");
+            }
+            let delay = RECONNECT_BACKOFF[retries.min(RECONNECT_BACKOFF.len() - 1)];
+            retries += 1;
+            std::thread::sleep(delay);
+        }
+    }
+
+    /// Connect, bind the globals and dispatch events until the connection fails. After
+    /// a reconnect, `reclaim_entry_id` is set as the selection on every seat again.
+    fn monitor_connection(
+        &self,
+        reconnecting: bool,
+        reclaim_entry_id: Option<u64>,
+    ) -> Result<(), String> {
         let connection = Connection::connect_to_env()
             .map_err(|e| format!("Failed to connect to Wayland: {e}"))?;
         let (globals, mut event_queue): (GlobalList, EventQueue<MutexBackendState>) =
             registry_queue_init::<MutexBackendState>(&connection)
                 .map_err(|e| format!("Failed to init registry: {e}"))?;
 
+        let (completed_reads, receiver) = mpsc::unbounded_channel();
+        spawn_read_consumer(self.backend_state.clone(), receiver);
         let mut shared_state_wrapper = MutexBackendState {
             backend_state: self.backend_state.clone(),
+            runtime: tokio::runtime::Handle::current(),
+            completed_reads,
         };
 
         let qh = event_queue.handle();
@@ -54,32 +170,88 @@ impl WaylandClipboardMonitor {
             state.connection = Some(connection);
         }
 
-        // Bind wl_seat — required to create a data device for clipboard access.
-        match globals.bind::<wayland_client::protocol::wl_seat::WlSeat, _, _>(&qh, 1..=9, ()) {
-            Ok(seat) => self.backend_state.lock().unwrap().seat = Some(seat),
-            Err(_) => {
-                error!(
-                    "Critical Wayland interface 'wl_seat' is not available. \
-                    Your compositor did not expose an input seat, which is required \
-                    for clipboard access. Exiting."
+        // Bind every advertised wl_seat — each one needs its own data device for clipboard access.
+        let seat_globals: Vec<_> = globals
+            .contents()
+            .clone_list()
+            .into_iter()
+            .filter(|global| global.interface == WlSeat::interface().name)
+            .collect();
+        if seat_globals.is_empty() {
+            error!(
+                "Critical Wayland interface 'wl_seat' is not available. \
+                Your compositor did not expose an input seat, which is required \
+                for clipboard access. Exiting."
+            );
+            std::process::exit(1);
+        }
+        {
+            let mut state = self.backend_state.lock().unwrap();
+            for global in seat_globals {
+                let seat = globals.registry().bind::<WlSeat, _, _>(
+                    global.name,
+                    global.version.min(9),
+                    &qh,
+                    global.name,
                 );
-                std::process::exit(1);
+                state.add_seat(global.name, seat);
             }
+            info!("Tracking {} wl_seat(s)", state.seats.len());
         }
 
-        // Bind data control manager: prefer ext-data-control, fall back to wlr-data-control.
-        if let Ok(manager) = globals.bind::<ExtDataControlManagerV1, _, _>(&qh, 1..=1, ()) {
-            self.bind_data_device(DataControlManager::Ext(manager), &qh);
-            info!("Using ext_data_control_manager_v1 clipboard protocol");
-        } else if let Ok(manager) = globals.bind::<ZwlrDataControlManagerV1, _, _>(&qh, 2..=2, ()) {
-            self.bind_data_device(DataControlManager::Wlr(manager), &qh);
-            info!("Using zwlr_data_control_manager_v1 clipboard protocol");
-        } else {
+        // Focus tracking is only needed to honour the app exclude list
+        {
+            let mut state = self.backend_state.lock().unwrap();
+            if !state.config.exclude_apps.is_empty() {
+                state.toplevel_tracker.bind(&globals, &qh);
+            }
+        }
+
+        // Idle notifications are per seat; input on the first seat is a good enough signal
+        {
+            let mut state = self.backend_state.lock().unwrap();
+            let timeout_secs = state.config.idle_timeout_secs;
+            let first_seat = state.seats.values().next().map(|seat| seat.seat.clone());
+            if timeout_secs != 0
+                && let Some(seat) = first_seat
+            {
+                state.idle_tracker.bind(&globals, &qh, &seat, timeout_secs);
+            }
+        }
+
+        // Bind data control manager: ext-data-control unless the compositor quirks prefer
+        // wlr-data-control, falling back to the other one.
+        let preferred = self
+            .backend_state
+            .lock()
+            .unwrap()
+            .quirks
+            .preferred_data_control;
+        let Some((manager, global_name)) = bind_data_control_manager(
+            globals.registry(),
+            &globals.contents().clone_list(),
+            &qh,
+            preferred,
+        ) else {
             error!(
                 "Neither 'ext_data_control_manager_v1' nor 'zwlr_data_control_manager_v1' is \
                 available. Clipboard monitoring cannot function without one of these protocols. Exiting."
             );
             std::process::exit(1);
+        };
+        {
+            let mut state = self.backend_state.lock().unwrap();
+            state.attach_data_control_manager(manager, global_name);
+            if reconnecting {
+                info!("Reconnected to the Wayland compositor");
+                record_wayland_reconnect();
+            }
+            if let Some(entry_id) = reclaim_entry_id {
+                let seat_keys: Vec<u32> = state.seats.keys().copied().collect();
+                for seat_key in seat_keys {
+                    take_ownership(&mut state, entry_id, seat_key);
+                }
+            }
         }
 
         info!("Wayland clipboard monitor initialized, monitoring changes...");
@@ -90,29 +262,14 @@ impl WaylandClipboardMonitor {
                 .map_err(|e| format!("Failed to dispatch events: {e}"))?;
         }
     }
-
-    fn bind_data_device(&self, manager: DataControlManager, qh: &QueueHandle<MutexBackendState>) {
-        let mut state = self.backend_state.lock().unwrap();
-        // seat is guaranteed set before this is called
-        let device = manager.get_data_device(state.seat.as_ref().unwrap(), qh);
-        state.data_control_manager = Some(manager);
-        state.data_control_device = Some(device);
-    }
 }
 
 impl Drop for WaylandClipboardMonitor {
     fn drop(&mut self) {
         if let Ok(mut state) = self.backend_state.lock() {
-            if let Some(dev) = state.data_control_device.take() {
-                dev.destroy();
-            }
-            if let Some(src) = state.current_source_object.take() {
-                src.destroy();
-            }
-            if let Some(mgr) = state.data_control_manager.take() {
-                mgr.destroy();
-            }
-            state.seat.take(); // wl_seat proxies auto-drop; no explicit destroy
+            // Destroys every seat's device and owned source along with the manager
+            state.detach_data_control_manager();
+            state.seats.clear(); // wl_seat proxies auto-drop; no explicit destroy
             if let Some(conn) = &state.connection {
                 let _ = conn.flush();
             }
@@ -120,6 +277,22 @@ impl Drop for WaylandClipboardMonitor {
     }
 }
 
+/// Whether a Wayland compositor with a seat and a data-control manager is reachable,
+/// i.e. whether [`WaylandClipboardMonitor`] can work.
+pub fn data_control_available() -> bool {
+    let Ok(connection) = Connection::connect_to_env() else {
+        return false;
+    };
+    let Ok((globals, _event_queue)) = registry_queue_init::<MutexBackendState>(&connection) else {
+        return false;
+    };
+    let globals = globals.contents().clone_list();
+    let offers = |interface: &str| globals.iter().any(|global| global.interface == interface);
+    offers(WlSeat::interface().name)
+        && (offers(ExtDataControlManagerV1::interface().name)
+            || offers(ZwlrDataControlManagerV1::interface().name))
+}
+
 // ================= Shared event helpers =================
 
 /// Handle a new data offer from either Wlr or Ext device.
@@ -143,18 +316,24 @@ fn handle_offer_mime(
 }
 
 /// Handle a Selection event from either Wlr or Ext device.
-/// `read_mime_data` is called (with the lock released) to read the data from the offer.
-fn handle_selection_event<F>(
+/// The source is asked for its data with the lock released; the returned pipes are read
+/// on the runtime so a stalled source cannot hold up dispatch.
+fn handle_selection_event(
     wrapper: &mut MutexBackendState,
-    offer_id: wayland_client::backend::ObjectId,
-    destroy_offer: impl FnOnce(),
-    read_mime_data: F,
-) where
-    F: FnOnce(Vec<String>) -> IndexMap<String, Bytes>,
-{
+    seat_key: u32,
+    offer: DataControlOffer,
+    conn: &Connection,
+) {
+    let offer_id = offer.id();
+    debug!("Selection changed to offer ID: {offer_id:?}");
     let (mime_list, already_current, suppress_read) = {
         let state = wrapper.backend_state.lock().unwrap();
-        let already_current = state
+        let Some(seat) = state.seats.get(&seat_key) else {
+            drop(state);
+            offer.destroy();
+            return;
+        };
+        let already_current = seat
             .current_data_offer
             .as_ref()
             .is_some_and(|o| o == &offer_id);
@@ -162,7 +341,7 @@ fn handle_selection_event<F>(
         (
             mime_list,
             already_current,
-            state.suppress_next_selection_read,
+            seat.suppress_next_selection_read,
         )
     };
 
@@ -176,88 +355,579 @@ fn handle_selection_event<F>(
     );
 
     if suppress_read {
-        wrapper.backend_state.lock().unwrap().current_data_offer = Some(offer_id);
+        set_current_data_offer(wrapper, seat_key, Some(offer_id));
         debug!(
             "Suppressed reading our own just-set selection; waiting for Cancelled to re-enable reads"
         );
-        destroy_offer();
+        offer.destroy();
         return;
     }
 
     if already_current {
-        destroy_offer();
+        offer.destroy();
         return;
     }
 
-    {
+    let (recording_enabled, excluded_app) = {
+        let state = wrapper.backend_state.lock().unwrap();
+        (state.recording_enabled, state.excluded_focused_app())
+    };
+    if !recording_enabled || excluded_app.is_some() {
+        match excluded_app {
+            Some(app_id) => info!("Not recording selection copied from excluded app {app_id}"),
+            None => debug!("Recording paused; not reading selection"),
+        }
         let mut state = wrapper.backend_state.lock().unwrap();
-        state.current_data_offer = Some(offer_id);
         state.mime_type_offers.clear();
+        if let Some(seat) = state.seats.get_mut(&seat_key) {
+            seat.current_data_offer = Some(offer_id);
+            seat.last_recorded_entry_id = None;
+        }
+        drop(state);
+        offer.destroy();
+        return;
     }
 
-    let mime_map = read_mime_data(mime_list);
-    if !mime_map.is_empty() {
+    let (seat_name, limits, mime_list, lazy) = {
         let mut state = wrapper.backend_state.lock().unwrap();
-        if let Some(new_id) = state.add_clipboard_item_from_mime_map(mime_map)
-            && !state.monitor_only
-            && !state.suppress_next_selection_read
-        {
-            if let Err(e) = state.set_clipboard_by_id(new_id, false) {
-                warn!("Failed to take ownership of selection id {new_id}: {e}");
-            } else {
-                debug!("Took ownership of external selection (id {new_id})");
-            }
+        state.mime_type_offers.clear();
+        let seat_name = state.seats.get_mut(&seat_key).map(|seat| {
+            seat.current_data_offer = Some(offer_id.clone());
+            seat.name.clone()
+        });
+        let mime_list = state.config.mime_policy.filter(mime_list);
+        (
+            seat_name,
+            ReadLimits::from_config(&state.config),
+            mime_list,
+            state.config.lazy_mime_fetch,
+        )
+    };
+
+    let (eager_mimes, lazy_mimes) = if lazy {
+        split_primary_mimes(select_target_mimes(&mime_list))
+    } else {
+        (mime_list, Vec::new())
+    };
+    let pipes = request_data_formats(&offer, eager_mimes, conn);
+    let lazy_offer = if lazy_mimes.is_empty() {
+        offer.destroy();
+        None
+    } else {
+        debug!(
+            "Leaving {} with the source until pasted",
+            lazy_mimes.join(", ")
+        );
+        Some(LazyOffer {
+            offer,
+            mime_types: lazy_mimes,
+        })
+    };
+    if pipes.is_empty() {
+        return;
+    }
+
+    let completed_reads = wrapper.completed_reads.clone();
+    wrapper.runtime.spawn(async move {
+        let mime_map = read_pipes(pipes, limits).await;
+        let _ = completed_reads.send(CompletedRead {
+            seat_key,
+            offer_id,
+            seat_name,
+            mime_map,
+            lazy_offer,
+        });
+    });
+}
+
+/// Split target MIME types into the primary formats read right away (plain text, the
+/// image, the file list) and the rest. Without any primary format everything is read.
+fn split_primary_mimes(targets: Vec<String>) -> (Vec<String>, Vec<String>) {
+    let plain_text = targets
+        .iter()
+        .find(|mime| mime.as_str() == PLAIN_TEXT_MIME)
+        .or_else(|| targets.iter().find(|mime| mime.starts_with("text/plain")))
+        .cloned();
+    let (primary, secondary): (Vec<String>, Vec<String>) = targets.into_iter().partition(|mime| {
+        Some(mime) == plain_text.as_ref() || mime.starts_with("image/") || mime == URI_LIST_MIME
+    });
+    if primary.is_empty() {
+        return (secondary, Vec::new());
+    }
+    (primary, secondary)
+}
+
+/// Record selections as their payload reads finish, in the order they complete.
+fn spawn_read_consumer(
+    backend_state: Arc<Mutex<BackendState>>,
+    mut receiver: mpsc::UnboundedReceiver<CompletedRead>,
+) {
+    tokio::spawn(async move {
+        while let Some(read) = receiver.recv().await {
+            finish_selection(&backend_state, read);
         }
+    });
+}
+
+/// Hand a fully read selection to the debouncer, or record it right away.
+fn finish_selection(backend_state: &Arc<Mutex<BackendState>>, read: CompletedRead) {
+    let CompletedRead {
+        seat_key,
+        offer_id,
+        seat_name,
+        mime_map,
+        lazy_offer,
+    } = read;
+    if mime_map.is_empty() {
+        return;
+    }
+
+    let debounce_ms = backend_state.lock().unwrap().config.selection_debounce_ms;
+    if debounce_ms == 0 {
+        record_selection(
+            backend_state,
+            seat_key,
+            offer_id,
+            mime_map,
+            seat_name,
+            lazy_offer,
+        );
+    } else {
+        debounce_selection(
+            backend_state,
+            seat_key,
+            PendingSelection {
+                offer_id,
+                mime_map,
+                seat_name,
+                lazy_offer,
+            },
+            debounce_ms,
+        );
     }
-    destroy_offer();
 }
 
-/// Handle a Source Send event for either Wlr or Ext source.
-fn handle_source_send(state: &BackendState, mime_type: String, fd: std::os::fd::OwnedFd) {
-    use std::io::Write;
-    debug!("Data source Send event for MIME type: {mime_type}");
-    let Some(item_id) = state.current_source_entry_id else {
-        warn!("No current_source_id set when Send event received");
+/// Hold a selection back for `debounce_ms`. A newer selection on the seat replaces it,
+/// so apps that update the selection many times a second (terminals extending a
+/// mouse selection, office suites) only leave the final, stable one in the history.
+fn debounce_selection(
+    backend_state: &Arc<Mutex<BackendState>>,
+    seat_key: u32,
+    pending: PendingSelection,
+    debounce_ms: u64,
+) {
+    let offer_id = pending.offer_id.clone();
+    {
+        let mut state = backend_state.lock().unwrap();
+        let Some(seat) = state.seats.get_mut(&seat_key) else {
+            return;
+        };
+        if seat.pending_selection.replace(pending).is_some() {
+            debug!("Coalesced a selection replaced within {debounce_ms} ms");
+        }
+    }
+
+    let backend_state = backend_state.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(debounce_ms));
+        let pending = {
+            let mut state = backend_state.lock().unwrap();
+            let Some(seat) = state.seats.get_mut(&seat_key) else {
+                return;
+            };
+            // Superseded by a newer selection, which has its own timer
+            let Some(pending) = seat
+                .pending_selection
+                .take_if(|pending| pending.offer_id == offer_id)
+            else {
+                return;
+            };
+            pending
+        };
+        if looks_partial(&pending.mime_map) {
+            debug!("Ignoring a partial selection (no usable content)");
+            return;
+        }
+        record_selection(
+            &backend_state,
+            seat_key,
+            pending.offer_id,
+            pending.mime_map,
+            pending.seat_name,
+            pending.lazy_offer,
+        );
+    });
+}
+
+/// Offers an app publishes while it is still assembling the selection: every payload
+/// empty, or text that is only whitespace with no image or file list beside it.
+fn looks_partial(mime_map: &IndexMap<String, Bytes>) -> bool {
+    let is_text = |mime: &str| {
+        mime.starts_with("text/") && mime != URI_LIST_MIME
+            || matches!(mime, "UTF8_STRING" | "STRING" | "TEXT")
+    };
+    let has_text = mime_map.keys().any(|mime| is_text(mime));
+    !mime_map.iter().any(|(mime, data)| {
+        if is_text(mime) {
+            !data.iter().all(u8::is_ascii_whitespace)
+        } else {
+            // App-private targets next to text don't make an empty text meaningful
+            !data.is_empty() && (!has_text || mime.starts_with("image/") || mime == URI_LIST_MIME)
+        }
+    })
+}
+
+/// Add a read selection to the history and, unless monitoring only, re-offer it so it
+/// survives the source app closing.
+fn record_selection(
+    backend_state: &Arc<Mutex<BackendState>>,
+    seat_key: u32,
+    offer_id: wayland_client::backend::ObjectId,
+    mime_map: IndexMap<String, Bytes>,
+    seat_name: Option<String>,
+    lazy_offer: Option<LazyOffer>,
+) {
+    let mut state = backend_state.lock().unwrap();
+    let new_id = state.add_clipboard_item_from_mime_map(mime_map, seat_name);
+    let Some(seat) = state.seats.get_mut(&seat_key) else {
         return;
     };
-    let Some(item) = state.get_item_by_id(item_id) else {
-        warn!("Clipboard item id {item_id} no longer exists in history");
+    seat.last_recorded_entry_id = new_id;
+    // Replacing the previous lazy offer destroys it
+    seat.lazy_offer = new_id.zip(lazy_offer);
+    // A debounced selection may have been replaced by one we set ourselves meanwhile
+    let keep_selection =
+        seat.suppress_next_selection_read || seat.current_data_offer.as_ref() != Some(&offer_id);
+    let Some(new_id) = new_id else {
         return;
     };
-    let mut file: std::fs::File = fd.into();
-    if let Some(bytes) = item.mime_data.get(&mime_type) {
-        if let Err(e) = file.write_all(bytes.as_ref()) {
-            error!("Failed writing selection data (id {item_id}, mime {mime_type}): {e}");
+    if state.monitor_only || keep_selection {
+        return;
+    }
+
+    // Selections set while nobody is at the keyboard come from background jobs;
+    // record them but leave ownership with the setter
+    if state.idle_tracker.is_idle() {
+        debug!("Session idle; not taking ownership of selection id {new_id}");
+        return;
+    }
+
+    let delay_ms = state
+        .config
+        .ownership_takeover_delay_ms
+        .unwrap_or(state.quirks.ownership_takeover_delay_ms);
+    if delay_ms == 0 {
+        take_ownership(&mut state, new_id, seat_key);
+    } else {
+        drop(state);
+        schedule_ownership_takeover(backend_state, new_id, seat_key, offer_id, delay_ms);
+    }
+}
+
+fn take_ownership(state: &mut BackendState, entry_id: u64, seat_key: u32) {
+    if let Err(e) = state.set_selection_on_seat(entry_id, seat_key, None) {
+        warn!("Failed to take ownership of selection id {entry_id}: {e}");
+        state.record_error(format!("Failed to take ownership of a selection: {e}"));
+    } else {
+        debug!("Took ownership of external selection (id {entry_id})");
+    }
+}
+
+/// Re-own an external selection after a hold-off, unless the selection changed in the
+/// meantime. Apps that set the clipboard in several steps (e.g. LibreOffice) otherwise
+/// lose their later offers to our copy of the first one.
+fn schedule_ownership_takeover(
+    backend_state: &Arc<Mutex<BackendState>>,
+    entry_id: u64,
+    seat_key: u32,
+    offer_id: wayland_client::backend::ObjectId,
+    delay_ms: u64,
+) {
+    let backend_state = backend_state.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(delay_ms));
+        let mut state = backend_state.lock().unwrap();
+        let selection_unchanged = state
+            .seats
+            .get(&seat_key)
+            .is_some_and(|seat| seat.current_data_offer.as_ref() == Some(&offer_id));
+        if selection_unchanged {
+            take_ownership(&mut state, entry_id, seat_key);
         } else {
-            debug!(
+            debug!("Selection changed during takeover hold-off; not re-owning id {entry_id}");
+        }
+    });
+}
+
+/// The selection went away without a new one, usually because the app that copied it
+/// exited. With `persist_on_exit` in monitor-only mode, offer that copy ourselves.
+fn handle_selection_cleared(wrapper: &MutexBackendState, seat_key: u32) {
+    debug!("Selection cleared");
+    set_current_data_offer(wrapper, seat_key, None);
+
+    let mut state = wrapper.backend_state.lock().unwrap();
+    // A selection still being debounced when its owner exits is final; record it now
+    if let Some(pending) = state
+        .seats
+        .get_mut(&seat_key)
+        .and_then(|seat| seat.pending_selection.take())
+        && !looks_partial(&pending.mime_map)
+    {
+        let new_id = state.add_clipboard_item_from_mime_map(pending.mime_map, pending.seat_name);
+        if let Some(seat) = state.seats.get_mut(&seat_key) {
+            seat.last_recorded_entry_id = new_id;
+        }
+    }
+    if !state.monitor_only || !state.config.persist_on_exit {
+        return;
+    }
+    let Some(entry_id) = state
+        .seats
+        .get_mut(&seat_key)
+        .filter(|seat| seat.current_source_object.is_none())
+        .and_then(|seat| seat.last_recorded_entry_id.take())
+    else {
+        return;
+    };
+    // Password managers clear the clipboard on purpose; don't bring secrets back
+    match state.get_item_by_id(entry_id) {
+        Some(item) if item.content_type != ClipboardContentType::Password => {
+            info!("Selection owner went away; re-offering the last copy (id {entry_id})");
+            take_ownership(&mut state, entry_id, seat_key);
+        }
+        _ => debug!("Not re-offering id {entry_id} after the selection was cleared"),
+    }
+}
+
+fn set_current_data_offer(
+    wrapper: &MutexBackendState,
+    seat_key: u32,
+    offer_id: Option<wayland_client::backend::ObjectId>,
+) {
+    if let Some(seat) = wrapper
+        .backend_state
+        .lock()
+        .unwrap()
+        .seats
+        .get_mut(&seat_key)
+    {
+        seat.current_data_offer = offer_id;
+    }
+}
+
+/// Handle a Source Send event for either Wlr or Ext source. The payload is looked up
+/// under the lock but written on the runtime, so a slow paste target cannot stall the
+/// monitor.
+fn handle_source_send(
+    wrapper: &MutexBackendState,
+    source_id: wayland_client::backend::ObjectId,
+    mime_type: String,
+    fd: std::os::fd::OwnedFd,
+) {
+    debug!("Data source Send event for MIME type: {mime_type}");
+    schedule_paste_queue_advance(wrapper, &source_id);
+    let (item_id, payload) = {
+        let state = wrapper.backend_state.lock().unwrap();
+        let Some(seat) = state
+            .seat_key_for_source(&source_id)
+            .map(|seat_key| &state.seats[&seat_key])
+        else {
+            warn!("No current_source_id set when Send event received");
+            return;
+        };
+        let primary = seat.is_primary_source(&source_id);
+        let entry_id = if primary {
+            seat.primary_source_entry_id
+        } else {
+            seat.current_source_entry_id
+        };
+        let Some(item_id) = entry_id else {
+            warn!("No current_source_id set when Send event received");
+            return;
+        };
+        // A filtered selection (e.g. paste as plain text) serves its own payload
+        if !primary && let Some(payload) = &seat.current_source_payload {
+            (
+                item_id,
+                payload
+                    .get(&mime_type)
+                    .map(|bytes| (bytes.clone(), None, false)),
+            )
+        } else {
+            let Some(item) = state.history.iter().find(|item| item.item_id == item_id) else {
+                warn!("Clipboard item id {item_id} no longer exists in history");
+                return;
+            };
+            if !item.mime_data.contains_key(&mime_type)
+                && let Some((lazy_entry_id, lazy_offer)) = &seat.lazy_offer
+                && *lazy_entry_id == item_id
+                && lazy_offer.provides(&mime_type)
+            {
+                // Let the source app write straight to the pasting client
+                use std::os::fd::AsFd;
+                lazy_offer.offer.receive(mime_type.clone(), fd.as_fd());
+                if let Some(conn) = &state.connection
+                    && let Err(e) = conn.flush()
+                {
+                    warn!("Flush failed: {e}");
+                }
+                debug!("Forwarded {mime_type} request for id {item_id} to the source app");
+                return;
+            }
+            // HTML-only entries also offer plain text, converted from the HTML on request
+            let convert_html = !item.mime_data.contains_key(&mime_type)
+                && SYNTHESIZED_TEXT_MIMES.contains(&mime_type.as_str())
+                && needs_synthesized_text(&item.mime_data);
+            let stored_mime = if convert_html {
+                HTML_MIME
+            } else {
+                mime_type.as_str()
+            };
+            // Decompressed (and converted) on the writer thread, outside the lock
+            let payload = item.mime_data.get(stored_mime).map(|bytes| {
+                (
+                    bytes.clone(),
+                    item.compressed.get(stored_mime).copied(),
+                    convert_html,
+                )
+            });
+            (item_id, payload)
+        }
+    };
+
+    let Some((bytes, original_size, convert_html)) = payload else {
+        warn!("No data stored for MIME {mime_type} (id {item_id}), nothing written");
+        return;
+    };
+    wrapper.runtime.spawn(async move {
+        let prepared = tokio::task::spawn_blocking(move || {
+            let bytes = match original_size {
+                Some(original_size) => decompress_payload(&bytes, original_size)?,
+                None => bytes,
+            };
+            Ok::<_, std::io::Error>(if convert_html {
+                Bytes::from(strip_html(&String::from_utf8_lossy(&bytes)))
+            } else {
+                bytes
+            })
+        })
+        .await;
+        let bytes = match prepared {
+            Ok(Ok(bytes)) => bytes,
+            Ok(Err(e)) => {
+                error!("Failed to decompress selection data (id {item_id}, mime {mime_type}): {e}");
+                return;
+            }
+            Err(e) => {
+                error!("Failed to prepare selection data (id {item_id}, mime {mime_type}): {e}");
+                return;
+            }
+        };
+        match write_payload(fd, &bytes).await {
+            Ok(()) => debug!(
                 "Wrote {} bytes for id {item_id} (mime {mime_type})",
                 bytes.len()
-            );
+            ),
+            Err(e) => {
+                error!("Failed writing selection data (id {item_id}, mime {mime_type}): {e}")
+            }
+        }
+    });
+}
+
+/// Write a payload to a pasting client, giving up after `PAYLOAD_WRITE_TIMEOUT` so a
+/// client that never reads its pipe doesn't hold the task forever. Clients normally pass
+/// a pipe; anything else (a regular file) is written directly.
+async fn write_payload(fd: OwnedFd, bytes: &Bytes) -> std::io::Result<()> {
+    use tokio::io::AsyncWriteExt;
+
+    match tokio::net::unix::pipe::Sender::from_owned_fd(fd.try_clone()?) {
+        Ok(mut sender) => {
+            drop(fd);
+            tokio::time::timeout(PAYLOAD_WRITE_TIMEOUT, sender.write_all(bytes))
+                .await
+                .map_err(|_| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::TimedOut,
+                        format!("client did not take the payload within {PAYLOAD_WRITE_TIMEOUT:?}"),
+                    )
+                })?
+        }
+        Err(_) => {
+            let bytes = bytes.clone();
+            tokio::task::spawn_blocking(move || {
+                use std::io::Write;
+                std::fs::File::from(fd).write_all(&bytes)
+            })
+            .await
+            .map_err(std::io::Error::other)?
         }
-    } else {
-        warn!("No data stored for MIME {mime_type} (id {item_id}), nothing written");
     }
 }
 
+/// Stack mode: a paste of the queue's front moves the selection on to the next queued
+/// item. Delayed so that other formats of the same paste are still served by this source.
+fn schedule_paste_queue_advance(
+    wrapper: &MutexBackendState,
+    source_id: &wayland_client::backend::ObjectId,
+) {
+    let mut state = wrapper.backend_state.lock().unwrap();
+    let Some(seat_key) = state.seat_key_for_source(source_id) else {
+        return;
+    };
+    // Middle-click pastes leave the queue alone
+    if state.seats[&seat_key].is_primary_source(source_id) {
+        return;
+    }
+    let Some(item_id) = state.seats[&seat_key].current_source_entry_id else {
+        return;
+    };
+    if state.paste_queue_advancing || state.paste_queue.front() != Some(&item_id) {
+        return;
+    }
+    state.paste_queue_advancing = true;
+    drop(state);
+
+    let backend_state = wrapper.backend_state.clone();
+    wrapper.runtime.spawn(async move {
+        tokio::time::sleep(PASTE_QUEUE_ADVANCE_DELAY).await;
+        backend_state
+            .lock()
+            .unwrap()
+            .advance_paste_queue(item_id, seat_key);
+    });
+}
+
 /// Handle a Source Cancelled event. Re-enables selection reading if this is the active source.
 fn handle_source_cancelled(state: &mut BackendState, source_id: wayland_client::backend::ObjectId) {
     debug!("Data source cancelled (object id {source_id:?})");
     // If the cancelled source is still the active one, an external client took ownership — re-enable reads.
-    if state.current_source_object.as_ref().map(|s| s.id()) == Some(source_id) {
-        state.suppress_next_selection_read = false;
-        state.current_source_object = None;
-        debug!("Re-enabled selection reading (external client took over)");
+    if let Some(seat_key) = state.seat_key_for_source(&source_id)
+        && let Some(seat) = state.seats.get_mut(&seat_key)
+    {
+        if seat.is_primary_source(&source_id) {
+            // Another app took the primary selection; the clipboard is unaffected
+            seat.release_primary_source();
+            return;
+        }
+        seat.suppress_next_selection_read = false;
+        seat.current_source_object = None;
+        crash_backup::clear(&seat.name);
+        debug!(
+            "Re-enabled selection reading on {} (external client took over)",
+            seat.name
+        );
     }
 }
 
 // ================= Dispatch Implementations =================
 
-impl Dispatch<ZwlrDataControlDeviceV1, ()> for MutexBackendState {
+impl Dispatch<ZwlrDataControlDeviceV1, u32> for MutexBackendState {
     fn event(
         wrapper: &mut Self,
         _: &ZwlrDataControlDeviceV1,
         event: zwlr_data_control_device_v1::Event,
-        (): &(),
+        seat_key: &u32,
         conn: &Connection,
         _qh: &QueueHandle<Self>,
     ) {
@@ -267,18 +937,14 @@ impl Dispatch<ZwlrDataControlDeviceV1, ()> for MutexBackendState {
             }
             zwlr_data_control_device_v1::Event::Selection { id } => {
                 if let Some(offer_id) = id {
-                    let offer_key = offer_id.id();
-                    debug!("Selection changed to offer ID: {offer_key:?}");
-                    let conn = conn.clone();
                     handle_selection_event(
                         wrapper,
-                        offer_key,
-                        || offer_id.destroy(),
-                        |mime_list| read_all_data_formats(&offer_id, mime_list, &conn),
+                        *seat_key,
+                        DataControlOffer::Wlr(offer_id),
+                        conn,
                     );
                 } else {
-                    debug!("Selection cleared");
-                    wrapper.backend_state.lock().unwrap().current_data_offer = None;
+                    handle_selection_cleared(wrapper, *seat_key);
                 }
             }
             zwlr_data_control_device_v1::Event::PrimarySelection { .. } => {}
@@ -327,7 +993,7 @@ impl Dispatch<ZwlrDataControlSourceV1, ()> for MutexBackendState {
     ) {
         match event {
             zwlr_data_control_source_v1::Event::Send { mime_type, fd } => {
-                handle_source_send(&wrapper.backend_state.lock().unwrap(), mime_type, fd);
+                handle_source_send(wrapper, event_source.id(), mime_type, fd);
             }
             zwlr_data_control_source_v1::Event::Cancelled => {
                 let source_id = event_source.id();
@@ -339,12 +1005,12 @@ impl Dispatch<ZwlrDataControlSourceV1, ()> for MutexBackendState {
     }
 }
 
-impl Dispatch<ExtDataControlDeviceV1, ()> for MutexBackendState {
+impl Dispatch<ExtDataControlDeviceV1, u32> for MutexBackendState {
     fn event(
         wrapper: &mut Self,
         _: &ExtDataControlDeviceV1,
         event: ext_data_control_device_v1::Event,
-        (): &(),
+        seat_key: &u32,
         conn: &Connection,
         _qh: &QueueHandle<Self>,
     ) {
@@ -354,18 +1020,14 @@ impl Dispatch<ExtDataControlDeviceV1, ()> for MutexBackendState {
             }
             ext_data_control_device_v1::Event::Selection { id } => {
                 if let Some(offer_id) = id {
-                    let offer_key = offer_id.id();
-                    debug!("Selection changed to offer ID: {offer_key:?}");
-                    let conn = conn.clone();
                     handle_selection_event(
                         wrapper,
-                        offer_key,
-                        || offer_id.destroy(),
-                        |mime_list| read_all_data_formats(&offer_id, mime_list, &conn),
+                        *seat_key,
+                        DataControlOffer::Ext(offer_id),
+                        conn,
                     );
                 } else {
-                    debug!("Selection cleared");
-                    wrapper.backend_state.lock().unwrap().current_data_offer = None;
+                    handle_selection_cleared(wrapper, *seat_key);
                 }
             }
             ext_data_control_device_v1::Event::PrimarySelection { .. } => {}
@@ -414,7 +1076,7 @@ impl Dispatch<ExtDataControlSourceV1, ()> for MutexBackendState {
     ) {
         match event {
             ext_data_control_source_v1::Event::Send { mime_type, fd } => {
-                handle_source_send(&wrapper.backend_state.lock().unwrap(), mime_type, fd);
+                handle_source_send(wrapper, event_source.id(), mime_type, fd);
             }
             ext_data_control_source_v1::Event::Cancelled => {
                 let source_id = event_source.id();
@@ -430,23 +1092,137 @@ impl Dispatch<ExtDataControlSourceV1, ()> for MutexBackendState {
 
 delegate_noop!(MutexBackendState: ignore ZwlrDataControlManagerV1);
 delegate_noop!(MutexBackendState: ignore ExtDataControlManagerV1);
-delegate_noop!(MutexBackendState: ignore WlSeat);
+
+impl Dispatch<WlSeat, u32> for MutexBackendState {
+    fn event(
+        wrapper: &mut Self,
+        _: &WlSeat,
+        event: wl_seat::Event,
+        seat_key: &u32,
+        _: &Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        if let wl_seat::Event::Name { name } = event
+            && let Some(seat) = wrapper
+                .backend_state
+                .lock()
+                .unwrap()
+                .seats
+                .get_mut(seat_key)
+        {
+            debug!("Seat {seat_key} is named {name}");
+            seat.name = name;
+        }
+    }
+}
 
 impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for MutexBackendState {
     fn event(
-        _state: &mut Self,
-        _proxy: &wl_registry::WlRegistry,
-        _event: wl_registry::Event,
-        _data: &GlobalListContents,
+        wrapper: &mut Self,
+        registry: &wl_registry::WlRegistry,
+        event: wl_registry::Event,
+        globals: &GlobalListContents,
         _conn: &Connection,
-        _qhandle: &QueueHandle<MutexBackendState>,
+        qhandle: &QueueHandle<MutexBackendState>,
     ) {
-        // No-op
+        // Only called after the initial roundtrip: these are globals (re-)appearing or
+        // disappearing at runtime, e.g. when the compositor restarts its data-control support.
+        let mut state = wrapper.backend_state.lock().unwrap();
+        match event {
+            wl_registry::Event::Global {
+                name,
+                interface,
+                version,
+            } => {
+                if interface == WlSeat::interface().name {
+                    info!("New wl_seat advertised (global {name})");
+                    let seat = registry.bind::<WlSeat, _, _>(name, version.min(9), qhandle, name);
+                    state.add_seat(name, seat);
+                } else if state.data_control_manager.is_none()
+                    && is_data_control_interface(&interface)
+                    && let Some((manager, global_name)) = bind_data_control_manager(
+                        registry,
+                        &globals.clone_list(),
+                        qhandle,
+                        state.quirks.preferred_data_control,
+                    )
+                {
+                    info!("Data-control manager re-advertised; resuming clipboard monitoring");
+                    state.attach_data_control_manager(manager, global_name);
+                    record_wayland_reconnect();
+                }
+            }
+            wl_registry::Event::GlobalRemove { name } => {
+                if state.data_control_global == Some(name) {
+                    warn!("Compositor removed the bound data-control manager (global {name})");
+                    state.detach_data_control_manager();
+                    // The other protocol flavour may still be around
+                    if let Some((manager, global_name)) = bind_data_control_manager(
+                        registry,
+                        &globals.clone_list(),
+                        qhandle,
+                        state.quirks.preferred_data_control,
+                    ) {
+                        state.attach_data_control_manager(manager, global_name);
+                        record_wayland_reconnect();
+                    } else {
+                        state.update_monitor_state();
+                    }
+                } else if state.remove_seat(name) {
+                    info!("wl_seat (global {name}) was removed");
+                }
+            }
+            _ => {}
+        }
     }
 }
 
 // ================= Helper functions =================
 
+fn is_data_control_interface(interface: &str) -> bool {
+    interface == ExtDataControlManagerV1::interface().name
+        || interface == ZwlrDataControlManagerV1::interface().name
+}
+
+/// Bind the preferred data-control manager among the advertised globals, falling back
+/// to the other flavour. wlr-data-control is bound at up to v2; v1 works without the
+/// primary selection.
+fn bind_data_control_manager(
+    registry: &wl_registry::WlRegistry,
+    globals: &[Global],
+    qh: &QueueHandle<MutexBackendState>,
+    preferred: DataControlProtocol,
+) -> Option<(DataControlManager, u32)> {
+    let bind_ext = || {
+        let global = globals
+            .iter()
+            .find(|g| g.interface == ExtDataControlManagerV1::interface().name)?;
+        let manager = registry.bind::<ExtDataControlManagerV1, _, _>(global.name, 1, qh, ());
+        info!("Using ext_data_control_manager_v1 clipboard protocol");
+        Some((DataControlManager::Ext(manager), global.name))
+    };
+    let bind_wlr = || {
+        let global = globals
+            .iter()
+            .find(|g| g.interface == ZwlrDataControlManagerV1::interface().name)?;
+        let version = global.version.min(WLR_DATA_CONTROL_VERSION);
+        let manager = registry.bind::<ZwlrDataControlManagerV1, _, _>(global.name, version, qh, ());
+        if version < WLR_DATA_CONTROL_VERSION {
+            info!(
+                "Using zwlr_data_control_manager_v1 clipboard protocol v{version} (no primary selection)"
+            );
+        } else {
+            info!("Using zwlr_data_control_manager_v1 clipboard protocol v{version}");
+        }
+        Some((DataControlManager::Wlr(manager), global.name))
+    };
+
+    match preferred {
+        DataControlProtocol::Ext => bind_ext().or_else(bind_wlr),
+        DataControlProtocol::Wlr => bind_wlr().or_else(bind_ext),
+    }
+}
+
 /// Create a pipe, returning `OwnedFd` handles for the read and write ends.
 fn create_pipes() -> Result<(std::os::fd::OwnedFd, std::os::fd::OwnedFd), Box<dyn std::error::Error>>
 {
@@ -461,12 +1237,13 @@ fn create_pipes() -> Result<(std::os::fd::OwnedFd, std::os::fd::OwnedFd), Box<dy
 }
 
 /// Select the MIME types to actually read from the available list.
-/// When image types are present, keep only PNG image data and all non-image variants.
-fn select_target_mimes(available_mimes: &[String]) -> Vec<String> {
-    if available_mimes.iter().any(|m| m.starts_with("image/")) {
+/// When image types are present, keep a single preferred image format (converted to
+/// PNG after reading) and all non-image variants.
+pub fn select_target_mimes(available_mimes: &[String]) -> Vec<String> {
+    if let Some(image_mime) = preferred_image_mime(available_mimes) {
         return available_mimes
             .iter()
-            .filter(|m| m.as_str() == "image/png" || !m.starts_with("image/"))
+            .filter(|m| m.as_str() == image_mime || !m.starts_with("image/"))
             .cloned()
             .collect();
     }
@@ -474,38 +1251,16 @@ fn select_target_mimes(available_mimes: &[String]) -> Vec<String> {
     available_mimes.to_vec()
 }
 
-/// Trait abstracting over offer types that can receive data via a pipe fd.
-trait DataOfferReceive {
-    fn receive_mime(&self, mime_type: String, fd: std::os::fd::BorrowedFd<'_>);
-}
-
-impl DataOfferReceive for ZwlrDataControlOfferV1 {
-    fn receive_mime(&self, mime_type: String, fd: std::os::fd::BorrowedFd<'_>) {
-        self.receive(mime_type, fd);
-    }
-}
-
-impl DataOfferReceive for ExtDataControlOfferV1 {
-    fn receive_mime(&self, mime_type: String, fd: std::os::fd::BorrowedFd<'_>) {
-        self.receive(mime_type, fd);
-    }
-}
-
-/// Read clipboard data for all target MIME types from any offer type.
-fn read_all_data_formats<O: DataOfferReceive>(
-    data_offer: &O,
+/// Ask the source for every target MIME type, each through its own pipe. Nothing is
+/// read here; [`read_pipes`] drains the pipes without blocking event dispatch.
+fn request_data_formats(
+    data_offer: &DataControlOffer,
     mime_types: Vec<String>,
     conn: &Connection,
-) -> IndexMap<String, Bytes> {
-    use std::io::Read;
+) -> Vec<(String, OwnedFd)> {
     use std::os::fd::AsFd;
 
-    let mut mime_map: IndexMap<String, Bytes> = IndexMap::new();
-
-    if mime_types.is_empty() {
-        return mime_map;
-    }
-
+    let mut pipes = Vec::new();
     for mime in select_target_mimes(&mime_types) {
         let (reader_fd, writer_fd) = match create_pipes() {
             Ok(pair) => pair,
@@ -515,20 +1270,86 @@ fn read_all_data_formats<O: DataOfferReceive>(
             }
         };
         debug!("Requesting {mime} content...");
-        data_offer.receive_mime(mime.clone(), writer_fd.as_fd());
+        data_offer.receive(mime.clone(), writer_fd.as_fd());
         // Drop the write end so the provider gets EOF after writing.
         drop(writer_fd);
-        if let Err(e) = conn.flush() {
-            warn!("Flush failed: {e}");
+        pipes.push((mime, reader_fd));
+    }
+    if !pipes.is_empty()
+        && let Err(e) = conn.flush()
+    {
+        warn!("Flush failed: {e}");
+    }
+
+    pipes
+}
+
+/// Read each requested MIME type to EOF. A type that fails, exceeds the per-MIME timeout
+/// or is still pending at the selection deadline is skipped; whatever arrived is kept,
+/// so one slow format (a huge `image/tiff`) does not cost the whole item.
+async fn read_pipes(pipes: Vec<(String, OwnedFd)>, limits: ReadLimits) -> IndexMap<String, Bytes> {
+    use tokio::io::AsyncReadExt;
+
+    let deadline = limits
+        .total
+        .map(|total| tokio::time::Instant::now() + total);
+    let mut mime_map: IndexMap<String, Bytes> = IndexMap::new();
+    let mut skipped = Vec::new();
+    for (mime, reader_fd) in pipes {
+        let remaining = deadline
+            .map(|deadline| deadline.saturating_duration_since(tokio::time::Instant::now()));
+        if remaining.is_some_and(|remaining| remaining.is_zero()) {
+            skipped.push(mime);
+            continue;
         }
-        let mut reader_file = std::fs::File::from(reader_fd);
+        let timeout = match (limits.per_mime, remaining) {
+            (Some(per_mime), Some(remaining)) => Some(per_mime.min(remaining)),
+            (per_mime, remaining) => per_mime.or(remaining),
+        };
+
+        let mut receiver = match tokio::net::unix::pipe::Receiver::from_owned_fd(reader_fd) {
+            Ok(receiver) => receiver,
+            Err(e) => {
+                warn!("Could not watch pipe for {mime}: {e}");
+                continue;
+            }
+        };
         let mut buf = Vec::new();
-        match reader_file.read_to_end(&mut buf) {
-            Ok(_) if !buf.is_empty() => {
+        let read = receiver.read_to_end(&mut buf);
+        let result = match timeout {
+            Some(timeout) => tokio::time::timeout(timeout, read).await,
+            None => Ok(read.await),
+        };
+        match result {
+            Ok(Ok(_)) if !buf.is_empty() => {
                 mime_map.insert(mime, Bytes::from(buf));
             }
-            Ok(_) => {}
-            Err(e) => warn!("Failed reading data for mime: {e}"),
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => warn!("Failed reading data for {mime}: {e}"),
+            Err(_) => {
+                warn!(
+                    "Timed out reading {mime} from the selection source after {}ms ({} bytes received)",
+                    timeout.unwrap_or_default().as_millis(),
+                    buf.len()
+                );
+                skipped.push(mime);
+            }
+        }
+    }
+
+    if !skipped.is_empty() {
+        if mime_map.is_empty() {
+            warn!(
+                "No MIME type of the selection arrived in time; skipped {}",
+                skipped.join(", ")
+            );
+        } else {
+            info!(
+                "Recording selection without {} ({} of {} types arrived)",
+                skipped.join(", "),
+                mime_map.len(),
+                mime_map.len() + skipped.len()
+            );
         }
     }
 
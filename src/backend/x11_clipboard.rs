@@ -0,0 +1,493 @@
+//! Clipboard monitoring for X11 sessions and compositors without data-control: XFixes
+//! reports every change of the CLIPBOARD owner, the new contents are converted to our
+//! hidden window and recorded in the same history as Wayland copies. History items are
+//! offered back by owning the selection and answering `SelectionRequest`s.
+
+use crate::backend::backend_state::BackendState;
+use crate::backend::plain_text::PLAIN_TEXT_MIME;
+use crate::backend::wayland_clipboard::select_target_mimes;
+use bytes::Bytes;
+use indexmap::IndexMap;
+use log::{debug, info, warn};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use x11rb::connection::{Connection, RequestConnection};
+use x11rb::protocol::Event;
+use x11rb::protocol::xfixes::{ConnectionExt as _, SelectionEventMask};
+use x11rb::protocol::xproto::{
+    Atom, AtomEnum, ConnectionExt as _, CreateWindowAux, EventMask, PropMode, Property,
+    SELECTION_NOTIFY_EVENT, SelectionNotifyEvent, SelectionRequestEvent, Window, WindowClass,
+};
+use x11rb::rust_connection::RustConnection;
+use x11rb::wrapper::ConnectionExt as _;
+use x11rb::{COPY_DEPTH_FROM_PARENT, CURRENT_TIME, NONE};
+
+/// Reported as the data-control protocol in `Status`
+pub const X11_PROTOCOL_NAME: &str = "x11-xfixes";
+
+// Plain-text targets of X11 apps; stored under `PLAIN_TEXT_MIME` like Wayland copies
+const X11_TEXT_TARGETS: &[&str] = &["UTF8_STRING", "STRING", "TEXT"];
+
+#[derive(Clone, Copy)]
+struct Atoms {
+    clipboard: Atom,
+    targets: Atom,
+    incr: Atom,
+    utf8_string: Atom,
+    // Property on our window that conversions are written to
+    transfer: Atom,
+}
+
+/// Our ownership of the X11 CLIPBOARD, shared by the event loop (which answers
+/// requests) and `BackendState` (which sets history items).
+pub struct X11Selection {
+    connection: Arc<RustConnection>,
+    window: Window,
+    atoms: Atoms,
+    owned: Arc<Mutex<Option<IndexMap<String, Bytes>>>>,
+}
+
+impl X11Selection {
+    /// Become the CLIPBOARD owner, serving `payload` until another client takes over.
+    pub fn set_selection(&self, mut payload: IndexMap<String, Bytes>) -> Result<(), String> {
+        if let Some(text) = payload.get(PLAIN_TEXT_MIME).cloned() {
+            for target in X11_TEXT_TARGETS {
+                payload.entry((*target).to_string()).or_insert(text.clone());
+            }
+        }
+        *self.owned.lock().unwrap() = Some(payload);
+
+        let connection = &self.connection;
+        connection
+            .set_selection_owner(self.window, self.atoms.clipboard, CURRENT_TIME)
+            .map_err(x11_error)?;
+        let owner = connection
+            .get_selection_owner(self.atoms.clipboard)
+            .map_err(x11_error)?
+            .reply()
+            .map_err(x11_error)?
+            .owner;
+        if owner != self.window {
+            *self.owned.lock().unwrap() = None;
+            return Err("Another X11 client kept the clipboard".to_string());
+        }
+        Ok(())
+    }
+
+    /// Empty the CLIPBOARD, whoever owns it.
+    pub fn clear_selection(&self) -> Result<(), String> {
+        *self.owned.lock().unwrap() = None;
+        self.connection
+            .set_selection_owner(NONE, self.atoms.clipboard, CURRENT_TIME)
+            .map_err(x11_error)?;
+        self.connection.flush().map_err(x11_error)
+    }
+}
+
+/// A conversion of the current selection in progress.
+enum Pending {
+    Idle,
+    Targets,
+    Data {
+        // Target being read, then the ones still to read
+        current: (Atom, String),
+        remaining: VecDeque<(Atom, String)>,
+        received: IndexMap<String, Bytes>,
+        // Chunks of an INCR transfer, None for a regular one
+        incremental: Option<Vec<u8>>,
+    },
+}
+
+pub struct X11ClipboardMonitor {
+    backend_state: Arc<Mutex<BackendState>>,
+}
+
+impl X11ClipboardMonitor {
+    pub const fn new(backend_state: Arc<Mutex<BackendState>>) -> Self {
+        Self { backend_state }
+    }
+
+    pub fn start_monitoring(&self) -> Result<(), String> {
+        let (connection, screen_num) =
+            x11rb::connect(None).map_err(|e| format!("Failed to connect to X11: {e}"))?;
+        let connection = Arc::new(connection);
+        let screen = &connection.setup().roots[screen_num];
+
+        connection
+            .xfixes_query_version(5, 0)
+            .map_err(x11_error)?
+            .reply()
+            .map_err(|e| format!("The X server lacks the XFixes extension: {e}"))?;
+
+        let window = connection.generate_id().map_err(x11_error)?;
+        connection
+            .create_window(
+                COPY_DEPTH_FROM_PARENT,
+                window,
+                screen.root,
+                0,
+                0,
+                1,
+                1,
+                0,
+                WindowClass::INPUT_OUTPUT,
+                0,
+                &CreateWindowAux::new().event_mask(EventMask::PROPERTY_CHANGE),
+            )
+            .map_err(x11_error)?;
+
+        let atoms = Atoms {
+            clipboard: intern_atom(&connection, "CLIPBOARD")?,
+            targets: intern_atom(&connection, "TARGETS")?,
+            incr: intern_atom(&connection, "INCR")?,
+            utf8_string: intern_atom(&connection, "UTF8_STRING")?,
+            transfer: intern_atom(&connection, "CURSOR_CLIP_SELECTION")?,
+        };
+        connection
+            .xfixes_select_selection_input(
+                window,
+                atoms.clipboard,
+                SelectionEventMask::SET_SELECTION_OWNER
+                    | SelectionEventMask::SELECTION_WINDOW_DESTROY
+                    | SelectionEventMask::SELECTION_CLIENT_CLOSE,
+            )
+            .map_err(x11_error)?;
+        connection.flush().map_err(x11_error)?;
+
+        let owned = Arc::new(Mutex::new(None));
+        self.backend_state.lock().unwrap().x11_selection = Some(X11Selection {
+            connection: connection.clone(),
+            window,
+            atoms,
+            owned: owned.clone(),
+        });
+        info!("X11 clipboard monitor initialized, monitoring changes...");
+
+        let mut pending = Pending::Idle;
+        loop {
+            let event = connection.wait_for_event().map_err(x11_error)?;
+            let result = match event {
+                Event::XfixesSelectionNotify(event) if event.selection == atoms.clipboard => {
+                    if event.owner == window || event.owner == NONE {
+                        continue;
+                    }
+                    debug!("X11 clipboard owner changed to window {}", event.owner);
+                    // Someone else owns the clipboard now; drop what we were serving
+                    *owned.lock().unwrap() = None;
+                    pending = Pending::Targets;
+                    convert(&connection, window, atoms, atoms.targets, event.timestamp)
+                }
+                Event::SelectionNotify(event) if event.selection == atoms.clipboard => self
+                    .handle_selection_notify(
+                        &connection,
+                        window,
+                        atoms,
+                        &mut pending,
+                        event.property,
+                    ),
+                Event::PropertyNotify(event)
+                    if event.window == window
+                        && event.atom == atoms.transfer
+                        && event.state == Property::NEW_VALUE =>
+                {
+                    self.handle_incremental_chunk(&connection, window, atoms, &mut pending)
+                }
+                Event::SelectionRequest(event) => {
+                    answer_selection_request(&connection, atoms, &owned, &event)
+                }
+                Event::SelectionClear(event) if event.selection == atoms.clipboard => {
+                    *owned.lock().unwrap() = None;
+                    Ok(())
+                }
+                _ => Ok(()),
+            };
+            if let Err(e) = result {
+                warn!("X11 clipboard: {e}");
+                pending = Pending::Idle;
+            }
+            connection.flush().map_err(x11_error)?;
+        }
+    }
+
+    /// A conversion we asked for finished: read the targets list or one target's data.
+    fn handle_selection_notify(
+        &self,
+        connection: &RustConnection,
+        window: Window,
+        atoms: Atoms,
+        pending: &mut Pending,
+        property: Atom,
+    ) -> Result<(), String> {
+        match pending {
+            Pending::Idle => Ok(()),
+            Pending::Targets => {
+                if property == NONE {
+                    *pending = Pending::Idle;
+                    return Ok(());
+                }
+                let reply = read_property(connection, window, atoms.transfer)?;
+                let available: Vec<Atom> = reply.value32().into_iter().flatten().collect();
+                let mut names = Vec::with_capacity(available.len());
+                for atom in available {
+                    let name = connection
+                        .get_atom_name(atom)
+                        .map_err(x11_error)?
+                        .reply()
+                        .map_err(x11_error)?
+                        .name;
+                    names.push((atom, String::from_utf8_lossy(&name).into_owned()));
+                }
+                let mut remaining = wanted_targets(&names);
+                let Some(current) = remaining.pop_front() else {
+                    debug!("X11 clipboard offers no targets we record");
+                    *pending = Pending::Idle;
+                    return Ok(());
+                };
+                convert(connection, window, atoms, current.0, CURRENT_TIME)?;
+                *pending = Pending::Data {
+                    current,
+                    remaining,
+                    received: IndexMap::new(),
+                    incremental: None,
+                };
+                Ok(())
+            }
+            Pending::Data {
+                current,
+                incremental,
+                ..
+            } => {
+                if property != NONE {
+                    let reply = read_property(connection, window, atoms.transfer)?;
+                    if reply.type_ == atoms.incr {
+                        // Large transfer: the owner now writes chunks, one PropertyNotify each
+                        debug!("Reading {} from the X11 clipboard incrementally", current.1);
+                        *incremental = Some(Vec::new());
+                        return Ok(());
+                    }
+                    self.store_target(connection, window, atoms, pending, reply.value)
+                } else {
+                    debug!("X11 clipboard owner refused to convert {}", current.1);
+                    self.store_target(connection, window, atoms, pending, Vec::new())
+                }
+            }
+        }
+    }
+
+    /// Next chunk of an INCR transfer; an empty chunk ends it.
+    fn handle_incremental_chunk(
+        &self,
+        connection: &RustConnection,
+        window: Window,
+        atoms: Atoms,
+        pending: &mut Pending,
+    ) -> Result<(), String> {
+        let Pending::Data {
+            incremental: Some(chunks),
+            ..
+        } = pending
+        else {
+            return Ok(());
+        };
+        let reply = read_property(connection, window, atoms.transfer)?;
+        if !reply.value.is_empty() {
+            chunks.extend_from_slice(&reply.value);
+            return Ok(());
+        }
+        let data = std::mem::take(chunks);
+        self.store_target(connection, window, atoms, pending, data)
+    }
+
+    /// Keep the data of the target just read and request the next one, or record the
+    /// item once all targets are read.
+    fn store_target(
+        &self,
+        connection: &RustConnection,
+        window: Window,
+        atoms: Atoms,
+        pending: &mut Pending,
+        data: Vec<u8>,
+    ) -> Result<(), String> {
+        let Pending::Data {
+            current,
+            remaining,
+            received,
+            incremental,
+        } = pending
+        else {
+            return Ok(());
+        };
+        if !data.is_empty() {
+            let mime = if current.0 == atoms.utf8_string {
+                PLAIN_TEXT_MIME.to_string()
+            } else {
+                current.1.clone()
+            };
+            received.entry(mime).or_insert_with(|| Bytes::from(data));
+        }
+
+        if let Some(next) = remaining.pop_front() {
+            *current = next;
+            *incremental = None;
+            return convert(connection, window, atoms, current.0, CURRENT_TIME);
+        }
+
+        let received = std::mem::take(received);
+        *pending = Pending::Idle;
+        if received.is_empty() {
+            return Ok(());
+        }
+        let mut state = self.backend_state.lock().unwrap();
+        let Some(new_id) = state.add_clipboard_item_from_mime_map(received, None) else {
+            return Ok(());
+        };
+        // Keep the contents available after the copying app exits, as on Wayland
+        if !state.monitor_only
+            && !state.idle_tracker.is_idle()
+            && let Some(item) = state.get_item_by_id(new_id)
+            && let Some(selection) = &state.x11_selection
+            && let Err(e) = selection.set_selection(item.mime_data)
+        {
+            warn!("Failed to take ownership of the X11 clipboard: {e}");
+        }
+        Ok(())
+    }
+}
+
+/// Targets worth recording: MIME types (one image format) plus UTF8_STRING as text.
+fn wanted_targets(names: &[(Atom, String)]) -> VecDeque<(Atom, String)> {
+    let mimes: Vec<String> = names
+        .iter()
+        .filter(|(_, name)| name.contains('/') && !name.starts_with("video"))
+        .map(|(_, name)| name.clone())
+        .collect();
+    let selected = select_target_mimes(&mimes);
+    let has_utf8_text = selected.iter().any(|mime| mime == PLAIN_TEXT_MIME);
+    names
+        .iter()
+        .filter(|(_, name)| {
+            selected.contains(name) || (!has_utf8_text && name.as_str() == "UTF8_STRING")
+        })
+        .cloned()
+        .collect()
+}
+
+/// Serve a paste from our selection. Payloads larger than one X request are refused,
+/// since answering with INCR is not supported.
+fn answer_selection_request(
+    connection: &RustConnection,
+    atoms: Atoms,
+    owned: &Mutex<Option<IndexMap<String, Bytes>>>,
+    request: &SelectionRequestEvent,
+) -> Result<(), String> {
+    // Obsolete clients use the target as property
+    let property = if request.property == NONE {
+        request.target
+    } else {
+        request.property
+    };
+    let mut reply_property = NONE;
+
+    if let Some(payload) = owned.lock().unwrap().as_ref()
+        && request.selection == atoms.clipboard
+    {
+        if request.target == atoms.targets {
+            let mut targets = vec![atoms.targets];
+            for mime in payload.keys() {
+                targets.push(intern_atom(connection, mime)?);
+            }
+            connection
+                .change_property32(
+                    PropMode::REPLACE,
+                    request.requestor,
+                    property,
+                    AtomEnum::ATOM,
+                    &targets,
+                )
+                .map_err(x11_error)?;
+            reply_property = property;
+        } else {
+            let name = connection
+                .get_atom_name(request.target)
+                .map_err(x11_error)?
+                .reply()
+                .map_err(x11_error)?
+                .name;
+            let name = String::from_utf8_lossy(&name);
+            // Leave room for the request header
+            let max_bytes = connection.maximum_request_bytes().saturating_sub(64);
+            match payload.get(name.as_ref()) {
+                Some(data) if data.len() <= max_bytes => {
+                    connection
+                        .change_property8(
+                            PropMode::REPLACE,
+                            request.requestor,
+                            property,
+                            request.target,
+                            data,
+                        )
+                        .map_err(x11_error)?;
+                    reply_property = property;
+                }
+                Some(data) => warn!(
+                    "Not serving {name} ({} bytes) to X11: too large for a single transfer",
+                    data.len()
+                ),
+                None => debug!("X11 client asked for unavailable target {name}"),
+            }
+        }
+    }
+
+    let notify = SelectionNotifyEvent {
+        response_type: SELECTION_NOTIFY_EVENT,
+        sequence: 0,
+        time: request.time,
+        requestor: request.requestor,
+        selection: request.selection,
+        target: request.target,
+        property: reply_property,
+    };
+    connection
+        .send_event(false, request.requestor, EventMask::NO_EVENT, notify)
+        .map_err(x11_error)?;
+    Ok(())
+}
+
+fn convert(
+    connection: &RustConnection,
+    window: Window,
+    atoms: Atoms,
+    target: Atom,
+    time: u32,
+) -> Result<(), String> {
+    connection
+        .convert_selection(window, atoms.clipboard, target, atoms.transfer, time)
+        .map_err(x11_error)?;
+    Ok(())
+}
+
+/// Read and delete a property of our window; deleting it lets INCR owners continue.
+fn read_property(
+    connection: &RustConnection,
+    window: Window,
+    property: Atom,
+) -> Result<x11rb::protocol::xproto::GetPropertyReply, String> {
+    connection
+        .get_property(true, window, property, AtomEnum::ANY, 0, u32::MAX / 4)
+        .map_err(x11_error)?
+        .reply()
+        .map_err(x11_error)
+}
+
+fn intern_atom(connection: &RustConnection, name: &str) -> Result<Atom, String> {
+    Ok(connection
+        .intern_atom(false, name.as_bytes())
+        .map_err(x11_error)?
+        .reply()
+        .map_err(x11_error)?
+        .atom)
+}
+
+fn x11_error(error: impl std::fmt::Display) -> String {
+    format!("X11 request failed: {error}")
+}
@@ -0,0 +1,85 @@
+use crate::frontend::ipc_client::FrontendClient;
+use crate::shared::config_dir::config_path;
+use crate::shared::{CursorClipError, CursorClipResult};
+use log::{debug, info, warn};
+use serde::Deserialize;
+use std::fs;
+use std::os::unix::process::CommandExt;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// How long to wait for a freshly spawned daemon to open its socket
+pub const STARTUP_TIMEOUT: Duration = Duration::from_secs(3);
+pub const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct ConfigWithAutostart {
+    /// Spawn `cursor-clip daemon` when the overlay finds no daemon.
+    /// Turn off when the daemon is managed by systemd or the compositor's autostart.
+    autostart_daemon: bool,
+}
+
+impl Default for ConfigWithAutostart {
+    fn default() -> Self {
+        Self {
+            autostart_daemon: true,
+        }
+    }
+}
+
+/// Connect to the daemon, starting it first if it is not running and
+/// `autostart_daemon` allows it.
+pub fn connect_or_start_daemon() -> CursorClipResult<FrontendClient> {
+    match FrontendClient::new() {
+        Err(CursorClipError::DaemonNotRunning) if load_autostart() => {}
+        result => return result,
+    }
+
+    info!("Clipboard daemon is not running; starting it");
+    spawn_daemon()?;
+
+    let started = Instant::now();
+    loop {
+        match FrontendClient::new() {
+            Ok(client) => {
+                debug!("Daemon answered after {:?}", started.elapsed());
+                return Ok(client);
+            }
+            Err(CursorClipError::DaemonNotRunning) if started.elapsed() < STARTUP_TIMEOUT => {
+                std::thread::sleep(POLL_INTERVAL);
+            }
+            Err(e) => {
+                warn!("Started the daemon, but it did not open its socket: {e}");
+                return Err(e);
+            }
+        }
+    }
+}
+
+/// Spawn `cursor-clip daemon` detached from this process, without waiting for its
+/// socket. Also used by the overlay's "Start daemon" button, whatever `autostart_daemon` says.
+pub fn spawn_daemon() -> CursorClipResult<()> {
+    let exe = std::env::current_exe()?;
+    Command::new(exe)
+        .arg("daemon")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        // Own process group, so closing the overlay (or its terminal) does not take the daemon along
+        .process_group(0)
+        .spawn()?;
+    Ok(())
+}
+
+fn load_autostart() -> bool {
+    let Ok(contents) = fs::read_to_string(config_path()) else {
+        return ConfigWithAutostart::default().autostart_daemon;
+    };
+    toml::from_str::<ConfigWithAutostart>(&contents)
+        .map(|config| config.autostart_daemon)
+        .unwrap_or_else(|e| {
+            warn!("Failed to parse autostart_daemon from config, starting the daemon: {e}");
+            true
+        })
+}
@@ -5,13 +5,14 @@ use wayland_client::delegate_noop;
 // Core protocol objects
 use wayland_client::protocol::{
     wl_buffer::WlBuffer, wl_compositor::WlCompositor, wl_region::WlRegion, wl_registry::WlRegistry,
-    wl_shm::WlShm, wl_shm_pool::WlShmPool, wl_surface::WlSurface,
+    wl_shm::WlShm, wl_shm_pool::WlShmPool,
 };
 
 // WLR layer shell
 use wayland_protocols_wlr::layer_shell::v1::client::zwlr_layer_shell_v1::ZwlrLayerShellV1;
 
-// Viewporter & single pixel buffer
+// Viewporter, fractional scale & single pixel buffer
+use wayland_protocols::wp::fractional_scale::v1::client::wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1;
 use wayland_protocols::wp::single_pixel_buffer::v1::client::wp_single_pixel_buffer_manager_v1::WpSinglePixelBufferManagerV1;
 use wayland_protocols::wp::viewporter::client::{
     wp_viewport::WpViewport, wp_viewporter::WpViewporter,
@@ -20,10 +21,10 @@ use wayland_protocols::wp::viewporter::client::{
 // Generate the noop dispatch implementations
 delegate_noop!(State: WlCompositor);
 delegate_noop!(State: WlRegion);
-delegate_noop!(State: WlSurface);
 delegate_noop!(State: ZwlrLayerShellV1);
 delegate_noop!(State: WpViewporter);
 delegate_noop!(State: WpViewport);
+delegate_noop!(State: WpFractionalScaleManagerV1);
 delegate_noop!(State: WpSinglePixelBufferManagerV1);
 
 //ignore Keyword as objects emit a Release event
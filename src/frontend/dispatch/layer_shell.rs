@@ -109,6 +109,9 @@ pub fn cleanup_capture_layer(state: &mut State) {
     if let Some(viewport) = state.update_viewport.take() {
         viewport.destroy();
     }
+    if let Some(fractional_scale) = state.capture_fractional_scale.take() {
+        fractional_scale.destroy();
+    }
 
     // Destroy update layer resources first if present
     if let Some(update_layer_surface) = state.update_layer_surface.take() {
@@ -1,4 +1,7 @@
 pub mod empty_dispatch;
 pub mod frame_callback;
 pub mod layer_shell;
+pub mod output;
 pub mod pointer;
+pub mod toplevel;
+pub mod touch;
@@ -0,0 +1,117 @@
+use wayland_client::protocol::{wl_output, wl_surface};
+use wayland_client::{Connection, Dispatch, QueueHandle, WEnum};
+
+use wayland_protocols::wp::fractional_scale::v1::client::wp_fractional_scale_v1;
+
+use crate::frontend::frontend_state::State;
+use crate::frontend::scaling::fractional_scale;
+use log::debug;
+
+impl Dispatch<wl_output::WlOutput, ()> for State {
+    fn event(
+        state: &mut Self,
+        output: &wl_output::WlOutput,
+        event: wl_output::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let Some(info) = state.outputs.iter_mut().find(|info| &info.output == output) else {
+            return;
+        };
+        match event {
+            wl_output::Event::Name { name } => {
+                debug!("Output {:?} is named {name}", output);
+                info.name = Some(name);
+            }
+            wl_output::Event::Scale { factor } => {
+                info.scale = factor;
+            }
+            wl_output::Event::Geometry { transform, .. } => {
+                let transposed = matches!(
+                    transform,
+                    WEnum::Value(
+                        wl_output::Transform::_90
+                            | wl_output::Transform::_270
+                            | wl_output::Transform::Flipped90
+                            | wl_output::Transform::Flipped270
+                    )
+                );
+                if transposed != info.transposed
+                    && let Some((width, height)) = info.physical_size
+                {
+                    info.physical_size = Some((height, width));
+                }
+                info.transposed = transposed;
+            }
+            wl_output::Event::Mode {
+                flags: WEnum::Value(flags),
+                width,
+                height,
+                ..
+            } => {
+                if flags.contains(wl_output::Mode::Current) {
+                    info.physical_size = Some(if info.transposed {
+                        (height, width)
+                    } else {
+                        (width, height)
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Surfaces report which output they are shown on; used to place the overlay on the
+// monitor the pointer was captured on.
+impl Dispatch<wl_surface::WlSurface, ()> for State {
+    fn event(
+        state: &mut Self,
+        surface: &wl_surface::WlSurface,
+        event: wl_surface::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        if state.capture_surface.as_ref() != Some(surface) {
+            return;
+        }
+        match event {
+            wl_surface::Event::Enter { output } => {
+                if let Some(info) = state.outputs.iter().find(|info| info.output == output) {
+                    debug!(
+                        "Capture surface entered output {:?} (scale {})",
+                        info.name, info.scale
+                    );
+                }
+                state.capture_output = Some(output);
+            }
+            wl_surface::Event::Leave { output } => {
+                if state.capture_output.as_ref() == Some(&output) {
+                    state.capture_output = None;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// The capture surface's preferred scale; with fractional scaling it is the only way to
+// tell the logical size of an output from its mode.
+impl Dispatch<wp_fractional_scale_v1::WpFractionalScaleV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _fractional_scale: &wp_fractional_scale_v1::WpFractionalScaleV1,
+        event: wp_fractional_scale_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        if let wp_fractional_scale_v1::Event::PreferredScale { scale } = event {
+            let scale = fractional_scale(scale);
+            debug!("Capture surface prefers scale {scale}");
+            state.capture_scale = Some(scale);
+        }
+    }
+}
@@ -26,6 +26,12 @@ impl Dispatch<wl_seat::WlSeat, ()> for State {
                 } else {
                     debug!("No pointer capabilities detected");
                 }
+                // Touchscreens position the overlay with the first tap instead
+                if capabilities.contains(wl_seat::Capability::Touch) {
+                    let touch = seat.get_touch(qhandle, ());
+                    state.touch = Some(touch);
+                    debug!("Touch created");
+                }
             } else {
                 debug!("Unknown capability enumerator");
             }
@@ -71,7 +77,7 @@ impl Dispatch<wl_pointer::WlPointer, ()> for State {
                     && let WEnum::Value(wl_pointer::ButtonState::Pressed) = button_state
                 {
                     debug!("Left mouse button clicked on capture layer - requesting close");
-                    state.capture_layer_clicked = true; // future handling of outside click to close overlay
+                    state.capture_layer_clicked = true;
                 }
             }
             _ => {}
@@ -0,0 +1,81 @@
+use std::sync::Arc;
+use wayland_client::{Connection, Dispatch, Proxy, QueueHandle};
+use wayland_protocols_wlr::foreign_toplevel::v1::client::{
+    zwlr_foreign_toplevel_handle_v1::{self, ZwlrForeignToplevelHandleV1},
+    zwlr_foreign_toplevel_manager_v1::{self, ZwlrForeignToplevelManagerV1},
+};
+
+use crate::frontend::frontend_state::{State, ToplevelInfo};
+
+// Only used to find a focused fullscreen window before the overlay opens
+impl Dispatch<ZwlrForeignToplevelManagerV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        _manager: &ZwlrForeignToplevelManagerV1,
+        event: zwlr_foreign_toplevel_manager_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        match event {
+            zwlr_foreign_toplevel_manager_v1::Event::Toplevel { toplevel } => {
+                state
+                    .toplevels
+                    .insert(toplevel.id(), ToplevelInfo::default());
+            }
+            zwlr_foreign_toplevel_manager_v1::Event::Finished => {
+                state.toplevels.clear();
+            }
+            _ => {}
+        }
+    }
+
+    fn event_created_child(
+        opcode: u16,
+        qhandle: &QueueHandle<Self>,
+    ) -> Arc<dyn wayland_client::backend::ObjectData> {
+        match opcode {
+            zwlr_foreign_toplevel_manager_v1::EVT_TOPLEVEL_OPCODE => {
+                qhandle.make_data::<ZwlrForeignToplevelHandleV1, ()>(())
+            }
+            _ => panic!("Unknown child object for opcode {opcode}"),
+        }
+    }
+}
+
+impl Dispatch<ZwlrForeignToplevelHandleV1, ()> for State {
+    fn event(
+        state: &mut Self,
+        handle: &ZwlrForeignToplevelHandleV1,
+        event: zwlr_foreign_toplevel_handle_v1::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+        let toplevel = state.toplevels.entry(handle.id()).or_default();
+        match event {
+            zwlr_foreign_toplevel_handle_v1::Event::State { state } => {
+                // Array of native-endian u32 state values
+                let states: Vec<u32> = state
+                    .chunks_exact(4)
+                    .map(|chunk| u32::from_ne_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                    .collect();
+                toplevel.activated =
+                    states.contains(&(zwlr_foreign_toplevel_handle_v1::State::Activated as u32));
+                toplevel.fullscreen =
+                    states.contains(&(zwlr_foreign_toplevel_handle_v1::State::Fullscreen as u32));
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::OutputEnter { output } => {
+                toplevel.outputs.push(output);
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::OutputLeave { output } => {
+                toplevel.outputs.retain(|entered| entered != &output);
+            }
+            zwlr_foreign_toplevel_handle_v1::Event::Closed => {
+                state.toplevels.remove(&handle.id());
+                handle.destroy();
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use wayland_client::protocol::wl_touch;
+use wayland_client::{Connection, Dispatch, QueueHandle};
+
+use crate::frontend::frontend_state::State;
+use log::debug;
+
+impl Dispatch<wl_touch::WlTouch, ()> for State {
+    fn event(
+        state: &mut Self,
+        _touch: &wl_touch::WlTouch,
+        event: wl_touch::Event,
+        _data: &(),
+        _conn: &Connection,
+        _qhandle: &QueueHandle<State>,
+    ) {
+        if let wl_touch::Event::Down {
+            serial: _,
+            time: _,
+            surface,
+            id,
+            x,
+            y,
+        } = event
+        {
+            debug!("Touch point {id} down on surface: {surface:?} at ({x}, {y})");
+            if state.capture_surface.as_ref() != Some(&surface) {
+                return;
+            }
+            if state.coords_received {
+                // The overlay is open; a tap outside of it dismisses it like a click
+                debug!("Tap on capture layer - requesting close");
+                state.capture_layer_clicked = true;
+            } else {
+                // Without a pointer the first tap tells us where to open
+                state.coords_received = true;
+                state.received_x = x;
+                state.received_y = y;
+            }
+        }
+    }
+}
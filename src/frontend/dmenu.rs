@@ -0,0 +1,110 @@
+//! `cursor-clip menu --dmenu`: the history as dmenu-style lines (`ID<TAB>preview`) for
+//! rofi, fuzzel, wofi or dmenu, and the way back from a chosen line to the clipboard.
+//! Useful where the overlay can't be shown (no layer-shell) or a launcher is preferred.
+
+use crate::frontend::ipc_client::FrontendClient;
+use crate::shared::{
+    ClipboardContentType, ClipboardItemPreview, CursorClipError, CursorClipResult,
+};
+use std::io::{BufRead, Write};
+use std::process::{Command, Stdio};
+
+// Characters of a preview per line; launchers show one line per item anyway
+const LINE_CHARS: usize = 100;
+
+/// Print one line per history item, pinned first, as the overlay lists them.
+pub fn print_menu(client: &mut FrontendClient) -> CursorClipResult<()> {
+    let mut stdout = std::io::stdout().lock();
+    for item in client.get_history()? {
+        writeln!(stdout, "{}", menu_line(&item))?;
+    }
+    Ok(())
+}
+
+/// Read the chosen line from stdin and set that item as the clipboard. Returns false
+/// when nothing was chosen (the launcher was cancelled).
+pub fn select_from_stdin(
+    client: &mut FrontendClient,
+    instant_paste: bool,
+) -> CursorClipResult<bool> {
+    let mut line = String::new();
+    std::io::stdin().lock().read_line(&mut line)?;
+    select_line(client, &line, instant_paste)
+}
+
+/// Run `launcher` (e.g. `fuzzel --dmenu`) with the menu on its stdin and set the
+/// chosen item as the clipboard. Returns false when nothing was chosen.
+pub fn run_launcher(
+    client: &mut FrontendClient,
+    launcher: &str,
+    instant_paste: bool,
+) -> CursorClipResult<bool> {
+    let mut words = launcher.split_whitespace();
+    let program = words
+        .next()
+        .ok_or_else(|| CursorClipError::InvalidRequest("Empty launcher command".into()))?;
+    let menu: String = client
+        .get_history()?
+        .iter()
+        .map(|item| menu_line(item) + "\n")
+        .collect();
+
+    let mut child = Command::new(program)
+        .args(words)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| CursorClipError::Io(format!("Failed to run {program}: {e}")))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // Launchers may exit before reading everything; their choice still counts
+        let _ = stdin.write_all(menu.as_bytes());
+    }
+    let output = child.wait_with_output()?;
+    select_line(
+        client,
+        &String::from_utf8_lossy(&output.stdout),
+        instant_paste,
+    )
+}
+
+fn select_line(
+    client: &mut FrontendClient,
+    line: &str,
+    instant_paste: bool,
+) -> CursorClipResult<bool> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    if line.is_empty() {
+        return Ok(false);
+    }
+    let id = line
+        .split('\t')
+        .next()
+        .and_then(|id| id.trim().parse::<u64>().ok())
+        .ok_or_else(|| {
+            CursorClipError::InvalidRequest(format!(
+                "Expected a line printed by `cursor-clip menu --dmenu`, got {line:?}"
+            ))
+        })?;
+    client.set_clipboard_by_id(id, instant_paste, None, None)?;
+    Ok(true)
+}
+
+/// `ID<TAB>preview` on a single line; passwords are masked.
+fn menu_line(item: &ClipboardItemPreview) -> String {
+    let preview = if item.content_type == ClipboardContentType::Password {
+        "••••••••".to_string()
+    } else {
+        let flat = item
+            .content_preview
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut preview: String = flat.chars().take(LINE_CHARS).collect();
+        if flat.chars().count() > LINE_CHARS {
+            preview.push('…');
+        }
+        preview
+    };
+    let pin = if item.pinned { "📌 " } else { "" };
+    format!("{}\t{pin}{preview}", item.item_id)
+}
@@ -0,0 +1,130 @@
+use gtk4::gdk;
+use gtk4::gdk::subclass::prelude::*;
+use gtk4::gio;
+use gtk4::glib;
+use gtk4::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::future::Future;
+use std::pin::Pin;
+
+use bytes::Bytes;
+use indexmap::IndexMap;
+
+use crate::frontend::ipc_client::with_client;
+use crate::shared::{ClipboardContentType, ClipboardItemPreview};
+
+const TEXT_MIMES: &[&str] = &["text/plain;charset=utf-8", "text/plain"];
+const URI_LIST_MIME: &str = "text/uri-list";
+// The daemon stores every image as PNG
+const IMAGE_MIME: &str = "image/png";
+
+mod imp {
+    use super::*;
+
+    #[derive(Default)]
+    pub struct ItemContentProvider {
+        pub item_id: Cell<u64>,
+        pub mime_types: RefCell<Vec<&'static str>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ItemContentProvider {
+        const NAME: &'static str = "CursorClipItemContentProvider";
+        type Type = super::ItemContentProvider;
+        type ParentType = gdk::ContentProvider;
+    }
+
+    impl ObjectImpl for ItemContentProvider {}
+
+    impl ContentProviderImpl for ItemContentProvider {
+        fn formats(&self) -> gdk::ContentFormats {
+            gdk::ContentFormats::new(&self.mime_types.borrow())
+        }
+
+        fn write_mime_type_future(
+            &self,
+            mime_type: &str,
+            stream: &gio::OutputStream,
+            io_priority: glib::Priority,
+        ) -> Pin<Box<dyn Future<Output = Result<(), glib::Error>> + 'static>> {
+            let item_id = self.item_id.get();
+            let mime_type = mime_type.to_string();
+            let stream = stream.clone();
+            Box::pin(async move {
+                let data = with_client(|client| client.get_item_data(item_id, &mime_type))
+                    .map_err(|e| glib::Error::new(gio::IOErrorEnum::Failed, &e.to_string()))?;
+                let (_, _, partial_error) = stream
+                    .write_all_future(data, io_priority)
+                    .await
+                    .map_err(|(_, e)| e)?;
+                if let Some(e) = partial_error {
+                    return Err(e);
+                }
+                stream.close_future(io_priority).await
+            })
+        }
+    }
+}
+
+glib::wrapper! {
+    /// Drag-and-drop content of a history item. Only the MIME types are known up front;
+    /// the payload is fetched from the daemon once the drop target asks for one.
+    pub struct ItemContentProvider(ObjectSubclass<imp::ItemContentProvider>)
+        @extends gdk::ContentProvider;
+}
+
+impl ItemContentProvider {
+    pub fn new(item: &ClipboardItemPreview) -> Self {
+        let provider: Self = glib::Object::new();
+        let mime_types = if !item.files.is_empty() {
+            vec![URI_LIST_MIME]
+        } else if item.content_type == ClipboardContentType::Image {
+            vec![IMAGE_MIME]
+        } else {
+            TEXT_MIMES.to_vec()
+        };
+        provider.imp().item_id.set(item.item_id);
+        provider.imp().mime_types.replace(mime_types);
+        provider
+    }
+}
+
+/// The types a drop onto the overlay is accepted as, most specific first.
+pub fn drop_types() -> [glib::Type; 3] {
+    [
+        gdk::FileList::static_type(),
+        gdk::Texture::static_type(),
+        String::static_type(),
+    ]
+}
+
+/// The MIME map of a dropped value, as the daemon would have read it from a copy.
+pub fn dropped_mime_data(value: &glib::Value) -> Option<IndexMap<String, Bytes>> {
+    let mut mime_data = IndexMap::new();
+    if let Ok(files) = value.get::<gdk::FileList>() {
+        let uris: Vec<String> = files.files().iter().map(|file| file.uri().into()).collect();
+        if uris.is_empty() {
+            return None;
+        }
+        mime_data.insert(URI_LIST_MIME.to_string(), Bytes::from(uris.join("\r\n")));
+    } else if let Ok(texture) = value.get::<gdk::Texture>() {
+        let png = texture.save_to_png_bytes();
+        mime_data.insert(IMAGE_MIME.to_string(), Bytes::copy_from_slice(&png));
+    } else if let Ok(text) = value.get::<String>() {
+        if text.is_empty() {
+            return None;
+        }
+        mime_data = text_mime_data(&text);
+    } else {
+        return None;
+    }
+    Some(mime_data)
+}
+
+/// The MIME map of plain text, under the types a text copy is offered as.
+pub fn text_mime_data(text: &str) -> IndexMap<String, Bytes> {
+    TEXT_MIMES
+        .iter()
+        .map(|mime| (mime.to_string(), Bytes::copy_from_slice(text.as_bytes())))
+        .collect()
+}
@@ -0,0 +1,135 @@
+//! The overlay's optional Emoji tab: recently used emoji (tracked by the daemon when
+//! single-emoji items are copied or picked) and the full Unicode set, searchable by
+//! name and shortcode.
+
+use gtk4::prelude::*;
+use gtk4::{Align, Box, FlowBox, FlowBoxChild, Label, Orientation, SearchEntry};
+use std::cell::Cell;
+use std::rc::Rc;
+
+const EMOJI_PER_LINE: u32 = 8;
+
+/// Build the tab. `on_pick` is called with the chosen emoji.
+pub fn build_emoji_page(recent: &[String], on_pick: impl Fn(&str) + 'static) -> Box {
+    let on_pick: Rc<dyn Fn(&str)> = Rc::new(on_pick);
+    let page = Box::new(Orientation::Vertical, 6);
+    page.set_margin_start(12);
+    page.set_margin_end(12);
+    page.set_margin_bottom(6);
+
+    let search_entry = SearchEntry::new();
+    search_entry.set_placeholder_text(Some("Search emoji by name"));
+    page.append(&search_entry);
+
+    let content = Box::new(Orientation::Vertical, 6);
+    let scroller = gtk4::ScrolledWindow::new();
+    scroller.set_policy(gtk4::PolicyType::Never, gtk4::PolicyType::Automatic);
+    scroller.set_vexpand(true);
+    scroller.set_child(Some(&content));
+    page.append(&scroller);
+
+    let recent_label = section_label("Recently used");
+    let recent_grid = emoji_grid(on_pick.clone());
+    for emoji in recent {
+        let name = emojis::get(emoji).map_or(emoji.as_str(), |known| known.name());
+        recent_grid.append(&emoji_child(emoji, name));
+    }
+    let has_recent = !recent.is_empty();
+    recent_label.set_visible(has_recent);
+    recent_grid.set_visible(has_recent);
+    content.append(&recent_label);
+    content.append(&recent_grid);
+
+    content.append(&section_label("All emoji"));
+    let all_grid = emoji_grid(on_pick.clone());
+    content.append(&all_grid);
+
+    // ~1900 children: only built once the tab is first shown
+    let populated = Rc::new(Cell::new(false));
+    let all_grid_for_map = all_grid.clone();
+    let search_entry_for_map = search_entry.clone();
+    page.connect_map(move |_| {
+        if !populated.replace(true) {
+            for emoji in emojis::iter() {
+                let keywords = std::iter::once(emoji.name())
+                    .chain(emoji.shortcodes())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                all_grid_for_map.append(&emoji_child(emoji.as_str(), &keywords));
+            }
+        }
+        search_entry_for_map.grab_focus();
+    });
+
+    let all_grid_for_search = all_grid.clone();
+    let recent_label_for_search = recent_label.clone();
+    let recent_grid_for_search = recent_grid.clone();
+    search_entry.connect_search_changed(move |entry| {
+        let query = entry.text().to_lowercase();
+        all_grid_for_search.set_filter_func(move |child| {
+            query.is_empty() || child.widget_name().contains(query.as_str())
+        });
+        // Recent emoji are a shortcut for an empty query only
+        let show_recent = has_recent && entry.text().is_empty();
+        recent_label_for_search.set_visible(show_recent);
+        recent_grid_for_search.set_visible(show_recent);
+    });
+    // Enter picks the first match
+    search_entry.connect_activate(move |_| {
+        let mut child = all_grid.first_child();
+        while let Some(widget) = child {
+            if widget.is_child_visible()
+                && let Some(emoji) = emoji_of(&widget)
+            {
+                on_pick(&emoji);
+                return;
+            }
+            child = widget.next_sibling();
+        }
+    });
+
+    page
+}
+
+fn section_label(title: &str) -> Label {
+    let label = Label::new(Some(title));
+    label.add_css_class("heading");
+    label.set_halign(Align::Start);
+    label.set_margin_top(6);
+    label
+}
+
+fn emoji_grid(on_pick: Rc<dyn Fn(&str)>) -> FlowBox {
+    let grid = FlowBox::new();
+    grid.set_selection_mode(gtk4::SelectionMode::None);
+    grid.set_activate_on_single_click(true);
+    grid.set_homogeneous(true);
+    grid.set_min_children_per_line(EMOJI_PER_LINE);
+    grid.set_max_children_per_line(EMOJI_PER_LINE);
+    grid.set_valign(Align::Start);
+    grid.connect_child_activated(move |_, child| {
+        if let Some(emoji) = emoji_of(child.upcast_ref()) {
+            on_pick(&emoji);
+        }
+    });
+    grid
+}
+
+/// A grid cell showing `emoji`; its widget name holds the lowercase search keywords.
+fn emoji_child(emoji: &str, keywords: &str) -> FlowBoxChild {
+    let label = Label::new(Some(emoji));
+    label.add_css_class("title-2");
+    let child = FlowBoxChild::new();
+    child.set_child(Some(&label));
+    child.set_tooltip_text(Some(keywords));
+    child.set_widget_name(&keywords.to_lowercase());
+    child
+}
+
+fn emoji_of(widget: &gtk4::Widget) -> Option<String> {
+    widget
+        .downcast_ref::<FlowBoxChild>()?
+        .child()
+        .and_downcast::<Label>()
+        .map(|label| label.text().to_string())
+}
@@ -1,25 +1,54 @@
+use std::collections::HashMap;
 use std::fs::File;
+use wayland_client::backend::ObjectId;
 use wayland_client::protocol::{
-    wl_buffer, wl_callback, wl_compositor, wl_pointer, wl_seat, wl_shm, wl_shm_pool, wl_surface,
+    wl_buffer, wl_callback, wl_compositor, wl_output, wl_pointer, wl_seat, wl_shm, wl_shm_pool,
+    wl_surface, wl_touch,
 };
 
+use wayland_protocols_wlr::foreign_toplevel::v1::client::zwlr_foreign_toplevel_manager_v1;
 use wayland_protocols_wlr::layer_shell::v1::client::{zwlr_layer_shell_v1, zwlr_layer_surface_v1};
 
 use wayland_protocols::wp::{
+    fractional_scale::v1::client::{wp_fractional_scale_manager_v1, wp_fractional_scale_v1},
     single_pixel_buffer::v1::client::wp_single_pixel_buffer_manager_v1,
     viewporter::client::{wp_viewport, wp_viewporter},
 };
 
-use crate::shared::ClipboardItemPreview;
+use crate::frontend::scaling::{logical_size, surface_scale};
+use crate::shared::{ClipboardItemPreview, CursorClipError};
+
+/// A bound `wl_output` and what the compositor told us about it.
+pub struct OutputInfo {
+    pub output: wl_output::WlOutput,
+    // Connector name (e.g. "DP-1"), matches `gdk::Monitor::connector()`; needs wl_output v4
+    pub name: Option<String>,
+    pub scale: i32,
+    // Current mode in physical pixels, already swapped for 90°/270° transforms
+    pub physical_size: Option<(i32, i32)>,
+    // Rotated by 90° or 270°, so the mode's width is the output's height
+    pub transposed: bool,
+}
+
+/// Window state from wlr-foreign-toplevel-management, used for fullscreen detection.
+#[derive(Debug, Default)]
+pub struct ToplevelInfo {
+    pub activated: bool,
+    pub fullscreen: bool,
+    pub outputs: Vec<wl_output::WlOutput>,
+}
 
 pub struct State {
     pub compositor: Option<wl_compositor::WlCompositor>,
     pub layer_shell: Option<zwlr_layer_shell_v1::ZwlrLayerShellV1>,
     pub pointer: Option<wl_pointer::WlPointer>,
+    pub touch: Option<wl_touch::WlTouch>,
     pub seat: Option<wl_seat::WlSeat>,
     pub single_pixel_buffer_manager:
         Option<wp_single_pixel_buffer_manager_v1::WpSinglePixelBufferManagerV1>,
     pub viewporter: Option<wp_viewporter::WpViewporter>,
+    pub fractional_scale_manager:
+        Option<wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1>,
     pub shm: Option<wl_shm::WlShm>,
     pub shm_pool: Option<wl_shm_pool::WlShmPool>,
     pub shm_file: Option<File>,
@@ -37,11 +66,22 @@ pub struct State {
     pub update_frame_callback: Option<wl_callback::WlCallback>,
     pub capture_viewport: Option<wp_viewport::WpViewport>,
     pub update_viewport: Option<wp_viewport::WpViewport>,
+    pub capture_fractional_scale: Option<wp_fractional_scale_v1::WpFractionalScaleV1>,
+    // Scale the compositor prefers for the capture surface (wp_fractional_scale_v1)
+    pub capture_scale: Option<f64>,
     pub overlay_width: i32,
     pub overlay_height: i32,
     pub monitor_width: i32,
     pub monitor_height: i32,
     pub clipboard_history: Vec<ClipboardItemPreview>,
+    // Why the daemon can't serve the overlay (not running, no clipboard protocol)
+    pub daemon_problem: Option<CursorClipError>,
+    pub outputs: Vec<OutputInfo>,
+    // Output the capture surface (and thus the pointer) is on
+    pub capture_output: Option<wl_output::WlOutput>,
+    pub foreign_toplevel_manager:
+        Option<zwlr_foreign_toplevel_manager_v1::ZwlrForeignToplevelManagerV1>,
+    pub toplevels: HashMap<ObjectId, ToplevelInfo>,
 }
 
 impl Default for State {
@@ -56,9 +96,11 @@ impl State {
             compositor: None,
             layer_shell: None,
             pointer: None,
+            touch: None,
             seat: None,
             single_pixel_buffer_manager: None,
             viewporter: None,
+            fractional_scale_manager: None,
             shm: None,
             shm_pool: None,
             shm_file: None,
@@ -76,11 +118,55 @@ impl State {
             update_frame_callback: None,
             capture_viewport: None,
             update_viewport: None,
+            capture_fractional_scale: None,
+            capture_scale: None,
             overlay_width: 200,
             overlay_height: 400,
             monitor_width: 0,
             monitor_height: 0,
             clipboard_history: Vec::new(),
+            daemon_problem: None,
+            outputs: Vec::new(),
+            capture_output: None,
+            foreign_toplevel_manager: None,
+            toplevels: HashMap::new(),
         }
     }
+
+    /// Connector name of the output the pointer was captured on, if known.
+    pub fn capture_output_name(&self) -> Option<String> {
+        let capture_output = self.capture_output.as_ref()?;
+        self.outputs
+            .iter()
+            .find(|info| &info.output == capture_output)
+            .and_then(|info| info.name.clone())
+    }
+
+    /// Logical size of the output the pointer was captured on, from its mode and the
+    /// capture surface's fractional scale (or the output's integer scale). Used when the
+    /// capture surface itself was never configured with a size.
+    pub fn capture_output_logical_size(&self) -> Option<(i32, i32)> {
+        let capture_output = self.capture_output.as_ref()?;
+        let info = self
+            .outputs
+            .iter()
+            .find(|info| &info.output == capture_output)?;
+        let scale = surface_scale(self.capture_scale, info.scale);
+        Some(logical_size(info.physical_size?, scale))
+    }
+
+    pub fn output_by_name(&self, name: &str) -> Option<wl_output::WlOutput> {
+        self.outputs
+            .iter()
+            .find(|info| info.name.as_deref() == Some(name))
+            .map(|info| info.output.clone())
+    }
+
+    /// Outputs showing the focused window if it is fullscreen, otherwise `None`.
+    pub fn focused_fullscreen_outputs(&self) -> Option<Vec<wl_output::WlOutput>> {
+        self.toplevels
+            .values()
+            .find(|toplevel| toplevel.activated && toplevel.fullscreen)
+            .map(|toplevel| toplevel.outputs.clone())
+    }
 }
@@ -0,0 +1,36 @@
+use log::warn;
+use serde::Deserialize;
+use std::fs;
+
+use crate::shared::config_dir::config_path;
+
+/// What to do when the overlay is summoned while the focused window is fullscreen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FullscreenPolicy {
+    /// Open as usual
+    #[default]
+    Open,
+    /// Do not open the overlay at all
+    Skip,
+    /// Open on a monitor without the fullscreen window; skip if there is none
+    OtherMonitor,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ConfigWithFullscreenPolicy {
+    on_fullscreen: FullscreenPolicy,
+}
+
+pub fn load_policy() -> FullscreenPolicy {
+    let Ok(contents) = fs::read_to_string(config_path()) else {
+        return FullscreenPolicy::default();
+    };
+    toml::from_str::<ConfigWithFullscreenPolicy>(&contents)
+        .map(|config| config.on_fullscreen)
+        .unwrap_or_else(|e| {
+            warn!("Failed to parse on_fullscreen from config, opening normally: {e}");
+            FullscreenPolicy::default()
+        })
+}
@@ -1,5 +1,24 @@
-use crate::frontend::ipc_client::FrontendClient;
-use crate::shared::{ClipboardContentType, ClipboardItemPreview};
+use crate::frontend::autostart::{POLL_INTERVAL, STARTUP_TIMEOUT, spawn_daemon};
+use crate::frontend::drag_content::{
+    ItemContentProvider, drop_types, dropped_mime_data, text_mime_data,
+};
+use crate::frontend::emoji_picker::build_emoji_page;
+use crate::frontend::ipc_client::{
+    FrontendClient, HistoryUpdate, last_history_revision, set_disconnect_handler, with_client,
+};
+use crate::frontend::item_object::ClipboardItemObject;
+use crate::frontend::renderer::watch_first_frames;
+use crate::frontend::scaling::capture_to_monitor;
+use crate::frontend::shortcuts::{
+    KEYBINDING_SECTIONS, PickerAction, action_for, quick_pick_number,
+};
+use crate::frontend::syntax_highlight::highlighted_code_markup;
+use crate::shared::config_dir::config_path;
+use crate::shared::{
+    BackendMessage, ClipboardContentType, ClipboardItemPreview, CursorClipError, DaemonSettings,
+    HISTORY_PAGE_SIZE, HistoryOrder, HistoryView, ItemDetails, MAX_FULL_PREVIEW_BYTES,
+    MergeSeparator, MimeFilter, Snippet, TextTransform, format_byte_size,
+};
 use gtk4::prelude::*;
 use gtk4::{
     Align, Application, Box, Button, CheckButton, Label, Orientation, Overlay, Revealer,
@@ -10,19 +29,84 @@ use libadwaita::{self as adw, prelude::*};
 use log::{debug, error, info, warn};
 use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::Once;
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
 
 static INIT: Once = Once::new();
+
+const MIN_TEXT_SCALE: f64 = 0.75;
+const MAX_TEXT_SCALE: f64 = 2.0;
+const TEXT_SCALE_STEP: f64 = 0.1;
+// Distance from the screen edges for `--anchor` placements
+const ANCHOR_MARGIN: i32 = 12;
+// Longer code is shown without syntax highlighting
+const MAX_HIGHLIGHTED_BYTES: usize = 64 * 1024;
+// Grace period before losing keyboard focus closes the overlay; some compositors
+// (niri, Sway with focus_follows_mouse) report a focus change for a moment
+const FOCUS_LOSS_GRACE_MS: u64 = 150;
+// A deleted row fades out while sliding this far to the side
+const ROW_REMOVAL_MS: u32 = 180;
+const ROW_REMOVAL_SLIDE: f64 = 24.0;
+// History list size without `overlay_width`/`overlay_max_height` in config.toml
+const DEFAULT_OVERLAY_WIDTH: i32 = 200;
+const DEFAULT_OVERLAY_HEIGHT: i32 = 400;
+// Smallest list the config or the resize grip can make
+const MIN_OVERLAY_WIDTH: i32 = 200;
+const MIN_LIST_HEIGHT: i32 = 120;
 pub static CLOSE_REQUESTED: AtomicBool = AtomicBool::new(false);
+// Set while the header's keep-open toggle is on: clicks elsewhere and focus loss are ignored
+static STAY_OPEN: AtomicBool = AtomicBool::new(false);
+// One history subscription per process, started with the first overlay; later overlays
+// (non-resident mode) take over its events through `FOLLOWED_LIST`
+static HISTORY_SUBSCRIPTION: Once = Once::new();
 
 // Thread-local storage for the overlay state since GTK objects aren't Send/Sync
 thread_local! {
     static OVERLAY_WINDOW: RefCell<Option<adw::ApplicationWindow>> = const { RefCell::new(None) };
     static OVERLAY_APP: RefCell<Option<Application>> = const { RefCell::new(None) };
+    // Set in resident mode: the prebuilt overlay, hidden instead of quitting on close
+    static RESIDENT: RefCell<Option<ResidentOverlay>> = const { RefCell::new(None) };
+    // `connect_map` handler re-fitting the window at its last placement
+    static REFIT_HANDLER: RefCell<Option<gtk4::glib::SignalHandlerId>> = const { RefCell::new(None) };
+    // Toast offering to undo the latest delete or Clear All
+    static UNDO_TOAST: RefCell<Option<adw::Toast>> = const { RefCell::new(None) };
+    // List of the overlay built last, updated when the history changes elsewhere
+    static FOLLOWED_LIST: RefCell<Option<(gtk4::glib::WeakRef<gtk4::ListView>, HistoryListState)>> =
+        const { RefCell::new(None) };
+}
+
+/// Light/dark preference for the overlay; `System` follows the desktop's color scheme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum ThemePreference {
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl ThemePreference {
+    const ALL: [ThemePreference; 3] = [Self::System, Self::Light, Self::Dark];
+
+    fn label(self) -> &'static str {
+        match self {
+            Self::System => "System",
+            Self::Light => "Light",
+            Self::Dark => "Dark",
+        }
+    }
+
+    fn color_scheme(self) -> adw::ColorScheme {
+        match self {
+            Self::System => adw::ColorScheme::Default,
+            Self::Light => adw::ColorScheme::ForceLight,
+            Self::Dark => adw::ColorScheme::ForceDark,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -33,25 +117,138 @@ struct UserConfig {
     #[serde(alias = "persistent_history")]
     persistence_enabled: bool,
     instant_paste: bool,
+    text_scale: f64,
+    theme: ThemePreference,
+    // Any CSS color (e.g. "#e66100"); replaces the desktop's accent color in the overlay
+    accent_color: Option<String>,
+    // Close when clicking outside the overlay or focusing another window; false starts
+    // with the keep-open toggle on
+    close_on_outside_click: bool,
+    // Joins the items of Append & paste
+    merge_separator: MergeSeparator,
+    // Show the Emoji tab (recently used emoji and a searchable picker)
+    emoji_tab: bool,
+    // Middle-click on a row offers it as the primary selection
+    middle_click_primary: bool,
+    // Right-click on a row opens a context menu instead of the item's details
+    right_click_menu: bool,
+    // The mouse wheel over the header and tab bar switches tabs
+    scroll_header_tabs: bool,
+    // Width of the history list in pixels
+    overlay_width: i32,
+    // Let the list grow with its items up to this height instead of always being
+    // `DEFAULT_OVERLAY_HEIGHT` tall
+    overlay_max_height: Option<i32>,
+    // Lines of text shown per row before the preview is cut off
+    preview_lines: i32,
+    // One line per row without wrapping, and smaller image thumbnails
+    compact_rows: bool,
+    // Number badges 1-9 on the first rows, for pasting with Alt+number
+    quick_pick_badges: bool,
+    // Order of the unpinned history: most recent or most used (frecency) first
+    history_order: HistoryOrder,
+    // Hovering a row shows a longer excerpt and its metadata
+    row_tooltips: bool,
+}
+
+/// Mouse gestures on history rows, from `UserConfig`
+#[derive(Clone, Copy)]
+struct RowGestures {
+    middle_click_primary: bool,
+    right_click_menu: bool,
+}
+
+/// How much of an item a history row shows, from `UserConfig`
+#[derive(Clone, Copy)]
+struct RowLayout {
+    preview_lines: i32,
+    compact: bool,
+    badges: bool,
+    tooltips: bool,
 }
 
 #[derive(Clone)]
 struct HistoryListState {
-    items: Rc<RefCell<Vec<ClipboardItemPreview>>>,
-    all_items: Rc<RefCell<Vec<ClipboardItemPreview>>>,
+    // Every loaded history item as `ClipboardItemObject`, in daemon order (pinned first)
+    store: gtk4::gio::ListStore,
+    // Selection over `store`; the ListView's model
+    selection: gtk4::SingleSelection,
+    // While non-empty, `store` holds the daemon's `SearchHistory` results instead of pages
     search_query: Rc<RefCell<String>>,
+    // Item ID -> byte ranges of its preview matching `search_query`
+    highlights: Rc<RefCell<HashMap<u64, Vec<(usize, usize)>>>>,
+    // Why the daemon rejected `search_query` (e.g. an invalid `re:` pattern)
+    search_error: Rc<RefCell<Option<String>>>,
     show_trash: Rc<RefCell<bool>>,
     show_pin: Rc<RefCell<bool>>,
+    instant_paste: Rc<RefCell<bool>>,
+    window_title: adw::WindowTitle,
+    // Switches between the history list and an item's detail page
+    detail_stack: gtk4::Stack,
+    // False while older history pages are still to be fetched from the daemon
+    history_complete: Rc<RefCell<bool>>,
+    // Tabs: one page per `HistoryView` (the visible one holds `detail_stack`) plus snippets
+    view_stack: adw::ViewStack,
+    // History view whose items are in `store`
+    view: Rc<RefCell<HistoryView>>,
+    // Order `store` is paged in
+    order: Rc<RefCell<HistoryOrder>>,
+    // Rows of the Snippets tab
+    snippet_list: Box,
+    merge: MergeSelection,
+    paste_queue: PasteQueueBanner,
+    gestures: RowGestures,
+    layout: RowLayout,
+    // Row widgets currently bound to items, for animating their removal
+    bound_rows: Rc<RefCell<HashMap<u64, gtk4::glib::WeakRef<gtk4::Widget>>>>,
+    // Item ID -> excerpt for the row tooltip (text, whether there is more), fetched on
+    // first hover
+    tooltip_excerpts: Rc<RefCell<HashMap<u64, (String, bool)>>>,
+}
+
+/// Items picked for Append & paste (checkboxes or Ctrl+click) and the bar acting on them
+#[derive(Clone)]
+struct MergeSelection {
+    // Item IDs in the order they were picked, which is the order they are joined in
+    marked: Rc<RefCell<Vec<u64>>>,
+    separator: Rc<RefCell<MergeSeparator>>,
+    bar: Revealer,
+    count_label: Label,
+    paste_button: Button,
+    queue_button: Button,
+    clear_button: Button,
+}
+
+/// Banner above the list while the paste queue (stack mode) holds items
+#[derive(Clone)]
+struct PasteQueueBanner {
+    // Queued item IDs as last reported by the daemon, next first
+    ids: Rc<RefCell<Vec<u64>>>,
+    banner: Box,
+    label: Label,
+    paste_next_button: Button,
+    skip_button: Button,
+    clear_button: Button,
 }
 
 struct OverlayContent {
     overlay: Overlay,
-    list_box: gtk4::ListBox,
+    list_view: gtk4::ListView,
     history_state: HistoryListState,
     search_entry: SearchEntry,
     search_revealer: Revealer,
 }
 
+/// The window kept alive by `cursor-clip resident` between showings
+struct ResidentOverlay {
+    window: adw::ApplicationWindow,
+    content: OverlayContent,
+    // Requested overlay width and height, for placing it again
+    size: (i32, i32),
+    // Signalled once the current showing is hidden again; the opening thread waits on it
+    closed: Option<mpsc::Sender<()>>,
+}
+
 impl Default for UserConfig {
     fn default() -> Self {
         Self {
@@ -59,16 +256,41 @@ impl Default for UserConfig {
             show_pin: true,
             persistence_enabled: false,
             instant_paste: true,
+            text_scale: 1.0,
+            theme: ThemePreference::System,
+            accent_color: None,
+            close_on_outside_click: true,
+            merge_separator: MergeSeparator::Newline,
+            emoji_tab: false,
+            middle_click_primary: true,
+            right_click_menu: true,
+            scroll_header_tabs: true,
+            overlay_width: DEFAULT_OVERLAY_WIDTH,
+            overlay_max_height: None,
+            preview_lines: 3,
+            compact_rows: false,
+            quick_pick_badges: true,
+            history_order: HistoryOrder::Recent,
+            row_tooltips: true,
         }
     }
 }
 
-fn config_path() -> PathBuf {
-    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-    PathBuf::from(home)
-        .join(".config")
-        .join("cursor-clip")
-        .join("config.toml")
+/// Order the history is listed in, from config.toml.
+pub fn configured_history_order() -> HistoryOrder {
+    load_or_create_config().history_order
+}
+
+/// Requested overlay width and height from config.toml, used for the first placement
+/// before the window has been allocated.
+pub fn configured_overlay_size() -> (i32, i32) {
+    let config = load_or_create_config();
+    (
+        config.overlay_width.max(MIN_OVERLAY_WIDTH),
+        config
+            .overlay_max_height
+            .map_or(DEFAULT_OVERLAY_HEIGHT, |height| height.max(MIN_LIST_HEIGHT)),
+    )
 }
 
 fn load_or_create_config() -> UserConfig {
@@ -97,7 +319,19 @@ fn save_config(config: &UserConfig) -> Result<(), std::io::Error> {
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)?;
     }
-    let contents = toml::to_string_pretty(config)
+    // Merge into the file on disk so keys owned by the daemon (e.g. max_items written
+    // by `SetConfig` after this overlay opened) are kept
+    let mut table = fs::read_to_string(&path)
+        .ok()
+        .and_then(|contents| toml::from_str::<toml::Table>(&contents).ok())
+        .unwrap_or_default();
+    let toml::Value::Table(own_keys) = toml::Value::try_from(config)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?
+    else {
+        return Err(std::io::Error::other("config did not serialize to a table"));
+    };
+    table.extend(own_keys);
+    let contents = toml::to_string_pretty(&table)
         .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
     fs::write(path, contents)
 }
@@ -110,9 +344,42 @@ pub fn reset_close_flags() {
     CLOSE_REQUESTED.store(false, Ordering::Relaxed);
 }
 
+/// Whether the keep-open toggle in the header is on.
+pub fn is_pinned_open() -> bool {
+    STAY_OPEN.load(Ordering::Relaxed)
+}
+
+/// Close the overlay from a thread other than GTK's (e.g. the capture-layer watcher).
+pub fn request_quit_from_thread() {
+    gtk4::glib::MainContext::default().invoke(request_quit);
+}
+
+/// Exclusive keyboard grab, or on-demand while kept open so other windows can be typed in.
+fn overlay_keyboard_mode() -> gtk4_layer_shell::KeyboardMode {
+    if is_pinned_open() {
+        gtk4_layer_shell::KeyboardMode::OnDemand
+    } else {
+        gtk4_layer_shell::KeyboardMode::Exclusive
+    }
+}
+
 // Centralized quit path to avoid double-close reentrancy and ensure flags + app quit
 fn request_quit() {
     CLOSE_REQUESTED.store(true, Ordering::Relaxed);
+    // A resident overlay is only hidden, ready to be shown again
+    let resident = RESIDENT.with_borrow_mut(|resident| {
+        let resident = resident.as_mut()?;
+        resident.window.set_visible(false);
+        if let Some(closed) = resident.closed.take() {
+            let _ = closed.send(());
+        }
+        Some(())
+    });
+    if resident.is_some() {
+        debug!("Resident overlay hidden");
+        return;
+    }
+
     // Prefer quitting the application (cleaner teardown) over closing the window directly
     OVERLAY_APP.with(|a| {
         if let Some(ref app) = *a.borrow() {
@@ -128,19 +395,75 @@ fn request_quit() {
     });
 }
 
+/// Where to show the overlay, as captured by the layer-shell pointer probe.
+#[derive(Debug, Clone)]
+pub struct OverlayPlacement {
+    // Pointer position in surface-local (logical) coordinates of the capture surface
+    pub x: f64,
+    pub y: f64,
+    pub overlay_width: i32,
+    pub overlay_height: i32,
+    // Logical size of the capture surface, i.e. of the output it covers
+    pub monitor_width: i32,
+    pub monitor_height: i32,
+    // Connector name of the output the pointer is on (e.g. "DP-1")
+    pub output_name: Option<String>,
+    // Pin the overlay to a screen corner or the center instead of `x`/`y` (`--anchor`)
+    pub anchor: Option<AnchorPreset>,
+}
+
+/// Fixed overlay positions for `--anchor`, expressed as layer-shell anchors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AnchorPreset {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    Center,
+}
+
+impl AnchorPreset {
+    pub const NAMES: [&'static str; 5] = [
+        "top-left",
+        "top-right",
+        "bottom-left",
+        "bottom-right",
+        "center",
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "top-left" => Some(Self::TopLeft),
+            "top-right" => Some(Self::TopRight),
+            "bottom-left" => Some(Self::BottomLeft),
+            "bottom-right" => Some(Self::BottomRight),
+            "center" => Some(Self::Center),
+            _ => None,
+        }
+    }
+
+    /// Edges the window is anchored to; none centers it on the output.
+    fn edges(self) -> &'static [Edge] {
+        match self {
+            Self::TopLeft => &[Edge::Top, Edge::Left],
+            Self::TopRight => &[Edge::Top, Edge::Right],
+            Self::BottomLeft => &[Edge::Bottom, Edge::Left],
+            Self::BottomRight => &[Edge::Bottom, Edge::Right],
+            Self::Center => &[],
+        }
+    }
+}
+
 pub fn init_clipboard_overlay(
-    x: f64,
-    y: f64,
-    overlay_width: i32,
-    overlay_height: i32,
-    monitor_width: i32,
-    monitor_height: i32,
+    placement: OverlayPlacement,
     prefetched_items: Vec<ClipboardItemPreview>,
+    daemon_problem: Option<CursorClipError>,
 ) -> Result<(), std::boxed::Box<dyn std::error::Error + Send + Sync>> {
     INIT.call_once(|| {
         adw::init().expect("Failed to initialize libadwaita");
     });
-    configure_color_scheme();
+    configure_color_scheme(load_or_create_config().theme);
 
     // Create the application (was returned from init_application())
     let app: Application = adw::Application::builder()
@@ -152,13 +475,9 @@ pub fn init_clipboard_overlay(
     app.connect_activate(move |_| {
         let window = create_layer_shell_window(
             &app_clone,
-            x,
-            y,
-            overlay_width,
-            overlay_height,
-            monitor_width,
-            monitor_height,
+            placement.clone(),
             prefetched_items.clone(),
+            daemon_problem.clone(),
         );
 
         // Store the window in our thread-local storage
@@ -170,9 +489,12 @@ pub fn init_clipboard_overlay(
             *a.borrow_mut() = Some(app_clone.clone());
         });
 
-        window.present();
+        present_animated(&window);
 
-        debug!("Libadwaita overlay window created at ({}, {})", x, y);
+        debug!(
+            "Libadwaita overlay window created at ({}, {})",
+            placement.x, placement.y
+        );
     });
 
     // Run the application
@@ -188,22 +510,416 @@ pub fn init_clipboard_overlay(
     Ok(())
 }
 
-fn configure_color_scheme() {
+/// Run the overlay for `cursor-clip resident`: GTK starts once and the window is
+/// built right away but stays hidden until `show_resident_overlay`; closing it only
+/// hides it again. Blocks for the lifetime of the process.
+pub fn run_resident_overlay(
+    overlay_width: i32,
+    overlay_height: i32,
+) -> Result<(), std::boxed::Box<dyn std::error::Error + Send + Sync>> {
+    INIT.call_once(|| {
+        adw::init().expect("Failed to initialize libadwaita");
+    });
+    configure_color_scheme(load_or_create_config().theme);
+
+    // A separate ID, so one-shot overlays don't activate the resident instance
+    let app: Application = adw::Application::builder()
+        .application_id("com.cursor-clip.Resident")
+        .build()
+        .upcast();
+    // Keep running while no window is shown
+    let _hold = app.hold();
+
+    let app_clone = app.clone();
+    app.connect_activate(move |_| {
+        if RESIDENT.with_borrow(Option::is_some) {
+            return;
+        }
+        let (window, content) =
+            build_overlay_window(&app_clone, overlay_width, overlay_height, Vec::new(), None);
+        RESIDENT.set(Some(ResidentOverlay {
+            window,
+            content,
+            size: (overlay_width, overlay_height),
+            closed: None,
+        }));
+        debug!("Resident overlay built; waiting for a toggle");
+    });
+
+    app.run_with_args::<String>(&[]);
+    RESIDENT.set(None);
+    Ok(())
+}
+
+/// Show the resident overlay at `placement` with `items` as the first history page (or
+/// `daemon_problem` instead), and block until it is hidden again. Called from a thread
+/// other than GTK's.
+pub fn show_resident_overlay(
+    placement: OverlayPlacement,
+    items: Vec<ClipboardItemPreview>,
+    daemon_problem: Option<CursorClipError>,
+) {
+    let (closed, hidden) = mpsc::channel();
+    gtk4::glib::MainContext::default()
+        .invoke(move || present_resident(placement, items, daemon_problem, closed));
+    // Also returns if the overlay could not be shown and dropped the sender
+    let _ = hidden.recv();
+}
+
+/// Move the shown resident overlay to `x`/`y` (`ShowAt` while it is open). Called from
+/// a thread other than GTK's.
+pub fn move_resident_overlay(x: f64, y: f64) {
+    gtk4::glib::MainContext::default().invoke(move || {
+        RESIDENT.with_borrow(|resident| {
+            let Some(resident) = resident else {
+                return;
+            };
+            let (overlay_width, overlay_height) = resident.size;
+            debug!("Moving resident overlay to ({x}, {y})");
+            place_window(
+                &resident.window,
+                OverlayPlacement {
+                    x,
+                    y,
+                    overlay_width,
+                    overlay_height,
+                    monitor_width: 0,
+                    monitor_height: 0,
+                    output_name: None,
+                    anchor: None,
+                },
+            );
+        });
+    });
+}
+
+fn present_resident(
+    placement: OverlayPlacement,
+    items: Vec<ClipboardItemPreview>,
+    daemon_problem: Option<CursorClipError>,
+    closed: mpsc::Sender<()>,
+) {
+    reset_close_flags();
+    RESIDENT.with_borrow_mut(|resident| {
+        let Some(resident) = resident.as_mut() else {
+            warn!("Resident overlay not built yet; ignoring show request");
+            return;
+        };
+        debug!(
+            "Showing resident overlay at ({}, {})",
+            placement.x, placement.y
+        );
+        // Keep the size the overlay was last resized to
+        let (overlay_width, overlay_height) = resident.size;
+        let placement = OverlayPlacement {
+            overlay_width,
+            overlay_height,
+            ..placement
+        };
+        place_window(&resident.window, placement);
+        reset_keep_open(&resident.window);
+        refresh_resident_content(&resident.content, items, daemon_problem.as_ref());
+        resident.closed = Some(closed);
+        present_animated(&resident.window);
+    });
+}
+
+/// Handle in the bottom-right corner that resizes the history list by dragging. In
+/// resident mode the new size is kept for the following showings.
+fn build_resize_grip(scrolled_window: &gtk4::ScrolledWindow) -> Box {
+    let grip = Box::new(Orientation::Horizontal, 0);
+    grip.add_css_class("resize-grip");
+    grip.set_halign(Align::End);
+    grip.set_valign(Align::End);
+    grip.set_cursor_from_name(Some("se-resize"));
+    grip.set_tooltip_text(Some("Drag to resize"));
+
+    // Offsets are taken from surface coordinates: the grip itself moves while dragging,
+    // the window's top-left corner (its layer-shell anchor) does not
+    let surface_position =
+        |gesture: &gtk4::GestureDrag| gesture.current_event().and_then(|event| event.position());
+    let start = Rc::new(RefCell::new(None::<((f64, f64), (i32, i32))>));
+    let drag = gtk4::GestureDrag::new();
+    let start_for_begin = start.clone();
+    let scrolled_for_begin = scrolled_window.clone();
+    drag.connect_drag_begin(move |gesture, _, _| {
+        gesture.set_state(gtk4::EventSequenceState::Claimed);
+        *start_for_begin.borrow_mut() = surface_position(gesture).map(|position| {
+            (
+                position,
+                (scrolled_for_begin.width(), scrolled_for_begin.height()),
+            )
+        });
+    });
+    let start_for_update = start.clone();
+    let scrolled_for_update = scrolled_window.clone();
+    drag.connect_drag_update(move |gesture, _, _| {
+        let (Some(((start_x, start_y), (width, height))), Some((x, y))) =
+            (*start_for_update.borrow(), surface_position(gesture))
+        else {
+            return;
+        };
+        let width = (width + (x - start_x).round() as i32).max(MIN_OVERLAY_WIDTH);
+        let height = (height + (y - start_y).round() as i32).max(MIN_LIST_HEIGHT);
+        scrolled_for_update.set_min_content_width(width);
+        if scrolled_for_update.max_content_height() >= 0 {
+            // Unset the minimum first; it may never exceed the maximum
+            scrolled_for_update.set_min_content_height(-1);
+            scrolled_for_update.set_max_content_height(height);
+        }
+        scrolled_for_update.set_min_content_height(height);
+    });
+    let scrolled_for_end = scrolled_window.clone();
+    drag.connect_drag_end(move |_, _, _| {
+        if start.borrow_mut().take().is_none() {
+            return;
+        }
+        let size = (
+            scrolled_for_end.min_content_width(),
+            scrolled_for_end.min_content_height(),
+        );
+        debug!("Overlay resized to {}x{}", size.0, size.1);
+        RESIDENT.with_borrow_mut(|resident| {
+            if let Some(resident) = resident.as_mut() {
+                resident.size = size;
+            }
+        });
+    });
+    grip.add_controller(drag);
+    grip
+}
+
+/// Present `window` with a short fade and slide in. With animations turned off in the
+/// GTK settings (reduced motion), CSS transitions are skipped and it simply appears.
+fn present_animated(window: &adw::ApplicationWindow) {
+    if animations_enabled() {
+        window.add_css_class("overlay-entering");
+        window.add_tick_callback(|window, _| {
+            window.remove_css_class("overlay-entering");
+            gtk4::glib::ControlFlow::Break
+        });
+    }
+    window.present();
+}
+
+/// The `gtk-enable-animations` setting, which GTK and libadwaita animations follow.
+fn animations_enabled() -> bool {
+    gtk4::Settings::default().is_none_or(|settings| settings.is_gtk_enable_animations())
+}
+
+/// Start every showing like a fresh overlay: no query, the All tab and the list page,
+/// with `items` replacing whatever was listed last time.
+fn refresh_resident_content(
+    content: &OverlayContent,
+    items: Vec<ClipboardItemPreview>,
+    daemon_problem: Option<&CursorClipError>,
+) {
+    let history_state = &content.history_state;
+    content.search_entry.set_text("");
+    content.search_revealer.set_reveal_child(false);
+    history_state
+        .view_stack
+        .set_visible_child_name(HistoryView::All.name());
+    history_state.detail_stack.set_visible_child_name(LIST_PAGE);
+    clear_marked(history_state);
+
+    history_state.highlights.borrow_mut().clear();
+    // Items may have been edited or replaced since the last showing
+    history_state.tooltip_excerpts.borrow_mut().clear();
+    *history_state.search_error.borrow_mut() = None;
+    *history_state.history_complete.borrow_mut() = items.len() < HISTORY_PAGE_SIZE;
+    let objects: Vec<ClipboardItemObject> =
+        items.into_iter().map(ClipboardItemObject::new).collect();
+    let store = &history_state.store;
+    store.splice(0, store.n_items(), &objects);
+    refresh_stats(history_state);
+    select_first_row(&content.list_view);
+    if let Some(problem) = daemon_problem {
+        show_daemon_problem(&content.list_view, history_state, problem);
+    }
+}
+
+/// Position a window edge at the cursor so the window stays fully visible: open
+/// towards the bottom/right, flip above/left of the cursor when that would overflow,
+/// and clamp to the screen as a last resort.
+fn fit_on_screen(cursor: f64, window_size: f64, screen_size: f64, margin: f64) -> f64 {
+    let max = (screen_size - window_size - margin).max(margin);
+    if cursor + window_size + margin <= screen_size {
+        cursor.max(margin)
+    } else if cursor - window_size >= margin {
+        cursor - window_size
+    } else {
+        cursor.clamp(margin, max)
+    }
+}
+
+fn find_monitor_by_connector(connector: &str) -> Option<gtk4::gdk::Monitor> {
+    let display = gtk4::gdk::Display::default()?;
+    let monitors = display.monitors();
+    (0..monitors.n_items())
+        .filter_map(|index| monitors.item(index)?.downcast::<gtk4::gdk::Monitor>().ok())
+        .find(|monitor| monitor.connector().as_deref() == Some(connector))
+}
+
+fn configure_color_scheme(theme: ThemePreference) {
     let style_manager = adw::StyleManager::default();
-    style_manager.set_color_scheme(adw::ColorScheme::Default);
+    style_manager.set_color_scheme(theme.color_scheme());
+}
+
+/// Anchor `window` top-left at `x`/`y` (surface-local), fitted to stay fully visible.
+fn place_at_position(
+    window: &adw::ApplicationWindow,
+    (x, y): (f64, f64),
+    (overlay_width, overlay_height): (i32, i32),
+    (monitor_width, monitor_height): (i32, i32),
+) {
+    // Anchor to top-left corner for precise positioning
+    window.set_anchor(Edge::Top, true);
+    window.set_anchor(Edge::Left, true);
+
+    // Set margins to position the window at the specified coordinates, pre-fitted with
+    // the requested size so it does not flash off-screen before the first allocation
+    let margin = 5.0;
+    let (initial_x, initial_y) = if monitor_width > 0 && monitor_height > 0 {
+        (
+            fit_on_screen(x, overlay_width as f64, monitor_width as f64, margin),
+            fit_on_screen(y, overlay_height as f64, monitor_height as f64, margin),
+        )
+    } else {
+        (x, y)
+    };
+    window.set_margin(Edge::Top, initial_y.round() as i32);
+    window.set_margin(Edge::Left, initial_x.round() as i32);
+
+    // Re-fit with the real allocated size to avoid off-screen spawn.
+    let handler = window.connect_map(move |mapped_window| {
+        let mapped_window = mapped_window.clone();
+        gtk4::glib::idle_add_local_once(move || {
+            // Without a size from the capture surface, ask GDK which monitor we ended up on
+            let (screen_width, screen_height) = if monitor_width > 0 && monitor_height > 0 {
+                (monitor_width, monitor_height)
+            } else if let Some(geometry) = mapped_window.surface().and_then(|surface| {
+                surface
+                    .display()
+                    .monitor_at_surface(&surface)
+                    .map(|monitor| monitor.geometry())
+            }) {
+                (geometry.width(), geometry.height())
+            } else {
+                return;
+            };
+
+            let window_width = mapped_window.allocated_width().max(overlay_width) as f64;
+            let window_height = mapped_window.allocated_height().max(overlay_height) as f64;
+
+            let clamped_x =
+                fit_on_screen(x, window_width, screen_width as f64, margin).round() as i32;
+            let clamped_y =
+                fit_on_screen(y, window_height, screen_height as f64, margin).round() as i32;
+
+            mapped_window.set_margin(Edge::Top, clamped_y);
+            mapped_window.set_margin(Edge::Left, clamped_x);
+        });
+    });
+    REFIT_HANDLER.set(Some(handler));
 }
 
 /// Create and configure the sync layer shell window
 fn create_layer_shell_window(
     app: &Application,
-    x: f64,
-    y: f64,
+    placement: OverlayPlacement,
+    prefetched_items: Vec<ClipboardItemPreview>,
+    daemon_problem: Option<CursorClipError>,
+) -> adw::ApplicationWindow {
+    let (window, _content) = build_overlay_window(
+        app,
+        placement.overlay_width,
+        placement.overlay_height,
+        prefetched_items,
+        daemon_problem,
+    );
+    place_window(&window, placement);
+    reset_keep_open(&window);
+    window
+}
+
+/// Move the overlay to `placement`. Earlier anchors and margins are reset, so a
+/// resident window can be placed again for every showing.
+fn place_window(window: &adw::ApplicationWindow, placement: OverlayPlacement) {
+    let OverlayPlacement {
+        mut x,
+        mut y,
+        overlay_width,
+        overlay_height,
+        mut monitor_width,
+        mut monitor_height,
+        output_name,
+        anchor,
+    } = placement;
+
+    for edge in [Edge::Top, Edge::Bottom, Edge::Left, Edge::Right] {
+        window.set_anchor(edge, false);
+        window.set_margin(edge, 0);
+    }
+    if let Some(handler) = REFIT_HANDLER.take() {
+        window.disconnect(handler);
+    }
+
+    // Show the overlay on the monitor the pointer is on instead of the compositor's choice
+    if let Some(monitor) = output_name.as_deref().and_then(find_monitor_by_connector) {
+        let geometry = monitor.geometry();
+        // The capture surface may report a different logical size than GDK (e.g. with
+        // fractional scaling); map the pointer position into GDK's coordinate space.
+        (x, y) = capture_to_monitor(
+            (x, y),
+            (monitor_width, monitor_height),
+            (geometry.width(), geometry.height()),
+        );
+        monitor_width = geometry.width();
+        monitor_height = geometry.height();
+        debug!(
+            "Placing overlay on monitor {output_name:?} ({monitor_width}x{monitor_height}, scale {})",
+            monitor.scale_factor()
+        );
+        window.set_monitor(Some(&monitor));
+    } else {
+        window.set_monitor(None);
+    }
+
+    if let Some(anchor) = anchor {
+        // The compositor keeps an anchored window on screen; no fitting needed
+        for edge in anchor.edges() {
+            window.set_anchor(*edge, true);
+            window.set_margin(*edge, ANCHOR_MARGIN);
+        }
+    } else {
+        place_at_position(
+            window,
+            (x, y),
+            (overlay_width, overlay_height),
+            (monitor_width, monitor_height),
+        );
+    }
+}
+
+/// Start a showing with the keep-open toggle as configured and the matching keyboard mode.
+fn reset_keep_open(window: &adw::ApplicationWindow) {
+    // Make window keyboard interactive
+    STAY_OPEN.store(
+        !load_or_create_config().close_on_outside_click,
+        Ordering::Relaxed,
+    );
+    window.set_keyboard_mode(overlay_keyboard_mode());
+}
+
+/// Build the overlay window and its content, not yet placed or shown.
+fn build_overlay_window(
+    app: &Application,
     overlay_width: i32,
     overlay_height: i32,
-    monitor_width: i32,
-    monitor_height: i32,
     prefetched_items: Vec<ClipboardItemPreview>,
-) -> adw::ApplicationWindow {
+    daemon_problem: Option<CursorClipError>,
+) -> (adw::ApplicationWindow, OverlayContent) {
     // Create the main window using Adwaita ApplicationWindow
     let window = adw::ApplicationWindow::builder()
         .application(app)
@@ -218,54 +934,59 @@ fn create_layer_shell_window(
     window.set_layer(Layer::Overlay);
     window.set_namespace(Some("cursor-clip"));
 
-    // Anchor to top-left corner for precise positioning
-    window.set_anchor(Edge::Top, true);
-    window.set_anchor(Edge::Left, true);
-
-    // Set margins to position the window at the specified coordinates
-    window.set_margin(Edge::Top, y as i32);
-    window.set_margin(Edge::Left, x as i32);
-
-    // clamp with the real allocated size to avoid off-screen spawn.
-    if monitor_width > 0 && monitor_height > 0 {
-        window.connect_map(move |mapped_window| {
-            let mapped_window = mapped_window.clone();
-            gtk4::glib::idle_add_local_once(move || {
-                let margin = 5.0;
-                let window_width = mapped_window.allocated_width().max(overlay_width) as f64;
-                let window_height = mapped_window.allocated_height().max(overlay_height) as f64;
-
-                let max_x = (monitor_width as f64 - window_width - margin).max(margin);
-                let max_y = (monitor_height as f64 - window_height - margin).max(margin);
-                let clamped_x = x.clamp(margin, max_x) as i32;
-                let clamped_y = y.clamp(margin, max_y) as i32;
-
-                mapped_window.set_margin(Edge::Top, clamped_y);
-                mapped_window.set_margin(Edge::Left, clamped_x);
-            });
-        });
-    }
-
     window.set_exclusive_zone(-1);
-
-    // Make window keyboard interactive
-    window.set_keyboard_mode(gtk4_layer_shell::KeyboardMode::Exclusive);
+    watch_first_frames(&window);
 
     // Apply custom styling
-    apply_custom_styling(&window);
+    apply_custom_styling(&window, load_or_create_config().accent_color.as_deref());
 
-    // Create and set content (also obtain list_box for navigation)
+    // Create and set content (also obtain list_view for navigation)
     let content = generate_overlay_content(prefetched_items, overlay_width, overlay_height);
     window.set_content(Some(&content.overlay));
+    if let Some(problem) = &daemon_problem {
+        show_daemon_problem(&content.list_view, &content.history_state, problem);
+    }
 
     // Add key controller (Esc/j/k/Enter navigation & activation)
     let key_controller = generate_key_controller(
-        &content.list_box,
+        &content.list_view,
         &content.history_state,
         &content.search_entry,
         &content.search_revealer,
     );
     window.add_controller(key_controller);
+    window.add_controller(generate_escape_controller(
+        &content.list_view,
+        &content.history_state,
+        &content.search_entry,
+        &content.search_revealer,
+    ));
+    window.add_controller(generate_drop_target(
+        &content.list_view,
+        &content.history_state,
+    ));
+
+    // Close when another window gets the focus, unless that lasts only a moment
+    window.connect_is_active_notify(|window| {
+        // A hidden resident window losing the focus is already closed
+        if window.is_active() || is_pinned_open() || !window.is_visible() {
+            return;
+        }
+        let window = window.clone();
+        gtk4::glib::timeout_add_local_once(
+            std::time::Duration::from_millis(FOCUS_LOSS_GRACE_MS),
+            move || {
+                // Our own dialogs (e.g. preferences) taking the focus don't count
+                let app_focused = window
+                    .application()
+                    .is_some_and(|app| app.windows().iter().any(|w| w.is_active()));
+                if !app_focused && !is_pinned_open() {
+                    debug!("Overlay lost keyboard focus - closing");
+                    request_quit();
+                }
+            },
+        );
+    });
 
     // Add close request handler to ensure any window close goes through our logic
     window.connect_close_request(|_window| {
@@ -275,7 +996,7 @@ fn create_layer_shell_window(
         gtk4::glib::Propagation::Stop
     });
 
-    window
+    (window, content)
 }
 
 /// Create a Windows 11-style clipboard history list with provided (prefetched) backend data.
@@ -290,7 +1011,9 @@ fn generate_overlay_content(
 
     // Header bar
     let header_bar = adw::HeaderBar::new();
-    header_bar.set_title_widget(Some(&Label::new(Some("Clipboard History"))));
+    // Subtitle shows what Clear All or retention changes would affect
+    let window_title = adw::WindowTitle::new("Clipboard History", "");
+    header_bar.set_title_widget(Some(&window_title));
     // Layer-shell + undecorated windows can render built-in title buttons unreliably.
     // Use an explicit close button styled like a normal Adwaita title button instead.
     header_bar.set_show_end_title_buttons(false);
@@ -301,11 +1024,12 @@ fn generate_overlay_content(
     let show_pin_default = config_state.borrow().show_pin;
     let persistence_enabled_default = config_state.borrow().persistence_enabled;
     let instant_paste_default = config_state.borrow().instant_paste;
+    let text_scale_provider = create_text_scale_provider(config_state.borrow().text_scale);
     let show_trash_state = Rc::new(RefCell::new(show_trash_default));
     let show_pin_state = Rc::new(RefCell::new(show_pin_default));
 
-    if let Ok(mut client) = FrontendClient::new()
-        && let Err(e) = client.set_persistence_enabled(persistence_enabled_default)
+    if let Err(e) =
+        with_client(|client| client.set_persistence_enabled(persistence_enabled_default))
     {
         warn!("Failed to sync persistence setting with backend: {}", e);
     }
@@ -405,10 +1129,62 @@ fn generate_overlay_content(
     instant_paste_toggle_row.append(&instant_paste_toggle_check);
     menu_box.append(&instant_paste_toggle_row);
 
+    let frecency_toggle_row = Box::new(Orientation::Horizontal, 8);
+    let frecency_toggle_label = Label::new(Some("Most used first"));
+    frecency_toggle_label.set_halign(Align::Start);
+    frecency_toggle_label.set_hexpand(true);
+    frecency_toggle_row.set_tooltip_text(Some(
+        "Order the history by how often and how recently items were pasted or copied",
+    ));
+    let frecency_toggle_check = CheckButton::new();
+    frecency_toggle_check.set_active(config_state.borrow().history_order == HistoryOrder::Frecency);
+    frecency_toggle_row.append(&frecency_toggle_label);
+    frecency_toggle_row.append(&frecency_toggle_check);
+    menu_box.append(&frecency_toggle_row);
+
+    let theme_row = Box::new(Orientation::Horizontal, 8);
+    let theme_label = Label::new(Some("Theme"));
+    theme_label.set_halign(Align::Start);
+    theme_label.set_hexpand(true);
+    let theme_labels: Vec<&str> = ThemePreference::ALL
+        .iter()
+        .map(|theme| theme.label())
+        .collect();
+    let theme_dropdown = gtk4::DropDown::from_strings(&theme_labels);
+    let theme_default = config_state.borrow().theme;
+    theme_dropdown.set_selected(
+        ThemePreference::ALL
+            .iter()
+            .position(|theme| *theme == theme_default)
+            .unwrap_or(0) as u32,
+    );
+    theme_row.append(&theme_label);
+    theme_row.append(&theme_dropdown);
+    menu_box.append(&theme_row);
+
+    let preferences_button = Button::with_label("Preferences…");
+    preferences_button.add_css_class("flat");
+    menu_box.append(&preferences_button);
+
     menu_revealer.set_child(Some(&menu_box));
     header_bar.pack_end(&close_button);
     header_bar.pack_end(&header_action_group);
 
+    let keep_open_button = gtk4::ToggleButton::new();
+    keep_open_button.set_icon_name("view-pin-symbolic");
+    keep_open_button.add_css_class("flat");
+    keep_open_button.set_tooltip_text(Some("Keep open when clicking elsewhere"));
+    keep_open_button.set_active(!config_state.borrow().close_on_outside_click);
+    keep_open_button.connect_toggled(|button| {
+        STAY_OPEN.store(button.is_active(), Ordering::Relaxed);
+        if let Some(window) = button.root().and_downcast::<adw::ApplicationWindow>() {
+            window.set_keyboard_mode(overlay_keyboard_mode());
+        }
+    });
+    header_bar.pack_end(&keep_open_button);
+    let (pause_button, paused_banner) = build_recording_controls();
+    header_bar.pack_end(&pause_button);
+
     close_button.connect_clicked(move |_| {
         request_quit();
     });
@@ -452,135 +1228,354 @@ fn generate_overlay_content(
     let scrolled_window = gtk4::ScrolledWindow::new();
     scrolled_window.set_policy(gtk4::PolicyType::Never, gtk4::PolicyType::Automatic);
     scrolled_window.set_min_content_width(overlay_width);
-    scrolled_window.set_min_content_height(overlay_height);
-
-    // Create list box for clipboard items
-    let list_box = gtk4::ListBox::new();
-    // Use custom styling instead of the default boxed-list to create floating cards
-    list_box.add_css_class("clipboard-list");
-    //list_box.set_margin_top(6);
-    list_box.set_margin_bottom(6);
-    list_box.set_margin_start(4);
-    list_box.set_margin_end(4);
-    list_box.set_selection_mode(gtk4::SelectionMode::Single);
+    if config_state.borrow().overlay_max_height.is_some() {
+        scrolled_window.set_propagate_natural_height(true);
+        scrolled_window.set_max_content_height(overlay_height);
+        scrolled_window.set_min_content_height(MIN_LIST_HEIGHT.min(overlay_height));
+    } else {
+        scrolled_window.set_min_content_height(overlay_height);
+    }
 
     // Start with prefetched items; if empty try one lazy fetch (non-fatal if it fails)
 
     if prefetched_items.is_empty() {
         debug!("Prefetched clipboard history empty - trying on-demand fetch...");
-        if let Ok(mut client) = FrontendClient::new() {
-            match client.get_history() {
-                Ok(fetched) => prefetched_items = fetched,
-                Err(e) => warn!("Error fetching clipboard history on-demand: {}", e),
-            }
+        let order = config_state.borrow().history_order;
+        match with_client(|client| {
+            client.get_history_page(0, HISTORY_PAGE_SIZE, HistoryView::All, order)
+        }) {
+            Ok(page) => prefetched_items = page.items,
+            Err(e) => warn!("Error fetching clipboard history on-demand: {}", e),
         }
     }
+    // A short first page is the whole history
+    let history_complete = prefetched_items.len() < HISTORY_PAGE_SIZE;
+
+    // Items live in a ListStore; only rows scrolled into view get widgets
+    let store = gtk4::gio::ListStore::new::<ClipboardItemObject>();
+    let objects: Vec<ClipboardItemObject> = prefetched_items
+        .into_iter()
+        .map(ClipboardItemObject::new)
+        .collect();
+    store.extend_from_slice(&objects);
+
+    let selection = gtk4::SingleSelection::new(Some(store.clone()));
+    selection.set_autoselect(false);
+    selection.set_can_unselect(true);
+
+    let list_view = gtk4::ListView::new(Some(selection.clone()), None::<gtk4::ListItemFactory>);
+    // Use custom styling instead of the default rich-list to create floating cards
+    list_view.add_css_class("clipboard-list");
+    if config_state.borrow().compact_rows {
+        list_view.add_css_class("compact");
+    }
+    list_view.set_margin_bottom(6);
+    list_view.set_margin_start(4);
+    list_view.set_margin_end(4);
+    // Clicking an item pastes it, like the Windows 11 clipboard history
+    list_view.set_single_click_activate(true);
 
     let history_state = HistoryListState {
-        items: Rc::new(RefCell::new(Vec::new())),
-        all_items: Rc::new(RefCell::new(prefetched_items)),
+        store,
+        selection,
         search_query: Rc::new(RefCell::new(String::new())),
+        highlights: Rc::new(RefCell::new(HashMap::new())),
+        search_error: Rc::new(RefCell::new(None)),
         show_trash: show_trash_state,
         show_pin: show_pin_state,
+        instant_paste: Rc::new(RefCell::new(instant_paste_default)),
+        window_title,
+        detail_stack: gtk4::Stack::new(),
+        history_complete: Rc::new(RefCell::new(history_complete)),
+        view_stack: adw::ViewStack::new(),
+        view: Rc::new(RefCell::new(HistoryView::All)),
+        order: Rc::new(RefCell::new(config_state.borrow().history_order)),
+        snippet_list: Box::new(Orientation::Vertical, 2),
+        merge: build_merge_bar(&config_state),
+        paste_queue: build_paste_queue_banner(),
+        gestures: RowGestures {
+            middle_click_primary: config_state.borrow().middle_click_primary,
+            right_click_menu: config_state.borrow().right_click_menu,
+        },
+        layout: RowLayout {
+            preview_lines: config_state.borrow().preview_lines.max(1),
+            compact: config_state.borrow().compact_rows,
+            badges: config_state.borrow().quick_pick_badges,
+            tooltips: config_state.borrow().row_tooltips,
+        },
+        bound_rows: Rc::new(RefCell::new(HashMap::new())),
+        tooltip_excerpts: Rc::new(RefCell::new(HashMap::new())),
     };
 
-    rebuild_list(&list_box, &history_state);
-    select_first_row(&list_box);
-
-    // Handle item activation (Enter/Space/double-click) instead of mere selection
-    let history_state_for_activation = history_state.clone();
-    let config_for_activation = config_state.clone();
-    list_box.connect_row_activated(move |_, row| {
-        let index = row.index() as usize;
-        let items = history_state_for_activation.items.borrow();
-        if index < items.len() {
-            let item = &items[index];
-            let instant_paste = config_for_activation.borrow().instant_paste;
-            debug!(
-                "Activated clipboard item ID {}: {}",
-                item.item_id, item.content_preview
-            );
-
-            match FrontendClient::new() {
-                Ok(mut client) => {
-                    if let Err(e) = client.set_clipboard_by_id(item.item_id, instant_paste) {
-                        error!("Error setting clipboard by ID: {}", e);
-                    } else {
-                        info!("Clipboard set by ID: {}", item.item_id);
-                        request_quit();
-                    }
-                }
-                Err(e) => {
-                    error!("Error creating frontend client: {}", e);
-                }
+    // Rows are built when an item scrolls into view and dropped when it leaves
+    let factory = gtk4::SignalListItemFactory::new();
+    let list_view_for_rows = list_view.downgrade();
+    let history_state_for_rows = history_state.clone();
+    factory.connect_bind(move |_, object| {
+        let Some(list_item) = object.downcast_ref::<gtk4::ListItem>() else {
+            return;
+        };
+        let (Some(list_view), Some(entry)) = (
+            list_view_for_rows.upgrade(),
+            list_item.item().and_downcast::<ClipboardItemObject>(),
+        ) else {
+            return;
+        };
+        let row = build_item_row(
+            &entry.item(),
+            list_item,
+            &list_view,
+            &history_state_for_rows,
+        );
+        history_state_for_rows.bound_rows.borrow_mut().insert(
+            entry.item_id(),
+            row.upcast_ref::<gtk4::Widget>().downgrade(),
+        );
+        list_item.set_child(Some(&row));
+    });
+    let bound_rows = history_state.bound_rows.clone();
+    factory.connect_unbind(move |_, object| {
+        if let Some(list_item) = object.downcast_ref::<gtk4::ListItem>() {
+            if let Some(entry) = list_item.item().and_downcast::<ClipboardItemObject>() {
+                bound_rows.borrow_mut().remove(&entry.item_id());
             }
+            list_item.set_child(None::<&gtk4::Widget>);
         }
     });
+    list_view.set_factory(Some(&factory));
+    follow_history_changes(&list_view, &history_state);
 
-    scrolled_window.set_child(Some(&list_box));
-    main_box.append(&scrolled_window);
-
-    set_delete_buttons_visible(&list_box, show_trash_default);
-    set_pin_icons_visible(&list_box, show_pin_default);
+    refresh_stats(&history_state);
 
-    let list_box_for_toggle = list_box.clone();
-    let config_for_toggle = config_state.clone();
-    let history_state_for_toggle = history_state.clone();
-    toggle_check.connect_toggled(move |check| {
-        let state = check.is_active();
+    // Handle item activation (Enter/click) instead of mere selection
+    let history_state_for_activation = history_state.clone();
+    list_view.connect_activate(move |list_view, position| {
+        let item = list_view
+            .model()
+            .and_then(|model| model.item(position))
+            .and_downcast::<ClipboardItemObject>()
+            .map(|entry| entry.item());
+        let Some(item) = item else {
+            return;
+        };
+        // While items are picked for Append & paste, clicking picks more instead
+        if history_state_for_activation
+            .merge
+            .marked
+            .borrow()
+            .is_empty()
         {
-            let mut config = config_for_toggle.borrow_mut();
-            config.show_trash = state;
-            if let Err(e) = save_config(&config) {
-                warn!("Failed to save config: {}", e);
-            }
+            paste_item(&history_state_for_activation, item.item_id, item.seat, None);
+        } else if is_mergeable(&item) {
+            toggle_marked(&history_state_for_activation, item.item_id);
         }
-        *history_state_for_toggle.show_trash.borrow_mut() = state;
-        set_delete_buttons_visible(&list_box_for_toggle, state);
     });
 
-    let list_box_for_pin_toggle = list_box.clone();
-    let config_for_pin_toggle = config_state.clone();
-    let history_state_for_pin_toggle = history_state.clone();
-    pin_toggle_check.connect_toggled(move |check| {
-        let state = check.is_active();
-        {
-            let mut config = config_for_pin_toggle.borrow_mut();
-            config.show_pin = state;
-            if let Err(e) = save_config(&config) {
-                warn!("Failed to save config: {}", e);
-            }
+    scrolled_window.set_child(Some(&list_view));
+    let history_state_for_paging = history_state.clone();
+    scrolled_window.connect_edge_reached(move |_, position| {
+        if position == gtk4::PositionType::Bottom {
+            load_next_history_page(&history_state_for_paging);
         }
-        *history_state_for_pin_toggle.show_pin.borrow_mut() = state;
-        set_pin_icons_visible(&list_box_for_pin_toggle, state);
     });
+    scrolled_window.add_controller(generate_zoom_controller(
+        &config_state,
+        &text_scale_provider,
+    ));
+
+    // ListView has no placeholder row; show a label over the empty list instead
+    let placeholder = Label::new(None);
+    placeholder.add_css_class("dim-label");
+    placeholder.set_valign(Align::Start);
+    placeholder.set_margin_top(20);
+    placeholder.set_can_target(false);
+    let list_overlay = Overlay::new();
+    list_overlay.set_child(Some(&scrolled_window));
+    list_overlay.add_overlay(&placeholder);
+    update_placeholder(&placeholder, &history_state);
+    let history_state_for_placeholder = history_state.clone();
+    history_state
+        .selection
+        .connect_items_changed(move |_, _, _, _| {
+            update_placeholder(&placeholder, &history_state_for_placeholder);
+        });
 
-    let config_for_persistence_toggle = config_state.clone();
-    persistence_toggle_check.connect_toggled(move |check| {
-        let state = check.is_active();
-        {
-            let mut config = config_for_persistence_toggle.borrow_mut();
-            config.persistence_enabled = state;
+    let detail_stack = &history_state.detail_stack;
+    detail_stack.set_transition_type(gtk4::StackTransitionType::SlideLeftRight);
+    detail_stack.set_transition_duration(120);
+    detail_stack.set_vexpand(true);
+    detail_stack.add_named(&list_overlay, Some(LIST_PAGE));
+
+    // Tabs switch between filtered views of the history (queried from the daemon) and
+    // the snippet library. The list is moved into whichever history page is visible.
+    let view_stack = &history_state.view_stack;
+    view_stack.set_vexpand(true);
+    for view in HistoryView::ALL {
+        let page_box = Box::new(Orientation::Vertical, 0);
+        if view == HistoryView::All {
+            page_box.append(detail_stack);
+        }
+        let page = view_stack.add_titled(&page_box, Some(view.name()), view.title());
+        page.set_icon_name(Some(view.icon_name()));
+    }
+    let snippet_page = build_snippet_page(&history_state);
+    let page = view_stack.add_titled(&snippet_page, Some(SNIPPETS_VIEW), "Snippets");
+    page.set_icon_name(Some("accessories-text-editor-symbolic"));
+    match with_client(|client| client.get_snippets()) {
+        Ok(snippets) => refresh_snippets(&history_state, &snippets),
+        Err(e) => debug!("Snippets unavailable: {e}"),
+    }
+    if config_state.borrow().emoji_tab {
+        let recent = with_client(|client| client.get_recent_emoji()).unwrap_or_else(|e| {
+            debug!("Recent emoji unavailable: {e}");
+            Vec::new()
+        });
+        let history_state_for_emoji = history_state.clone();
+        let emoji_page = build_emoji_page(&recent, move |emoji| {
+            paste_emoji(&history_state_for_emoji, emoji);
+        });
+        let page = view_stack.add_titled(&emoji_page, Some(EMOJI_VIEW), "Emoji");
+        page.set_icon_name(Some("face-smile-symbolic"));
+    }
+
+    let view_switcher = adw::ViewSwitcher::new();
+    view_switcher.set_stack(Some(view_stack));
+    view_switcher.set_policy(adw::ViewSwitcherPolicy::Narrow);
+    view_switcher.set_margin_start(12);
+    view_switcher.set_margin_end(12);
+    view_switcher.set_margin_bottom(6);
+    if config_state.borrow().scroll_header_tabs {
+        header_bar.add_controller(generate_tab_scroll_controller(&history_state));
+        view_switcher.add_controller(generate_tab_scroll_controller(&history_state));
+    }
+    main_box.append(&view_switcher);
+    main_box.append(view_stack);
+    main_box.append(&history_state.merge.bar);
+
+    let history_state_for_merge = history_state.clone();
+    history_state.merge.paste_button.connect_clicked(move |_| {
+        merge_and_paste(&history_state_for_merge);
+    });
+    let history_state_for_merge_clear = history_state.clone();
+    history_state.merge.clear_button.connect_clicked(move |_| {
+        clear_marked(&history_state_for_merge_clear);
+    });
+    let history_state_for_queue = history_state.clone();
+    history_state.merge.queue_button.connect_clicked(move |_| {
+        queue_marked(&history_state_for_queue);
+    });
+
+    main_box.insert_child_after(&history_state.paste_queue.banner, Some(&search_revealer));
+    match with_client(|client| client.get_paste_queue()) {
+        Ok(ids) => update_paste_queue(&history_state, ids),
+        Err(e) => debug!("Paste queue unavailable: {e}"),
+    }
+    let history_state_for_paste_next = history_state.clone();
+    history_state
+        .paste_queue
+        .paste_next_button
+        .connect_clicked(move |_| {
+            let next = history_state_for_paste_next
+                .paste_queue
+                .ids
+                .borrow()
+                .first()
+                .copied();
+            if let Some(item_id) = next {
+                paste_item(&history_state_for_paste_next, item_id, None, None);
+            }
+        });
+    let history_state_for_skip = history_state.clone();
+    history_state
+        .paste_queue
+        .skip_button
+        .connect_clicked(
+            move |_| match with_client(|client| client.pop_paste_queue(false)) {
+                Ok(ids) => update_paste_queue(&history_state_for_skip, ids),
+                Err(e) => error!("Error skipping queued item: {}", e),
+            },
+        );
+    let history_state_for_queue_clear = history_state.clone();
+    history_state
+        .paste_queue
+        .clear_button
+        .connect_clicked(
+            move |_| match with_client(|client| client.clear_paste_queue()) {
+                Ok(()) => update_paste_queue(&history_state_for_queue_clear, Vec::new()),
+                Err(e) => error!("Error clearing the paste queue: {}", e),
+            },
+        );
+
+    let list_view_for_tabs = list_view.clone();
+    let history_state_for_tabs = history_state.clone();
+    view_stack.connect_visible_child_name_notify(move |stack| {
+        let name = stack.visible_child_name().unwrap_or_default();
+        if let Some(view) = HistoryView::ALL
+            .into_iter()
+            .find(|view| view.name() == name)
+        {
+            switch_history_view(&list_view_for_tabs, &history_state_for_tabs, view);
+        }
+    });
+    main_box.insert_child_after(&paused_banner, Some(&search_revealer));
+    if let Some(banner) = build_recovery_banner(&history_state) {
+        main_box.insert_child_after(&banner, Some(&search_revealer));
+    }
+    select_first_row(&list_view);
+
+    let config_for_toggle = config_state.clone();
+    let history_state_for_toggle = history_state.clone();
+    toggle_check.connect_toggled(move |check| {
+        let state = check.is_active();
+        {
+            let mut config = config_for_toggle.borrow_mut();
+            config.show_trash = state;
             if let Err(e) = save_config(&config) {
                 warn!("Failed to save config: {}", e);
             }
         }
+        *history_state_for_toggle.show_trash.borrow_mut() = state;
+        refresh_rows(&history_state_for_toggle);
+    });
 
-        match FrontendClient::new() {
-            Ok(mut client) => {
-                if let Err(e) = client.set_persistence_enabled(state) {
-                    warn!("Failed to update persistence in backend: {}", e);
-                }
+    let config_for_pin_toggle = config_state.clone();
+    let history_state_for_pin_toggle = history_state.clone();
+    pin_toggle_check.connect_toggled(move |check| {
+        let state = check.is_active();
+        {
+            let mut config = config_for_pin_toggle.borrow_mut();
+            config.show_pin = state;
+            if let Err(e) = save_config(&config) {
+                warn!("Failed to save config: {}", e);
             }
-            Err(e) => {
-                warn!("Failed to connect to backend for persistence toggle: {}", e);
+        }
+        *history_state_for_pin_toggle.show_pin.borrow_mut() = state;
+        refresh_rows(&history_state_for_pin_toggle);
+    });
+
+    let config_for_persistence_toggle = config_state.clone();
+    persistence_toggle_check.connect_toggled(move |check| {
+        let state = check.is_active();
+        {
+            let mut config = config_for_persistence_toggle.borrow_mut();
+            config.persistence_enabled = state;
+            if let Err(e) = save_config(&config) {
+                warn!("Failed to save config: {}", e);
             }
         }
+
+        if let Err(e) = with_client(|client| client.set_persistence_enabled(state)) {
+            warn!("Failed to update persistence in backend: {}", e);
+        }
     });
 
     let config_for_instant_paste_toggle = config_state.clone();
+    let history_state_for_instant_paste_toggle = history_state.clone();
     instant_paste_toggle_check.connect_toggled(move |check| {
         let state = check.is_active();
+        *history_state_for_instant_paste_toggle
+            .instant_paste
+            .borrow_mut() = state;
         let mut config = config_for_instant_paste_toggle.borrow_mut();
         config.instant_paste = state;
         if let Err(e) = save_config(&config) {
@@ -588,55 +1583,88 @@ fn generate_overlay_content(
         }
     });
 
-    let list_box_for_search = list_box.clone();
+    let config_for_frecency_toggle = config_state.clone();
+    let history_state_for_frecency_toggle = history_state.clone();
+    frecency_toggle_check.connect_toggled(move |check| {
+        let order = if check.is_active() {
+            HistoryOrder::Frecency
+        } else {
+            HistoryOrder::Recent
+        };
+        *history_state_for_frecency_toggle.order.borrow_mut() = order;
+        {
+            let mut config = config_for_frecency_toggle.borrow_mut();
+            config.history_order = order;
+            if let Err(e) = save_config(&config) {
+                warn!("Failed to save config: {}", e);
+            }
+        }
+        reload_history(&history_state_for_frecency_toggle);
+    });
+
+    let config_for_theme = config_state.clone();
+    theme_dropdown.connect_selected_notify(move |dropdown| {
+        let Some(theme) = ThemePreference::ALL
+            .get(dropdown.selected() as usize)
+            .copied()
+        else {
+            return;
+        };
+        configure_color_scheme(theme);
+        let mut config = config_for_theme.borrow_mut();
+        config.theme = theme;
+        if let Err(e) = save_config(&config) {
+            warn!("Failed to save config: {}", e);
+        }
+    });
+
+    let list_view_for_search = list_view.clone();
     let history_state_for_search = history_state.clone();
     search_entry.connect_search_changed(move |entry| {
         *history_state_for_search.search_query.borrow_mut() = entry.text().to_string();
-        rebuild_list(&list_box_for_search, &history_state_for_search);
-        select_first_row_without_focus(&list_box_for_search);
+        reload_history(&history_state_for_search);
+        select_first_row_without_focus(&list_view_for_search);
     });
 
-    let list_box_for_search_activate = list_box.clone();
+    let history_state_for_search_activate = history_state.clone();
     search_entry.connect_activate(move |_| {
-        if let Some(row) = list_box_for_search_activate.selected_row() {
-            row.emit_by_name::<()>("activate", &[]);
+        if let Some(item) = selected_item(&history_state_for_search_activate) {
+            paste_item(
+                &history_state_for_search_activate,
+                item.item_id,
+                item.seat,
+                None,
+            );
         }
     });
 
-    let list_box_for_stop_search = list_box.clone();
+    let list_view_for_stop_search = list_view.clone();
     search_entry.connect_stop_search(move |_| {
-        if list_box_for_stop_search.selected_row().is_none() {
-            select_first_row(&list_box_for_stop_search);
-        } else {
-            list_box_for_stop_search.grab_focus();
-        }
+        focus_list(&list_view_for_stop_search);
     });
 
     let search_key_controller = gtk4::EventControllerKey::new();
-    let list_box_for_search_keys = list_box.clone();
+    let list_view_for_search_keys = list_view.clone();
     let search_entry_for_search_keys = search_entry.clone();
     search_key_controller.connect_key_pressed(move |_, key, _, state| {
-        use gtk4::gdk::{Key, ModifierType};
+        use gtk4::gdk::Key;
 
-        match key {
-            Key::u | Key::U
-                if state.contains(ModifierType::CONTROL_MASK)
-                    && search_entry_for_search_keys.position() > 0 =>
-            {
+        match (action_for(key, state), key) {
+            (Some(PickerAction::ClearSearch), _) if search_entry_for_search_keys.position() > 0 => {
                 let cursor = search_entry_for_search_keys.position();
                 search_entry_for_search_keys.delete_text(0, cursor);
                 search_entry_for_search_keys.set_position(0);
                 gtk4::glib::Propagation::Stop
             }
-            Key::Down => {
-                if select_next_row(&list_box_for_search_keys, true) {
+            (_, Key::Down) => {
+                if select_next_row(&list_view_for_search_keys, true) {
                     gtk4::glib::Propagation::Stop
                 } else {
                     gtk4::glib::Propagation::Proceed
                 }
             }
-            Key::Up => {
-                if select_previous_row(&list_box_for_search_keys, true) {
+            (_, Key::Up) => {
+                if select_previous_row(&list_view_for_search_keys, true) {
                     gtk4::glib::Propagation::Stop
                 } else {
                     gtk4::glib::Propagation::Proceed
@@ -647,6 +1675,18 @@ fn generate_overlay_content(
     });
     search_entry.add_controller(search_key_controller);
 
+    let menu_revealer_for_preferences = menu_revealer.clone();
+    preferences_button.connect_clicked(move |button| {
+        menu_revealer_for_preferences.set_reveal_child(false);
+        menu_revealer_for_preferences.set_visible(false);
+        show_preferences_window(
+            button,
+            &instant_paste_toggle_check,
+            &persistence_toggle_check,
+            &theme_dropdown,
+        );
+    });
+
     let menu_revealer_toggle = menu_revealer.clone();
     three_dot_menu.connect_clicked(move |_| {
         let next_state = !menu_revealer_toggle.is_child_revealed();
@@ -654,66 +1694,531 @@ fn generate_overlay_content(
         menu_revealer_toggle.set_reveal_child(next_state);
     });
 
+    // Toasts report a daemon that went away while the overlay is open, and offer to undo
+    // deletions
+    let toast_overlay = adw::ToastOverlay::new();
+    toast_overlay.set_child(Some(&main_box));
+
     // Connect button signals
+    let toast_overlay_for_clear = toast_overlay.downgrade();
+    let history_state_for_clear = history_state.clone();
     clear_button.connect_clicked(move |_| {
-        match FrontendClient::new() {
-            Ok(mut client) => {
-                if let Err(e) = client.clear_history() {
-                    error!("Error clearing clipboard history: {}", e);
-                } else {
-                    info!("Clipboard history cleared");
-                    // Close the overlay after clearing
-                    request_quit();
-                }
-            }
-            Err(e) => {
-                error!("Error creating frontend client: {}", e);
-            }
+        if let Err(e) = with_client(|client| client.clear_history()) {
+            error!("Error clearing clipboard history: {}", e);
+            return;
+        }
+        info!("Clipboard history cleared");
+        // Stay open so the clear can be undone
+        reload_history(&history_state_for_clear);
+        refresh_stats(&history_state_for_clear);
+        if let Some(toast_overlay) = toast_overlay_for_clear.upgrade() {
+            offer_undo(&toast_overlay, &history_state_for_clear, "History cleared");
         }
     });
 
+    let toast_weak = toast_overlay.downgrade();
+    set_disconnect_handler(move |error| {
+        let Some(toast_overlay) = toast_weak.upgrade() else {
+            return;
+        };
+        let title = match error {
+            CursorClipError::DaemonNotRunning => "Clipboard daemon is not running",
+            _ => "Lost connection to the clipboard daemon",
+        };
+        toast_overlay.add_toast(adw::Toast::new(title));
+    });
+
     let overlay = Overlay::new();
-    overlay.set_child(Some(&main_box));
+    overlay.set_child(Some(&toast_overlay));
     overlay.add_overlay(&menu_revealer);
+    overlay.add_overlay(&build_resize_grip(&scrolled_window));
 
     OverlayContent {
         overlay,
-        list_box,
+        list_view,
         history_state,
         search_entry,
         search_revealer,
     }
 }
 
-/// Build the key controller handling Esc (close), j/k or arrows (navigate) and Enter (activate)
+/// Open the preferences window. Settings shared with the header menu are bound to its
+/// widgets, so both stay in sync and reuse the menu's save/IPC handlers. Daemon settings
+/// go through `GetConfig`/`SetConfig`; the daemon writes them to config.toml.
+fn show_preferences_window(
+    anchor: &impl IsA<gtk4::Widget>,
+    instant_paste_check: &CheckButton,
+    persistence_check: &CheckButton,
+    theme_dropdown: &gtk4::DropDown,
+) {
+    let preferences = adw::PreferencesWindow::new();
+    preferences.set_title(Some("Cursor Clip Preferences"));
+    preferences.set_default_size(480, 560);
+    preferences.set_search_enabled(false);
+
+    // The overlay grabs the keyboard exclusively; release it while preferences are open
+    if let Some(overlay_window) = anchor.root().and_downcast::<adw::ApplicationWindow>() {
+        preferences.set_application(overlay_window.application().as_ref());
+        overlay_window.set_keyboard_mode(gtk4_layer_shell::KeyboardMode::OnDemand);
+        preferences.connect_close_request(move |_| {
+            overlay_window.set_keyboard_mode(overlay_keyboard_mode());
+            gtk4::glib::Propagation::Proceed
+        });
+    }
+
+    let daemon_settings = match with_client(|client| client.get_config()) {
+        Ok(settings) => Some(settings),
+        Err(e) => {
+            warn!("Failed to read daemon settings: {}", e);
+            None
+        }
+    };
+    let daemon_available = daemon_settings.is_some();
+    let daemon_settings = Rc::new(RefCell::new(daemon_settings.unwrap_or_default()));
+    let current = daemon_settings.borrow().clone();
+
+    // General
+    let general_page = adw::PreferencesPage::new();
+    general_page.set_title("General");
+    general_page.set_icon_name(Some("preferences-system-symbolic"));
+
+    let behavior_group = adw::PreferencesGroup::new();
+    behavior_group.set_title("Behavior");
+
+    let instant_paste_switch = gtk4::Switch::new();
+    instant_paste_check
+        .bind_property("active", &instant_paste_switch, "active")
+        .bidirectional()
+        .sync_create()
+        .build();
+    behavior_group.add(&switch_row(
+        "Instant paste",
+        "Paste into the focused app right after picking an item",
+        &instant_paste_switch,
+    ));
+
+    let theme_row = adw::ComboRow::new();
+    theme_row.set_title("Theme");
+    let theme_labels: Vec<&str> = ThemePreference::ALL
+        .iter()
+        .map(|theme| theme.label())
+        .collect();
+    theme_row.set_model(Some(&gtk4::StringList::new(&theme_labels)));
+    theme_dropdown
+        .bind_property("selected", &theme_row, "selected")
+        .bidirectional()
+        .sync_create()
+        .build();
+    behavior_group.add(&theme_row);
+    general_page.add(&behavior_group);
+
+    let monitoring_group = adw::PreferencesGroup::new();
+    monitoring_group.set_title("Monitoring");
+
+    let monitor_only_switch = gtk4::Switch::new();
+    monitor_only_switch.set_active(current.monitor_only);
+    let settings_for_monitor_only = daemon_settings.clone();
+    monitor_only_switch.connect_active_notify(move |switch| {
+        let enabled = switch.is_active();
+        update_daemon_settings(&settings_for_monitor_only, |settings| {
+            settings.monitor_only = enabled;
+        });
+    });
+    monitoring_group.add(&switch_row(
+        "Monitor only",
+        "Record copies but leave the selection with the app that set it",
+        &monitor_only_switch,
+    ));
+
+    let persist_on_exit_switch = gtk4::Switch::new();
+    persist_on_exit_switch.set_active(current.persist_on_exit);
+    monitor_only_switch
+        .bind_property("active", &persist_on_exit_switch, "sensitive")
+        .sync_create()
+        .build();
+    let settings_for_persist_on_exit = daemon_settings.clone();
+    persist_on_exit_switch.connect_active_notify(move |switch| {
+        let enabled = switch.is_active();
+        update_daemon_settings(&settings_for_persist_on_exit, |settings| {
+            settings.persist_on_exit = enabled;
+        });
+    });
+    monitoring_group.add(&switch_row(
+        "Keep copies of closed apps",
+        "In monitor-only mode, offer the last copy when the app that made it exits",
+        &persist_on_exit_switch,
+    ));
+
+    let exclude_entry = gtk4::Entry::new();
+    exclude_entry.set_placeholder_text(Some("org.keepassxc.KeePassXC, kitty"));
+    exclude_entry.set_valign(Align::Center);
+    exclude_entry.set_hexpand(true);
+    exclude_entry.set_text(&current.exclude_apps.join(", "));
+    // Applied on Enter so half-typed app ids never reach the daemon
+    let settings_for_exclude = daemon_settings.clone();
+    exclude_entry.connect_activate(move |entry| {
+        let text = entry.text();
+        update_daemon_settings(&settings_for_exclude, |settings| {
+            if let Err(e) = settings.set_from_str("exclude_apps", &text) {
+                warn!("{e}");
+            }
+        });
+    });
+    let exclude_row = adw::ActionRow::new();
+    exclude_row.set_title("Excluded apps");
+    exclude_row
+        .set_subtitle("Comma-separated app ids whose copies are never recorded (Enter to apply)");
+    exclude_row.add_suffix(&exclude_entry);
+    monitoring_group.add(&exclude_row);
+    mark_daemon_group(&monitoring_group, daemon_available);
+    general_page.add(&monitoring_group);
+
+    // History
+    let history_page = adw::PreferencesPage::new();
+    history_page.set_title("History");
+    history_page.set_icon_name(Some("document-open-recent-symbolic"));
+
+    let storage_group = adw::PreferencesGroup::new();
+    storage_group.set_title("Storage");
+    let persistence_switch = gtk4::Switch::new();
+    persistence_check
+        .bind_property("active", &persistence_switch, "active")
+        .bidirectional()
+        .sync_create()
+        .build();
+    storage_group.add(&switch_row(
+        "Persistent history",
+        "Keep history across restarts in an encrypted database",
+        &persistence_switch,
+    ));
+    history_page.add(&storage_group);
+
+    let retention_group = adw::PreferencesGroup::new();
+    retention_group.set_title("Retention");
+    retention_group.set_description(Some("Pinned items are never removed. 0 disables a limit."));
+
+    let max_items_spin = gtk4::SpinButton::with_range(0.0, 10_000.0, 10.0);
+    max_items_spin.set_value(current.retention.max_items as f64);
+    let max_age_spin = gtk4::SpinButton::with_range(0.0, 3650.0, 1.0);
+    max_age_spin.set_value(current.retention.max_age_days as f64);
+    for (spin, key) in [
+        (&max_items_spin, "max_items"),
+        (&max_age_spin, "max_age_days"),
+    ] {
+        let settings_for_retention = daemon_settings.clone();
+        spin.set_valign(Align::Center);
+        spin.connect_value_changed(move |spin| {
+            let value = spin.value_as_int().to_string();
+            update_daemon_settings(&settings_for_retention, |settings| {
+                if let Err(e) = settings.set_from_str(key, &value) {
+                    warn!("{e}");
+                }
+            });
+        });
+    }
+    let max_items_row = adw::ActionRow::new();
+    max_items_row.set_title("History size");
+    max_items_row.set_subtitle("Maximum number of items to keep");
+    max_items_row.add_suffix(&max_items_spin);
+    retention_group.add(&max_items_row);
+    let max_age_row = adw::ActionRow::new();
+    max_age_row.set_title("Maximum age");
+    max_age_row.set_subtitle("Days after which items are removed");
+    max_age_row.add_suffix(&max_age_spin);
+    retention_group.add(&max_age_row);
+    mark_daemon_group(&retention_group, daemon_available);
+    history_page.add(&retention_group);
+
+    preferences.add(&general_page);
+    preferences.add(&history_page);
+    preferences.add(&build_stats_page());
+    preferences.present();
+}
+
+/// Preferences page with the daemon's `GetStats` numbers, to see what the history
+/// holds and whether the retention limits keep it in check.
+fn build_stats_page() -> adw::PreferencesPage {
+    let page = adw::PreferencesPage::new();
+    page.set_title("Statistics");
+    page.set_icon_name(Some("utilities-system-monitor-symbolic"));
+
+    let overview_group = adw::PreferencesGroup::new();
+    overview_group.set_title("Overview");
+    page.add(&overview_group);
+    let stats = match with_client(|client| client.get_stats()) {
+        Ok(stats) => stats,
+        Err(e) => {
+            warn!("History statistics unavailable: {e}");
+            mark_daemon_group(&overview_group, false);
+            return page;
+        }
+    };
+
+    overview_group.add(&value_row("Items", &stats.items.to_string()));
+    overview_group.add(&value_row("Pinned", &stats.pinned.to_string()));
+    overview_group.add(&value_row(
+        "Pasted from history",
+        &stats.total_pastes.to_string(),
+    ));
+    overview_group.add(&value_row(
+        "Stored size",
+        &format_byte_size(stats.total_bytes),
+    ));
+    if let Some(oldest) = stats.oldest_timestamp {
+        overview_group.add(&value_row(
+            "Oldest unpinned item",
+            &format_timestamp(oldest),
+        ));
+    }
+
+    if !stats.by_type.is_empty() {
+        let types_group = adw::PreferencesGroup::new();
+        types_group.set_title("Content Types");
+        for (content_type, count) in &stats.by_type {
+            types_group.add(&value_row(
+                &format!("{} {}", content_type.icon(), content_type.as_str()),
+                &count.to_string(),
+            ));
+        }
+        page.add(&types_group);
+    }
+
+    let busiest_hours = stats.busiest_hours(3);
+    if !busiest_hours.is_empty() {
+        let hours_group = adw::PreferencesGroup::new();
+        hours_group.set_title("Busiest Hours");
+        hours_group.set_description(Some("Copies in the history by time of day"));
+        for (hour, copies) in busiest_hours {
+            hours_group.add(&value_row(
+                &format!("{hour:02}:00–{hour:02}:59"),
+                &format!("{copies} {}", if copies == 1 { "copy" } else { "copies" }),
+            ));
+        }
+        page.add(&hours_group);
+    }
+
+    if !stats.most_pasted.is_empty() {
+        let pasted_group = adw::PreferencesGroup::new();
+        pasted_group.set_title("Most Pasted");
+        for item in &stats.most_pasted {
+            let preview = item.content_preview.lines().next().unwrap_or_default();
+            let row = value_row(preview, &format!("{}×", item.paste_count));
+            match item.last_pasted {
+                Some(last_pasted) => row.set_subtitle(&format!(
+                    "{} · last {}",
+                    item.content_type.as_str(),
+                    format_timestamp(last_pasted)
+                )),
+                None => row.set_subtitle(item.content_type.as_str()),
+            }
+            pasted_group.add(&row);
+        }
+        page.add(&pasted_group);
+    }
+
+    page
+}
+
+/// Row showing a read-only value on the right.
+fn value_row(title: &str, value: &str) -> adw::ActionRow {
+    let row = adw::ActionRow::new();
+    // Row titles are Pango markup and previews may contain `<` or `&`
+    row.set_title(&gtk4::glib::markup_escape_text(title));
+    let value_label = Label::new(Some(value));
+    value_label.add_css_class("dim-label");
+    row.add_suffix(&value_label);
+    row
+}
+
+fn switch_row(title: &str, subtitle: &str, switch: &gtk4::Switch) -> adw::ActionRow {
+    switch.set_valign(Align::Center);
+    let row = adw::ActionRow::new();
+    row.set_title(title);
+    row.set_subtitle(subtitle);
+    row.add_suffix(switch);
+    row.set_activatable_widget(Some(switch));
+    row
+}
+
+/// Daemon settings can only be edited while the daemon is reachable.
+fn mark_daemon_group(group: &adw::PreferencesGroup, daemon_available: bool) {
+    group.set_sensitive(daemon_available);
+    if !daemon_available {
+        group.set_description(Some("The clipboard daemon is not running"));
+    }
+}
+
+/// Change the daemon settings and send the whole set with `SetConfig`.
+fn update_daemon_settings(
+    daemon_settings: &Rc<RefCell<DaemonSettings>>,
+    change: impl FnOnce(&mut DaemonSettings),
+) {
+    let mut settings = daemon_settings.borrow_mut();
+    change(&mut settings);
+    match with_client(|client| client.set_config(settings.clone())) {
+        Ok(applied) => *settings = applied,
+        Err(e) => warn!("Failed to update daemon settings: {}", e),
+    }
+}
+
+// Bytes of text fetched for a row tooltip, and the lines of it shown
+const TOOLTIP_EXCERPT_BYTES: usize = 1024;
+const TOOLTIP_EXCERPT_LINES: usize = 16;
+
+/// Tooltip on a history row with a longer excerpt than the row shows and the item's
+/// metadata. The excerpt is fetched from the daemon on the first hover and kept for the
+/// rest of the showing.
+fn attach_row_tooltip(row: &Box, item: &ClipboardItemPreview, history_state: &HistoryListState) {
+    let mut meta = vec![item.content_type.as_str().to_string()];
+    if let Some(image_info) = &item.image_info {
+        meta.push(image_info.describe());
+    }
+    if item.times_copied > 1 {
+        meta.push(format!("copied {}×", item.times_copied));
+    }
+    if item.paste_count > 0 {
+        meta.push(format!("pasted {}×", item.paste_count));
+    }
+    if let Some(app_id) = &item.source_app {
+        meta.push(format!("from {}", app_display_name(app_id)));
+    }
+    meta.push(format_timestamp(item.last_copied.unwrap_or(item.timestamp)));
+    let meta = meta.join(" · ");
+
+    let item_id = item.item_id;
+    let has_text = is_mergeable(item);
+    let excerpts = history_state.tooltip_excerpts.clone();
+    row.set_has_tooltip(true);
+    row.connect_query_tooltip(move |_, _, _, _, tooltip| {
+        let excerpt = has_text
+            .then(|| {
+                if let Some(excerpt) = excerpts.borrow().get(&item_id) {
+                    return Some(excerpt.clone());
+                }
+                let fetched = with_client(|client| {
+                    client.get_item_full_preview(item_id, Some(TOOLTIP_EXCERPT_BYTES))
+                });
+                match fetched {
+                    Ok(excerpt) => {
+                        excerpts.borrow_mut().insert(item_id, excerpt.clone());
+                        Some(excerpt)
+                    }
+                    Err(e) => {
+                        debug!("No tooltip excerpt for item {item_id}: {e}");
+                        None
+                    }
+                }
+            })
+            .flatten();
+
+        let mut markup = String::new();
+        if let Some((text, truncated)) = excerpt {
+            let mut lines = text.lines();
+            let shown: Vec<&str> = lines.by_ref().take(TOOLTIP_EXCERPT_LINES).collect();
+            markup.push_str(&glib::markup_escape_text(shown.join("\n").trim_end()));
+            if truncated || lines.next().is_some() {
+                markup.push('…');
+            }
+            markup.push_str("\n\n");
+        }
+        markup.push_str(&format!(
+            "<small>{}</small>",
+            glib::markup_escape_text(&meta)
+        ));
+        tooltip.set_markup(Some(&markup));
+        true
+    });
+}
+
+/// Number badge for the row of `list_item`. It follows the row's position, so the badges
+/// stay 1 to 9 from the top as rows are deleted or a search replaces the list.
+fn build_quick_pick_badge(list_item: &gtk4::ListItem) -> Label {
+    let badge = Label::new(None);
+    badge.add_css_class("caption");
+    badge.add_css_class("quick-pick-badge");
+    badge.set_valign(Align::Center);
+    list_item
+        .bind_property("position", &badge, "label")
+        .transform_to(|_, position: u32| {
+            Some(quick_pick_number(position).map_or_else(String::new, |number| number.to_string()))
+        })
+        .sync_create()
+        .build();
+    list_item
+        .bind_property("position", &badge, "visible")
+        .transform_to(|_, position: u32| Some(quick_pick_number(position).is_some()))
+        .sync_create()
+        .build();
+    list_item
+        .bind_property("position", &badge, "tooltip-text")
+        .transform_to(|_, position: u32| {
+            quick_pick_number(position).map(|number| format!("Paste with Alt+{number}"))
+        })
+        .sync_create()
+        .build();
+    badge
+}
+
+/// Build the key controller that dispatches picker actions from the keybinding registry
 fn generate_key_controller(
-    list_box: &gtk4::ListBox,
+    list_view: &gtk4::ListView,
     history_state: &HistoryListState,
     search_entry: &SearchEntry,
     search_revealer: &Revealer,
 ) -> gtk4::EventControllerKey {
     let controller = gtk4::EventControllerKey::new();
-    let list_box_for_keys = list_box.clone();
+    let list_view_for_keys = list_view.clone();
     let history_state_for_keys = history_state.clone();
     let search_entry_for_keys = search_entry.clone();
     let search_revealer_for_keys = search_revealer.clone();
-    controller.connect_key_pressed(move |_, key, _, _| {
+    controller.connect_key_pressed(move |_, key, _, modifiers| {
         use gtk4::gdk::Key;
-        match key {
-            Key::Escape => {
+        // Detail and cheatsheet pages handle their own widgets; only leaving them is intercepted
+        let page = history_state_for_keys.detail_stack.visible_child_name();
+        // There is no list to go back to while the daemon is unavailable
+        if page.as_deref() == Some(PROBLEM_PAGE) {
+            if key == Key::Escape {
+                request_quit();
+                return gtk4::glib::Propagation::Stop;
+            }
+            return gtk4::glib::Propagation::Proceed;
+        }
+        if page.as_deref() != Some(LIST_PAGE) {
+            // The editor takes Backspace and ? as text
+            let leaves_page = if page.as_deref() == Some(EDIT_PAGE) {
+                key == Key::Escape
+            } else {
+                matches!(key, Key::Escape | Key::BackSpace | Key::question)
+            };
+            if leaves_page {
+                show_list_page(&list_view_for_keys, &history_state_for_keys);
+                return gtk4::glib::Propagation::Stop;
+            }
+            return gtk4::glib::Propagation::Proceed;
+        }
+        let Some(action) = action_for(key, modifiers) else {
+            return gtk4::glib::Propagation::Proceed;
+        };
+        // Plain letters belong to the search query while it has focus
+        let typed_into_search = search_entry_for_keys.has_focus() && key.to_unicode().is_some();
+        match action {
+            PickerAction::Close => {
                 if search_revealer_for_keys.is_child_revealed() && search_entry_for_keys.has_focus()
                 {
-                    if list_box_for_keys.selected_row().is_none() {
-                        select_first_row(&list_box_for_keys);
-                    } else {
-                        list_box_for_keys.grab_focus();
-                    }
+                    focus_list(&list_view_for_keys);
                     return gtk4::glib::Propagation::Stop;
                 }
                 request_quit();
                 gtk4::glib::Propagation::Stop
             }
-            Key::slash => {
+            PickerAction::Cheatsheet => {
+                if typed_into_search {
+                    return gtk4::glib::Propagation::Proceed;
+                }
+                show_cheatsheet(&history_state_for_keys);
+                gtk4::glib::Propagation::Stop
+            }
+            PickerAction::Search => {
                 if search_entry_for_keys.has_focus() {
                     return gtk4::glib::Propagation::Proceed;
                 }
@@ -722,459 +2227,441 @@ fn generate_key_controller(
                 search_entry_for_keys.grab_focus();
                 gtk4::glib::Propagation::Stop
             }
-            Key::j | Key::J | Key::Down => {
-                if matches!(key, Key::j | Key::J) && search_entry_for_keys.has_focus() {
+            PickerAction::Next => {
+                if typed_into_search {
                     return gtk4::glib::Propagation::Proceed;
                 }
-                if key == Key::Down && search_entry_for_keys.has_focus() {
-                    list_box_for_keys.grab_focus();
-                }
-                select_next_row(&list_box_for_keys, false);
+                select_next_row(&list_view_for_keys, false);
                 gtk4::glib::Propagation::Stop
             }
-            Key::k | Key::K | Key::Up => {
-                if matches!(key, Key::k | Key::K) && search_entry_for_keys.has_focus() {
+            PickerAction::Previous => {
+                if typed_into_search {
                     return gtk4::glib::Propagation::Proceed;
                 }
-                if key == Key::Up && search_entry_for_keys.has_focus() {
-                    list_box_for_keys.grab_focus();
-                }
-                select_previous_row(&list_box_for_keys, false);
+                select_previous_row(&list_view_for_keys, false);
                 gtk4::glib::Propagation::Stop
             }
-            Key::Return | Key::KP_Enter => {
-                if let Some(row) = list_box_for_keys.selected_row() {
-                    row.emit_by_name::<()>("activate", &[]);
-                    return gtk4::glib::Propagation::Stop;
-                }
-                gtk4::glib::Propagation::Proceed
+            PickerAction::Paste | PickerAction::PastePlainText => {
+                let Some(item) = selected_item(&history_state_for_keys) else {
+                    return gtk4::glib::Propagation::Proceed;
+                };
+                let mime_filter =
+                    (action == PickerAction::PastePlainText).then_some(MimeFilter::PlainText);
+                paste_item(
+                    &history_state_for_keys,
+                    item.item_id,
+                    item.seat,
+                    mime_filter,
+                );
+                gtk4::glib::Propagation::Stop
             }
-            Key::Delete => {
+            PickerAction::QuickPaste => {
+                let Some(item) = key
+                    .to_unicode()
+                    .and_then(|digit| digit.to_digit(10))
+                    .and_then(|number| history_state_for_keys.store.item(number.checked_sub(1)?))
+                    .and_downcast::<ClipboardItemObject>()
+                    .map(|entry| entry.item())
+                else {
+                    return gtk4::glib::Propagation::Proceed;
+                };
+                paste_item(&history_state_for_keys, item.item_id, item.seat, None);
+                gtk4::glib::Propagation::Stop
+            }
+            PickerAction::Delete => {
                 if search_entry_for_keys.has_focus() {
                     return gtk4::glib::Propagation::Proceed;
                 }
-                if let Some(row) = list_box_for_keys.selected_row() {
-                    let index = row.index() as usize;
-                    let item_id = {
-                        let items = history_state_for_keys.items.borrow();
-                        if index >= items.len() {
-                            return gtk4::glib::Propagation::Stop;
-                        }
-                        items[index].item_id
-                    };
-
-                    match FrontendClient::new() {
-                        Ok(mut client) => {
-                            if let Err(e) = client.delete_item_by_id(item_id) {
-                                error!("Error deleting clipboard item by ID: {}", e);
-                                return gtk4::glib::Propagation::Stop;
-                            }
-                        }
-                        Err(e) => {
-                            error!("Error creating frontend client: {}", e);
-                            return gtk4::glib::Propagation::Stop;
-                        }
-                    }
-
-                    {
-                        let mut items = history_state_for_keys.all_items.borrow_mut();
-                        if let Some(index) = items.iter().position(|item| item.item_id == item_id) {
-                            items.remove(index);
-                        }
-                    }
-
-                    rebuild_list(&list_box_for_keys, &history_state_for_keys);
-                    select_first_row(&list_box_for_keys);
-                    return gtk4::glib::Propagation::Stop;
-                }
-                gtk4::glib::Propagation::Proceed
+                let Some(item) = selected_item(&history_state_for_keys) else {
+                    return gtk4::glib::Propagation::Proceed;
+                };
+                delete_item(&list_view_for_keys, &history_state_for_keys, item.item_id);
+                gtk4::glib::Propagation::Stop
             }
-            Key::p | Key::P => {
+            PickerAction::TogglePin => {
                 if search_entry_for_keys.has_focus() {
                     return gtk4::glib::Propagation::Proceed;
                 }
-                if let Some(row) = list_box_for_keys.selected_row() {
-                    let index = row.index() as usize;
-                    let item_id = {
-                        let items = history_state_for_keys.items.borrow();
-                        if index >= items.len() {
-                            return gtk4::glib::Propagation::Stop;
-                        }
-                        items[index].item_id
-                    };
-
-                    let Some(pinned) = next_pinned_state(&history_state_for_keys, item_id) else {
-                        return gtk4::glib::Propagation::Stop;
-                    };
-
-                    match FrontendClient::new() {
-                        Ok(mut client) => {
-                            if let Err(e) = client.set_pinned(item_id, pinned) {
-                                error!("Error updating pinned state: {}", e);
-                                return gtk4::glib::Propagation::Stop;
-                            }
-                        }
-                        Err(e) => {
-                            error!("Error creating frontend client: {}", e);
-                            return gtk4::glib::Propagation::Stop;
-                        }
-                    }
-
-                    apply_pinned_state(&history_state_for_keys, item_id, pinned);
-                    rebuild_list(&list_box_for_keys, &history_state_for_keys);
-                    select_row_by_item_id(&list_box_for_keys, &history_state_for_keys, item_id);
-                    debug!("Updated pinned state for clipboard item ID {}", item_id);
-                    return gtk4::glib::Propagation::Stop;
+                let Some(item) = selected_item(&history_state_for_keys) else {
+                    return gtk4::glib::Propagation::Proceed;
+                };
+                toggle_pinned(&list_view_for_keys, &history_state_for_keys, item.item_id);
+                gtk4::glib::Propagation::Stop
+            }
+            PickerAction::ToggleMerge => {
+                let Some(item) = selected_item(&history_state_for_keys).filter(is_mergeable) else {
+                    return gtk4::glib::Propagation::Proceed;
+                };
+                toggle_marked(&history_state_for_keys, item.item_id);
+                gtk4::glib::Propagation::Stop
+            }
+            PickerAction::MergePaste => {
+                if history_state_for_keys.merge.marked.borrow().len() < 2 {
+                    return gtk4::glib::Propagation::Proceed;
                 }
-                gtk4::glib::Propagation::Proceed
+                merge_and_paste(&history_state_for_keys);
+                gtk4::glib::Propagation::Stop
             }
-            _ => gtk4::glib::Propagation::Proceed,
+            PickerAction::NextTab | PickerAction::PreviousTab => {
+                cycle_tab(&history_state_for_keys, action == PickerAction::NextTab);
+                gtk4::glib::Propagation::Stop
+            }
+            PickerAction::ClearSearch => gtk4::glib::Propagation::Proceed,
         }
     });
     controller
 }
 
-/// Apply custom CSS styling for modern GNOME-style rounded window
-fn apply_custom_styling(window: &adw::ApplicationWindow) {
-    let css_provider = gtk4::CssProvider::new();
-    let display = gtk4::prelude::WidgetExt::display(window);
-    let style_manager = adw::StyleManager::for_display(&display);
-
-    load_overlay_css(&css_provider, style_manager.is_dark());
-
-    {
-        let css_provider = css_provider.clone();
-        style_manager.connect_dark_notify(move |style_manager| {
-            load_overlay_css(&css_provider, style_manager.is_dark());
-        });
-    }
-
-    gtk4::style_context_add_provider_for_display(
-        &display,
-        &css_provider,
-        gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION,
-    );
-}
-
-fn load_overlay_css(css_provider: &gtk4::CssProvider, is_dark: bool) {
-    css_provider.load_from_data(if is_dark {
-        "
-        window {
-            border-radius: 12px;
-            background: #222226;
-            background: @window_bg_color;
-            color: @window_fg_color;
-            border: 1px solid alpha(#ffffff, 0.10);
-            border: 1px solid alpha(@window_fg_color, 0.10);
-            box-shadow: 0 10px 30px alpha(#000000, 0.22);
-        }
-
-        headerbar {
-            background: transparent;
-            box-shadow: none;
-        }
-
-        .clipboard-list {
-            background: transparent;
-        }
-
-        .clipboard-item {
-            background: #343437;
-            background: @card_bg_color;
-            border: 2px solid transparent;
-            border-radius: 10px;
-            padding: 4px 4px;
-            margin: 6px 12px;
-            transition: border-color 150ms ease, box-shadow 150ms ease, background 150ms ease;
+/// Dropping text, files or an image onto the overlay adds it to the history.
+fn generate_drop_target(
+    list_view: &gtk4::ListView,
+    history_state: &HistoryListState,
+) -> gtk4::DropTarget {
+    let target = gtk4::DropTarget::new(gtk4::glib::Type::INVALID, gtk4::gdk::DragAction::COPY);
+    target.set_types(&drop_types());
+    let list_view = list_view.clone();
+    let history_state = history_state.clone();
+    target.connect_drop(move |target, value, _, _| {
+        // A row dragged out and back in is already in the history
+        if target.current_drop().and_then(|drop| drop.drag()).is_some() {
+            return false;
         }
-
-        .clipboard-item:hover {
-            border-color: #3584E4;
-            border-color: @accent_bg_color;
-            background: shade(#343437, 1.05);
-            background: mix(@card_bg_color, @window_fg_color, 0.08);
+        let Some(mime_data) = dropped_mime_data(value) else {
+            return false;
+        };
+        match with_client(|client| client.add_item(mime_data.clone(), false, false)) {
+            Ok(id) => {
+                info!("Added dropped content as item {id}");
+                reload_history(&history_state);
+                refresh_stats(&history_state);
+                select_first_row(&list_view);
+                true
+            }
+            Err(e) => {
+                error!("Error adding dropped content: {e}");
+                false
+            }
         }
+    });
+    target
+}
 
-        .clipboard-item:selected {
-            border-color: #3584E4;
-            border-color: @accent_bg_color;
-            background: alpha(#3584E4, 0.18);
-            background: alpha(@accent_bg_color, 0.18);
-        }
-
-        .clipboard-preview {
-            opacity: 0.9;
-            color: @window_fg_color;
-        }
-
-        .clipboard-preview.monospace {
-            font-family: monospace;
-        }
-
-        .clipboard-time {
-            font-size: 0.8em;
-            opacity: 0.6;
-            color: @window_fg_color;
-        }
-
-        .clipboard-delete {
-            color: #bfc3c7;
-            color: alpha(@window_fg_color, 0.75);
-            padding: 2px 4px;
-        }
-
-        .clipboard-pin {
-            color: #bfc3c7;
-            color: alpha(@window_fg_color, 0.75);
-            padding: 2px 4px;
+/// Escape in the capture phase, so it closes the overlay (or leaves the search field)
+/// even when the focused widget would consume it.
+fn generate_escape_controller(
+    list_view: &gtk4::ListView,
+    history_state: &HistoryListState,
+    search_entry: &SearchEntry,
+    search_revealer: &Revealer,
+) -> gtk4::EventControllerKey {
+    let controller = gtk4::EventControllerKey::new();
+    controller.set_propagation_phase(gtk4::PropagationPhase::Capture);
+    let list_view = list_view.clone();
+    let history_state = history_state.clone();
+    let search_entry = search_entry.clone();
+    let search_revealer = search_revealer.clone();
+    controller.connect_key_pressed(move |_, key, _, _| {
+        // Detail and cheatsheet pages go back to the list on Escape instead
+        if key != gtk4::gdk::Key::Escape
+            || history_state.detail_stack.visible_child_name().as_deref() != Some(LIST_PAGE)
+        {
+            return gtk4::glib::Propagation::Proceed;
         }
-
-        .clipboard-item:hover .clipboard-delete,
-        .clipboard-delete:hover {
-            color: #ffffff;
-            color: @window_fg_color;
+        if search_revealer.is_child_revealed() && search_entry.has_focus() {
+            focus_list(&list_view);
+        } else if !history_state.merge.marked.borrow().is_empty() {
+            clear_marked(&history_state);
+        } else {
+            request_quit();
         }
+        gtk4::glib::Propagation::Stop
+    });
+    controller
+}
 
-        .clipboard-item:hover .clipboard-pin {
-            color: #ffffff;
-            color: @window_fg_color;
+/// The mouse wheel over the header switches to the next or previous tab. Only discrete
+/// steps count, so touchpad scrolling doesn't race through the tabs.
+fn generate_tab_scroll_controller(history_state: &HistoryListState) -> gtk4::EventControllerScroll {
+    let controller = gtk4::EventControllerScroll::new(
+        gtk4::EventControllerScrollFlags::VERTICAL | gtk4::EventControllerScrollFlags::DISCRETE,
+    );
+    let history_state = history_state.clone();
+    controller.connect_scroll(move |_, _, dy| {
+        if dy == 0.0 {
+            return gtk4::glib::Propagation::Proceed;
         }
+        cycle_tab(&history_state, dy > 0.0);
+        gtk4::glib::Propagation::Stop
+    });
+    controller
+}
 
-        .clipboard-pin:hover {
-            color: #ffffff;
-            color: @window_fg_color;
+/// Ctrl+scroll over the list zooms preview and caption text; plain scrolling is left untouched
+fn generate_zoom_controller(
+    config_state: &Rc<RefCell<UserConfig>>,
+    text_scale_provider: &gtk4::CssProvider,
+) -> gtk4::EventControllerScroll {
+    let controller = gtk4::EventControllerScroll::new(gtk4::EventControllerScrollFlags::VERTICAL);
+    let config_for_zoom = config_state.clone();
+    let provider_for_zoom = text_scale_provider.clone();
+    controller.connect_scroll(move |controller, _, dy| {
+        if !controller
+            .current_event_state()
+            .contains(gtk4::gdk::ModifierType::CONTROL_MASK)
+            || dy == 0.0
+        {
+            return gtk4::glib::Propagation::Proceed;
         }
 
-        .clipboard-pin.pinned {
-            color: #ffffff;
-            color: @accent_color;
+        let mut config = config_for_zoom.borrow_mut();
+        let step = if dy < 0.0 {
+            TEXT_SCALE_STEP
+        } else {
+            -TEXT_SCALE_STEP
+        };
+        let text_scale = clamp_text_scale(config.text_scale + step);
+        if (text_scale - config.text_scale).abs() > f64::EPSILON {
+            config.text_scale = text_scale;
+            load_text_scale_css(&provider_for_zoom, text_scale);
+            if let Err(e) = save_config(&config) {
+                warn!("Failed to save config: {}", e);
+            }
         }
+        gtk4::glib::Propagation::Stop
+    });
+    controller
+}
 
-        .manual-close-button {
-            min-width: 28px;
-            min-height: 28px;
-            padding: 0;
-            background: transparent;
-            box-shadow: none;
-        }
+fn clamp_text_scale(scale: f64) -> f64 {
+    if scale.is_finite() {
+        // Round to the step grid so repeated zooming doesn't accumulate float drift
+        ((scale / TEXT_SCALE_STEP).round() * TEXT_SCALE_STEP).clamp(MIN_TEXT_SCALE, MAX_TEXT_SCALE)
+    } else {
+        1.0
+    }
+}
 
-        .manual-close-button:hover,
-        .manual-close-button:active {
-            background: transparent;
-            box-shadow: none;
-        }
+/// Separate provider so zooming only reloads the font-size rule, not the whole theme
+fn create_text_scale_provider(text_scale: f64) -> gtk4::CssProvider {
+    let provider = gtk4::CssProvider::new();
+    load_text_scale_css(&provider, clamp_text_scale(text_scale));
+    if let Some(display) = gtk4::gdk::Display::default() {
+        gtk4::style_context_add_provider_for_display(
+            &display,
+            &provider,
+            gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION + 1,
+        );
+    }
+    provider
+}
 
-        .manual-close-icon {
-            min-width: 28px;
-            min-height: 28px;
-            border-radius: 999px;
-            background: #343437;
-            background: @card_bg_color;
-        }
+fn load_text_scale_css(css_provider: &gtk4::CssProvider, text_scale: f64) {
+    // Child labels use relative sizes (em / caption), so scaling the row scales all of them
+    css_provider.load_from_data(&format!(
+        ".clipboard-list > row {{ font-size: {:.0}%; }}",
+        text_scale * 100.0
+    ));
+}
 
-        .manual-close-icon image {
-            color: #f4f5f6;
-            color: @window_fg_color;
-        }
+/// Apply custom CSS styling for modern GNOME-style rounded window
+fn apply_custom_styling(window: &adw::ApplicationWindow, accent_color: Option<&str>) {
+    let css_provider = gtk4::CssProvider::new();
+    let display = gtk4::prelude::WidgetExt::display(window);
 
-        .manual-close-button:hover .manual-close-icon {
-            background: shade(#343437, 1.12);
-            background: mix(@card_bg_color, @window_fg_color, 0.12);
-        }
+    // Colors come from libadwaita's named variables, so the overlay follows the
+    // light/dark switch of the style manager without reloading
+    css_provider.load_from_data(&format!("{}{}", accent_css(accent_color), OVERLAY_CSS));
 
-        .manual-close-button:hover .manual-close-icon image {
-            color: #ffffff;
-            color: @window_fg_color;
-        }
+    gtk4::style_context_add_provider_for_display(
+        &display,
+        &css_provider,
+        gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION,
+    );
+}
 
-        .manual-close-button:active .manual-close-icon {
-            background: shade(#343437, 0.92);
-            background: mix(@card_bg_color, @window_fg_color, 0.04);
+/// Override libadwaita's accent variables with a user-configured color.
+fn accent_css(accent_color: Option<&str>) -> String {
+    let Some(accent_color) = accent_color
+        .map(str::trim)
+        .filter(|color| !color.is_empty())
+    else {
+        return String::new();
+    };
+    match gtk4::gdk::RGBA::parse(accent_color) {
+        Ok(rgba) => format!(
+            "@define-color accent_bg_color {rgba};\n\
+             @define-color accent_color {rgba};\n\
+             @define-color accent_fg_color #ffffff;\n"
+        ),
+        Err(_) => {
+            warn!("Ignoring invalid accent_color {accent_color:?} in config");
+            String::new()
         }
+    }
+}
 
-        .compact-header-action {
-            min-width: 28px;
-            min-height: 28px;
-            padding-left: 0;
-            padding-right: 0;
-        }
+const OVERLAY_CSS: &str = "
+window {
+    border-radius: 12px;
+    background: @window_bg_color;
+    color: @window_fg_color;
+    border: 1px solid alpha(@window_fg_color, 0.10);
+    box-shadow: 0 10px 30px alpha(@shade_color, 0.6);
+    transition: opacity 160ms ease-out, transform 160ms ease-out;
+}
 
-        .menu-revealer {
-            background: #2b2b2f;
-            background: @popover_bg_color;
-            border: 1px solid alpha(#ffffff, 0.10);
-            border: 1px solid alpha(@popover_fg_color, 0.10);
-            border-radius: 8px;
-            padding: 6px 8px;
-            color: @popover_fg_color;
-        }
-        "
-    } else {
-        "
-        window {
-            border-radius: 12px;
-            background: #f6f7f9;
-            background: @window_bg_color;
-            color: @window_fg_color;
-            border: 1px solid alpha(#000000, 0.10);
-            border: 1px solid alpha(@window_fg_color, 0.10);
-            box-shadow: 0 10px 30px alpha(#000000, 0.12);
-        }
+/* Removed on the first frame, so the overlay fades and slides in */
+window.overlay-entering {
+    opacity: 0;
+    transform: translateY(-8px);
+}
 
-        headerbar {
-            background: transparent;
-            box-shadow: none;
-        }
+headerbar {
+    background: transparent;
+    box-shadow: none;
+}
 
-        .clipboard-list {
-            background: transparent;
-        }
+.clipboard-list {
+    background: transparent;
+}
 
-        .clipboard-item {
-            background: #ffffff;
-            background: @card_bg_color;
-            border: 2px solid transparent;
-            border-radius: 10px;
-            padding: 4px 4px;
-            margin: 6px 12px;
-            transition: border-color 150ms ease, box-shadow 150ms ease, background 150ms ease;
-        }
+.clipboard-list > row {
+    background: @card_bg_color;
+    border: 2px solid transparent;
+    border-radius: 10px;
+    padding: 4px 4px;
+    margin: 6px 12px;
+    transition: border-color 150ms ease, box-shadow 150ms ease, background 150ms ease;
+}
 
-        .clipboard-item:hover {
-            border-color: #1c71d8;
-            border-color: @accent_bg_color;
-            background: shade(#ffffff, 0.96);
-            background: mix(@card_bg_color, @window_fg_color, 0.04);
-        }
+.clipboard-list.compact > row {
+    padding: 0 4px;
+    margin: 2px 8px;
+}
 
-        .clipboard-item:selected {
-            border-color: #1c71d8;
-            border-color: @accent_bg_color;
-            background: alpha(#1c71d8, 0.12);
-            background: alpha(@accent_bg_color, 0.12);
-        }
+.resize-grip {
+    min-width: 14px;
+    min-height: 14px;
+    margin: 2px;
+    border-radius: 0 0 10px 0;
+    background: linear-gradient(135deg, transparent 50%, alpha(@window_fg_color, 0.25) 50%);
+}
 
-        .clipboard-preview {
-            opacity: 0.9;
-            color: @window_fg_color;
-        }
+.clipboard-editor {
+    border-radius: 8px;
+    background: @card_bg_color;
+}
 
-        .clipboard-preview.monospace {
-            font-family: monospace;
-        }
+.quick-pick-badge {
+    min-width: 16px;
+    padding: 0 4px;
+    border-radius: 6px;
+    font-weight: bold;
+    background: alpha(@accent_bg_color, 0.2);
+    color: @accent_color;
+}
 
-        .clipboard-time {
-            font-size: 0.8em;
-            opacity: 0.6;
-            color: @window_fg_color;
-        }
+.clipboard-list > row:hover {
+    border-color: @accent_bg_color;
+    background: mix(@card_bg_color, @window_fg_color, 0.06);
+}
 
-        .clipboard-delete {
-            color: #5e6268;
-            color: alpha(@window_fg_color, 0.7);
-            padding: 2px 4px;
-        }
+.clipboard-list > row:selected {
+    border-color: @accent_bg_color;
+    background: alpha(@accent_bg_color, 0.15);
+}
 
-        .clipboard-pin {
-            color: #5e6268;
-            color: alpha(@window_fg_color, 0.7);
-            padding: 2px 4px;
-        }
+.clipboard-preview {
+    opacity: 0.9;
+    color: @window_fg_color;
+}
 
-        .clipboard-item:hover .clipboard-delete,
-        .clipboard-delete:hover {
-            color: #1f2328;
-            color: @window_fg_color;
-        }
+.clipboard-preview.monospace {
+    font-family: monospace;
+}
 
-        .clipboard-item:hover .clipboard-pin {
-            color: #1f2328;
-            color: @window_fg_color;
-        }
+.clipboard-time {
+    font-size: 0.8em;
+    opacity: 0.6;
+    color: @window_fg_color;
+}
 
-        .clipboard-pin:hover {
-            color: #1f2328;
-            color: @window_fg_color;
-        }
+.clipboard-delete,
+.clipboard-pin {
+    color: alpha(@window_fg_color, 0.7);
+    padding: 2px 4px;
+}
 
-        .clipboard-pin.pinned {
-            color: #1f2328;
-            color: @accent_color;
-        }
+.clipboard-list > row:hover .clipboard-delete,
+.clipboard-delete:hover,
+.clipboard-list > row:hover .clipboard-pin,
+.clipboard-pin:hover {
+    color: @window_fg_color;
+}
 
-        .manual-close-button {
-            min-width: 28px;
-            min-height: 28px;
-            padding: 0;
-            background: transparent;
-            box-shadow: none;
-        }
+.clipboard-pin.pinned {
+    color: @accent_color;
+}
 
-        .manual-close-button:hover,
-        .manual-close-button:active {
-            background: transparent;
-            box-shadow: none;
-        }
+.manual-close-button {
+    min-width: 28px;
+    min-height: 28px;
+    padding: 0;
+    background: transparent;
+    box-shadow: none;
+}
 
-        .manual-close-icon {
-            min-width: 28px;
-            min-height: 28px;
-            border-radius: 999px;
-            background: #ffffff;
-            background: @card_bg_color;
-        }
+.manual-close-button:hover,
+.manual-close-button:active {
+    background: transparent;
+    box-shadow: none;
+}
 
-        .manual-close-icon image {
-            color: #1f2328;
-            color: @window_fg_color;
-        }
+.manual-close-icon {
+    min-width: 28px;
+    min-height: 28px;
+    border-radius: 999px;
+    background: @card_bg_color;
+}
 
-        .manual-close-button:hover .manual-close-icon {
-            background: shade(#ffffff, 0.92);
-            background: mix(@card_bg_color, @window_fg_color, 0.08);
-        }
+.manual-close-icon image {
+    color: @window_fg_color;
+}
 
-        .manual-close-button:hover .manual-close-icon image {
-            color: #111318;
-            color: @window_fg_color;
-        }
+.manual-close-button:hover .manual-close-icon {
+    background: mix(@card_bg_color, @window_fg_color, 0.10);
+}
 
-        .manual-close-button:active .manual-close-icon {
-            background: shade(#ffffff, 0.86);
-            background: mix(@card_bg_color, @window_fg_color, 0.12);
-        }
+.manual-close-button:active .manual-close-icon {
+    background: mix(@card_bg_color, @window_fg_color, 0.16);
+}
 
-        .compact-header-action {
-            min-width: 28px;
-            min-height: 28px;
-            padding-left: 0;
-            padding-right: 0;
-        }
+.compact-header-action {
+    min-width: 28px;
+    min-height: 28px;
+    padding-left: 0;
+    padding-right: 0;
+}
 
-        .menu-revealer {
-            background: #ffffff;
-            background: @popover_bg_color;
-            border: 1px solid alpha(#000000, 0.10);
-            border: 1px solid alpha(@popover_fg_color, 0.10);
-            border-radius: 8px;
-            padding: 6px 8px;
-            box-shadow: 0 2px 8px alpha(#000000, 0.10);
-            color: @popover_fg_color;
-        }
-        "
-    });
+.menu-revealer {
+    background: @popover_bg_color;
+    border: 1px solid alpha(@popover_fg_color, 0.10);
+    border-radius: 8px;
+    padding: 6px 8px;
+    box-shadow: 0 2px 8px alpha(@shade_color, 0.5);
+    color: @popover_fg_color;
 }
+";
 
-/// Create a clipboard history item row from backend data
-fn generate_listboxrow_from_preview(
+/// Create the widgets of a clipboard history item row from backend data
+fn build_item_row(
     item: &ClipboardItemPreview,
-    list_box: &gtk4::ListBox,
+    list_item: &gtk4::ListItem,
+    list_view: &gtk4::ListView,
     history_state: &HistoryListState,
-    show_trash: bool,
-    show_pin: bool,
-) -> gtk4::ListBoxRow {
-    let row = gtk4::ListBoxRow::new();
-    row.add_css_class("clipboard-item");
+) -> Box {
+    let show_trash = *history_state.show_trash.borrow();
+    let show_pin = *history_state.show_pin.borrow();
 
     let main_box = Box::new(Orientation::Vertical, 6);
     main_box.set_margin_top(8);
@@ -1184,6 +2671,9 @@ fn generate_listboxrow_from_preview(
 
     // Header with content type and time
     let header_box = Box::new(Orientation::Horizontal, 8);
+    if history_state.layout.badges {
+        header_box.append(&build_quick_pick_badge(list_item));
+    }
 
     let type_label = Label::new(Some(item.content_type.icon()));
     type_label.add_css_class("caption");
@@ -1192,11 +2682,25 @@ fn generate_listboxrow_from_preview(
     type_text.add_css_class("caption");
     type_text.set_halign(Align::Start);
     type_text.set_hexpand(true);
+    if let Some(source_id) = item.derived_from {
+        type_text.set_tooltip_text(Some(&format!("Transformed from item #{source_id}")));
+    }
 
-    let time_label = Label::new(Some(&format_timestamp(item.timestamp)));
+    let copied_at = format_timestamp(item.last_copied.unwrap_or(item.timestamp));
+    let mut time_text = match &item.source_app {
+        Some(app_id) => format!("from {} · {copied_at}", app_display_name(app_id)),
+        None => copied_at,
+    };
+    if item.times_copied > 1 {
+        time_text = format!("copied {}× · {time_text}", item.times_copied);
+    }
+    let time_label = Label::new(Some(&time_text));
     time_label.add_css_class("caption");
     time_label.add_css_class("clipboard-time");
     time_label.set_halign(Align::End);
+    if let Some(app_id) = &item.source_app {
+        time_label.set_tooltip_text(Some(&format!("Copied from {app_id}")));
+    }
 
     let pin_button = Button::builder().icon_name("view-pin-symbolic").build();
     pin_button.add_css_class("flat");
@@ -1209,6 +2713,43 @@ fn generate_listboxrow_from_preview(
     }
     pin_button.set_visible(show_pin);
 
+    // Images have nothing to paste as plain text
+    let plain_paste_button = Button::builder().icon_name("edit-paste-symbolic").build();
+    plain_paste_button.add_css_class("flat");
+    plain_paste_button.add_css_class("clipboard-plain-paste");
+    plain_paste_button.set_tooltip_text(Some("Paste as plain text (Shift+Enter)"));
+    let is_file_copy = !item.files.is_empty();
+    plain_paste_button
+        .set_visible(item.content_type != ClipboardContentType::Image && !is_file_copy);
+
+    let copy_path_button = Button::builder().icon_name("edit-copy-symbolic").build();
+    copy_path_button.add_css_class("flat");
+    copy_path_button.set_tooltip_text(Some("Copy path as text"));
+    copy_path_button.set_visible(is_file_copy);
+
+    let open_folder_button = Button::builder().icon_name("folder-open-symbolic").build();
+    open_folder_button.add_css_class("flat");
+    open_folder_button.set_tooltip_text(Some("Open containing folder"));
+    open_folder_button.set_visible(is_file_copy);
+
+    let transform_box = Box::new(Orientation::Vertical, 0);
+    let transform_popover = gtk4::Popover::new();
+    transform_popover.set_child(Some(&transform_box));
+    let transform_button = gtk4::MenuButton::builder()
+        .icon_name("format-text-rich-symbolic")
+        .popover(&transform_popover)
+        .build();
+    transform_button.add_css_class("flat");
+    transform_button.add_css_class("clipboard-transform");
+    transform_button.set_tooltip_text(Some("Transform and paste"));
+    transform_button.set_visible(item.content_type != ClipboardContentType::Image && !is_file_copy);
+
+    let details_button = Button::builder()
+        .icon_name("dialog-information-symbolic")
+        .build();
+    details_button.add_css_class("flat");
+    details_button.set_tooltip_text(Some("Details"));
+
     let delete_button = Button::builder().icon_name("user-trash-symbolic").build();
     delete_button.add_css_class("flat");
     delete_button.add_css_class("destructive-action");
@@ -1216,9 +2757,38 @@ fn generate_listboxrow_from_preview(
     delete_button.set_tooltip_text(Some("Delete item"));
     delete_button.set_visible(show_trash);
 
+    // Shown while items are picked for Append & paste
+    let mergeable = is_mergeable(item);
+    let merge_check = CheckButton::new();
+    merge_check.set_tooltip_text(Some("Select for Append & paste"));
+    {
+        let marked = history_state.merge.marked.borrow();
+        merge_check.set_visible(mergeable && !marked.is_empty());
+        merge_check.set_active(marked.contains(&item.item_id));
+    }
+
+    header_box.append(&merge_check);
     header_box.append(&type_label);
     header_box.append(&type_text);
+    if let Some(position) = history_state
+        .paste_queue
+        .ids
+        .borrow()
+        .iter()
+        .position(|id| *id == item.item_id)
+    {
+        let queued_label = Label::new(Some(&format!("Queued #{}", position + 1)));
+        queued_label.add_css_class("caption");
+        queued_label.add_css_class("accent");
+        queued_label.set_tooltip_text(Some("Position in the paste queue"));
+        header_box.append(&queued_label);
+    }
     let action_box = Box::new(Orientation::Horizontal, 0);
+    action_box.append(&plain_paste_button);
+    action_box.append(&copy_path_button);
+    action_box.append(&open_folder_button);
+    action_box.append(&transform_button);
+    action_box.append(&details_button);
     action_box.append(&pin_button);
     action_box.append(&delete_button);
 
@@ -1226,6 +2796,9 @@ fn generate_listboxrow_from_preview(
     header_box.append(&action_box);
 
     main_box.append(&header_box);
+    if history_state.layout.tooltips {
+        attach_row_tooltip(&main_box, item, history_state);
+    }
 
     let rendered_image = item.thumbnail.as_ref().and_then(|bytes| {
         let gbytes = glib::Bytes::from(bytes);
@@ -1236,12 +2809,35 @@ fn generate_listboxrow_from_preview(
         let picture = gtk4::Picture::for_paintable(&texture);
         picture.set_can_shrink(true);
         picture.set_hexpand(true);
-        picture.set_height_request(180);
+        picture.set_height_request(if history_state.layout.compact {
+            48
+        } else {
+            180
+        });
         picture.set_halign(gtk4::Align::Center);
         picture.add_css_class("clipboard-preview");
         main_box.append(&picture);
+
+        if let Some(image_info) = &item.image_info {
+            let image_info_label = Label::new(Some(&image_info.describe()));
+            image_info_label.add_css_class("caption");
+            image_info_label.add_css_class("clipboard-time");
+            image_info_label.set_halign(Align::Start);
+            main_box.append(&image_info_label);
+        }
+    } else if is_file_copy {
+        main_box.append(&build_file_list(&item.files));
     } else {
-        let content_label = Label::new(Some(&item.content_preview));
+        let content_label = Label::new(None);
+        match history_state.highlights.borrow().get(&item.item_id) {
+            Some(ranges) if !ranges.is_empty() => {
+                content_label.set_markup(&highlighted_markup(&item.content_preview, ranges));
+            }
+            _ => match code_markup(item, &item.content_preview) {
+                Some(markup) => content_label.set_markup(&markup),
+                None => content_label.set_text(&item.content_preview),
+            },
+        }
         content_label.add_css_class("clipboard-preview");
         if matches!(
             item.content_type,
@@ -1250,271 +2846,1874 @@ fn generate_listboxrow_from_preview(
             content_label.add_css_class("monospace");
         }
         content_label.set_halign(Align::Start);
-        content_label.set_wrap(true);
-        content_label.set_wrap_mode(gtk4::pango::WrapMode::WordChar);
+        if history_state.layout.compact {
+            content_label.set_single_line_mode(true);
+        } else {
+            content_label.set_wrap(true);
+            content_label.set_wrap_mode(gtk4::pango::WrapMode::WordChar);
+            content_label.set_lines(history_state.layout.preview_lines);
+        }
         content_label.set_max_width_chars(50);
-        content_label.set_lines(3);
         content_label.set_ellipsize(gtk4::pango::EllipsizeMode::End);
         main_box.append(&content_label);
     }
 
-    row.set_child(Some(&main_box));
-
-    let list_box = list_box.clone();
+    let list_view = list_view.clone();
     let history_state = history_state.clone();
     let item_id = item.item_id;
-    let list_box_for_delete = list_box.clone();
+    let list_view_for_delete = list_view.clone();
     let history_state_for_delete = history_state.clone();
     delete_button.connect_clicked(move |_| {
-        match FrontendClient::new() {
-            Ok(mut client) => {
-                if let Err(e) = client.delete_item_by_id(item_id) {
-                    error!("Error deleting clipboard item by ID: {}", e);
-                    return;
-                }
-            }
-            Err(e) => {
-                error!("Error creating frontend client: {}", e);
-                return;
-            }
-        }
-
+        delete_item(&list_view_for_delete, &history_state_for_delete, item_id);
+    });
+    let history_state_for_plain_paste = history_state.clone();
+    let seat = item.seat.clone();
+    plain_paste_button.connect_clicked(move |_| {
+        paste_item(
+            &history_state_for_plain_paste,
+            item_id,
+            seat.clone(),
+            Some(MimeFilter::PlainText),
+        );
+    });
+    let list_view_for_details = list_view.clone();
+    let history_state_for_details = history_state.clone();
+    details_button.connect_clicked(move |_| {
+        show_item_details(&list_view_for_details, &history_state_for_details, item_id);
+    });
+    let right_click = gtk4::GestureClick::new();
+    right_click.set_button(gtk4::gdk::BUTTON_SECONDARY);
+    let list_view_for_right_click = list_view.clone();
+    let history_state_for_right_click = history_state.clone();
+    let menu_item = item.clone();
+    right_click.connect_pressed(move |gesture, _, x, y| {
+        if history_state_for_right_click.gestures.right_click_menu
+            && let Some(row) = gesture.widget()
         {
-            let mut items = history_state_for_delete.all_items.borrow_mut();
-            if let Some(index) = items.iter().position(|entry| entry.item_id == item_id) {
-                items.remove(index);
-            }
+            show_row_menu(
+                &row,
+                (x, y),
+                &list_view_for_right_click,
+                &history_state_for_right_click,
+                &menu_item,
+            );
+        } else {
+            show_item_details(
+                &list_view_for_right_click,
+                &history_state_for_right_click,
+                item_id,
+            );
+        }
+    });
+    main_box.add_controller(right_click);
+    if history_state.gestures.middle_click_primary {
+        let middle_click = gtk4::GestureClick::new();
+        middle_click.set_button(gtk4::gdk::BUTTON_MIDDLE);
+        middle_click.connect_pressed(move |_, _, _, _| set_primary(item_id));
+        main_box.add_controller(middle_click);
+    }
+    let drag_source = gtk4::DragSource::new();
+    drag_source.set_actions(gtk4::gdk::DragAction::COPY);
+    drag_source.set_content(Some(&ItemContentProvider::new(item)));
+    drag_source.connect_drag_begin(|source, drag| {
+        if let Some(row) = source.widget() {
+            source.set_icon(Some(&gtk4::WidgetPaintable::new(Some(&row))), 0, 0);
+        }
+        // Like a paste, a completed drop is done with the overlay
+        drag.connect_dnd_finished(|_| request_quit());
+    });
+    main_box.add_controller(drag_source);
+    let history_state_for_copy_path = history_state.clone();
+    let seat = item.seat.clone();
+    copy_path_button.connect_clicked(move |_| {
+        paste_item(
+            &history_state_for_copy_path,
+            item_id,
+            seat.clone(),
+            Some(MimeFilter::PathsAsText),
+        );
+    });
+    let first_file = item.files.first().cloned();
+    open_folder_button.connect_clicked(move |_| {
+        if let Some(path) = &first_file {
+            open_containing_folder(path);
         }
-
-        rebuild_list(&list_box_for_delete, &history_state_for_delete);
-        select_first_row(&list_box_for_delete);
     });
-    let list_box_for_pin = list_box.clone();
+    for transform in TextTransform::ALL {
+        let transform_action = Button::with_label(transform.label());
+        transform_action.add_css_class("flat");
+        let history_state = history_state.clone();
+        let seat = item.seat.clone();
+        let popover = transform_popover.clone();
+        transform_action.connect_clicked(move |_| {
+            popover.popdown();
+            transform_and_paste(&history_state, item_id, transform, seat.clone());
+        });
+        transform_box.append(&transform_action);
+    }
+    let list_view_for_pin = list_view.clone();
     let history_state_for_pin = history_state.clone();
     pin_button.connect_clicked(move |_| {
-        let Some(pinned) = next_pinned_state(&history_state_for_pin, item_id) else {
-            return;
-        };
-
-        match FrontendClient::new() {
-            Ok(mut client) => {
-                if let Err(e) = client.set_pinned(item_id, pinned) {
-                    error!("Error updating pinned state: {}", e);
-                    return;
-                }
-            }
-            Err(e) => {
-                error!("Error creating frontend client: {}", e);
-                return;
-            }
-        }
-
-        apply_pinned_state(&history_state_for_pin, item_id, pinned);
-        rebuild_list(&list_box_for_pin, &history_state_for_pin);
-        select_row_by_item_id(&list_box_for_pin, &history_state_for_pin, item_id);
-        debug!("Updated pinned state for clipboard item ID {}", item_id);
+        toggle_pinned(&list_view_for_pin, &history_state_for_pin, item_id);
     });
-    row
+    let history_state_for_merge = history_state.clone();
+    merge_check.connect_toggled(move |_| {
+        toggle_marked(&history_state_for_merge, item_id);
+    });
+    if mergeable {
+        // Claimed in the capture phase so Ctrl+click doesn't also paste the item
+        let ctrl_click = gtk4::GestureClick::new();
+        ctrl_click.set_propagation_phase(gtk4::PropagationPhase::Capture);
+        ctrl_click.connect_pressed(move |gesture, _, _, _| {
+            if gesture
+                .current_event_state()
+                .contains(gtk4::gdk::ModifierType::CONTROL_MASK)
+            {
+                gesture.set_state(gtk4::EventSequenceState::Claimed);
+                toggle_marked(&history_state, item_id);
+            }
+        });
+        main_box.add_controller(ctrl_click);
+    }
+    main_box
 }
 
-fn rebuild_list(list_box: &gtk4::ListBox, history_state: &HistoryListState) {
-    while let Some(child) = list_box.first_child() {
-        list_box.remove(&child);
+/// Syntax-highlighted markup for the text of a Code item, in the current light/dark style.
+fn code_markup(item: &ClipboardItemPreview, text: &str) -> Option<String> {
+    if item.content_type != ClipboardContentType::Code || text.len() > MAX_HIGHLIGHTED_BYTES {
+        return None;
     }
+    let language = item.language.as_deref()?;
+    highlighted_code_markup(text, language, adw::StyleManager::default().is_dark())
+}
 
-    let query = history_state.search_query.borrow().trim().to_lowercase();
-    let filtered_items: Vec<ClipboardItemPreview> = history_state
-        .all_items
-        .borrow()
+/// Whether an item has text that Append & paste can join.
+fn is_mergeable(item: &ClipboardItemPreview) -> bool {
+    item.content_type != ClipboardContentType::Image && item.files.is_empty()
+}
+
+/// The bar under the list with the Append & paste action; hidden until an item is picked.
+fn build_merge_bar(config_state: &Rc<RefCell<UserConfig>>) -> MergeSelection {
+    let separator = config_state.borrow().merge_separator;
+
+    let count_label = Label::new(None);
+    count_label.add_css_class("caption");
+    count_label.set_halign(Align::Start);
+    count_label.set_hexpand(true);
+
+    let separator_labels: Vec<&str> = MergeSeparator::ALL
         .iter()
-        .filter(|item| item_matches_query(item, &query))
-        .cloned()
+        .map(|separator| separator.label())
         .collect();
+    let separator_dropdown = gtk4::DropDown::from_strings(&separator_labels);
+    separator_dropdown.set_tooltip_text(Some("Separator"));
+    separator_dropdown.set_selected(
+        MergeSeparator::ALL
+            .iter()
+            .position(|candidate| *candidate == separator)
+            .unwrap_or(0) as u32,
+    );
+
+    let paste_button = Button::with_label("Append & paste");
+    paste_button.add_css_class("suggested-action");
+    paste_button.set_tooltip_text(Some(
+        "Join the selected items into a new entry (Ctrl+Enter)",
+    ));
+    let queue_button = Button::with_label("Queue");
+    queue_button.set_tooltip_text(Some(
+        "Paste the selected items one after another: each paste moves on to the next",
+    ));
+    let clear_button = Button::builder().icon_name("edit-clear-symbolic").build();
+    clear_button.add_css_class("flat");
+    clear_button.set_tooltip_text(Some("Clear selection (Esc)"));
+
+    let bar_box = Box::new(Orientation::Horizontal, 6);
+    bar_box.set_margin_top(6);
+    bar_box.set_margin_bottom(6);
+    bar_box.set_margin_start(12);
+    bar_box.set_margin_end(12);
+    bar_box.append(&count_label);
+    bar_box.append(&separator_dropdown);
+    bar_box.append(&queue_button);
+    bar_box.append(&paste_button);
+    bar_box.append(&clear_button);
+
+    let bar = Revealer::new();
+    bar.set_reveal_child(false);
+    bar.set_visible(false);
+    bar.set_transition_duration(120);
+    bar.set_transition_type(gtk4::RevealerTransitionType::SlideUp);
+    bar.set_child(Some(&bar_box));
+
+    let merge = MergeSelection {
+        marked: Rc::new(RefCell::new(Vec::new())),
+        separator: Rc::new(RefCell::new(separator)),
+        bar,
+        count_label,
+        paste_button,
+        queue_button,
+        clear_button,
+    };
+
+    let separator_state = merge.separator.clone();
+    let config_for_separator = config_state.clone();
+    separator_dropdown.connect_selected_notify(move |dropdown| {
+        let Some(separator) = MergeSeparator::ALL
+            .get(dropdown.selected() as usize)
+            .copied()
+        else {
+            return;
+        };
+        *separator_state.borrow_mut() = separator;
+        let mut config = config_for_separator.borrow_mut();
+        config.merge_separator = separator;
+        if let Err(e) = save_config(&config) {
+            warn!("Failed to save config: {}", e);
+        }
+    });
+    merge
+}
 
+/// Pick or unpick an item for Append & paste.
+fn toggle_marked(history_state: &HistoryListState, item_id: u64) {
     {
-        let mut visible_items = history_state.items.borrow_mut();
-        *visible_items = filtered_items;
+        let mut marked = history_state.merge.marked.borrow_mut();
+        match marked.iter().position(|id| *id == item_id) {
+            Some(index) => {
+                marked.remove(index);
+            }
+            None => marked.push(item_id),
+        }
     }
+    update_merge_bar(history_state);
+}
 
-    let show_trash = *history_state.show_trash.borrow();
-    let show_pin = *history_state.show_pin.borrow();
-    for item in history_state.items.borrow().iter() {
-        let row =
-            generate_listboxrow_from_preview(item, list_box, history_state, show_trash, show_pin);
-        list_box.append(&row);
-    }
+fn clear_marked(history_state: &HistoryListState) {
+    history_state.merge.marked.borrow_mut().clear();
+    update_merge_bar(history_state);
+}
 
-    if history_state.items.borrow().is_empty() {
-        list_box.append(&make_placeholder_row_with_message(if query.is_empty() {
-            "No clipboard history yet"
-        } else {
-            "No matches found"
-        }));
-    }
+/// Sync the bar and the row checkboxes with the picked items.
+fn update_merge_bar(history_state: &HistoryListState) {
+    let merge = &history_state.merge;
+    let count = merge.marked.borrow().len();
+    merge.count_label.set_text(&format!("{count} selected"));
+    merge.paste_button.set_sensitive(count >= 2);
+    merge.queue_button.set_sensitive(count >= 1);
+    merge.bar.set_visible(count > 0);
+    merge.bar.set_reveal_child(count > 0);
+    // Rows may be rebuilt from one of their own signal handlers; rebind once that returns
+    let history_state = history_state.clone();
+    gtk4::glib::idle_add_local_once(move || refresh_rows(&history_state));
 }
 
-fn item_matches_query(item: &ClipboardItemPreview, query: &str) -> bool {
-    if query.is_empty() {
-        return true;
+/// Put the picked items in the paste queue; the first one becomes the selection.
+fn queue_marked(history_state: &HistoryListState) {
+    let ids = history_state.merge.marked.borrow().clone();
+    match with_client(|client| client.push_paste_queue(ids.clone())) {
+        Ok(queue) => {
+            info!("Queued items {ids:?}; {} in the paste queue", queue.len());
+            request_quit();
+        }
+        Err(e) => error!("Error queueing clipboard items: {}", e),
     }
-
-    item.content_preview.to_lowercase().contains(query)
-        || item.content_type.as_str().to_lowercase().contains(query)
 }
 
-fn select_first_row(list_box: &gtk4::ListBox) {
-    select_first_row_with_focus(list_box, true);
+/// The paste queue banner; hidden while the queue is empty.
+fn build_paste_queue_banner() -> PasteQueueBanner {
+    let banner = Box::new(Orientation::Horizontal, 8);
+    banner.add_css_class("card");
+    banner.set_margin_start(12);
+    banner.set_margin_end(12);
+    banner.set_margin_bottom(6);
+    banner.set_visible(false);
+    let label = Label::new(None);
+    label.set_wrap(true);
+    label.set_xalign(0.0);
+    label.set_hexpand(true);
+    label.set_margin_start(10);
+    label.set_margin_top(6);
+    label.set_margin_bottom(6);
+    let paste_next_button = Button::with_label("Paste next");
+    paste_next_button.add_css_class("suggested-action");
+    paste_next_button.set_valign(Align::Center);
+    let skip_button = Button::with_label("Skip");
+    skip_button.add_css_class("flat");
+    skip_button.set_valign(Align::Center);
+    let clear_button = Button::builder().icon_name("window-close-symbolic").build();
+    clear_button.add_css_class("flat");
+    clear_button.set_valign(Align::Center);
+    clear_button.set_margin_end(6);
+    clear_button.set_tooltip_text(Some("Clear the paste queue"));
+    banner.append(&label);
+    banner.append(&paste_next_button);
+    banner.append(&skip_button);
+    banner.append(&clear_button);
+
+    PasteQueueBanner {
+        ids: Rc::new(RefCell::new(Vec::new())),
+        banner,
+        label,
+        paste_next_button,
+        skip_button,
+        clear_button,
+    }
 }
 
-fn select_first_row_without_focus(list_box: &gtk4::ListBox) {
-    select_first_row_with_focus(list_box, false);
+/// Show the daemon's paste queue in the banner and the row badges.
+fn update_paste_queue(history_state: &HistoryListState, ids: Vec<u64>) {
+    let paste_queue = &history_state.paste_queue;
+    paste_queue.label.set_text(&format!(
+        "Paste queue: {} left, each paste moves on to the next",
+        ids.len()
+    ));
+    paste_queue.banner.set_visible(!ids.is_empty());
+    *paste_queue.ids.borrow_mut() = ids;
+    let history_state = history_state.clone();
+    gtk4::glib::idle_add_local_once(move || refresh_rows(&history_state));
 }
 
-fn select_first_row_with_focus(list_box: &gtk4::ListBox, grab_focus: bool) -> bool {
-    if let Some(row) = list_box.row_at_index(0)
-        && row.is_selectable()
-    {
-        list_box.select_row(Some(&row));
-        if grab_focus {
-            row.grab_focus();
+/// Paste the picked items joined into one entry; the daemon records it in the history.
+fn merge_and_paste(history_state: &HistoryListState) {
+    let ids = history_state.merge.marked.borrow().clone();
+    let separator = *history_state.merge.separator.borrow();
+    let instant_paste = *history_state.instant_paste.borrow();
+    debug!("Merging clipboard items {ids:?} ({separator:?})");
+
+    match with_client(|client| client.merge_and_set(ids.clone(), separator, instant_paste, None)) {
+        Ok(item) => {
+            info!("Clipboard set to merged item ID: {}", item.item_id);
+            request_quit();
         }
-        return true;
+        Err(e) => error!("Error merging clipboard items: {}", e),
     }
-    false
 }
 
-fn select_next_row(list_box: &gtk4::ListBox, wrap_to_first: bool) -> bool {
-    if let Some(current) = list_box.selected_row() {
-        let next_index = current.index() + 1;
-        if let Some(next_row) = list_box.row_at_index(next_index) {
-            list_box.select_row(Some(&next_row));
-            next_row.grab_focus();
-            return true;
-        }
-
-        return wrap_to_first && select_first_row_with_focus(list_box, true);
+const LIST_PAGE: &str = "list";
+const DETAIL_PAGE: &str = "detail";
+const CHEATSHEET_PAGE: &str = "cheatsheet";
+const EDIT_PAGE: &str = "edit";
+const PROBLEM_PAGE: &str = "problem";
+/// Pause before subscribing again after the daemon went away
+const HISTORY_FOLLOW_RETRY: std::time::Duration = std::time::Duration::from_secs(2);
+/// How long the Undo toast of a delete or Clear All stays up; the daemon keeps the
+/// items longer (`trash_minutes`)
+const UNDO_TOAST_SECS: u32 = 10;
+
+/// Show the keyboard cheatsheet, built from the keybinding registry on first use.
+fn show_cheatsheet(history_state: &HistoryListState) {
+    let stack = &history_state.detail_stack;
+    if stack.child_by_name(CHEATSHEET_PAGE).is_none() {
+        stack.add_named(&build_cheatsheet_page(), Some(CHEATSHEET_PAGE));
     }
-
-    select_first_row_with_focus(list_box, true)
+    stack.set_visible_child_name(CHEATSHEET_PAGE);
 }
 
-fn select_previous_row(list_box: &gtk4::ListBox, wrap_to_first: bool) -> bool {
-    if let Some(current) = list_box.selected_row() {
-        if current.index() > 0 {
-            let prev_index = current.index() - 1;
-            if let Some(prev_row) = list_box.row_at_index(prev_index) {
-                list_box.select_row(Some(&prev_row));
-                prev_row.grab_focus();
-                return true;
+fn build_cheatsheet_page() -> gtk4::ScrolledWindow {
+    let page = Box::new(Orientation::Vertical, 6);
+    page.set_margin_top(8);
+    page.set_margin_bottom(8);
+    page.set_margin_start(12);
+    page.set_margin_end(12);
+
+    for (section, bindings) in KEYBINDING_SECTIONS {
+        let heading = Label::new(Some(section));
+        heading.add_css_class("heading");
+        heading.set_halign(Align::Start);
+        heading.set_margin_top(6);
+        page.append(&heading);
+
+        for binding in *bindings {
+            let row = Box::new(Orientation::Horizontal, 12);
+            let keys = Box::new(Orientation::Horizontal, 4);
+            match binding.accelerators {
+                // A run of keys (Alt+1 to Alt+9) is shown as its first and last
+                [first, .., last] if binding.accelerators.len() > 3 => {
+                    keys.append(&gtk4::ShortcutLabel::new(first));
+                    keys.append(&Label::new(Some("…")));
+                    keys.append(&gtk4::ShortcutLabel::new(last));
+                }
+                accelerators => {
+                    for accelerator in accelerators {
+                        keys.append(&gtk4::ShortcutLabel::new(accelerator));
+                    }
+                }
+            }
+            let description = Label::new(Some(binding.description));
+            description.set_halign(Align::Start);
+            description.set_hexpand(true);
+            description.set_xalign(0.0);
+            row.append(&description);
+            row.append(&keys);
+            page.append(&row);
+
+            if let Some(cli) = binding.cli {
+                let cli_label = Label::new(Some(cli));
+                cli_label.add_css_class("caption");
+                cli_label.add_css_class("monospace");
+                cli_label.add_css_class("clipboard-time");
+                cli_label.set_selectable(true);
+                cli_label.set_wrap(true);
+                cli_label.set_wrap_mode(gtk4::pango::WrapMode::WordChar);
+                cli_label.set_xalign(0.0);
+                cli_label.set_halign(Align::Start);
+                page.append(&cli_label);
             }
         }
-
-        return wrap_to_first && select_first_row_with_focus(list_box, true);
     }
 
-    select_first_row_with_focus(list_box, true)
+    let hint = Label::new(Some("Right-click an item for details · Esc to go back"));
+    hint.add_css_class("caption");
+    hint.add_css_class("clipboard-time");
+    hint.set_margin_top(8);
+    page.append(&hint);
+
+    let scroller = gtk4::ScrolledWindow::new();
+    scroller.set_policy(gtk4::PolicyType::Never, gtk4::PolicyType::Automatic);
+    scroller.set_vexpand(true);
+    scroller.set_child(Some(&page));
+    scroller
 }
 
-fn select_row_by_item_id(list_box: &gtk4::ListBox, history_state: &HistoryListState, item_id: u64) {
-    let Some(index) = history_state
-        .items
-        .borrow()
-        .iter()
-        .position(|item| item.item_id == item_id)
-    else {
-        select_first_row(list_box);
-        return;
+/// Fetch an item's details from the daemon and show them in place of the list.
+fn show_item_details(list_view: &gtk4::ListView, history_state: &HistoryListState, item_id: u64) {
+    let details = match with_client(|client| client.get_item_details(item_id)) {
+        Ok(details) => details,
+        Err(e) => {
+            error!("Error fetching clipboard item details: {}", e);
+            return;
+        }
     };
 
-    if let Some(row) = list_box.row_at_index(index as i32) {
-        list_box.select_row(Some(&row));
-        row.grab_focus();
+    let stack = &history_state.detail_stack;
+    if let Some(previous) = stack.child_by_name(DETAIL_PAGE) {
+        stack.remove(&previous);
     }
+    let page = build_detail_page(list_view, history_state, &details);
+    stack.add_named(&page, Some(DETAIL_PAGE));
+    stack.set_visible_child_name(DETAIL_PAGE);
 }
 
-fn next_pinned_state(history_state: &HistoryListState, item_id: u64) -> Option<bool> {
-    history_state
-        .all_items
-        .borrow()
-        .iter()
-        .find(|item| item.item_id == item_id)
-        .map(|item| !item.pinned)
+/// Replace the list with a page explaining why the daemon can't serve the overlay, with
+/// buttons to check again and to start the daemon.
+fn show_daemon_problem(
+    list_view: &gtk4::ListView,
+    history_state: &HistoryListState,
+    problem: &CursorClipError,
+) {
+    let stack = &history_state.detail_stack;
+    if let Some(previous) = stack.child_by_name(PROBLEM_PAGE) {
+        stack.remove(&previous);
+    }
+    let page = build_problem_page(list_view, history_state, problem);
+    stack.add_named(&page, Some(PROBLEM_PAGE));
+    stack.set_visible_child_name(PROBLEM_PAGE);
 }
 
-fn apply_pinned_state(history_state: &HistoryListState, item_id: u64, pinned: bool) {
-    let mut items = history_state.all_items.borrow_mut();
-    let Some(index) = items.iter().position(|entry| entry.item_id == item_id) else {
-        return;
+fn build_problem_page(
+    list_view: &gtk4::ListView,
+    history_state: &HistoryListState,
+    problem: &CursorClipError,
+) -> adw::StatusPage {
+    let (icon_name, title, description) = match problem {
+        CursorClipError::DaemonNotRunning => (
+            "network-offline-symbolic",
+            "Clipboard daemon not running",
+            "Start it here, or add <tt>cursor-clip daemon</tt> to your compositor's autostart."
+                .to_string(),
+        ),
+        CursorClipError::ClipboardUnavailable(detail) => (
+            "dialog-warning-symbolic",
+            "Clipboard not available",
+            format!(
+                "{}. GNOME, for one, offers neither. Run <tt>cursor-clip doctor</tt> for details.",
+                gtk4::glib::markup_escape_text(detail)
+            ),
+        ),
+        other => (
+            "dialog-error-symbolic",
+            "Cannot reach the clipboard daemon",
+            gtk4::glib::markup_escape_text(&other.to_string()).to_string(),
+        ),
     };
+    let page = adw::StatusPage::builder()
+        .icon_name(icon_name)
+        .title(title)
+        .description(description)
+        .vexpand(true)
+        .build();
+    page.add_css_class("compact");
+
+    let buttons = Box::new(Orientation::Horizontal, 6);
+    buttons.set_halign(Align::Center);
+    let retry_button = Button::with_label("Retry");
+    retry_button.add_css_class("pill");
+    buttons.append(&retry_button);
+    // Starting a second daemon doesn't help one that can't reach the clipboard
+    let start_button = Button::with_label("Start daemon");
+    start_button.add_css_class("pill");
+    start_button.add_css_class("suggested-action");
+    start_button.set_visible(problem.is_connection_error());
+    buttons.append(&start_button);
+    page.set_child(Some(&buttons));
+
+    let list_view_for_retry = list_view.clone();
+    let history_state_for_retry = history_state.clone();
+    retry_button.connect_clicked(move |_| {
+        if let Err(e) = leave_daemon_problem(&list_view_for_retry, &history_state_for_retry) {
+            show_daemon_problem(&list_view_for_retry, &history_state_for_retry, &e);
+        }
+    });
 
-    let mut item = items.remove(index);
-    item.pinned = pinned;
-    let insert_index = if pinned {
-        0
-    } else {
-        items
-            .iter()
-            .position(|existing| !existing.pinned)
-            .unwrap_or(items.len())
+    let list_view = list_view.clone();
+    let history_state = history_state.clone();
+    start_button.connect_clicked(move |start_button| {
+        if let Err(e) = spawn_daemon() {
+            error!("Failed to start the clipboard daemon: {e}");
+            show_daemon_problem(&list_view, &history_state, &e);
+            return;
+        }
+        start_button.set_sensitive(false);
+        retry_button.set_sensitive(false);
+        // Poll without blocking GTK until the daemon opens its socket
+        let started = std::time::Instant::now();
+        let list_view = list_view.clone();
+        let history_state = history_state.clone();
+        gtk4::glib::timeout_add_local(POLL_INTERVAL, move || {
+            match leave_daemon_problem(&list_view, &history_state) {
+                Ok(()) => {}
+                Err(CursorClipError::DaemonNotRunning) if started.elapsed() < STARTUP_TIMEOUT => {
+                    return gtk4::glib::ControlFlow::Continue;
+                }
+                Err(e) => {
+                    warn!("Started the daemon, but the overlay still cannot use it: {e}");
+                    show_daemon_problem(&list_view, &history_state, &e);
+                }
+            }
+            gtk4::glib::ControlFlow::Break
+        });
+    });
+    page
+}
+
+/// Reload the list when another client (the CLI, D-Bus, a second overlay) changes the
+/// history, so the overlay neither shows deleted items nor misses ones added elsewhere.
+/// Changes made by the overlay itself are already on screen: their replies carry the
+/// new revision.
+fn follow_history_changes(list_view: &gtk4::ListView, history_state: &HistoryListState) {
+    FOLLOWED_LIST.with_borrow_mut(|followed| {
+        *followed = Some((list_view.downgrade(), history_state.clone()));
+    });
+    HISTORY_SUBSCRIPTION.call_once(subscribe_to_history_changes);
+}
+
+/// Start the process's one subscription: a thread reading the daemon's events, and a
+/// task on the GTK main loop that wakes only when one of them arrives.
+fn subscribe_to_history_changes() {
+    let (sender, mut revisions) = tokio::sync::mpsc::unbounded_channel();
+    std::thread::spawn(move || {
+        // Nobody is left to tell once the main loop's side of the channel is gone
+        while !sender.is_closed() {
+            match FrontendClient::new().and_then(FrontendClient::subscribe) {
+                Ok(events) => {
+                    for event in events {
+                        if let BackendMessage::HistoryChanged { revision } = event
+                            && sender.send(revision).is_err()
+                        {
+                            return;
+                        }
+                    }
+                }
+                Err(e) => debug!("Not following history changes: {e}"),
+            }
+            std::thread::sleep(HISTORY_FOLLOW_RETRY);
+        }
+    });
+
+    gtk4::glib::spawn_future_local(async move {
+        while let Some(mut revision) = revisions.recv().await {
+            // Catch up once for a burst of changes
+            while let Ok(next) = revisions.try_recv() {
+                revision = revision.max(next);
+            }
+            history_changed_elsewhere(revision);
+        }
+    });
+}
+
+fn history_changed_elsewhere(revision: u64) {
+    let followed = FOLLOWED_LIST.with_borrow(|followed| {
+        let (list_view, history_state) = followed.as_ref()?;
+        Some((list_view.upgrade()?, history_state.clone()))
+    });
+    let Some((list_view, history_state)) = followed else {
+        return;
+    };
+    // A hidden resident overlay reloads when it is presented anyway
+    if !list_view.is_mapped() {
+        return;
+    }
+    match last_history_revision() {
+        Some(seen) if seen >= revision => return,
+        Some(seen) => {
+            debug!("History changed elsewhere (revision {seen} -> {revision}); updating");
+            catch_up_history(&history_state, seen);
+        }
+        None => reload_history(&history_state),
+    }
+    refresh_stats(&history_state);
+}
+
+/// Bring the list from revision `seen` up to date. The default listing (all items,
+/// newest first, no search) is patched with only the changes (`GetHistorySince`);
+/// other listings are reloaded.
+fn catch_up_history(history_state: &HistoryListState, seen: u64) {
+    let patchable = history_state.search_query.borrow().trim().is_empty()
+        && *history_state.view.borrow() == HistoryView::All
+        && *history_state.order.borrow() == HistoryOrder::Recent;
+    if !patchable {
+        reload_history(history_state);
+        return;
+    }
+    let store = &history_state.store;
+    match with_client(|client| client.get_history_since(seen)) {
+        Ok(HistoryUpdate::Delta(delta)) => {
+            for item_id in delta
+                .removed
+                .iter()
+                .chain(delta.changed.iter().map(|(_, item)| &item.item_id))
+            {
+                if let Some((position, _)) = find_item(history_state, *item_id) {
+                    store.remove(position);
+                }
+            }
+            let complete = *history_state.history_complete.borrow();
+            for (index, item) in delta.changed {
+                // Items beyond the loaded pages come with the next page
+                let Ok(index) = u32::try_from(index) else {
+                    continue;
+                };
+                if index < store.n_items() || (complete && index == store.n_items()) {
+                    store.insert(index, &ClipboardItemObject::new(item));
+                }
+            }
+        }
+        Ok(HistoryUpdate::Full(items)) => {
+            let objects: Vec<ClipboardItemObject> =
+                items.into_iter().map(ClipboardItemObject::new).collect();
+            store.splice(0, store.n_items(), &objects);
+            *history_state.history_complete.borrow_mut() = true;
+        }
+        Err(e) => warn!("Error updating clipboard history: {e}"),
+    }
+}
+
+/// Check the daemon again and, if it can serve the overlay now, go back to the list
+/// with a fresh first page.
+fn leave_daemon_problem(
+    list_view: &gtk4::ListView,
+    history_state: &HistoryListState,
+) -> Result<(), CursorClipError> {
+    with_client(|client| client.handshake())?;
+    info!("Clipboard daemon available again");
+    reload_history(history_state);
+    show_list_page(list_view, history_state);
+    Ok(())
+}
+
+/// Leave the detail or cheatsheet page and return focus to the list.
+fn show_list_page(list_view: &gtk4::ListView, history_state: &HistoryListState) {
+    history_state.detail_stack.set_visible_child_name(LIST_PAGE);
+    focus_list(list_view);
+}
+
+fn build_detail_page(
+    list_view: &gtk4::ListView,
+    history_state: &HistoryListState,
+    details: &ItemDetails,
+) -> Box {
+    let item = &details.item;
+    let item_id = item.item_id;
+    let page = Box::new(Orientation::Vertical, 8);
+    page.set_margin_top(8);
+    page.set_margin_bottom(8);
+    page.set_margin_start(12);
+    page.set_margin_end(12);
+
+    let top_bar = Box::new(Orientation::Horizontal, 8);
+    let back_button = Button::builder().icon_name("go-previous-symbolic").build();
+    back_button.add_css_class("flat");
+    back_button.set_tooltip_text(Some("Back (Esc)"));
+    let title = Label::new(Some(&format!(
+        "{} {}",
+        item.content_type.icon(),
+        item.content_type.as_str()
+    )));
+    title.add_css_class("heading");
+    top_bar.append(&back_button);
+    top_bar.append(&title);
+    page.append(&top_bar);
+
+    let mut meta = vec![format_timestamp(item.timestamp)];
+    if let Some(last_copied) = item.last_copied.filter(|_| item.times_copied > 1) {
+        meta.push(format!(
+            "copied {}×, last {}",
+            item.times_copied,
+            format_timestamp(last_copied)
+        ));
+    }
+    if let Some(app_id) = &item.source_app {
+        meta.push(format!("from {} ({app_id})", app_display_name(app_id)));
+    }
+    if let Some(seat) = &item.seat {
+        meta.push(format!("copied on {seat}"));
+    }
+    if let Some(source_id) = item.derived_from {
+        meta.push(format!("transformed from item #{source_id}"));
+    }
+    if let Some(last_pasted) = item.last_pasted {
+        meta.push(format!(
+            "pasted {}×, last {}",
+            item.paste_count,
+            format_timestamp(last_pasted)
+        ));
+    }
+    if item.pinned {
+        meta.push("pinned".to_string());
+    }
+    let meta_label = Label::new(Some(&meta.join(" · ")));
+    meta_label.add_css_class("caption");
+    meta_label.add_css_class("clipboard-time");
+    meta_label.set_halign(Align::Start);
+    meta_label.set_wrap(true);
+    page.append(&meta_label);
+
+    let content = Box::new(Orientation::Vertical, 8);
+    let rendered_image = item.thumbnail.as_ref().and_then(|bytes| {
+        let gbytes = glib::Bytes::from(bytes);
+        gtk4::gdk::Texture::from_bytes(&gbytes).ok()
+    });
+    if let Some(texture) = rendered_image {
+        let picture = gtk4::Picture::for_paintable(&texture);
+        picture.set_can_shrink(true);
+        picture.set_height_request(180);
+        content.append(&picture);
+        if let Some(image_info) = &item.image_info {
+            content.append(&Label::new(Some(&image_info.describe())));
+        }
+    } else if !item.files.is_empty() {
+        content.append(&build_file_list(&item.files));
+    }
+    if let Some(full_text) = &details.full_text {
+        // Megabytes of text would stall the label; show the start of it
+        let (text, truncated) = with_client(|client| {
+            client.get_item_full_preview(item_id, Some(MAX_FULL_PREVIEW_BYTES))
+        })
+        .unwrap_or_else(|e| {
+            warn!("Error fetching the text of clipboard item {item_id}: {e}");
+            (
+                item.content_preview.clone(),
+                full_text.len() > item.content_preview.len(),
+            )
+        });
+        let text_label = Label::new(Some(&text));
+        if let Some(markup) = code_markup(item, &text) {
+            text_label.set_markup(&markup);
+        }
+        text_label.add_css_class("clipboard-preview");
+        if matches!(
+            item.content_type,
+            ClipboardContentType::Code | ClipboardContentType::File
+        ) {
+            text_label.add_css_class("monospace");
+        }
+        text_label.set_selectable(true);
+        text_label.set_wrap(true);
+        text_label.set_wrap_mode(gtk4::pango::WrapMode::WordChar);
+        text_label.set_halign(Align::Start);
+        text_label.set_valign(Align::Start);
+        text_label.set_xalign(0.0);
+        content.append(&text_label);
+        if truncated {
+            let more_label = Label::new(Some(&format!(
+                "Showing {} of {}; Edit & Copy shows all of it",
+                format_byte_size(text.len() as u64),
+                format_byte_size(full_text.len() as u64)
+            )));
+            more_label.add_css_class("caption");
+            more_label.add_css_class("clipboard-time");
+            more_label.set_halign(Align::Start);
+            content.append(&more_label);
+        }
+    }
+
+    let formats_heading = Label::new(Some("Formats"));
+    formats_heading.add_css_class("heading");
+    formats_heading.set_halign(Align::Start);
+    content.append(&formats_heading);
+    for (mime, byte_size) in &details.mime_types {
+        let mime_row = Box::new(Orientation::Horizontal, 8);
+        let mime_label = Label::new(Some(mime));
+        mime_label.add_css_class("monospace");
+        mime_label.set_halign(Align::Start);
+        mime_label.set_hexpand(true);
+        mime_label.set_ellipsize(gtk4::pango::EllipsizeMode::Middle);
+        let size_label = Label::new(Some(&format_byte_size(*byte_size)));
+        size_label.add_css_class("caption");
+        size_label.add_css_class("clipboard-time");
+        mime_row.append(&mime_label);
+        mime_row.append(&size_label);
+        content.append(&mime_row);
+    }
+
+    let content_scroller = gtk4::ScrolledWindow::new();
+    content_scroller.set_policy(gtk4::PolicyType::Never, gtk4::PolicyType::Automatic);
+    content_scroller.set_vexpand(true);
+    content_scroller.set_child(Some(&content));
+    page.append(&content_scroller);
+
+    let action_row = Box::new(Orientation::Horizontal, 8);
+    action_row.set_halign(Align::End);
+    let copy_button = Button::with_label("Copy");
+    copy_button.add_css_class("suggested-action");
+    let pin_button = Button::with_label(if item.pinned { "Unpin" } else { "Pin" });
+    let delete_button = Button::with_label("Delete");
+    delete_button.add_css_class("destructive-action");
+    action_row.append(&delete_button);
+    if details.full_text.is_some() {
+        let edit_button = Button::with_label("Edit & Copy");
+        edit_button.set_tooltip_text(Some("Change the text, then copy it as a new item"));
+        action_row.append(&edit_button);
+        let list_view_for_edit = list_view.clone();
+        let history_state_for_edit = history_state.clone();
+        edit_button.connect_clicked(move |_| {
+            show_item_editor(&list_view_for_edit, &history_state_for_edit, item_id);
+        });
+    }
+    if let Some(text) = details.full_text.clone() {
+        let snippet_button = Button::with_label("Save as Snippet");
+        snippet_button.set_tooltip_text(Some(
+            "Keep this text in the Snippets section; {date}, {time} and {clipboard} are filled in when pasting",
+        ));
+        action_row.append(&snippet_button);
+        let history_state_for_snippet = history_state.clone();
+        snippet_button.connect_clicked(move |button| {
+            let snippet = Snippet {
+                name: snippet_name(&text),
+                text: text.clone(),
+            };
+            match with_client(|client| client.add_snippet(snippet.clone())) {
+                Ok(snippets) => {
+                    refresh_snippets(&history_state_for_snippet, &snippets);
+                    button.set_label("Saved");
+                    button.set_sensitive(false);
+                }
+                Err(e) => error!("Error saving snippet: {}", e),
+            }
+        });
+    }
+    action_row.append(&pin_button);
+    action_row.append(&copy_button);
+    page.append(&action_row);
+
+    let list_view_for_back = list_view.clone();
+    let history_state_for_back = history_state.clone();
+    back_button.connect_clicked(move |_| {
+        show_list_page(&list_view_for_back, &history_state_for_back);
+    });
+
+    let history_state_for_copy = history_state.clone();
+    let seat = item.seat.clone();
+    copy_button.connect_clicked(move |_| {
+        paste_item(&history_state_for_copy, item_id, seat.clone(), None);
+    });
+
+    let list_view_for_pin = list_view.clone();
+    let history_state_for_pin = history_state.clone();
+    pin_button.connect_clicked(move |_| {
+        if toggle_pinned(&list_view_for_pin, &history_state_for_pin, item_id) {
+            show_item_details(&list_view_for_pin, &history_state_for_pin, item_id);
+        }
+    });
+
+    let list_view_for_delete = list_view.clone();
+    let history_state_for_delete = history_state.clone();
+    delete_button.connect_clicked(move |_| {
+        history_state_for_delete
+            .detail_stack
+            .set_visible_child_name(LIST_PAGE);
+        delete_item(&list_view_for_delete, &history_state_for_delete, item_id);
+    });
+
+    page
+}
+
+/// Open an item's full text in an editor in place of the list.
+fn show_item_editor(list_view: &gtk4::ListView, history_state: &HistoryListState, item_id: u64) {
+    let details = match with_client(|client| client.get_item_details(item_id)) {
+        Ok(details) => details,
+        Err(e) => {
+            error!("Error fetching clipboard item details: {}", e);
+            return;
+        }
     };
-    items.insert(insert_index, item);
-}
-
-fn make_placeholder_row_with_message(message: &str) -> gtk4::ListBoxRow {
-    let placeholder_row = gtk4::ListBoxRow::new();
-    let placeholder_label = Label::new(Some(message));
-    placeholder_label.add_css_class("dim-label");
-    placeholder_label.set_margin_top(20);
-    placeholder_label.set_margin_bottom(20);
-    placeholder_row.set_child(Some(&placeholder_label));
-    placeholder_row.set_selectable(false);
-    placeholder_row.set_activatable(false);
-    placeholder_row
-}
-
-fn set_delete_buttons_visible(list_box: &gtk4::ListBox, visible: bool) {
-    let mut child = list_box.first_child();
-    while let Some(widget) = child {
-        if let Ok(row) = widget.clone().downcast::<gtk4::ListBoxRow>()
-            && let Some(delete_button) = find_button_in_row(&row, "clipboard-delete")
+    let Some(text) = details.full_text.as_deref() else {
+        warn!("Clipboard item {item_id} has no text to edit");
+        return;
+    };
+
+    let stack = &history_state.detail_stack;
+    if let Some(previous) = stack.child_by_name(EDIT_PAGE) {
+        stack.remove(&previous);
+    }
+    let (page, text_view) = build_editor_page(list_view, history_state, &details.item, text);
+    stack.add_named(&page, Some(EDIT_PAGE));
+    stack.set_visible_child_name(EDIT_PAGE);
+    text_view.grab_focus();
+}
+
+/// Editor for "Edit & Copy": the edited text becomes a new history item and the current
+/// selection; the original item is left as it was.
+fn build_editor_page(
+    list_view: &gtk4::ListView,
+    history_state: &HistoryListState,
+    item: &ClipboardItemPreview,
+    text: &str,
+) -> (Box, gtk4::TextView) {
+    let page = Box::new(Orientation::Vertical, 8);
+    page.set_margin_top(8);
+    page.set_margin_bottom(8);
+    page.set_margin_start(12);
+    page.set_margin_end(12);
+
+    let top_bar = Box::new(Orientation::Horizontal, 8);
+    let back_button = Button::builder().icon_name("go-previous-symbolic").build();
+    back_button.add_css_class("flat");
+    back_button.set_tooltip_text(Some("Discard changes (Esc)"));
+    let title = Label::new(Some("Edit & Copy"));
+    title.add_css_class("heading");
+    top_bar.append(&back_button);
+    top_bar.append(&title);
+    page.append(&top_bar);
+
+    let text_view = gtk4::TextView::new();
+    text_view.buffer().set_text(text);
+    text_view.set_wrap_mode(gtk4::WrapMode::WordChar);
+    text_view.set_monospace(matches!(
+        item.content_type,
+        ClipboardContentType::Code | ClipboardContentType::File
+    ));
+    text_view.add_css_class("clipboard-editor");
+    text_view.set_left_margin(6);
+    text_view.set_right_margin(6);
+    text_view.set_top_margin(6);
+    text_view.set_bottom_margin(6);
+
+    let editor_scroller = gtk4::ScrolledWindow::new();
+    editor_scroller.set_policy(gtk4::PolicyType::Never, gtk4::PolicyType::Automatic);
+    editor_scroller.set_vexpand(true);
+    editor_scroller.set_child(Some(&text_view));
+    page.append(&editor_scroller);
+
+    let action_row = Box::new(Orientation::Horizontal, 8);
+    action_row.set_halign(Align::End);
+    let cancel_button = Button::with_label("Cancel");
+    let copy_button = Button::with_label("Copy");
+    copy_button.add_css_class("suggested-action");
+    copy_button.set_tooltip_text(Some("Save as a new item and copy it (Ctrl+Enter)"));
+    action_row.append(&cancel_button);
+    action_row.append(&copy_button);
+    page.append(&action_row);
+
+    for button in [&back_button, &cancel_button] {
+        let list_view = list_view.clone();
+        let history_state = history_state.clone();
+        button.connect_clicked(move |_| show_list_page(&list_view, &history_state));
+    }
+
+    let buffer = text_view.buffer();
+    let copy_edited = move || {
+        let (start, end) = buffer.bounds();
+        let text = buffer.text(&start, &end, false);
+        if text.is_empty() {
+            return;
+        }
+        match with_client(|client| client.add_item(text_mime_data(&text), false, true)) {
+            Ok(id) => {
+                info!("Copied edited text as item {id}");
+                request_quit();
+            }
+            Err(e) => error!("Error adding edited clipboard item: {}", e),
+        }
+    };
+    let copy_edited = Rc::new(copy_edited);
+    let copy_for_button = copy_edited.clone();
+    copy_button.connect_clicked(move |_| copy_for_button());
+
+    // Enter types a newline; Ctrl+Enter copies
+    let editor_keys = gtk4::EventControllerKey::new();
+    editor_keys.set_propagation_phase(gtk4::PropagationPhase::Capture);
+    editor_keys.connect_key_pressed(move |_, key, _, modifiers| {
+        use gtk4::gdk::Key;
+        if matches!(key, Key::Return | Key::KP_Enter)
+            && modifiers.contains(gtk4::gdk::ModifierType::CONTROL_MASK)
         {
-            delete_button.set_visible(visible);
+            copy_edited();
+            return gtk4::glib::Propagation::Stop;
+        }
+        gtk4::glib::Propagation::Proceed
+    });
+    text_view.add_controller(editor_keys);
+
+    (page, text_view)
+}
+
+/// Default snippet name: the first non-empty line, shortened.
+fn snippet_name(text: &str) -> String {
+    const MAX_CHARS: usize = 40;
+    let line = text
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or("Snippet");
+    if line.chars().count() > MAX_CHARS {
+        format!("{}…", line.chars().take(MAX_CHARS).collect::<String>())
+    } else {
+        line.to_string()
+    }
+}
+
+// File copies can contain hundreds of entries; the rest is summarised
+const MAX_LISTED_FILES: usize = 5;
+
+/// File names with their content-type icons; the full path is in the tooltip.
+fn build_file_list(files: &[String]) -> Box {
+    let file_box = Box::new(Orientation::Vertical, 2);
+    file_box.add_css_class("clipboard-preview");
+    for path in files.iter().take(MAX_LISTED_FILES) {
+        let path = std::path::Path::new(path);
+        let content_type = if path.is_dir() {
+            gtk4::glib::GString::from("inode/directory")
+        } else {
+            gtk4::gio::content_type_guess(Some(path), &[]).0
+        };
+        let icon = gtk4::Image::from_gicon(&gtk4::gio::content_type_get_icon(&content_type));
+
+        let name = path
+            .file_name()
+            .map_or_else(|| path.to_string_lossy(), |name| name.to_string_lossy());
+        let name_label = Label::new(Some(name.as_ref()));
+        name_label.set_halign(Align::Start);
+        name_label.set_ellipsize(gtk4::pango::EllipsizeMode::Middle);
+
+        let file_row = Box::new(Orientation::Horizontal, 6);
+        file_row.set_tooltip_text(Some(&path.to_string_lossy()));
+        file_row.append(&icon);
+        file_row.append(&name_label);
+        file_box.append(&file_row);
+    }
+    if files.len() > MAX_LISTED_FILES {
+        let more_label = Label::new(Some(&format!(
+            "and {} more",
+            files.len() - MAX_LISTED_FILES
+        )));
+        more_label.add_css_class("caption");
+        more_label.add_css_class("clipboard-time");
+        more_label.set_halign(Align::Start);
+        file_box.append(&more_label);
+    }
+    file_box
+}
+
+/// Show the folder containing `path` in the default file manager and close the overlay.
+fn open_containing_folder(path: &str) {
+    let Some(parent) = std::path::Path::new(path).parent() else {
+        return;
+    };
+    let uri = gtk4::gio::File::for_path(parent).uri();
+    match gtk4::gio::AppInfo::launch_default_for_uri(&uri, None::<&gtk4::gio::AppLaunchContext>) {
+        Ok(()) => request_quit(),
+        Err(e) => error!("Failed to open {uri}: {e}"),
+    }
+}
+
+/// Hand an item back to the daemon as the current selection and close the overlay.
+fn paste_item(
+    history_state: &HistoryListState,
+    item_id: u64,
+    seat: Option<String>,
+    mime_filter: Option<MimeFilter>,
+) {
+    let instant_paste = *history_state.instant_paste.borrow();
+    debug!("Pasting clipboard item ID {item_id} (filter: {mime_filter:?})");
+
+    if let Err(e) = with_client(|client| {
+        client.set_clipboard_by_id(item_id, instant_paste, seat.clone(), mime_filter)
+    }) {
+        error!("Error setting clipboard by ID: {}", e);
+    } else {
+        info!("Clipboard set by ID: {}", item_id);
+        request_quit();
+    }
+}
+
+/// Offer an item as the primary selection, to be pasted with a middle click elsewhere.
+fn set_primary(item_id: u64) {
+    debug!("Setting clipboard item ID {item_id} as the primary selection");
+    match with_client(|client| client.set_primary_by_id(item_id)) {
+        Ok(()) => {
+            info!("Primary selection set by ID: {}", item_id);
+            request_quit();
         }
-        child = widget.next_sibling();
+        Err(e) => error!("Error setting the primary selection: {}", e),
     }
 }
 
-fn set_pin_icons_visible(list_box: &gtk4::ListBox, visible: bool) {
-    let mut child = list_box.first_child();
-    while let Some(widget) = child {
-        if let Ok(row) = widget.clone().downcast::<gtk4::ListBoxRow>()
-            && let Some(pin_button) = find_button_in_row(&row, "clipboard-pin")
+/// Context menu of a row at `position` (row coordinates): pin, delete, paste as plain
+/// text and details, as row actions.
+fn show_row_menu(
+    row: &gtk4::Widget,
+    (x, y): (f64, f64),
+    list_view: &gtk4::ListView,
+    history_state: &HistoryListState,
+    item: &ClipboardItemPreview,
+) {
+    let item_id = item.item_id;
+    let actions = gtk4::gio::SimpleActionGroup::new();
+    let (list_view_for_pin, history_state_for_pin) = (list_view.clone(), history_state.clone());
+    add_row_action(&actions, "pin", move || {
+        toggle_pinned(&list_view_for_pin, &history_state_for_pin, item_id)
+    });
+    let (list_view_for_delete, history_state_for_delete) =
+        (list_view.clone(), history_state.clone());
+    add_row_action(&actions, "delete", move || {
+        delete_item(&list_view_for_delete, &history_state_for_delete, item_id)
+    });
+    let history_state_for_plain_paste = history_state.clone();
+    let seat = item.seat.clone();
+    add_row_action(&actions, "paste-plain", move || {
+        paste_item(
+            &history_state_for_plain_paste,
+            item_id,
+            seat.clone(),
+            Some(MimeFilter::PlainText),
+        )
+    });
+    let (list_view_for_details, history_state_for_details) =
+        (list_view.clone(), history_state.clone());
+    add_row_action(&actions, "details", move || {
+        show_item_details(&list_view_for_details, &history_state_for_details, item_id)
+    });
+    let (list_view_for_edit, history_state_for_edit) = (list_view.clone(), history_state.clone());
+    add_row_action(&actions, "edit", move || {
+        show_item_editor(&list_view_for_edit, &history_state_for_edit, item_id)
+    });
+    row.insert_action_group("row", Some(&actions));
+
+    let menu = gtk4::gio::Menu::new();
+    menu.append(
+        Some(if item.pinned { "Unpin" } else { "Pin" }),
+        Some("row.pin"),
+    );
+    if is_mergeable(item) {
+        menu.append(Some("Paste as plain text"), Some("row.paste-plain"));
+        menu.append(Some("Edit & copy"), Some("row.edit"));
+    }
+    menu.append(Some("Show details"), Some("row.details"));
+    menu.append(Some("Delete"), Some("row.delete"));
+
+    let popover = gtk4::PopoverMenu::from_model(Some(&menu));
+    popover.set_parent(row);
+    popover.set_has_arrow(false);
+    popover.set_pointing_to(Some(&gtk4::gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+    // Rows are rebuilt as they scroll; don't leave the popover attached to one
+    popover.connect_closed(|popover| {
+        let popover = popover.clone();
+        gtk4::glib::idle_add_local_once(move || popover.unparent());
+    });
+    popover.popup();
+}
+
+fn add_row_action(
+    actions: &gtk4::gio::SimpleActionGroup,
+    name: &str,
+    activate: impl Fn() + 'static,
+) {
+    let action = gtk4::gio::SimpleAction::new(name, None);
+    action.connect_activate(move |_, _| activate());
+    actions.add_action(&action);
+}
+
+/// Paste a transformed copy of an item; the daemon records it as a new history entry.
+fn transform_and_paste(
+    history_state: &HistoryListState,
+    item_id: u64,
+    transform: TextTransform,
+    seat: Option<String>,
+) {
+    let instant_paste = *history_state.instant_paste.borrow();
+    debug!("Transforming clipboard item ID {item_id} ({transform:?})");
+
+    match with_client(|client| {
+        client.transform_and_set(item_id, transform, instant_paste, seat.clone())
+    }) {
+        Ok(item) => {
+            info!("Clipboard set to transformed item ID: {}", item.item_id);
+            request_quit();
+        }
+        Err(e) => error!("Error transforming clipboard item: {}", e),
+    }
+}
+
+/// Expand a snippet and paste it; the daemon records the result as a history entry.
+fn paste_snippet(history_state: &HistoryListState, id: usize) {
+    let instant_paste = *history_state.instant_paste.borrow();
+    match with_client(|client| client.paste_snippet(id, instant_paste, None)) {
+        Ok(()) => {
+            info!("Clipboard set to snippet {id}");
+            request_quit();
+        }
+        Err(e) => error!("Error pasting snippet: {}", e),
+    }
+}
+
+/// Set the clipboard to a picked emoji; the daemon records it like any other copy.
+fn paste_emoji(history_state: &HistoryListState, emoji: &str) {
+    let instant_paste = *history_state.instant_paste.borrow();
+    match with_client(|client| client.paste_emoji(emoji.to_string(), instant_paste, None)) {
+        Ok(()) => {
+            info!("Clipboard set to emoji {emoji}");
+            request_quit();
+        }
+        Err(e) => error!("Error pasting emoji: {}", e),
+    }
+}
+
+const SNIPPETS_VIEW: &str = "snippets";
+const EMOJI_VIEW: &str = "emoji";
+
+/// The Snippets tab: the library rows (see [`refresh_snippets`]) in a scroller.
+fn build_snippet_page(history_state: &HistoryListState) -> gtk4::ScrolledWindow {
+    let snippet_list = &history_state.snippet_list;
+    snippet_list.set_margin_start(12);
+    snippet_list.set_margin_end(12);
+    snippet_list.set_margin_bottom(6);
+    let scroller = gtk4::ScrolledWindow::new();
+    scroller.set_policy(gtk4::PolicyType::Never, gtk4::PolicyType::Automatic);
+    scroller.set_vexpand(true);
+    scroller.set_child(Some(snippet_list));
+    scroller
+}
+
+/// Rebuild the Snippets tab from the daemon's library.
+fn refresh_snippets(history_state: &HistoryListState, snippets: &[Snippet]) {
+    let rows = &history_state.snippet_list;
+    while let Some(child) = rows.first_child() {
+        rows.remove(&child);
+    }
+    if snippets.is_empty() {
+        let placeholder = Label::new(Some(
+            "No snippets yet. Use “Save as Snippet” on an item's details or add [[snippets]] to config.toml.",
+        ));
+        placeholder.add_css_class("dim-label");
+        placeholder.set_wrap(true);
+        placeholder.set_margin_top(20);
+        rows.append(&placeholder);
+    }
+    for (id, snippet) in snippets.iter().enumerate() {
+        let row = Box::new(Orientation::Horizontal, 4);
+        let paste_button = Button::with_label(&snippet.name);
+        paste_button.add_css_class("flat");
+        paste_button.set_hexpand(true);
+        paste_button.set_tooltip_text(Some(&snippet.text));
+        if let Some(label) = paste_button.child().and_downcast::<Label>() {
+            label.set_xalign(0.0);
+            label.set_ellipsize(gtk4::pango::EllipsizeMode::End);
+        }
+        let remove_button = Button::builder().icon_name("user-trash-symbolic").build();
+        remove_button.add_css_class("flat");
+        remove_button.set_tooltip_text(Some("Remove snippet"));
+        row.append(&paste_button);
+        row.append(&remove_button);
+        rows.append(&row);
+
+        let history_state_for_paste = history_state.clone();
+        paste_button.connect_clicked(move |_| {
+            paste_snippet(&history_state_for_paste, id);
+        });
+        let history_state_for_remove = history_state.clone();
+        remove_button.connect_clicked(move |_| {
+            match with_client(|client| client.remove_snippet(id)) {
+                Ok(snippets) => refresh_snippets(&history_state_for_remove, &snippets),
+                Err(e) => error!("Error removing snippet: {}", e),
+            }
+        });
+    }
+}
+
+/// Move to the next (or previous) tab, wrapping around.
+fn cycle_tab(history_state: &HistoryListState, forward: bool) {
+    let names: Vec<&str> = HistoryView::ALL
+        .iter()
+        .map(|view| view.name())
+        .chain([SNIPPETS_VIEW, EMOJI_VIEW])
+        .filter(|name| history_state.view_stack.child_by_name(name).is_some())
+        .collect();
+    let stack = &history_state.view_stack;
+    let current = stack.visible_child_name().unwrap_or_default();
+    let index = names.iter().position(|name| *name == current).unwrap_or(0);
+    let next = if forward {
+        (index + 1) % names.len()
+    } else {
+        (index + names.len() - 1) % names.len()
+    };
+    stack.set_visible_child_name(names[next]);
+}
+
+/// Show another history view: the list moves into its tab and is reloaded from the daemon.
+fn switch_history_view(
+    list_view: &gtk4::ListView,
+    history_state: &HistoryListState,
+    view: HistoryView,
+) {
+    let page_box = history_state
+        .view_stack
+        .child_by_name(view.name())
+        .and_downcast::<Box>();
+    let detail_stack = &history_state.detail_stack;
+    if let Some(page_box) = page_box
+        && detail_stack.parent().as_ref() != Some(page_box.upcast_ref::<gtk4::Widget>())
+    {
+        if let Some(parent) = detail_stack.parent().and_downcast::<Box>() {
+            parent.remove(detail_stack);
+        }
+        page_box.append(detail_stack);
+    }
+    if *history_state.view.borrow() == view {
+        return;
+    }
+
+    *history_state.view.borrow_mut() = view;
+    detail_stack.set_visible_child_name(LIST_PAGE);
+    reload_history(history_state);
+    select_first_row(list_view);
+}
+
+/// Refill the store for the current view: the daemon's ranked search results while a
+/// query is entered, otherwise the first history page.
+fn reload_history(history_state: &HistoryListState) {
+    let store = &history_state.store;
+    let query = history_state.search_query.borrow().trim().to_string();
+    let view = *history_state.view.borrow();
+    history_state.highlights.borrow_mut().clear();
+    *history_state.search_error.borrow_mut() = None;
+
+    if query.is_empty() {
+        store.remove_all();
+        *history_state.history_complete.borrow_mut() = false;
+        fetch_history_page(history_state);
+        return;
+    }
+
+    // Searching covers the whole history, so there are no further pages to load
+    *history_state.history_complete.borrow_mut() = true;
+    let results = match with_client(|client| client.search_history(query.clone(), view, 0)) {
+        Ok(results) => results,
+        Err(CursorClipError::InvalidRequest(message)) => {
+            *history_state.search_error.borrow_mut() = Some(message);
+            Vec::new()
+        }
+        Err(e) => {
+            warn!("Error searching clipboard history for {query:?}: {e}");
+            Vec::new()
+        }
+    };
+    let mut highlights = history_state.highlights.borrow_mut();
+    let objects: Vec<ClipboardItemObject> = results
+        .into_iter()
+        .map(|result| {
+            highlights.insert(result.item.item_id, result.highlights);
+            ClipboardItemObject::new(result.item)
+        })
+        .collect();
+    drop(highlights);
+    store.splice(0, store.n_items(), &objects);
+}
+
+/// Offer to restore the selection the daemon recovered from a crash backup, if any.
+fn build_recovery_banner(history_state: &HistoryListState) -> Option<Box> {
+    let recovered_id = match with_client(|client| client.get_recovered_item()) {
+        Ok(id) => id?,
+        Err(e) => {
+            debug!("Recovered selection unavailable: {e}");
+            return None;
+        }
+    };
+    let seat = find_item(history_state, recovered_id)?.1.seat;
+
+    let banner = Box::new(Orientation::Horizontal, 8);
+    banner.add_css_class("card");
+    banner.add_css_class("clipboard-recovery");
+    banner.set_margin_start(12);
+    banner.set_margin_end(12);
+    banner.set_margin_bottom(6);
+    let label = Label::new(Some(
+        "Recovered the clipboard cursor-clip held when it stopped unexpectedly",
+    ));
+    label.set_wrap(true);
+    label.set_xalign(0.0);
+    label.set_hexpand(true);
+    label.set_margin_start(10);
+    label.set_margin_top(6);
+    label.set_margin_bottom(6);
+    let restore_button = Button::with_label("Restore");
+    restore_button.add_css_class("suggested-action");
+    restore_button.set_valign(Align::Center);
+    let dismiss_button = Button::builder().icon_name("window-close-symbolic").build();
+    dismiss_button.add_css_class("flat");
+    dismiss_button.set_valign(Align::Center);
+    dismiss_button.set_margin_end(6);
+    dismiss_button.set_tooltip_text(Some("Dismiss"));
+    banner.append(&label);
+    banner.append(&restore_button);
+    banner.append(&dismiss_button);
+
+    let history_state_for_restore = history_state.clone();
+    restore_button.connect_clicked(move |_| {
+        paste_item(&history_state_for_restore, recovered_id, seat.clone(), None);
+    });
+    let banner_for_dismiss = banner.clone();
+    dismiss_button.connect_clicked(move |_| {
+        if let Err(e) = with_client(|client| client.dismiss_recovered_item()) {
+            error!("Error dismissing recovered selection: {}", e);
+        }
+        banner_for_dismiss.set_visible(false);
+    });
+
+    Some(banner)
+}
+
+/// Header toggle pausing recording (`SetRecordingEnabled`) and the banner shown while
+/// paused, so recording isn't left off by accident.
+fn build_recording_controls() -> (gtk4::ToggleButton, Box) {
+    let pause_button = gtk4::ToggleButton::new();
+    pause_button.set_icon_name("media-playback-pause-symbolic");
+    pause_button.add_css_class("flat");
+    pause_button.set_tooltip_text(Some("Pause recording"));
+
+    let banner = Box::new(Orientation::Horizontal, 8);
+    banner.add_css_class("card");
+    banner.add_css_class("clipboard-paused");
+    banner.set_margin_start(12);
+    banner.set_margin_end(12);
+    banner.set_margin_bottom(6);
+    let label = Label::new(Some("Recording paused: new copies are not saved"));
+    label.set_wrap(true);
+    label.set_xalign(0.0);
+    label.set_hexpand(true);
+    label.set_margin_start(10);
+    label.set_margin_top(6);
+    label.set_margin_bottom(6);
+    let resume_button = Button::with_label("Resume");
+    resume_button.add_css_class("suggested-action");
+    resume_button.set_valign(Align::Center);
+    resume_button.set_margin_end(6);
+    banner.append(&label);
+    banner.append(&resume_button);
+
+    let paused = match with_client(|client| client.get_recording_state()) {
+        Ok(enabled) => !enabled,
+        Err(e) => {
+            debug!("Recording state unavailable: {e}");
+            false
+        }
+    };
+    pause_button.set_active(paused);
+    banner.set_visible(paused);
+
+    let banner_for_toggle = banner.clone();
+    pause_button.connect_toggled(move |button| {
+        let paused = button.is_active();
+        match with_client(|client| client.set_recording_enabled(!paused)) {
+            Ok(_) => banner_for_toggle.set_visible(paused),
+            Err(e) => error!("Error switching recording: {}", e),
+        }
+    });
+    let pause_button_for_resume = pause_button.clone();
+    resume_button.connect_clicked(move |_| {
+        pause_button_for_resume.set_active(false);
+    });
+
+    (pause_button, banner)
+}
+
+/// Append the next page of older items to the store. Returns false if nothing was fetched.
+fn fetch_history_page(history_state: &HistoryListState) -> bool {
+    let store = &history_state.store;
+    let offset = store.n_items() as usize;
+    let view = *history_state.view.borrow();
+    let order = *history_state.order.borrow();
+    let page =
+        match with_client(|client| client.get_history_page(offset, HISTORY_PAGE_SIZE, view, order))
         {
-            pin_button.set_visible(visible);
+            Ok(page) => page,
+            Err(e) => {
+                warn!("Error fetching clipboard history page at {offset}: {e}");
+                return false;
+            }
+        };
+    *history_state.history_complete.borrow_mut() = page.is_last();
+
+    // Items copied meanwhile shift the offsets; skip anything already listed
+    let new_items: Vec<ClipboardItemObject> = page
+        .items
+        .into_iter()
+        .filter(|item| find_item(history_state, item.item_id).is_none())
+        .map(ClipboardItemObject::new)
+        .collect();
+    debug!(
+        "Loaded {} more history items ({} of {})",
+        new_items.len(),
+        offset + new_items.len(),
+        page.total
+    );
+    store.extend_from_slice(&new_items);
+    !new_items.is_empty()
+}
+
+/// Load older items once the list is scrolled to the bottom. Appending keeps the
+/// selection and scroll position.
+fn load_next_history_page(history_state: &HistoryListState) {
+    if !*history_state.history_complete.borrow() {
+        fetch_history_page(history_state);
+    }
+}
+
+/// Update the header subtitle with the daemon's history statistics.
+fn refresh_stats(history_state: &HistoryListState) {
+    let stats = with_client(|client| client.get_stats());
+    match stats {
+        Ok(stats) => history_state.window_title.set_subtitle(&stats.describe()),
+        Err(e) => {
+            debug!("History statistics unavailable: {e}");
+            history_state.window_title.set_subtitle("");
         }
-        child = widget.next_sibling();
     }
 }
-fn find_button_in_row(row: &gtk4::ListBoxRow, class_name: &str) -> Option<gtk4::Button> {
-    let main_box = row.child()?.downcast::<gtk4::Box>().ok()?;
-    let header_box = main_box.first_child()?.downcast::<gtk4::Box>().ok()?;
-    let mut child = header_box.first_child();
-    while let Some(widget) = child {
-        if widget.has_css_class(class_name) {
-            return widget.downcast::<gtk4::Button>().ok();
+
+fn update_placeholder(placeholder: &Label, history_state: &HistoryListState) {
+    let empty = history_state.selection.n_items() == 0;
+    placeholder.set_visible(empty);
+    if empty {
+        if let Some(message) = history_state.search_error.borrow().as_deref() {
+            placeholder.set_label(message);
+            return;
         }
-        if let Ok(container) = widget.clone().downcast::<gtk4::Box>() {
-            let mut inner = container.first_child();
-            while let Some(inner_widget) = inner {
-                if inner_widget.has_css_class(class_name) {
-                    return inner_widget.downcast::<gtk4::Button>().ok();
+        placeholder.set_label(if !history_state.search_query.borrow().trim().is_empty() {
+            "No matches found"
+        } else {
+            match *history_state.view.borrow() {
+                HistoryView::All => "No clipboard history yet",
+                HistoryView::Pinned => "No pinned items",
+                HistoryView::Images => "No images copied yet",
+                HistoryView::Files => "No files copied yet",
+            }
+        });
+    }
+}
+
+/// Rebind all rows, e.g. after toggling which row buttons are shown.
+fn refresh_rows(history_state: &HistoryListState) {
+    let n_items = history_state.store.n_items();
+    history_state.store.items_changed(0, n_items, n_items);
+}
+
+/// Pango markup for `text` with the byte `ranges` emphasized.
+fn highlighted_markup(text: &str, ranges: &[(usize, usize)]) -> String {
+    let mut markup = String::with_capacity(text.len());
+    let mut offset = 0;
+    for &(start, end) in ranges {
+        let (Some(before), Some(matched)) = (text.get(offset..start), text.get(start..end)) else {
+            continue;
+        };
+        markup.push_str(&gtk4::glib::markup_escape_text(before));
+        markup.push_str("<span weight=\"bold\" underline=\"single\">");
+        markup.push_str(&gtk4::glib::markup_escape_text(matched));
+        markup.push_str("</span>");
+        offset = end;
+    }
+    markup.push_str(&gtk4::glib::markup_escape_text(&text[offset..]));
+    markup
+}
+
+/// Position in the store and a copy of the item with `item_id`.
+fn find_item(
+    history_state: &HistoryListState,
+    item_id: u64,
+) -> Option<(u32, ClipboardItemPreview)> {
+    let store = &history_state.store;
+    (0..store.n_items()).find_map(|position| {
+        store
+            .item(position)
+            .and_downcast::<ClipboardItemObject>()
+            .filter(|entry| entry.item_id() == item_id)
+            .map(|entry| (position, entry.item()))
+    })
+}
+
+fn selected_item(history_state: &HistoryListState) -> Option<ClipboardItemPreview> {
+    history_state
+        .selection
+        .selected_item()
+        .and_downcast::<ClipboardItemObject>()
+        .map(|entry| entry.item())
+}
+
+/// Delete an item in the daemon and drop it from the list once its row has faded out.
+fn delete_item(list_view: &gtk4::ListView, history_state: &HistoryListState, item_id: u64) {
+    if let Err(e) = with_client(|client| client.delete_item_by_id(item_id)) {
+        error!("Error deleting clipboard item by ID: {}", e);
+        return;
+    }
+    if let Some(toast_overlay) = list_view
+        .ancestor(adw::ToastOverlay::static_type())
+        .and_downcast::<adw::ToastOverlay>()
+    {
+        offer_undo(&toast_overlay, history_state, "Item deleted");
+    }
+    let list_view = list_view.clone();
+    let history_state = history_state.clone();
+    let remove = move || {
+        if let Some((position, _)) = find_item(&history_state, item_id) {
+            history_state.store.remove(position);
+        }
+        refresh_stats(&history_state);
+        select_first_row(&list_view);
+    };
+    let row = history_state
+        .bound_rows
+        .borrow()
+        .get(&item_id)
+        .and_then(|row| row.upgrade());
+    match row {
+        Some(row) => animate_row_removal(&row, remove),
+        None => remove(),
+    }
+}
+
+/// Show a toast whose Undo button restores what the last delete or Clear All removed
+/// (`UndoLastOperation`). It replaces the previous one, which would now undo this removal.
+fn offer_undo(toast_overlay: &adw::ToastOverlay, history_state: &HistoryListState, title: &str) {
+    let toast = adw::Toast::builder()
+        .title(title)
+        .button_label("Undo")
+        .timeout(UNDO_TOAST_SECS)
+        .build();
+    let toast_overlay_weak = toast_overlay.downgrade();
+    let history_state = history_state.clone();
+    toast.connect_button_clicked(move |_| {
+        match with_client(|client| client.undo_last_operation()) {
+            Ok(count) => {
+                info!("Restored {count} clipboard items");
+                reload_history(&history_state);
+                refresh_stats(&history_state);
+            }
+            Err(e) => {
+                warn!("Error undoing the last deletion: {e}");
+                // The daemon rejects the request only when its trash is empty
+                let title = match e {
+                    CursorClipError::InvalidRequest(_) => "Nothing to undo".to_string(),
+                    e => format!("Undo failed: {e}"),
+                };
+                if let Some(toast_overlay) = toast_overlay_weak.upgrade() {
+                    toast_overlay.add_toast(adw::Toast::new(&title));
                 }
-                inner = inner_widget.next_sibling();
             }
         }
-        child = widget.next_sibling();
+    });
+    if let Some(previous) = UNDO_TOAST.replace(Some(toast.clone())) {
+        previous.dismiss();
     }
-    None
+    toast_overlay.add_toast(toast);
+}
+
+/// Fade `row` out while sliding it aside, then run `done`. libadwaita skips straight to
+/// the end when animations are turned off.
+fn animate_row_removal(row: &gtk4::Widget, done: impl Fn() + 'static) {
+    let target = adw::CallbackAnimationTarget::new({
+        let row = row.downgrade();
+        move |value| {
+            if let Some(row) = row.upgrade() {
+                row.set_opacity(1.0 - value);
+                row.set_margin_start((value * ROW_REMOVAL_SLIDE) as i32);
+            }
+        }
+    });
+    let animation = adw::TimedAnimation::builder()
+        .widget(row)
+        .value_from(0.0)
+        .value_to(1.0)
+        .duration(ROW_REMOVAL_MS)
+        .easing(adw::Easing::EaseOutCubic)
+        .target(&target)
+        .build();
+    animation.connect_done(move |_| done());
+    animation.play();
+}
+
+/// Flip an item's pinned state in the daemon and move it accordingly. Returns false on failure.
+fn toggle_pinned(
+    list_view: &gtk4::ListView,
+    history_state: &HistoryListState,
+    item_id: u64,
+) -> bool {
+    let Some(pinned) = next_pinned_state(history_state, item_id) else {
+        return false;
+    };
+    if let Err(e) = with_client(|client| client.set_pinned(item_id, pinned)) {
+        error!("Error updating pinned state: {}", e);
+        return false;
+    }
+    apply_pinned_state(history_state, item_id, pinned);
+    select_row_by_item_id(list_view, item_id);
+    debug!("Updated pinned state for clipboard item ID {}", item_id);
+    true
+}
+
+/// Select the item at `position` of the (filtered) list and scroll it into view.
+fn select_position(list_view: &gtk4::ListView, position: u32, grab_focus: bool) -> bool {
+    let Some(selection) = list_view.model().and_downcast::<gtk4::SingleSelection>() else {
+        return false;
+    };
+    if position >= selection.n_items() {
+        return false;
+    }
+    selection.set_selected(position);
+    // Also moves the ListView's keyboard focus to the item
+    if let Err(e) = list_view.activate_action("list.scroll-to-item", Some(&position.to_variant())) {
+        debug!("Failed to scroll to list item {position}: {e}");
+    }
+    if grab_focus {
+        list_view.grab_focus();
+    }
+    true
+}
+
+fn selected_position(list_view: &gtk4::ListView) -> Option<u32> {
+    list_view
+        .model()
+        .and_downcast::<gtk4::SingleSelection>()
+        .map(|selection| selection.selected())
+        .filter(|position| *position != gtk4::INVALID_LIST_POSITION)
+}
+
+/// Move focus back to the list, selecting the first item if nothing is selected.
+fn focus_list(list_view: &gtk4::ListView) {
+    match selected_position(list_view) {
+        Some(position) => {
+            select_position(list_view, position, true);
+        }
+        None => select_first_row(list_view),
+    }
+}
+
+fn select_first_row(list_view: &gtk4::ListView) {
+    select_position(list_view, 0, true);
+}
+
+fn select_first_row_without_focus(list_view: &gtk4::ListView) {
+    select_position(list_view, 0, false);
+}
+
+fn select_next_row(list_view: &gtk4::ListView, wrap_to_first: bool) -> bool {
+    if let Some(current) = selected_position(list_view) {
+        return select_position(list_view, current + 1, true)
+            || (wrap_to_first && select_position(list_view, 0, true));
+    }
+
+    select_position(list_view, 0, true)
+}
+
+fn select_previous_row(list_view: &gtk4::ListView, wrap_to_first: bool) -> bool {
+    if let Some(current) = selected_position(list_view) {
+        return (current > 0 && select_position(list_view, current - 1, true))
+            || (wrap_to_first && select_position(list_view, 0, true));
+    }
+
+    select_position(list_view, 0, true)
+}
+
+fn select_row_by_item_id(list_view: &gtk4::ListView, item_id: u64) {
+    let position = list_view.model().and_then(|model| {
+        (0..model.n_items()).find(|position| {
+            model
+                .item(*position)
+                .and_downcast::<ClipboardItemObject>()
+                .is_some_and(|entry| entry.item_id() == item_id)
+        })
+    });
+    match position {
+        Some(position) => {
+            select_position(list_view, position, true);
+        }
+        None => select_first_row(list_view),
+    }
+}
+
+fn next_pinned_state(history_state: &HistoryListState, item_id: u64) -> Option<bool> {
+    find_item(history_state, item_id).map(|(_, item)| !item.pinned)
+}
+
+fn apply_pinned_state(history_state: &HistoryListState, item_id: u64, pinned: bool) {
+    let store = &history_state.store;
+    let Some((position, mut item)) = find_item(history_state, item_id) else {
+        return;
+    };
+
+    store.remove(position);
+    // An unpinned item no longer belongs in the Pinned tab
+    if !pinned && *history_state.view.borrow() == HistoryView::Pinned {
+        return;
+    }
+    item.pinned = pinned;
+    let insert_position = if pinned {
+        0
+    } else {
+        (0..store.n_items())
+            .find(|position| {
+                store
+                    .item(*position)
+                    .and_downcast::<ClipboardItemObject>()
+                    .is_some_and(|entry| !entry.item().pinned)
+            })
+            .unwrap_or(store.n_items())
+    };
+    store.insert(insert_position, &ClipboardItemObject::new(item));
+}
+
+thread_local! {
+    static APP_NAMES: RefCell<HashMap<String, String>> = RefCell::new(HashMap::new());
+}
+
+/// Human readable name of an app id, e.g. "Firefox" for "org.mozilla.firefox": the
+/// desktop file's name if one is installed, else the id's last part.
+fn app_display_name(app_id: &str) -> String {
+    APP_NAMES.with_borrow_mut(|names| {
+        names
+            .entry(app_id.to_string())
+            .or_insert_with(|| {
+                gtk4::gio::DesktopAppInfo::new(&format!("{app_id}.desktop"))
+                    .map(|info| info.display_name().to_string())
+                    .unwrap_or_else(|| {
+                        let last = app_id.rsplit('.').next().unwrap_or(app_id);
+                        let mut chars = last.chars();
+                        chars.next().map_or_else(String::new, |first| {
+                            first.to_uppercase().chain(chars).collect()
+                        })
+                    })
+            })
+            .clone()
+    })
 }
 
 /// Format Unix timestamp to relative time string
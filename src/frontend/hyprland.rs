@@ -0,0 +1,127 @@
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::os::unix::net::UnixStream;
+use std::path::PathBuf;
+use std::time::Duration;
+
+// Hyprland answers within a few ms; never hold up opening the overlay for long
+const IPC_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Workspace state of the session as reported by Hyprland.
+#[derive(Debug, Clone, Default)]
+pub struct HyprlandContext {
+    /// Connector name of the focused monitor (matches `wl_output.name`)
+    pub monitor: Option<String>,
+    /// Name of the special workspace shown on the focused monitor, if one is open
+    pub special_workspace: Option<String>,
+    pub fullscreen_focused: bool,
+}
+
+/// Query the focused monitor, special workspace and fullscreen state over Hyprland's socket IPC.
+pub fn query_context() -> Result<HyprlandContext, String> {
+    let monitors = request("j/monitors")?;
+    let focused = monitors
+        .as_array()
+        .and_then(|monitors| {
+            monitors
+                .iter()
+                .find(|monitor| monitor["focused"].as_bool() == Some(true))
+        })
+        .ok_or("Hyprland reported no focused monitor")?;
+
+    let special_workspace = focused["specialWorkspace"]["name"]
+        .as_str()
+        .filter(|name| !name.is_empty())
+        .map(str::to_string);
+
+    let active_window = request("j/activewindow")?;
+    // Newer releases report a fullscreen mode (0 = none), older ones a bool
+    let fullscreen = match &active_window["fullscreen"] {
+        Value::Bool(fullscreen) => *fullscreen,
+        Value::Number(mode) => mode.as_u64().is_some_and(|mode| mode != 0),
+        _ => false,
+    };
+    // An open special workspace covers a fullscreen window on the regular workspace
+    let fullscreen_focused = fullscreen
+        && special_workspace
+            .as_deref()
+            .is_none_or(|special| active_window["workspace"]["name"].as_str() == Some(special));
+
+    Ok(HyprlandContext {
+        monitor: focused["name"].as_str().map(str::to_string),
+        special_workspace,
+        fullscreen_focused,
+    })
+}
+
+/// Pointer position as the connector name of the monitor it is on and logical
+/// coordinates relative to that monitor's top-left corner.
+pub fn cursor_position() -> Result<(String, f64, f64), String> {
+    let cursor = request("j/cursorpos")?;
+    let (Some(x), Some(y)) = (cursor["x"].as_f64(), cursor["y"].as_f64()) else {
+        return Err("Hyprland reported no cursor position".to_string());
+    };
+
+    let monitors = request("j/monitors")?;
+    monitors
+        .as_array()
+        .into_iter()
+        .flatten()
+        .find_map(|monitor| {
+            let (left, top) = (monitor["x"].as_f64()?, monitor["y"].as_f64()?);
+            let scale = monitor["scale"].as_f64().filter(|scale| *scale > 0.0)?;
+            let (mut width, mut height) = (
+                monitor["width"].as_f64()? / scale,
+                monitor["height"].as_f64()? / scale,
+            );
+            // Transforms 1, 3, 5 and 7 rotate by 90 or 270 degrees
+            if monitor["transform"].as_u64().is_some_and(|t| t % 2 == 1) {
+                std::mem::swap(&mut width, &mut height);
+            }
+            let inside = (left..left + width).contains(&x) && (top..top + height).contains(&y);
+            if !inside {
+                return None;
+            }
+            Some((monitor["name"].as_str()?.to_string(), x - left, y - top))
+        })
+        .ok_or_else(|| "Cursor is not on any monitor Hyprland reported".to_string())
+}
+
+fn request(command: &str) -> Result<Value, String> {
+    let path = socket_path().ok_or("HYPRLAND_INSTANCE_SIGNATURE is not set")?;
+    let mut stream = UnixStream::connect(&path)
+        .map_err(|e| format!("Failed to connect to {}: {e}", path.display()))?;
+    stream
+        .set_read_timeout(Some(IPC_TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(IPC_TIMEOUT)))
+        .map_err(|e| e.to_string())?;
+    stream
+        .write_all(command.as_bytes())
+        .map_err(|e| format!("Failed to send {command} to Hyprland: {e}"))?;
+
+    // Hyprland closes the connection after replying
+    let mut reply = Vec::new();
+    stream
+        .read_to_end(&mut reply)
+        .map_err(|e| format!("Failed to read Hyprland reply to {command}: {e}"))?;
+    serde_json::from_slice(&reply).map_err(|e| format!("Invalid Hyprland reply to {command}: {e}"))
+}
+
+fn socket_path() -> Option<PathBuf> {
+    let signature = std::env::var("HYPRLAND_INSTANCE_SIGNATURE").ok()?;
+    // Hyprland >= 0.40 keeps its sockets in the runtime dir, older releases in /tmp
+    let runtime_socket = std::env::var_os("XDG_RUNTIME_DIR").map(|dir| {
+        PathBuf::from(dir)
+            .join("hypr")
+            .join(&signature)
+            .join(".socket.sock")
+    });
+    match runtime_socket {
+        Some(path) if path.exists() => Some(path),
+        _ => Some(
+            PathBuf::from("/tmp/hypr")
+                .join(signature)
+                .join(".socket.sock"),
+        ),
+    }
+}
@@ -1,90 +1,260 @@
 use wayland_client::{
     Connection, EventQueue,
     globals::{GlobalList, registry_queue_init},
-    protocol::{wl_compositor, wl_seat, wl_shm},
+    protocol::{wl_compositor, wl_output, wl_seat, wl_shm},
 };
 use wayland_protocols::wp::{
+    fractional_scale::v1::client::wp_fractional_scale_manager_v1,
     single_pixel_buffer::v1::client::wp_single_pixel_buffer_manager_v1,
     viewporter::client::wp_viewporter,
 };
+use wayland_protocols_wlr::foreign_toplevel::v1::client::zwlr_foreign_toplevel_manager_v1::ZwlrForeignToplevelManagerV1;
 use wayland_protocols_wlr::layer_shell::v1::client::{zwlr_layer_shell_v1, zwlr_layer_surface_v1};
 
+use crate::backend::demo::demo_items;
+use crate::frontend::autostart::connect_or_start_daemon;
 use crate::frontend::dispatch::layer_shell::cleanup_capture_layer;
-use crate::frontend::ipc_client::FrontendClient;
-use crate::frontend::{frontend_state::State, gtk_overlay};
-use log::{debug, error, warn};
+use crate::frontend::frontend_state::{OutputInfo, State};
+use crate::frontend::fullscreen::{self, FullscreenPolicy};
+use crate::frontend::gtk_overlay;
+use crate::frontend::hyprland;
+use crate::shared::quirks::{CaptureLayer, Compositor, active_quirks};
+use crate::shared::{ClipboardItemPreview, CursorClipError, HISTORY_PAGE_SIZE, HistoryView};
+use log::{debug, error, info, warn};
 use memmap2::{MmapMut, MmapOptions};
+use serde::{Deserialize, Serialize};
 use std::fs::OpenOptions;
 use std::os::fd::BorrowedFd;
 use std::os::unix::io::AsRawFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Shows the overlay and blocks until it is closed: a one-shot GTK application, or the
+/// window of a resident frontend (`cursor-clip resident`). The overlay shows the daemon problem,
+/// if any, instead of the history.
+pub type ShowOverlay<'a> =
+    &'a dyn Fn(gtk_overlay::OverlayPlacement, Vec<ClipboardItemPreview>, Option<CursorClipError>);
 
 fn run_main_event_loop(
     state: &mut State,
     queue: &mut EventQueue<State>,
+    show: ShowOverlay,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let mut gtk_window_created = false;
-
-    loop {
-        // Process Wayland events
+    // Process Wayland events until the capture layer reports the pointer
+    while !state.coords_received {
         queue.blocking_dispatch(state)?;
+    }
 
-        // Create GTK overlay window when coordinates are received
-        if state.coords_received && !gtk_window_created {
-            let x = state.received_x;
-            let y = state.received_y;
-
-            debug!("Capture layer ready; creating GTK overlay window at ({x}, {y})");
-
-            // Create the GTK window using the unified client backend communication
-            if let Err(e) = gtk_overlay::init_clipboard_overlay(
-                x,
-                y,
-                state.overlay_width,
-                state.overlay_height,
-                state.monitor_width,
-                state.monitor_height,
-                state.clipboard_history.clone(),
-            ) {
-                error!("Error creating GTK overlay: {e:?}");
-            }
+    let x = state.received_x;
+    let y = state.received_y;
+    let output_name = state.capture_output_name();
+    // Without a configured capture size, work out the output's logical size instead
+    let (monitor_width, monitor_height) = if state.monitor_width > 0 && state.monitor_height > 0 {
+        (state.monitor_width, state.monitor_height)
+    } else {
+        state.capture_output_logical_size().unwrap_or((0, 0))
+    };
 
-            gtk_window_created = true;
-        }
+    debug!(
+        "Capture layer ready; creating GTK overlay window at ({x}, {y}) on output {output_name:?}"
+    );
 
-        // Handle close requests
-        if gtk_window_created && (gtk_overlay::is_close_requested() || state.capture_layer_clicked)
-        {
-            gtk_overlay::reset_close_flags();
+    // Create the GTK window using the unified client backend communication
+    let placement = gtk_overlay::OverlayPlacement {
+        x,
+        y,
+        overlay_width: state.overlay_width,
+        overlay_height: state.overlay_height,
+        monitor_width,
+        monitor_height,
+        output_name,
+        anchor: None,
+    };
+    let history = state.clipboard_history.clone();
+    let daemon_problem = state.daemon_problem.clone();
+    // Showing blocks this thread until the overlay closes; keep serving the capture
+    // layer meanwhile so clicks outside the overlay close it
+    let stop_watcher = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        scope.spawn(|| watch_capture_layer(state, queue, &stop_watcher));
+        show(placement, history, daemon_problem);
+        stop_watcher.store(true, Ordering::Relaxed);
+    });
+
+    gtk_overlay::reset_close_flags();
+    cleanup_capture_layer(state);
+    Ok(())
+}
+
+/// Dispatch capture-layer events while GTK runs on the main thread, closing the overlay
+/// on outside clicks unless it is kept open.
+fn watch_capture_layer(state: &mut State, queue: &mut EventQueue<State>, stop: &AtomicBool) {
+    while !stop.load(Ordering::Relaxed) {
+        if let Err(e) = queue.dispatch_pending(state) {
+            warn!("Capture layer watcher stopped: {e}");
+            return;
+        }
+        if std::mem::take(&mut state.capture_layer_clicked) {
+            if gtk_overlay::is_pinned_open() {
+                debug!("Click outside the overlay ignored - kept open");
+            } else {
+                gtk_overlay::request_quit_from_thread();
+            }
+        }
+        if gtk_overlay::is_pinned_open() && state.capture_layer_surface.is_some() {
+            // Let clicks reach other windows while the overlay is kept open
             cleanup_capture_layer(state);
-            break;
         }
 
-        // Process GTK events if window has been created
-        if gtk_window_created {
-            gtk4::glib::MainContext::default().iteration(false);
+        if let Err(e) = queue.flush() {
+            warn!("Capture layer watcher stopped: {e}");
+            return;
+        }
+        let Some(guard) = queue.prepare_read() else {
+            continue;
+        };
+        let mut fds = [libc::pollfd {
+            fd: guard.connection_fd().as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        }];
+        // Wake up regularly to notice the overlay closing
+        let ready = unsafe { libc::poll(fds.as_mut_ptr(), 1, 50) };
+        if ready > 0
+            && let Err(e) = guard.read()
+        {
+            warn!("Capture layer watcher stopped: {e}");
+            return;
         }
     }
+}
 
-    Ok(())
+/// Where the overlay opens.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OverlayPosition {
+    /// At the pointer, found with the transparent capture layer
+    Pointer,
+    /// At fixed surface-local coordinates (`--at X,Y`)
+    At(f64, f64),
+    /// Pinned with layer-shell anchors (`--anchor`)
+    Anchor(gtk_overlay::AnchorPreset),
+    /// Without a capture layer: at the pointer if the compositor reports it, otherwise
+    /// centered (`--keyboard`, `summon_mode = "keyboard"`)
+    Keyboard,
 }
 
 // Frontend always uses its own Wayland connection (may change in future to support shared connection/hide feature)
-pub async fn run_frontend() -> Result<(), Box<dyn std::error::Error>> {
-    let mut state = State::new();
-    // Prefetch clipboard history for instant GTK overlay population
-    if let Ok(mut client) = FrontendClient::new() {
-        match client.get_history() {
-            Ok(items) => {
-                state.clipboard_history = items;
+pub async fn run_frontend(
+    demo: bool,
+    position: OverlayPosition,
+) -> Result<(), Box<dyn std::error::Error>> {
+    open_overlay(demo, position, &|placement, history, daemon_problem| {
+        if let Err(e) = gtk_overlay::init_clipboard_overlay(placement, history, daemon_problem) {
+            error!("Error creating GTK overlay: {e:?}");
+        }
+    })
+}
+
+/// Work out where the overlay goes (fullscreen policy, monitor, pointer position via the
+/// capture layer), prefetch the history and hand both to `show`. Each call uses its
+/// own Wayland connection.
+pub fn open_overlay(
+    demo: bool,
+    position: OverlayPosition,
+    show: ShowOverlay,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (compositor, quirks) = active_quirks();
+    debug!("Running on compositor {compositor}");
+
+    let hyprland_context = if compositor == Compositor::Hyprland {
+        match hyprland::query_context() {
+            Ok(context) => {
                 debug!(
-                    "Prefetched {} clipboard history items",
-                    state.clipboard_history.len()
+                    "Hyprland: focused monitor {:?}, special workspace {:?}, fullscreen {}",
+                    context.monitor, context.special_workspace, context.fullscreen_focused
                 );
+                Some(context)
+            }
+            Err(e) => {
+                warn!("Hyprland IPC unavailable: {e}");
+                None
+            }
+        }
+    } else {
+        None
+    };
+    // Keyboard summon asks the compositor for the pointer instead of capturing it
+    let mut cursor_monitor = None;
+    let position = match position {
+        OverlayPosition::Keyboard if hyprland_context.is_some() => {
+            match hyprland::cursor_position() {
+                Ok((monitor, x, y)) => {
+                    debug!("Hyprland: cursor at ({x}, {y}) on {monitor}");
+                    cursor_monitor = Some(monitor);
+                    OverlayPosition::At(x, y)
+                }
+                Err(e) => {
+                    warn!("Hyprland cursor position unavailable, centering the overlay: {e}");
+                    OverlayPosition::Anchor(gtk_overlay::AnchorPreset::Center)
+                }
             }
-            Err(e) => warn!("Failed to prefetch clipboard history: {e}"),
         }
+        OverlayPosition::Keyboard => OverlayPosition::Anchor(gtk_overlay::AnchorPreset::Center),
+        position => position,
+    };
+
+    // Hyprland reports fullscreen state without a Wayland roundtrip
+    let fullscreen_policy = fullscreen::load_policy();
+    if fullscreen_policy == FullscreenPolicy::Skip
+        && hyprland_context
+            .as_ref()
+            .is_some_and(|context| context.fullscreen_focused)
+    {
+        info!("Focused window is fullscreen; not opening the overlay");
+        return Ok(());
+    }
+
+    let mut state = State::new();
+    (state.overlay_width, state.overlay_height) = gtk_overlay::configured_overlay_size();
+    if demo {
+        // Fixture data only; the overlay works without a running daemon
+        state.clipboard_history = demo_items()
+            .iter()
+            .map(ClipboardItemPreview::from)
+            .collect();
+        debug!(
+            "Demo mode: using {} fixture items",
+            state.clipboard_history.len()
+        );
     } else {
-        warn!("Failed to connect to backend for history prefetch");
+        match connect_or_start_daemon().and_then(|mut client| {
+            client.handshake()?;
+            Ok(client)
+        }) {
+            Ok(mut client) => {
+                // Prefetch the first page for instant GTK overlay population; the rest
+                // is loaded as the list is scrolled
+                match client.get_history_page(
+                    0,
+                    HISTORY_PAGE_SIZE,
+                    HistoryView::All,
+                    gtk_overlay::configured_history_order(),
+                ) {
+                    Ok(page) => {
+                        state.clipboard_history = page.items;
+                        debug!(
+                            "Prefetched {} clipboard history items",
+                            state.clipboard_history.len()
+                        );
+                    }
+                    Err(e) => warn!("Failed to prefetch clipboard history: {e}"),
+                }
+            }
+            Err(e) => {
+                warn!("Clipboard daemon unavailable ({}): {e}", e.code());
+                state.daemon_problem = Some(e);
+            }
+        }
     }
 
     // Initialize Wayland for layer shell capture
@@ -97,11 +267,102 @@ pub async fn run_frontend() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize Wayland protocols
     init_wayland_protocols(&globals, &queue, &mut state)?;
 
+    // Other compositors expose fullscreen state through wlr-foreign-toplevel-management
+    if fullscreen_policy != FullscreenPolicy::Open && hyprland_context.is_none() {
+        match globals.bind::<ZwlrForeignToplevelManagerV1, _, _>(&queue.handle(), 1..=3, ()) {
+            Ok(manager) => state.foreign_toplevel_manager = Some(manager),
+            Err(_) => debug!(
+                "zwlr_foreign_toplevel_manager_v1 not available; cannot detect fullscreen windows"
+            ),
+        }
+    }
+    if hyprland_context.is_some() || state.foreign_toplevel_manager.is_some() {
+        // Collect wl_output names and the initial toplevel states
+        queue.roundtrip(&mut state)?;
+    }
+
+    // Open on the monitor Hyprland reports as focused (or the pointer is on) instead of
+    // letting the compositor pick
+    let focused_output = cursor_monitor
+        .as_deref()
+        .or_else(|| {
+            hyprland_context
+                .as_ref()
+                .and_then(|context| context.monitor.as_deref())
+        })
+        .and_then(|monitor| {
+            let output = state.output_by_name(monitor);
+            if output.is_none() {
+                debug!("No wl_output named {monitor}; letting the compositor choose");
+            }
+            output
+        });
+    let fullscreen_outputs = match &hyprland_context {
+        Some(context) => context
+            .fullscreen_focused
+            .then(|| focused_output.iter().cloned().collect()),
+        None => state.focused_fullscreen_outputs(),
+    };
+
+    let target_output = match (fullscreen_policy, fullscreen_outputs) {
+        (FullscreenPolicy::Open, _) | (_, None) => focused_output,
+        (FullscreenPolicy::Skip, Some(_)) => {
+            info!("Focused window is fullscreen; not opening the overlay");
+            return Ok(());
+        }
+        (FullscreenPolicy::OtherMonitor, Some(busy)) => {
+            match state
+                .outputs
+                .iter()
+                .find(|info| !busy.contains(&info.output))
+            {
+                Some(info) => {
+                    debug!("Focused window is fullscreen; opening on {:?}", info.name);
+                    Some(info.output.clone())
+                }
+                None => {
+                    info!(
+                        "Focused window is fullscreen and there is no other monitor; not opening the overlay"
+                    );
+                    return Ok(());
+                }
+            }
+        }
+    };
+
+    // A fixed position needs no pointer, so skip the capture layer entirely
+    let fixed = match position {
+        OverlayPosition::Pointer | OverlayPosition::Keyboard => None,
+        OverlayPosition::At(x, y) => Some((x, y, None)),
+        OverlayPosition::Anchor(anchor) => Some((0.0, 0.0, Some(anchor))),
+    };
+    if let Some((x, y, anchor)) = fixed {
+        state.capture_output = target_output;
+        let placement = gtk_overlay::OverlayPlacement {
+            x,
+            y,
+            overlay_width: state.overlay_width,
+            overlay_height: state.overlay_height,
+            monitor_width: 0,
+            monitor_height: 0,
+            output_name: state.capture_output_name(),
+            anchor,
+        };
+        show(placement, state.clipboard_history, state.daemon_problem);
+        gtk_overlay::reset_close_flags();
+        return Ok(());
+    }
+
     // Create capture surfaces for mouse coordinate detection
-    setup_capture_layer(&mut state, &queue);
+    setup_capture_layer(
+        &mut state,
+        &queue,
+        quirks.capture_layer,
+        target_output.as_ref(),
+    );
 
     // Main event loop (reuse existing implementation)
-    run_main_event_loop(&mut state, &mut queue)
+    run_main_event_loop(&mut state, &mut queue, show)
 }
 
 fn init_wayland_protocols(
@@ -146,6 +407,25 @@ fn init_wayland_protocols(
         std::process::exit(1);
     }
 
+    // Bind every wl_output so we know which monitor the pointer is on (v4 adds connector names)
+    for global in globals.contents().clone_list() {
+        if global.interface == "wl_output" {
+            let output = globals.registry().bind::<wl_output::WlOutput, _, _>(
+                global.name,
+                global.version.min(4),
+                &queue.handle(),
+                (),
+            );
+            state.outputs.push(OutputInfo {
+                output,
+                name: None,
+                scale: 1,
+                physical_size: None,
+                transposed: false,
+            });
+        }
+    }
+
     // Bind wp_viewporter
     if let Ok(viewporter) =
         globals.bind::<wp_viewporter::WpViewporter, _, _>(&queue.handle(), 1..=1, ())
@@ -155,6 +435,19 @@ fn init_wayland_protocols(
         debug!("wp_viewporter not available");
     }
 
+    // Bind wp_fractional_scale_manager_v1 to learn the capture output's exact scale
+    if let Ok(manager) = globals
+        .bind::<wp_fractional_scale_manager_v1::WpFractionalScaleManagerV1, _, _>(
+            &queue.handle(),
+            1..=1,
+            (),
+        )
+    {
+        state.fractional_scale_manager = Some(manager);
+    } else {
+        debug!("wp_fractional_scale_manager_v1 not available");
+    }
+
     // Bind wp_single_pixel_buffer_manager_v1 (preferred path)
     if let Ok(single_pixel_buffer_manager) =
         globals.bind::<wp_single_pixel_buffer_manager_v1::WpSinglePixelBufferManagerV1, _, _>(
@@ -180,7 +473,12 @@ fn init_wayland_protocols(
     Ok(())
 }
 
-fn setup_capture_layer(state: &mut State, queue: &EventQueue<State>) {
+fn setup_capture_layer(
+    state: &mut State,
+    queue: &EventQueue<State>,
+    layer: CaptureLayer,
+    output: Option<&wl_output::WlOutput>,
+) {
     // Limit the borrow of state by cloning the compositor proxy
     {
         let compositor = state
@@ -192,6 +490,10 @@ fn setup_capture_layer(state: &mut State, queue: &EventQueue<State>) {
         let capture_surface = compositor.create_surface(&queue.handle(), ());
         let update_surface = compositor.create_surface(&queue.handle(), ());
 
+        if let Some(manager) = &state.fractional_scale_manager {
+            state.capture_fractional_scale =
+                Some(manager.get_fractional_scale(&capture_surface, &queue.handle(), ()));
+        }
         state.capture_surface = Some(capture_surface.clone());
         state.update_surface = Some(update_surface);
     }
@@ -214,8 +516,11 @@ fn setup_capture_layer(state: &mut State, queue: &EventQueue<State>) {
 
     let capture_layer_surface = layer_shell.get_layer_surface(
         capture_surface_ref,
-        None,
-        zwlr_layer_shell_v1::Layer::Overlay,
+        output,
+        match layer {
+            CaptureLayer::Overlay => zwlr_layer_shell_v1::Layer::Overlay,
+            CaptureLayer::Top => zwlr_layer_shell_v1::Layer::Top,
+        },
         "cursor-clip-capture".to_string(),
         &queue.handle(),
         (),
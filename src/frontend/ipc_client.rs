@@ -1,103 +1,749 @@
-use crate::shared::{BackendMessage, ClipboardItemPreview, FrontendMessage};
-use std::io::{BufRead, BufReader, Write};
+use crate::shared::runtime_dir::{legacy_socket_path, socket_path};
+use crate::shared::{
+    BackendMessage, BackendResponse, ClipboardItemPreview, CursorClipError, CursorClipResult,
+    DaemonSettings, DaemonStatus, ExportFormat, FrontendMessage, HistoryDelta, HistoryOrder,
+    HistoryPage, HistoryStats, HistoryView, ItemDetails, MergeSeparator, MimeFilter, SearchResult,
+    Snippet, TextTransform,
+};
+use bytes::Bytes;
+use indexmap::IndexMap;
+use log::debug;
+use std::cell::RefCell;
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
 use std::os::unix::net::UnixStream;
+use std::time::Duration;
+
+/// Pauses before reconnecting after the daemon went away; one retry per entry
+const RECONNECT_BACKOFF: [Duration; 3] = [
+    Duration::from_millis(50),
+    Duration::from_millis(150),
+    Duration::from_millis(400),
+];
+
+/// How long a freshly started daemon may take to bind the compositor's clipboard
+const HANDSHAKE_STARTUP_GRACE: Duration = Duration::from_secs(2);
+const HANDSHAKE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+type DisconnectHandler = Box<dyn Fn(&CursorClipError)>;
+
+thread_local! {
+    // One long-lived connection per thread; all GTK handlers of the overlay share it
+    static SHARED_CLIENT: RefCell<Option<FrontendClient>> = const { RefCell::new(None) };
+    static DISCONNECT_HANDLER: RefCell<Option<DisconnectHandler>> = const { RefCell::new(None) };
+}
+
+/// Run `request` on this thread's shared connection. Connects lazily; if an established
+/// connection breaks (daemon restarted or went away), reconnects with backoff and
+/// retries the request. Once a command that must not run twice
+/// (`FrontendMessage::is_idempotent`) was sent, the daemon may have run it even though
+/// its reply was lost, so it is not retried and the error is returned instead.
+pub fn with_client<T>(
+    mut request: impl FnMut(&mut FrontendClient) -> CursorClipResult<T>,
+) -> CursorClipResult<T> {
+    let mut retries = RECONNECT_BACKOFF.iter();
+    let mut reconnecting = false;
+    loop {
+        let (result, was_connected, maybe_ran) = SHARED_CLIENT.with_borrow_mut(|slot| {
+            let was_connected = slot.is_some();
+            if slot.is_none() {
+                match FrontendClient::new() {
+                    Ok(client) => *slot = Some(client),
+                    Err(e) => return (Err(e), false, false),
+                }
+            }
+            let client = slot.as_mut().expect("connected above");
+            let result = request(client);
+            let maybe_ran = client.unanswered_request;
+            // The stream is dead or out of step with the daemon; start over next time
+            if let Err(
+                CursorClipError::DaemonNotRunning
+                | CursorClipError::Io(_)
+                | CursorClipError::Protocol(_),
+            ) = &result
+            {
+                *slot = None;
+            }
+            (result, was_connected, maybe_ran)
+        });
+        match result {
+            // Only a broken connection is retried; a daemon that was never reachable
+            // would just delay every action
+            Err(e) if e.is_connection_error() && (was_connected || reconnecting) && !maybe_ran => {
+                match retries.next() {
+                    Some(delay) => {
+                        debug!("Daemon connection lost ({e}); reconnecting in {delay:?}");
+                        reconnecting = true;
+                        std::thread::sleep(*delay);
+                    }
+                    None => {
+                        DISCONNECT_HANDLER.with_borrow(|handler| {
+                            if let Some(handler) = handler {
+                                handler(&e);
+                            }
+                        });
+                        return Err(e);
+                    }
+                }
+            }
+            result => return result,
+        }
+    }
+}
+
+/// History revision last seen on this thread's shared connection, if any.
+pub fn last_history_revision() -> Option<u64> {
+    SHARED_CLIENT.with_borrow(|slot| slot.as_ref().and_then(FrontendClient::history_revision))
+}
+
+/// Called on this thread when reconnecting to a daemon that went away failed.
+pub fn set_disconnect_handler(handler: impl Fn(&CursorClipError) + 'static) {
+    DISCONNECT_HANDLER.set(Some(Box::new(handler)));
+}
+
+/// Reply to `FrontendClient::get_history_since`
+pub enum HistoryUpdate {
+    Delta(HistoryDelta),
+    Full(Vec<ClipboardItemPreview>),
+}
+
+/// Byte stream the client speaks the IPC protocol over: the daemon's Unix socket, or an
+/// in-memory stream standing in for the daemon
+pub trait IpcTransport: Read + Write + Send {}
+
+impl<T: Read + Write + Send> IpcTransport for T {}
 
 /// Frontend client for communicating with the backend
 pub struct FrontendClient {
-    stream: UnixStream,
+    // Buffered for the whole connection: a reply and the events after it may arrive in
+    // the same read
+    stream: BufReader<Box<dyn IpcTransport>>,
+    // History revision of the last reply, if the daemon sends one
+    history_revision: Option<u64>,
+    // A request that must not run twice was sent and its reply has not arrived
+    unanswered_request: bool,
 }
 
 impl FrontendClient {
     /// Create a new client
-    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        let xdg_runtime_dir = std::env::var("XDG_RUNTIME_DIR")?;
-        let socket_path = format!("{xdg_runtime_dir}/cursor-clip/cursor-clip.sock");
-        let stream = UnixStream::connect(socket_path)?;
-        Ok(Self { stream })
+    pub fn new() -> CursorClipResult<Self> {
+        let (Some(socket_path), Some(legacy_path)) = (socket_path(), legacy_socket_path()) else {
+            return Err(CursorClipError::Config("XDG_RUNTIME_DIR is not set".into()));
+        };
+        // A daemon from an earlier release still listens on the old name
+        let stream = UnixStream::connect(&socket_path)
+            .or_else(|e| UnixStream::connect(legacy_path).map_err(|_| e))
+            .map_err(|e| match e.kind() {
+                ErrorKind::NotFound | ErrorKind::ConnectionRefused => {
+                    CursorClipError::DaemonNotRunning
+                }
+                _ => e.into(),
+            })?;
+        Ok(Self::with_transport(stream))
+    }
+
+    /// Create a client speaking over `transport` instead of the daemon's socket
+    pub fn with_transport(transport: impl IpcTransport + 'static) -> Self {
+        Self {
+            stream: BufReader::new(Box::new(transport)),
+            history_revision: None,
+            unanswered_request: false,
+        }
     }
 
     /// Send a message and get response
-    pub fn send_message(
-        &mut self,
-        message: FrontendMessage,
-    ) -> Result<BackendMessage, Box<dyn std::error::Error>> {
-        let message_json = serde_json::to_string(&message)?;
-        self.stream.write_all(message_json.as_bytes())?;
-        self.stream.write_all(b"\n")?;
+    pub fn send_message(&mut self, message: FrontendMessage) -> CursorClipResult<BackendMessage> {
+        self.write_message(&message)?;
+        self.unanswered_request = !message.is_idempotent();
+        let line = self
+            .read_line()?
+            .ok_or_else(|| CursorClipError::Io("The daemon closed the connection".into()))?;
+        self.unanswered_request = false;
+        let response = BackendResponse::from_json(line.trim())?;
+        if response.revision.is_some() {
+            self.history_revision = response.revision;
+        }
+        Ok(response.message)
+    }
 
-        let mut reader = BufReader::new(&self.stream);
-        let mut line = String::new();
-        reader.read_line(&mut line)?;
+    /// Revision of the daemon's history as of the last reply; a `HistoryChanged` event
+    /// with a higher one means another client changed it since
+    pub fn history_revision(&self) -> Option<u64> {
+        self.history_revision
+    }
+
+    /// Turn this connection into a stream of backend events (`Subscribe`). The iterator
+    /// blocks until the next event and ends when the daemon goes away.
+    pub fn subscribe(mut self) -> CursorClipResult<impl Iterator<Item = BackendMessage>> {
+        match self.send_message(FrontendMessage::Subscribe)? {
+            BackendMessage::Subscribed => {}
+            BackendMessage::Error { error } => return Err(error),
+            _ => return Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+        Ok(self
+            .stream
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str(line.trim()).ok()))
+    }
+
+    /// Write one request as a JSON line
+    fn write_message(&mut self, message: &FrontendMessage) -> CursorClipResult<()> {
+        let message_json = serde_json::to_string(message)?;
+        let stream = self.stream.get_mut();
+        stream.write_all(message_json.as_bytes())?;
+        stream.write_all(b"\n")?;
+        stream.flush()?;
+        Ok(())
+    }
 
-        let response: BackendMessage = serde_json::from_str(line.trim())?;
-        Ok(response)
+    /// Read the next line from the daemon, or None once it closed the connection
+    fn read_line(&mut self) -> CursorClipResult<Option<String>> {
+        let mut line = String::new();
+        if self.stream.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(line))
     }
 
     /// Get clipboard history
-    pub fn get_history(&mut self) -> Result<Vec<ClipboardItemPreview>, Box<dyn std::error::Error>> {
+    pub fn get_history(&mut self) -> CursorClipResult<Vec<ClipboardItemPreview>> {
         let response = self.send_message(FrontendMessage::GetHistory)?;
         match response {
             BackendMessage::History { items } => Ok(items),
-            BackendMessage::Error { message } => Err(message.into()),
-            _ => Err("Unexpected response".into()),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Get the changes to the history since `since_revision`, or the whole history if
+    /// the daemon can't tell them
+    pub fn get_history_since(&mut self, since_revision: u64) -> CursorClipResult<HistoryUpdate> {
+        let response = self.send_message(FrontendMessage::GetHistorySince { since_revision })?;
+        match response {
+            BackendMessage::HistoryDelta { delta } => Ok(HistoryUpdate::Delta(delta)),
+            BackendMessage::History { items } => Ok(HistoryUpdate::Full(items)),
+            // A daemon of an earlier release does not know the request
+            BackendMessage::Error {
+                error: CursorClipError::InvalidRequest(_),
+            } => self.get_history().map(HistoryUpdate::Full),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
         }
     }
 
-    /// Set clipboard by ID
+    /// Get `limit` items of `view` in `order` starting at `offset`
+    pub fn get_history_page(
+        &mut self,
+        offset: usize,
+        limit: usize,
+        view: HistoryView,
+        order: HistoryOrder,
+    ) -> CursorClipResult<HistoryPage> {
+        let response = self.send_message(FrontendMessage::GetHistoryPage {
+            offset,
+            limit,
+            view,
+            order,
+        })?;
+        match response {
+            BackendMessage::HistoryPage { page } => Ok(page),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Get aggregate numbers about the history
+    pub fn get_stats(&mut self) -> CursorClipResult<HistoryStats> {
+        let response = self.send_message(FrontendMessage::GetStats)?;
+        match response {
+            BackendMessage::Stats { stats } => Ok(stats),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Get the full text and MIME overview of one item
+    pub fn get_item_details(&mut self, id: u64) -> CursorClipResult<ItemDetails> {
+        let response = self.send_message(FrontendMessage::GetItemDetails { id })?;
+        match response {
+            BackendMessage::ItemDetails { details } => Ok(details),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Get up to `max_bytes` of an item's text (the daemon's default without it), and
+    /// whether it was cut short
+    pub fn get_item_full_preview(
+        &mut self,
+        id: u64,
+        max_bytes: Option<usize>,
+    ) -> CursorClipResult<(String, bool)> {
+        let response = self.send_message(FrontendMessage::GetItemFullPreview { id, max_bytes })?;
+        match response {
+            BackendMessage::ItemFullPreview {
+                text, truncated, ..
+            } => Ok((text, truncated)),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Get the payload of one item in `mime_type`
+    pub fn get_item_data(&mut self, id: u64, mime_type: &str) -> CursorClipResult<Bytes> {
+        let response = self.send_message(FrontendMessage::GetItemData {
+            id,
+            mime_type: mime_type.to_string(),
+        })?;
+        match response {
+            BackendMessage::ItemData { data, .. } => Ok(data),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Record `mime_data` as a new history item, optionally pinned and set as the
+    /// selection, and return its ID
+    pub fn add_item(
+        &mut self,
+        mime_data: IndexMap<String, Bytes>,
+        pin: bool,
+        select: bool,
+    ) -> CursorClipResult<u64> {
+        let response = self.send_message(FrontendMessage::AddItem {
+            mime_data,
+            pin,
+            select,
+        })?;
+        match response {
+            BackendMessage::ItemAdded { id } => Ok(id),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// The item restored from a crash backup at daemon startup, if it is still on offer
+    pub fn get_recovered_item(&mut self) -> CursorClipResult<Option<u64>> {
+        let response = self.send_message(FrontendMessage::GetRecoveredItem)?;
+        match response {
+            BackendMessage::RecoveredItem { id } => Ok(id),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Stop offering the recovered item
+    pub fn dismiss_recovered_item(&mut self) -> CursorClipResult<()> {
+        let response = self.send_message(FrontendMessage::DismissRecoveredItem)?;
+        match response {
+            BackendMessage::RecoveredItem { .. } => Ok(()),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Set clipboard by ID (on `seat`, or the item's originating seat when `None`),
+    /// optionally offering only the MIME types allowed by `mime_filter`
     pub fn set_clipboard_by_id(
         &mut self,
         id: u64,
         instant_paste: bool,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let response =
-            self.send_message(FrontendMessage::SetClipboardById { id, instant_paste })?;
+        seat: Option<String>,
+        mime_filter: Option<MimeFilter>,
+    ) -> CursorClipResult<()> {
+        let response = self.send_message(FrontendMessage::SetClipboardById {
+            id,
+            instant_paste,
+            seat,
+            mime_filter,
+        })?;
+        match response {
+            BackendMessage::ClipboardSet => Ok(()),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Offer an item as the primary selection (middle-click paste)
+    pub fn set_primary_by_id(&mut self, id: u64) -> CursorClipResult<()> {
+        let response = self.send_message(FrontendMessage::SetPrimaryById { id, seat: None })?;
         match response {
             BackendMessage::ClipboardSet => Ok(()),
-            BackendMessage::Error { message } => Err(message.into()),
-            _ => Err("Unexpected response".into()),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Set the selection to a transformed copy of an item; returns the new history entry
+    pub fn transform_and_set(
+        &mut self,
+        id: u64,
+        transform: TextTransform,
+        instant_paste: bool,
+        seat: Option<String>,
+    ) -> CursorClipResult<ClipboardItemPreview> {
+        let response = self.send_message(FrontendMessage::TransformAndSet {
+            id,
+            transform,
+            instant_paste,
+            seat,
+        })?;
+        match response {
+            BackendMessage::ItemTransformed { item, .. } => Ok(item),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Set the selection to the text of `ids` joined by `separator`; returns the new
+    /// history entry
+    pub fn merge_and_set(
+        &mut self,
+        ids: Vec<u64>,
+        separator: MergeSeparator,
+        instant_paste: bool,
+        seat: Option<String>,
+    ) -> CursorClipResult<ClipboardItemPreview> {
+        let response = self.send_message(FrontendMessage::MergeAndSet {
+            ids,
+            separator,
+            instant_paste,
+            seat,
+        })?;
+        match response {
+            BackendMessage::ItemsMerged { item, .. } => Ok(item),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Append items to the paste queue; returns the queue
+    pub fn push_paste_queue(&mut self, ids: Vec<u64>) -> CursorClipResult<Vec<u64>> {
+        let response = self.send_message(FrontendMessage::PushPasteQueue { ids, seat: None })?;
+        Self::paste_queue_response(response)
+    }
+
+    /// Skip the paste queue's front; returns the remaining queue
+    pub fn pop_paste_queue(&mut self, instant_paste: bool) -> CursorClipResult<Vec<u64>> {
+        let response = self.send_message(FrontendMessage::PopPasteQueue {
+            instant_paste,
+            seat: None,
+        })?;
+        Self::paste_queue_response(response)
+    }
+
+    /// Get the item IDs in the paste queue, next first
+    pub fn get_paste_queue(&mut self) -> CursorClipResult<Vec<u64>> {
+        let response = self.send_message(FrontendMessage::GetPasteQueue)?;
+        Self::paste_queue_response(response)
+    }
+
+    /// Empty the paste queue
+    pub fn clear_paste_queue(&mut self) -> CursorClipResult<()> {
+        let response = self.send_message(FrontendMessage::ClearPasteQueue)?;
+        Self::paste_queue_response(response).map(|_| ())
+    }
+
+    /// Re-run content-type classification over the history; returns how many items changed
+    pub fn reclassify_history(&mut self) -> CursorClipResult<usize> {
+        match self.send_message(FrontendMessage::ReclassifyHistory)? {
+            BackendMessage::HistoryReclassified { changed } => Ok(changed),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    fn paste_queue_response(response: BackendMessage) -> CursorClipResult<Vec<u64>> {
+        match response {
+            BackendMessage::PasteQueue { ids } => Ok(ids),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
         }
     }
 
     /// Set pinned state by ID
-    pub fn set_pinned(&mut self, id: u64, pinned: bool) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn set_pinned(&mut self, id: u64, pinned: bool) -> CursorClipResult<()> {
         let response = self.send_message(FrontendMessage::SetPinned { id, pinned })?;
         match response {
             BackendMessage::ItemPinned { .. } => Ok(()),
-            BackendMessage::Error { message } => Err(message.into()),
-            _ => Err("Unexpected response".into()),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
         }
     }
 
     /// Clear history
-    pub fn clear_history(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn clear_history(&mut self) -> CursorClipResult<()> {
         let response = self.send_message(FrontendMessage::ClearHistory)?;
         match response {
             BackendMessage::HistoryCleared => Ok(()),
-            BackendMessage::Error { message } => Err(message.into()),
-            _ => Err("Unexpected response".into()),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Restore the items of the last delete or Clear All; returns how many came back
+    pub fn undo_last_operation(&mut self) -> CursorClipResult<usize> {
+        let response = self.send_message(FrontendMessage::UndoLastOperation)?;
+        match response {
+            BackendMessage::ItemsRestored { count } => Ok(count),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
         }
     }
 
     /// Enable or disable persistent history in the backend
-    pub fn set_persistence_enabled(
-        &mut self,
-        enabled: bool,
-    ) -> Result<bool, Box<dyn std::error::Error>> {
+    pub fn set_persistence_enabled(&mut self, enabled: bool) -> CursorClipResult<bool> {
         let response = self.send_message(FrontendMessage::SetHistoryPersistence { enabled })?;
         match response {
             BackendMessage::PersistenceState { enabled } => Ok(enabled),
-            BackendMessage::Error { message } => Err(message.into()),
-            _ => Err("Unexpected response".into()),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Get a health snapshot of the daemon
+    pub fn get_status(&mut self) -> CursorClipResult<DaemonStatus> {
+        let response = self.send_message(FrontendMessage::Status)?;
+        match response {
+            BackendMessage::Status { status } => Ok(status),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Check that the daemon can actually reach the clipboard before the overlay relies on
+    /// it. A daemon without a bound data-control manager (or X11 selection) fails with
+    /// `ClipboardUnavailable`; one that just started gets a moment to bind first.
+    pub fn handshake(&mut self) -> CursorClipResult<DaemonStatus> {
+        loop {
+            let status = self.get_status()?;
+            if status.protocol.is_some() {
+                return Ok(status);
+            }
+            if status.uptime_secs >= HANDSHAKE_STARTUP_GRACE.as_secs() {
+                return Err(CursorClipError::ClipboardUnavailable(
+                    "The compositor offers no clipboard protocol the daemon can use \
+                    (ext-data-control-v1 or wlr-data-control-unstable-v1)"
+                        .into(),
+                ));
+            }
+            std::thread::sleep(HANDSHAKE_POLL_INTERVAL);
+        }
+    }
+
+    /// Pause (false) or resume (true) recording of new copies
+    pub fn set_recording_enabled(&mut self, enabled: bool) -> CursorClipResult<bool> {
+        let response = self.send_message(FrontendMessage::SetRecordingEnabled { enabled })?;
+        Self::recording_state_response(response)
+    }
+
+    /// Whether the daemon records new copies (false while paused)
+    pub fn get_recording_state(&mut self) -> CursorClipResult<bool> {
+        let response = self.send_message(FrontendMessage::GetRecordingState)?;
+        Self::recording_state_response(response)
+    }
+
+    fn recording_state_response(response: BackendMessage) -> CursorClipResult<bool> {
+        match response {
+            BackendMessage::RecordingState { enabled } => Ok(enabled),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Have the daemon write the history to `path` (absolute). Returns the item count.
+    pub fn export_history(
+        &mut self,
+        path: String,
+        format: ExportFormat,
+        include_sensitive: bool,
+    ) -> CursorClipResult<usize> {
+        let response = self.send_message(FrontendMessage::ExportHistory {
+            path,
+            format,
+            include_sensitive,
+        })?;
+        match response {
+            BackendMessage::HistoryExported { count, .. } => Ok(count),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Merge an export into the daemon's history. Returns (imported, skipped).
+    pub fn import_history(&mut self, path: String) -> CursorClipResult<(usize, usize)> {
+        let response = self.send_message(FrontendMessage::ImportHistory { path })?;
+        match response {
+            BackendMessage::HistoryImported { imported, skipped } => Ok((imported, skipped)),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Fuzzy-search the history; best matches first
+    pub fn search_history(
+        &mut self,
+        query: String,
+        view: HistoryView,
+        limit: usize,
+    ) -> CursorClipResult<Vec<SearchResult>> {
+        let response = self.send_message(FrontendMessage::SearchHistory { query, view, limit })?;
+        match response {
+            BackendMessage::SearchResults { results } => Ok(results),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Get the snippet library
+    pub fn get_snippets(&mut self) -> CursorClipResult<Vec<Snippet>> {
+        let response = self.send_message(FrontendMessage::GetSnippets)?;
+        Self::expect_snippets(response)
+    }
+
+    /// Append a snippet; returns the updated library
+    pub fn add_snippet(&mut self, snippet: Snippet) -> CursorClipResult<Vec<Snippet>> {
+        let response = self.send_message(FrontendMessage::AddSnippet { snippet })?;
+        Self::expect_snippets(response)
+    }
+
+    /// Remove the snippet at position `id`; returns the updated library
+    pub fn remove_snippet(&mut self, id: usize) -> CursorClipResult<Vec<Snippet>> {
+        let response = self.send_message(FrontendMessage::RemoveSnippet { id })?;
+        Self::expect_snippets(response)
+    }
+
+    fn expect_snippets(response: BackendMessage) -> CursorClipResult<Vec<Snippet>> {
+        match response {
+            BackendMessage::Snippets { snippets } => Ok(snippets),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Expand a snippet and set it as the selection
+    pub fn paste_snippet(
+        &mut self,
+        id: usize,
+        instant_paste: bool,
+        seat: Option<String>,
+    ) -> CursorClipResult<()> {
+        let response = self.send_message(FrontendMessage::PasteSnippet {
+            id,
+            instant_paste,
+            seat,
+        })?;
+        match response {
+            BackendMessage::ClipboardSet => Ok(()),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Get the recently used emoji, most recent first
+    pub fn get_recent_emoji(&mut self) -> CursorClipResult<Vec<String>> {
+        let response = self.send_message(FrontendMessage::GetRecentEmoji)?;
+        match response {
+            BackendMessage::RecentEmoji { emoji } => Ok(emoji),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Record an emoji in the history and set it as the selection
+    pub fn paste_emoji(
+        &mut self,
+        emoji: String,
+        instant_paste: bool,
+        seat: Option<String>,
+    ) -> CursorClipResult<()> {
+        let response = self.send_message(FrontendMessage::PasteEmoji {
+            emoji,
+            instant_paste,
+            seat,
+        })?;
+        match response {
+            BackendMessage::ClipboardSet => Ok(()),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Get the runtime-changeable daemon settings
+    pub fn get_config(&mut self) -> CursorClipResult<DaemonSettings> {
+        let response = self.send_message(FrontendMessage::GetConfig)?;
+        match response {
+            BackendMessage::Config { settings } => Ok(settings),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
+        }
+    }
+
+    /// Replace the daemon settings; the daemon applies and persists them
+    pub fn set_config(&mut self, settings: DaemonSettings) -> CursorClipResult<DaemonSettings> {
+        let response = self.send_message(FrontendMessage::SetConfig { settings })?;
+        match response {
+            BackendMessage::Config { settings } => Ok(settings),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
         }
     }
 
     /// Delete a single clipboard item by ID
-    pub fn delete_item_by_id(&mut self, id: u64) -> Result<(), Box<dyn std::error::Error>> {
+    pub fn delete_item_by_id(&mut self, id: u64) -> CursorClipResult<()> {
         let response = self.send_message(FrontendMessage::DeleteItemById { id })?;
         match response {
             BackendMessage::ItemDeleted { .. } => Ok(()),
-            BackendMessage::Error { message } => Err(message.into()),
-            _ => Err("Unexpected response".into()),
+            BackendMessage::Error { error } => Err(error),
+            _ => Err(CursorClipError::Protocol("Unexpected response".into())),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Send `message` to a daemon that reads it and goes away without replying
+    fn send_unanswered(message: FrontendMessage) -> FrontendClient {
+        let (client_end, daemon_end) = UnixStream::pair().unwrap();
+        let mut client = FrontendClient::with_transport(client_end);
+        let daemon = std::thread::spawn(move || {
+            let mut request = String::new();
+            BufReader::new(daemon_end).read_line(&mut request).unwrap();
+        });
+        let result = client.send_message(message);
+        daemon.join().unwrap();
+        assert!(matches!(result, Err(CursorClipError::Io(_))));
+        client
+    }
+
+    #[test]
+    fn lost_reply_to_a_read_may_be_retried() {
+        assert!(!send_unanswered(FrontendMessage::GetHistory).unanswered_request);
+    }
+
+    #[test]
+    fn lost_reply_to_a_change_is_not_retried() {
+        assert!(send_unanswered(FrontendMessage::ClearHistory).unanswered_request);
+        assert!(send_unanswered(FrontendMessage::UndoLastOperation).unanswered_request);
+    }
+
+    #[test]
+    fn answered_request_leaves_nothing_unanswered() {
+        let (client_end, daemon_end) = UnixStream::pair().unwrap();
+        let mut client = FrontendClient::with_transport(client_end);
+        let daemon = std::thread::spawn(move || {
+            let mut daemon_end = BufReader::new(daemon_end);
+            let mut request = String::new();
+            daemon_end.read_line(&mut request).unwrap();
+            daemon_end
+                .get_mut()
+                .write_all(b"{\"revision\":7,\"ItemAdded\":{\"id\":3}}\n")
+                .unwrap();
+        });
+        let _ = client.send_message(FrontendMessage::ClearHistory);
+        daemon.join().unwrap();
+        assert!(!client.unanswered_request);
+    }
+}
@@ -0,0 +1,52 @@
+use gtk4::glib;
+use gtk4::subclass::prelude::*;
+use std::cell::RefCell;
+
+use crate::shared::ClipboardItemPreview;
+
+mod imp {
+    use super::*;
+
+    #[derive(Default)]
+    pub struct ClipboardItemObject {
+        pub item: RefCell<Option<ClipboardItemPreview>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for ClipboardItemObject {
+        const NAME: &'static str = "CursorClipItemObject";
+        type Type = super::ClipboardItemObject;
+    }
+
+    impl ObjectImpl for ClipboardItemObject {}
+}
+
+glib::wrapper! {
+    /// GObject wrapper so history items can live in a `gio::ListStore` behind the overlay's ListView.
+    pub struct ClipboardItemObject(ObjectSubclass<imp::ClipboardItemObject>);
+}
+
+impl ClipboardItemObject {
+    pub fn new(item: ClipboardItemPreview) -> Self {
+        let object: Self = glib::Object::new();
+        object.imp().item.replace(Some(item));
+        object
+    }
+
+    /// A copy of the wrapped preview.
+    pub fn item(&self) -> ClipboardItemPreview {
+        self.imp()
+            .item
+            .borrow()
+            .clone()
+            .expect("ClipboardItemObject is always created with an item")
+    }
+
+    pub fn item_id(&self) -> u64 {
+        self.imp()
+            .item
+            .borrow()
+            .as_ref()
+            .map_or(0, |item| item.item_id)
+    }
+}
@@ -1,7 +1,21 @@
+pub mod autostart;
 pub mod dispatch;
+pub mod dmenu;
+pub mod drag_content;
+pub mod emoji_picker;
 pub mod frontend_state;
+pub mod fullscreen;
 pub mod gtk_overlay;
+pub mod hyprland;
 pub mod initializer;
 pub mod ipc_client;
+pub mod item_object;
+pub mod renderer;
+pub mod resident;
+pub mod scaling;
+pub mod shortcuts;
+pub mod status_bar;
+pub mod summon;
+pub mod syntax_highlight;
 
 pub use initializer::*;
@@ -0,0 +1,158 @@
+use gtk4::glib;
+use gtk4::prelude::*;
+use log::{debug, error, info, warn};
+use serde::Deserialize;
+use std::cell::Cell;
+use std::fs;
+use std::os::unix::process::CommandExt;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::shared::config_dir::config_path;
+use crate::shared::quirks::active_quirks;
+
+/// How long a freshly mapped overlay may go without presenting frames before the
+/// automatic renderer choice gives up on GTK's default renderer
+const FIRST_FRAME_TIMEOUT: Duration = Duration::from_secs(2);
+/// Frames that prove the compositor received buffers: each one after the first waits
+/// for the frame callback of the previous commit
+const FRAMES_FOR_HEALTHY_RENDERER: u32 = 3;
+
+/// Set when GTK picks its default renderer, which is then checked on the first showing
+static WATCH_DEFAULT_RENDERER: AtomicBool = AtomicBool::new(false);
+
+/// GSK renderer for the overlay (`--renderer`, `renderer` in config.toml).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RendererChoice {
+    /// The compositor quirk if there is one, else GTK's default (usually Vulkan), falling
+    /// back to GL if that shows nothing
+    #[default]
+    Auto,
+    Gl,
+    Cairo,
+}
+
+impl RendererChoice {
+    pub const NAMES: [&'static str; 3] = ["auto", "gl", "cairo"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(Self::Auto),
+            "gl" => Some(Self::Gl),
+            "cairo" => Some(Self::Cairo),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ConfigWithRenderer {
+    renderer: RendererChoice,
+}
+
+fn load_choice() -> RendererChoice {
+    let Ok(contents) = fs::read_to_string(config_path()) else {
+        return RendererChoice::default();
+    };
+    toml::from_str::<ConfigWithRenderer>(&contents)
+        .map(|config| config.renderer)
+        .unwrap_or_else(|e| {
+            warn!("Failed to parse renderer from config, choosing automatically: {e}");
+            RendererChoice::default()
+        })
+}
+
+/// Set `GSK_RENDERER` before GTK starts. A value already in the environment wins, then
+/// `choice` (from `--renderer`), then `renderer` in config.toml, then the compositor
+/// quirk. With nothing chosen, GTK's default renderer is watched by `watch_first_frames`.
+pub fn select_renderer(choice: Option<RendererChoice>) {
+    if let Some(renderer) = std::env::var_os("GSK_RENDERER") {
+        debug!(
+            "Using GSK renderer {} (GSK_RENDERER)",
+            renderer.to_string_lossy()
+        );
+        return;
+    }
+    let renderer = match choice.unwrap_or_else(load_choice) {
+        RendererChoice::Gl => "gl".to_string(),
+        RendererChoice::Cairo => "cairo".to_string(),
+        RendererChoice::Auto => match active_quirks().1.gsk_renderer {
+            Some(renderer) => {
+                debug!("Using GSK renderer {renderer} (compositor quirk)");
+                renderer
+            }
+            None => {
+                WATCH_DEFAULT_RENDERER.store(true, Ordering::Relaxed);
+                return;
+            }
+        },
+    };
+    debug!("Using GSK renderer {renderer}");
+    // SAFETY: runs before GTK is initialised and before any thread of ours reads the
+    // environment; the tokio worker threads never touch it.
+    unsafe { std::env::set_var("GSK_RENDERER", renderer) };
+}
+
+/// Log the renderer GTK created for `window`. If GTK picked its default renderer, check
+/// that the first showing presents frames and restart with the GL renderer if it
+/// doesn't: Vulkan on some drivers (e.g. RADV) never commits a buffer to the layer surface.
+pub fn watch_first_frames(window: &impl IsA<gtk4::Widget>) {
+    window.connect_realize(|window| {
+        if let Some(renderer) = window.native().and_then(|native| native.renderer()) {
+            info!("Overlay renders with {}", renderer.type_().name());
+        }
+    });
+    if !WATCH_DEFAULT_RENDERER.load(Ordering::Relaxed) {
+        return;
+    }
+    let checked = Cell::new(false);
+    window.connect_map(move |window| {
+        if checked.replace(true) {
+            return;
+        }
+        // The frame clock only advances once the compositor acknowledged a commit
+        let frames = Rc::new(Cell::new(0u32));
+        let tick = {
+            let frames = frames.clone();
+            window.add_tick_callback(move |_, _| {
+                frames.set(frames.get() + 1);
+                if frames.get() >= FRAMES_FOR_HEALTHY_RENDERER {
+                    glib::ControlFlow::Break
+                } else {
+                    glib::ControlFlow::Continue
+                }
+            })
+        };
+        glib::timeout_add_local_once(FIRST_FRAME_TIMEOUT, move || {
+            let frames = frames.get();
+            if frames >= FRAMES_FOR_HEALTHY_RENDERER {
+                return;
+            }
+            tick.remove();
+            restart_with_gl_renderer(frames);
+        });
+    });
+}
+
+/// Replace this process with the same command line, using the GL renderer.
+fn restart_with_gl_renderer(frames: u32) {
+    warn!(
+        "The overlay presented {frames} frame(s) in {FIRST_FRAME_TIMEOUT:?} with GTK's default \
+        renderer; restarting with GSK_RENDERER=gl. Set renderer = \"gl\" in config.toml to skip this check."
+    );
+    let exe = match std::env::current_exe() {
+        Ok(exe) => exe,
+        Err(e) => {
+            error!("Cannot restart with the GL renderer: {e}");
+            return;
+        }
+    };
+    let e = std::process::Command::new(exe)
+        .args(std::env::args_os().skip(1))
+        .env("GSK_RENDERER", "gl")
+        .exec();
+    error!("Cannot restart with the GL renderer: {e}");
+}
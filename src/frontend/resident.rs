@@ -0,0 +1,187 @@
+//! `cursor-clip resident`: keeps GTK and the overlay window alive, hidden, between
+//! invocations. `cursor-clip toggle` then only asks it over a socket in the runtime
+//! directory to place the window and refresh the history, instead of starting GTK,
+//! loading CSS and building every widget again. The same socket takes the `ShowAt` and
+//! `Close` IPC messages, which the daemon relays there as well.
+
+use crate::frontend::gtk_overlay;
+use crate::frontend::initializer::{OverlayPosition, open_overlay};
+use crate::shared::runtime_dir::{
+    FRONTEND_SOCKET_NAME, ensure_private_runtime_dir, frontend_socket_path,
+};
+use crate::shared::{BackendMessage, CursorClipError, FrontendMessage};
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Sent by `cursor-clip toggle`; carries the frontend's own notion of a position
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+enum ResidentCommand {
+    /// Show the overlay at `position`, or hide it if it is already shown
+    Toggle { position: OverlayPosition },
+}
+
+/// One request line on the resident frontend's socket: a toggle, or `ShowAt`/`Close`
+/// in the daemon's protocol, from the daemon's relay or any other client
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ResidentRequest {
+    Command(ResidentCommand),
+    Message(FrontendMessage),
+}
+
+/// Ask a running resident frontend to toggle the overlay. Returns false if none is
+/// running, so the caller can open a one-shot overlay instead.
+pub fn toggle(position: OverlayPosition) -> bool {
+    let Some(mut stream) = frontend_socket_path().and_then(|path| UnixStream::connect(path).ok())
+    else {
+        debug!("No resident frontend running");
+        return false;
+    };
+    let command = ResidentCommand::Toggle { position };
+    let sent = serde_json::to_string(&command)
+        .map_err(std::io::Error::other)
+        .and_then(|line| writeln!(stream, "{line}"));
+    match sent {
+        Ok(()) => true,
+        Err(e) => {
+            warn!("Failed to reach the resident frontend: {e}");
+            false
+        }
+    }
+}
+
+/// Run the resident frontend until the process is stopped.
+pub fn run_resident(demo: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let listener = bind_socket()?;
+    std::thread::Builder::new()
+        .name("cursor-clip-resident".to_string())
+        .spawn(move || serve(listener, demo))?;
+
+    info!("Resident frontend ready; open the overlay with `cursor-clip toggle`");
+    let (overlay_width, overlay_height) = gtk_overlay::configured_overlay_size();
+    if let Err(e) = gtk_overlay::run_resident_overlay(overlay_width, overlay_height) {
+        error!("Error running the resident overlay: {e:?}");
+    }
+    Ok(())
+}
+
+fn bind_socket() -> Result<UnixListener, String> {
+    let path = ensure_private_runtime_dir()?.join(FRONTEND_SOCKET_NAME);
+    if UnixStream::connect(&path).is_ok() {
+        return Err(format!(
+            "A resident frontend is already listening on {}",
+            path.display()
+        ));
+    }
+    // Left behind by a resident frontend that did not exit cleanly
+    let _ = std::fs::remove_file(&path);
+    let listener =
+        UnixListener::bind(&path).map_err(|e| format!("Failed to bind {}: {e}", path.display()))?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+        .map_err(|e| format!("Failed to restrict {}: {e}", path.display()))?;
+    Ok(listener)
+}
+
+fn serve(listener: UnixListener, demo: bool) {
+    // Set from the moment a toggle starts placing the overlay until it is hidden again
+    let shown = Arc::new(AtomicBool::new(false));
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Resident frontend failed to accept a connection: {e}");
+                continue;
+            }
+        };
+        let shown = shown.clone();
+        if let Err(e) = std::thread::Builder::new()
+            .name("cursor-clip-resident-client".to_string())
+            .spawn(move || handle_client(stream, demo, &shown))
+        {
+            warn!("Failed to spawn a resident frontend client thread: {e}");
+        }
+    }
+}
+
+/// Answer each request line of a connection with one `BackendMessage` line.
+fn handle_client(stream: UnixStream, demo: bool, shown: &Arc<AtomicBool>) {
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(e) => {
+            debug!("Failed to set up a resident frontend connection: {e}");
+            return;
+        }
+    };
+    for line in BufReader::new(stream).lines().map_while(Result::ok) {
+        let response = match serde_json::from_str::<ResidentRequest>(&line) {
+            Ok(ResidentRequest::Command(ResidentCommand::Toggle { position })) => {
+                let visible = !shown.load(Ordering::SeqCst);
+                if visible {
+                    open_in_background(position, demo, shown);
+                } else {
+                    gtk_overlay::request_quit_from_thread();
+                }
+                BackendMessage::OverlayState { visible }
+            }
+            Ok(ResidentRequest::Message(FrontendMessage::ShowAt { x, y })) => {
+                if shown.load(Ordering::SeqCst) {
+                    gtk_overlay::move_resident_overlay(x, y);
+                } else {
+                    open_in_background(OverlayPosition::At(x, y), demo, shown);
+                }
+                BackendMessage::OverlayState { visible: true }
+            }
+            Ok(ResidentRequest::Message(FrontendMessage::Close)) => {
+                if shown.load(Ordering::SeqCst) {
+                    gtk_overlay::request_quit_from_thread();
+                }
+                BackendMessage::OverlayState { visible: false }
+            }
+            // History and settings are the daemon's business
+            Ok(ResidentRequest::Message(message)) => BackendMessage::Error {
+                error: CursorClipError::InvalidRequest(format!(
+                    "the resident frontend only handles ShowAt and Close, not {}",
+                    message.name()
+                )),
+            },
+            Err(e) => BackendMessage::Error {
+                error: CursorClipError::InvalidRequest(e.to_string()),
+            },
+        };
+        let Ok(response_json) = serde_json::to_string(&response) else {
+            continue;
+        };
+        // `cursor-clip toggle` does not wait for the answer
+        if writeln!(writer, "{response_json}").is_err() {
+            return;
+        }
+    }
+}
+
+/// Open the overlay from a new thread, unless it is already shown or being placed.
+fn open_in_background(position: OverlayPosition, demo: bool, shown: &Arc<AtomicBool>) {
+    if shown.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    // Placing may wait for the pointer on the capture layer; the overlay then blocks
+    // this thread until it is hidden, like a one-shot frontend blocks in GTK
+    let shown_for_thread = shown.clone();
+    let spawned = std::thread::Builder::new()
+        .name("cursor-clip-show".to_string())
+        .spawn(move || {
+            if let Err(e) = open_overlay(demo, position, &gtk_overlay::show_resident_overlay) {
+                error!("Error opening the resident overlay: {e}");
+            }
+            shown_for_thread.store(false, Ordering::SeqCst);
+        });
+    if let Err(e) = spawned {
+        warn!("Failed to spawn the overlay thread: {e}");
+        shown.store(false, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,119 @@
+//! Coordinate conversions between the pointer capture surface and the monitor GTK
+//! places the overlay on. With fractional scaling (e.g. 1.5 or 1.6) the two can
+//! disagree on an output's logical size by a few pixels, or the capture surface size
+//! may be unknown, so positions are mapped rather than used as they are.
+
+/// `wp_fractional_scale_v1` reports scales in 120ths
+const FRACTIONAL_SCALE_DENOMINATOR: f64 = 120.0;
+
+/// The scale a `preferred_scale` event of `wp_fractional_scale_v1` stands for.
+pub fn fractional_scale(preferred_scale: u32) -> f64 {
+    f64::from(preferred_scale) / FRACTIONAL_SCALE_DENOMINATOR
+}
+
+/// Scale of the capture surface: the fractional one the compositor prefers, else the
+/// output's integer `wl_output` scale (compositors without `wp_fractional_scale_v1`).
+pub fn surface_scale(fractional: Option<f64>, integer_scale: i32) -> f64 {
+    fractional.unwrap_or(f64::from(integer_scale))
+}
+
+/// Logical size of an output from its current mode in physical pixels and its scale,
+/// rounded like compositors round surface sizes (halfway away from zero).
+pub fn logical_size((width, height): (i32, i32), scale: f64) -> (i32, i32) {
+    if scale <= 0.0 {
+        return (width, height);
+    }
+    (
+        (f64::from(width) / scale).round() as i32,
+        (f64::from(height) / scale).round() as i32,
+    )
+}
+
+/// Map a position on the capture surface (of logical size `capture_size`) into the
+/// logical space of the monitor the overlay is shown on (`monitor_size`, as GDK reports
+/// it). Unknown sizes (0) leave the position as it is; the result stays on the monitor.
+pub fn capture_to_monitor(
+    (x, y): (f64, f64),
+    (capture_width, capture_height): (i32, i32),
+    (monitor_width, monitor_height): (i32, i32),
+) -> (f64, f64) {
+    let map = |position: f64, from: i32, to: i32| {
+        if from <= 0 || to <= 0 {
+            return position;
+        }
+        (position * f64::from(to) / f64::from(from)).clamp(0.0, f64::from(to))
+    };
+    (
+        map(x, capture_width, monitor_width),
+        map(y, capture_height, monitor_height),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fractional_scales_come_in_120ths() {
+        assert_eq!(fractional_scale(180), 1.5);
+        assert_eq!(fractional_scale(192), 1.6);
+        assert_eq!(fractional_scale(120), 1.0);
+    }
+
+    #[test]
+    fn integer_output_scale_is_used_without_a_fractional_one() {
+        assert_eq!(surface_scale(None, 2), 2.0);
+        assert_eq!(surface_scale(Some(1.5), 2), 1.5);
+    }
+
+    #[test]
+    fn logical_sizes_at_fractional_scales() {
+        assert_eq!(logical_size((2880, 1800), 1.5), (1920, 1200));
+        // 2560 / 1.6 = 1600, 1440 / 1.6 = 900
+        assert_eq!(logical_size((2560, 1440), 1.6), (1600, 900));
+        // 1366 / 1.5 = 910.67 rounds up
+        assert_eq!(logical_size((1366, 768), 1.5), (911, 512));
+        assert_eq!(logical_size((1920, 1080), 0.0), (1920, 1080));
+    }
+
+    #[test]
+    fn positions_are_mapped_between_differing_logical_sizes() {
+        // The capture surface rounded 1366x768 at 1.5 down, GDK up
+        let (x, y) = capture_to_monitor((455.0, 256.0), (910, 512), (911, 512));
+        assert!((x - 455.5).abs() < 1e-9, "{x}");
+        assert_eq!(y, 256.0);
+    }
+
+    #[test]
+    fn unknown_sizes_leave_the_position_as_it_is() {
+        assert_eq!(
+            capture_to_monitor((300.0, 200.0), (0, 0), (1600, 900)),
+            (300.0, 200.0)
+        );
+        assert_eq!(
+            capture_to_monitor((300.0, 200.0), (1600, 900), (0, 0)),
+            (300.0, 200.0)
+        );
+    }
+
+    #[test]
+    fn positions_outside_the_monitor_are_clamped() {
+        assert_eq!(
+            capture_to_monitor((-5.0, 950.0), (1600, 900), (1600, 900)),
+            (0.0, 900.0)
+        );
+    }
+
+    #[test]
+    fn pointer_on_a_second_monitor_stays_monitor_local() {
+        // Second monitor at x = 1920 in the layout, 2560x1440 at 1.6. The capture surface
+        // covers only that output, so its positions are local to it and the layout
+        // offset never enters the mapping.
+        let monitor = logical_size((2560, 1440), fractional_scale(192));
+        let (x, y) = capture_to_monitor((100.0, 50.0), monitor, monitor);
+        assert_eq!((x, y), (100.0, 50.0));
+        // A pointer position still in layout coordinates would land off the monitor
+        let (x, _) = capture_to_monitor((1920.0 + 100.0, 50.0), monitor, monitor);
+        assert_eq!(x, 1600.0);
+    }
+}
@@ -0,0 +1,179 @@
+use gtk4::gdk::{Key, ModifierType};
+
+/// Something the picker does in response to a key press.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickerAction {
+    Close,
+    Search,
+    ClearSearch,
+    Next,
+    Previous,
+    Paste,
+    PastePlainText,
+    QuickPaste,
+    Delete,
+    TogglePin,
+    ToggleMerge,
+    MergePaste,
+    NextTab,
+    PreviousTab,
+    Cheatsheet,
+}
+
+/// A picker keybinding. The key controllers and the cheatsheet both read this table,
+/// so the cheatsheet always lists what the picker actually does.
+pub struct KeyBinding {
+    pub action: PickerAction,
+    /// GTK accelerator strings, as understood by `gtk4::accelerator_parse`
+    pub accelerators: &'static [&'static str],
+    pub description: &'static str,
+    /// Equivalent outside the overlay, if any
+    pub cli: Option<&'static str>,
+}
+
+pub const KEYBINDING_SECTIONS: &[(&str, &[KeyBinding])] = &[
+    (
+        "Navigation",
+        &[
+            KeyBinding {
+                action: PickerAction::Next,
+                accelerators: &["j", "Down"],
+                description: "Select next item",
+                cli: None,
+            },
+            KeyBinding {
+                action: PickerAction::Previous,
+                accelerators: &["k", "Up"],
+                description: "Select previous item",
+                cli: None,
+            },
+            KeyBinding {
+                action: PickerAction::NextTab,
+                accelerators: &["<Control>Tab", "<Control>Page_Down"],
+                description: "Next tab (All, Pinned, Images, Files, Snippets)",
+                cli: None,
+            },
+            KeyBinding {
+                action: PickerAction::PreviousTab,
+                accelerators: &["<Control><Shift>ISO_Left_Tab", "<Control>Page_Up"],
+                description: "Previous tab",
+                cli: None,
+            },
+            KeyBinding {
+                action: PickerAction::Search,
+                accelerators: &["slash"],
+                description: "Search history",
+                cli: None,
+            },
+            KeyBinding {
+                action: PickerAction::ClearSearch,
+                accelerators: &["<Control>u"],
+                description: "Clear search up to the cursor",
+                cli: None,
+            },
+            KeyBinding {
+                action: PickerAction::Cheatsheet,
+                accelerators: &["question"],
+                description: "Show this cheatsheet",
+                cli: None,
+            },
+            KeyBinding {
+                action: PickerAction::Close,
+                accelerators: &["Escape"],
+                description: "Close the overlay or go back",
+                cli: None,
+            },
+        ],
+    ),
+    (
+        "Items",
+        &[
+            KeyBinding {
+                action: PickerAction::Paste,
+                accelerators: &["Return", "KP_Enter"],
+                description: "Copy (and paste) the selected item",
+                cli: Some(
+                    "busctl --user call org.cursorclip.Daemon /org/cursorclip/Daemon org.cursorclip.Daemon SetClipboardById tb <id> true",
+                ),
+            },
+            KeyBinding {
+                action: PickerAction::PastePlainText,
+                accelerators: &["<Shift>Return", "<Shift>KP_Enter"],
+                description: "Paste as plain text",
+                cli: None,
+            },
+            KeyBinding {
+                action: PickerAction::QuickPaste,
+                accelerators: &[
+                    "<Alt>1", "<Alt>2", "<Alt>3", "<Alt>4", "<Alt>5", "<Alt>6", "<Alt>7", "<Alt>8",
+                    "<Alt>9",
+                ],
+                description: "Paste the item with that number badge",
+                cli: None,
+            },
+            KeyBinding {
+                action: PickerAction::TogglePin,
+                accelerators: &["p"],
+                description: "Pin or unpin",
+                cli: None,
+            },
+            KeyBinding {
+                action: PickerAction::ToggleMerge,
+                accelerators: &["<Control>space"],
+                description: "Select for Append & paste (also Ctrl+click)",
+                cli: None,
+            },
+            KeyBinding {
+                action: PickerAction::MergePaste,
+                accelerators: &["<Control>Return", "<Control>KP_Enter"],
+                description: "Append & paste the selected items",
+                cli: None,
+            },
+            KeyBinding {
+                action: PickerAction::Delete,
+                accelerators: &["Delete"],
+                description: "Delete from history",
+                cli: Some(
+                    "busctl --user call org.cursorclip.Daemon /org/cursorclip/Daemon org.cursorclip.Daemon Delete t <id>",
+                ),
+            },
+        ],
+    ),
+];
+
+/// Look up the action bound to a key press. Bindings with matching modifiers win;
+/// otherwise Shift is ignored so that e.g. `J` or a shifted `?` still match.
+pub fn action_for(key: Key, modifiers: ModifierType) -> Option<PickerAction> {
+    let key = key.to_lower();
+    let modifiers = modifiers
+        & (ModifierType::CONTROL_MASK | ModifierType::SHIFT_MASK | ModifierType::ALT_MASK);
+    let parsed = || {
+        KEYBINDING_SECTIONS
+            .iter()
+            .flat_map(|(_, bindings)| bindings.iter())
+            .flat_map(|binding| {
+                binding
+                    .accelerators
+                    .iter()
+                    .filter_map(|accelerator| gtk4::accelerator_parse(*accelerator))
+                    .map(move |parsed| (binding.action, parsed))
+            })
+    };
+
+    parsed()
+        .find(|(_, (bound_key, bound_modifiers))| {
+            *bound_key == key && *bound_modifiers == modifiers
+        })
+        .or_else(|| {
+            parsed().find(|(_, (bound_key, bound_modifiers))| {
+                *bound_key == key && *bound_modifiers == modifiers - ModifierType::SHIFT_MASK
+            })
+        })
+        .map(|(action, _)| action)
+}
+
+/// The number badge of the row at `position` (1 to 9 for the first nine rows), which
+/// `QuickPaste` (Alt+number) pastes.
+pub fn quick_pick_number(position: u32) -> Option<u32> {
+    (position < 9).then_some(position + 1)
+}
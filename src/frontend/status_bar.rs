@@ -0,0 +1,145 @@
+//! `cursor-clip status --waybar`: one JSON line per update for a Waybar custom module
+//! (`return-type: json`), optionally following the daemon's event stream.
+
+use crate::frontend::ipc_client::FrontendClient;
+use crate::shared::{
+    BackendMessage, ClipboardContentType, CursorClipResult, HistoryOrder, HistoryView, MonitorState,
+};
+use log::debug;
+use std::io::Write;
+use std::time::Duration;
+
+// Items listed in the tooltip
+const TOOLTIP_ITEMS: usize = 5;
+// Characters of a preview shown in the bar or a tooltip line
+const PREVIEW_CHARS: usize = 40;
+// Wait before reconnecting when following and the daemon is gone
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// What the bar text shows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BarText {
+    /// Number of items in the history
+    Count,
+    /// Preview of the newest item
+    Latest,
+}
+
+/// Print the module's JSON once, or with `follow` again after every history change.
+/// Following never returns; while the daemon is not running the module shows
+/// `class: stopped` and reconnects every few seconds.
+pub fn run_waybar(text: BarText, follow: bool) -> CursorClipResult<()> {
+    if !follow {
+        let line = FrontendClient::new().and_then(|mut client| waybar_line(&mut client, text))?;
+        println!("{line}");
+        return Ok(());
+    }
+
+    loop {
+        if let Err(e) = follow_daemon(text) {
+            debug!("Waybar output lost the daemon: {e}");
+        }
+        print_line(&stopped_line());
+        std::thread::sleep(RECONNECT_DELAY);
+    }
+}
+
+/// Print a line now and after every event, until the daemon goes away.
+fn follow_daemon(text: BarText) -> CursorClipResult<()> {
+    let mut client = FrontendClient::new()?;
+    let events = FrontendClient::new()?.subscribe()?;
+    print_line(&waybar_line(&mut client, text)?);
+    for event in events {
+        if matches!(
+            event,
+            BackendMessage::HistoryChanged { .. }
+                | BackendMessage::RecordingState { .. }
+                | BackendMessage::MonitorStateChanged { .. }
+        ) {
+            print_line(&waybar_line(&mut client, text)?);
+        }
+    }
+    Ok(())
+}
+
+fn print_line(line: &str) {
+    let mut stdout = std::io::stdout().lock();
+    // Waybar reads line by line; flush so updates aren't held in the buffer
+    let _ = writeln!(stdout, "{line}");
+    let _ = stdout.flush();
+}
+
+/// The module JSON: `text`, a `tooltip` with the top of the history and `class`
+/// (`active`, `paused` or `degraded`) for styling.
+fn waybar_line(client: &mut FrontendClient, text: BarText) -> CursorClipResult<String> {
+    let page = client.get_history_page(0, TOOLTIP_ITEMS, HistoryView::All, HistoryOrder::Recent)?;
+    let recording = client.get_recording_state()?;
+    let status = client.get_status()?;
+
+    let class = if !recording {
+        "paused"
+    } else if status.monitor_state == MonitorState::Degraded {
+        "degraded"
+    } else {
+        "active"
+    };
+    // Pinned items come first in the history, then the newest copy
+    let latest = page
+        .items
+        .iter()
+        .find(|item| !item.pinned)
+        .or(page.items.first());
+    let bar_text = match text {
+        BarText::Count => page.total.to_string(),
+        BarText::Latest => latest
+            .map(|item| preview_snippet(&item.content_preview, item.content_type))
+            .unwrap_or_default(),
+    };
+
+    let mut tooltip = format!(
+        "{} items{}",
+        page.total,
+        if recording {
+            ""
+        } else {
+            " · recording paused"
+        }
+    );
+    for item in &page.items {
+        tooltip.push('\n');
+        tooltip.push_str(item.content_type.icon());
+        tooltip.push(' ');
+        tooltip.push_str(&preview_snippet(&item.content_preview, item.content_type));
+    }
+
+    Ok(serde_json::json!({
+        "text": bar_text,
+        "tooltip": tooltip,
+        "class": class,
+        "alt": class,
+    })
+    .to_string())
+}
+
+fn stopped_line() -> String {
+    serde_json::json!({
+        "text": "",
+        "tooltip": "cursor-clip daemon not running",
+        "class": "stopped",
+        "alt": "stopped",
+    })
+    .to_string()
+}
+
+/// First line of a preview, shortened; passwords are never shown in the bar.
+fn preview_snippet(preview: &str, content_type: ClipboardContentType) -> String {
+    if content_type == ClipboardContentType::Password {
+        return "••••••••".to_string();
+    }
+    let line = preview.lines().next().unwrap_or_default().trim();
+    let mut snippet: String = line.chars().take(PREVIEW_CHARS).collect();
+    if line.chars().count() > PREVIEW_CHARS {
+        snippet.push('…');
+    }
+    snippet
+}
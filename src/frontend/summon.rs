@@ -0,0 +1,35 @@
+use log::warn;
+use serde::Deserialize;
+use std::fs;
+
+use crate::shared::config_dir::config_path;
+
+/// How the overlay finds its position when summoned (`summon_mode` in config.toml).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SummonMode {
+    /// Capture the pointer with a transparent layer and open at it
+    #[default]
+    Pointer,
+    /// No capture layer: open at the pointer as reported by the compositor (Hyprland),
+    /// otherwise centered on the focused output
+    Keyboard,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ConfigWithSummonMode {
+    summon_mode: SummonMode,
+}
+
+pub fn load_mode() -> SummonMode {
+    let Ok(contents) = fs::read_to_string(config_path()) else {
+        return SummonMode::default();
+    };
+    toml::from_str::<ConfigWithSummonMode>(&contents)
+        .map(|config| config.summon_mode)
+        .unwrap_or_else(|e| {
+            warn!("Failed to parse summon_mode from config, capturing the pointer: {e}");
+            SummonMode::default()
+        })
+}
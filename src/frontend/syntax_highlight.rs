@@ -0,0 +1,46 @@
+use std::fmt::Write;
+use std::sync::LazyLock;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{FontStyle, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use syntect::util::LinesWithEndings;
+
+// Loaded on first use; only overlays showing code pay for it
+static SYNTAXES: LazyLock<SyntaxSet> = LazyLock::new(SyntaxSet::load_defaults_newlines);
+static THEMES: LazyLock<ThemeSet> = LazyLock::new(ThemeSet::load_defaults);
+
+const LIGHT_THEME: &str = "InspiredGitHub";
+const DARK_THEME: &str = "base16-ocean.dark";
+
+/// Pango markup for `code` highlighted as `language` (a file extension such as "rs", as
+/// detected by the daemon). `None` if the language is unknown to the highlighter.
+pub fn highlighted_code_markup(code: &str, language: &str, dark: bool) -> Option<String> {
+    let syntax = SYNTAXES.find_syntax_by_token(language)?;
+    let theme = THEMES
+        .themes
+        .get(if dark { DARK_THEME } else { LIGHT_THEME })?;
+    let mut highlighter = HighlightLines::new(syntax, theme);
+
+    let mut markup = String::with_capacity(code.len() * 2);
+    for line in LinesWithEndings::from(code) {
+        let regions = highlighter.highlight_line(line, &SYNTAXES).ok()?;
+        for (style, text) in regions {
+            let color = style.foreground;
+            let _ = write!(
+                markup,
+                "<span foreground=\"#{:02x}{:02x}{:02x}\"",
+                color.r, color.g, color.b
+            );
+            if style.font_style.contains(FontStyle::BOLD) {
+                markup.push_str(" weight=\"bold\"");
+            }
+            if style.font_style.contains(FontStyle::ITALIC) {
+                markup.push_str(" style=\"italic\"");
+            }
+            markup.push('>');
+            markup.push_str(&gtk4::glib::markup_escape_text(text));
+            markup.push_str("</span>");
+        }
+    }
+    Some(markup)
+}
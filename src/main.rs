@@ -1,5 +1,7 @@
-use clap::{Arg, Command};
+use clap::parser::ValueSource;
+use clap::{Arg, ArgMatches, Command};
 use log::{error, info};
+use std::io::{Read, Write};
 
 mod backend;
 mod frontend;
@@ -9,43 +11,787 @@ const VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize logging (RUST_LOG overrides, default to info)
-    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info"))
-        .format_timestamp_secs()
-        .try_init()?;
+    let matches = build_cli().get_matches();
 
-    let matches = Command::new("cursor-clip")
+    shared::logging::init(
+        matches.get_count("verbose"),
+        matches.get_one::<std::path::PathBuf>("log-file").cloned(),
+    )?;
+
+    // The flags of earlier releases (`--daemon`, `--toggle`, ...) stand for subcommands;
+    // without either the overlay is shown
+    let (command, args) = match matches.subcommand() {
+        Some((command, args)) => (command, args),
+        None => (legacy_command(&matches), &matches),
+    };
+    if matches.subcommand().is_none()
+        && command != "daemon"
+        && (matches.get_flag("monitor-only") || matches.get_flag("persist-on-exit"))
+    {
+        error!("--monitor-only and --persist-on-exit only apply to `cursor-clip daemon`");
+        std::process::exit(1);
+    }
+
+    let result = match command {
+        "daemon" => {
+            let persist_on_exit = args.get_flag("persist-on-exit");
+            let monitor_only = args.get_flag("monitor-only") || persist_on_exit;
+            info!("Starting clipboard backend daemon...");
+            backend::run_backend(monitor_only, persist_on_exit, args.get_flag("demo")).await?;
+            return Ok(());
+        }
+        "resident" => {
+            info!("Starting resident clipboard frontend...");
+            frontend::renderer::select_renderer(renderer_choice(args));
+            frontend::resident::run_resident(args.get_flag("demo"))?;
+            return Ok(());
+        }
+        "show" | "toggle" => {
+            let position = overlay_position(args);
+            if command == "toggle" && frontend::resident::toggle(position) {
+                return Ok(());
+            }
+            info!("Starting clipboard frontend...");
+            frontend::renderer::select_renderer(renderer_choice(args));
+            frontend::run_frontend(args.get_flag("demo"), position).await?;
+            return Ok(());
+        }
+        "doctor" => {
+            println!("{}", shared::doctor::doctor_report());
+            Ok(())
+        }
+        "status" => {
+            if args.get_flag("waybar") {
+                let text = match args.get_one::<String>("text").map(String::as_str) {
+                    Some("latest") => frontend::status_bar::BarText::Latest,
+                    _ => frontend::status_bar::BarText::Count,
+                };
+                frontend::status_bar::run_waybar(text, args.get_flag("follow")).map_err(Into::into)
+            } else {
+                std::process::exit(run_status_command());
+            }
+        }
+        "config" => match args.subcommand() {
+            Some(("migrate", migrate)) => run_migrate_command(migrate.get_flag("dry-run")),
+            _ => {
+                let assignments: Vec<&String> = args
+                    .get_many::<String>("config")
+                    .map(Iterator::collect)
+                    .unwrap_or_default();
+                run_config_command(&assignments)
+            }
+        },
+        "list" => run_list_command(args),
+        "get" => run_get_command(args),
+        "copy" => run_copy_command(args, false),
+        "paste" => run_copy_command(args, true),
+        "clear" => run_clear_command(),
+        "undo" => run_undo_command(),
+        "export" => run_export_command(args),
+        "import" => run_import_command(args),
+        "search" => run_search_command(args),
+        "menu" => match run_menu_command(args) {
+            Ok(true) => Ok(()),
+            // Nothing chosen; launchers report a cancel the same way
+            Ok(false) => std::process::exit(1),
+            Err(e) => Err(e),
+        },
+        "add" => run_add_command(args),
+        "pause" | "resume" => run_recording_command(command == "resume"),
+        "reclassify" => run_reclassify_command(),
+        _ => unreachable!("clap only accepts the subcommands defined in build_cli"),
+    };
+    if let Err(e) = result {
+        error!("{e}");
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+/// The subcommand a command line without one stands for: the flags of earlier releases,
+/// or `show`.
+fn legacy_command(matches: &ArgMatches) -> &'static str {
+    if matches.get_flag("daemon") {
+        "daemon"
+    } else if matches.get_flag("resident") {
+        "resident"
+    } else if matches.get_flag("toggle") {
+        "toggle"
+    } else if matches.get_flag("doctor") {
+        "doctor"
+    } else if matches.value_source("config") == Some(ValueSource::CommandLine) {
+        "config"
+    } else {
+        "show"
+    }
+}
+
+fn build_cli() -> Command {
+    let command = Command::new("cursor-clip")
         .version(VERSION)
         .about("Clipboard manager with GUI overlay")
+        .after_help("Without a subcommand the overlay is shown, as with `cursor-clip show`.")
+        .args_conflicts_with_subcommands(true)
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .global(true)
+                .help("Log more detail: -v for debug, -vv for trace messages of cursor-clip, -vvv for trace messages of every library (RUST_LOG overrides this)")
+                .action(clap::ArgAction::Count),
+        )
+        .arg(
+            Arg::new("log-file")
+                .long("log-file")
+                .value_name("PATH")
+                .global(true)
+                .value_parser(clap::value_parser!(std::path::PathBuf))
+                .help("Also write log messages to this file, rotated at 5 MiB (or set log_file in config.toml)"),
+        );
+
+    // Flags of earlier releases, kept working but no longer listed
+    let command = daemon_args(overlay_args(command))
+        .arg(hidden_flag("daemon", "Alias for `cursor-clip daemon`"))
+        .arg(hidden_flag("resident", "Alias for `cursor-clip resident`"))
+        .arg(hidden_flag("toggle", "Alias for `cursor-clip toggle`"))
+        .arg(hidden_flag("doctor", "Alias for `cursor-clip doctor`"))
+        .arg(
+            Arg::new("config")
+                .long("config")
+                .value_name("KEY=VALUE")
+                .num_args(0..)
+                .hide(true)
+                .help("Alias for `cursor-clip config`")
+                .action(clap::ArgAction::Append),
+        )
+        .group(
+            clap::ArgGroup::new("legacy-command")
+                .args(["daemon", "resident", "toggle", "doctor", "config"])
+                .multiple(false),
+        );
+
+    command
+        .subcommand(daemon_args(
+            Command::new("daemon").about("Run the clipboard daemon in the background"),
+        ))
+        .subcommand(overlay_args(
+            Command::new("show").about("Open the overlay (the default without a subcommand)"),
+        ))
+        .subcommand(overlay_args(Command::new("toggle").about(
+            "Open the overlay through the resident frontend if one is running (or close it if it is shown); otherwise open it as usual",
+        )))
+        .subcommand(
+            Command::new("resident")
+                .about("Keep the overlay loaded in the background so `toggle` opens it instantly; run this once at login")
+                .arg(renderer_arg())
+                .arg(demo_arg()),
+        )
+        .subcommand(
+            Command::new("list")
+                .about("Print the clipboard history as `ID<TAB>type<TAB>preview` lines, pinned items first, newest next")
+                .arg(view_arg())
+                .arg(
+                    Arg::new("limit")
+                        .long("limit")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("0")
+                        .help("Print at most N items (0 for all)"),
+                ),
+        )
+        .subcommand(
+            Command::new("get")
+                .about("Write an item's text, or its data in another format, to stdout")
+                .arg(id_arg())
+                .arg(
+                    Arg::new("mime-type")
+                        .long("mime-type")
+                        .value_name("MIME")
+                        .help("Write the item's data in this MIME type as it is (e.g. image/png)"),
+                ),
+        )
+        .subcommand(
+            Command::new("copy")
+                .about("Set a history item as the clipboard")
+                .arg(id_arg()),
+        )
+        .subcommand(
+            Command::new("paste")
+                .about("Set a history item as the clipboard and paste it into the focused window")
+                .arg(id_arg()),
+        )
+        .subcommand(Command::new("clear").about("Delete the whole clipboard history"))
+        .subcommand(Command::new("undo").about(
+            "Restore the items of the last delete or clear, if they are still in the trash (trash_minutes)",
+        ))
+        .subcommand(
+            Command::new("status")
+                .about("Show whether the daemon is running and healthy: uptime, data-control protocol, monitoring state, history size and the last error")
+                .arg(
+                    Arg::new("waybar")
+                        .long("waybar")
+                        .help("Print a single-line JSON object for a Waybar custom module (text, tooltip, class)")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("follow")
+                        .long("follow")
+                        .requires("waybar")
+                        .help("Keep running and print a new line whenever the history changes")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("text")
+                        .long("text")
+                        .requires("waybar")
+                        .value_parser(["count", "latest"])
+                        .default_value("count")
+                        .help("Show the item count or the newest item's preview in the bar"),
+                ),
+        )
+        .subcommand(
+            Command::new("config")
+                .about("Print the running daemon's settings, or change them (e.g. config max_items=50 exclude_apps=kitty,org.keepassxc.KeePassXC). Changes apply immediately and are written to config.toml.")
+                .args_conflicts_with_subcommands(true)
+                .arg(
+                    Arg::new("config")
+                        .value_name("KEY=VALUE")
+                        .num_args(0..)
+                        .action(clap::ArgAction::Append),
+                )
+                .subcommand(
+                    Command::new("migrate")
+                        .about("Bring a history database written by an older release up to date, after copying it aside. The daemon does this itself when it starts.")
+                        .arg(
+                            Arg::new("dry-run")
+                                .long("dry-run")
+                                .help("Only report the schema version and what migrating would change")
+                                .action(clap::ArgAction::SetTrue),
+                        ),
+                ),
+        )
+        .subcommand(Command::new("doctor").about(
+            "Print the detected compositor, active quirks, supported Wayland protocols and a keybinding snippet",
+        ))
+        .subcommand(
+            Command::new("export")
+                .about("Write the clipboard history to a file (JSON, or a tarball for .tar paths) to restore it elsewhere with `import`")
+                .arg(Arg::new("path").value_name("PATH").required(true))
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_parser(["json", "tar"])
+                        .help("Output format; defaults to tar for .tar paths and json otherwise"),
+                )
+                .arg(
+                    Arg::new("include-sensitive")
+                        .long("include-sensitive")
+                        .help("Also export items that look like passwords")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Merge a file written by `export` into the history, keeping pinned items pinned")
+                .arg(Arg::new("path").value_name("PATH").required(true)),
+        )
+        .subcommand(
+            Command::new("search")
+                .about("Fuzzy-search the clipboard history and print the best matches with their IDs")
+                .arg(Arg::new("query").value_name("QUERY").required(true))
+                .arg(view_arg())
+                .arg(
+                    Arg::new("limit")
+                        .long("limit")
+                        .value_name("N")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("20")
+                        .help("Print at most N results (0 for all)"),
+                ),
+        )
+        .subcommand(
+            Command::new("menu")
+                .about("Pick from the history with rofi, fuzzel, wofi or dmenu instead of the overlay")
+                .arg(
+                    Arg::new("dmenu")
+                        .long("dmenu")
+                        .help("Print the history as `ID<TAB>preview` lines, for a dmenu-style launcher")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("select")
+                        .long("select")
+                        .requires("dmenu")
+                        .help("Read a chosen line on stdin and set that item as the clipboard")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("launcher")
+                        .long("launcher")
+                        .value_name("COMMAND")
+                        .conflicts_with("dmenu")
+                        .help("Run a dmenu-style launcher (e.g. \"fuzzel --dmenu\") and set the chosen item as the clipboard"),
+                )
+                .arg(
+                    Arg::new("paste")
+                        .long("paste")
+                        .help("Also paste the chosen item into the focused window")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(
+            Command::new("add")
+                .about("Add content to the history and print its ID; reads stdin without TEXT")
+                .arg(Arg::new("text").value_name("TEXT"))
+                .arg(
+                    Arg::new("mime-type")
+                        .long("mime-type")
+                        .value_name("MIME")
+                        .conflicts_with("text")
+                        .help("Store stdin under this MIME type instead of as text (e.g. image/png)"),
+                )
+                .arg(
+                    Arg::new("pin")
+                        .long("pin")
+                        .help("Pin the new item")
+                        .action(clap::ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("select")
+                        .long("select")
+                        .help("Also set the new item as the clipboard")
+                        .action(clap::ArgAction::SetTrue),
+                ),
+        )
+        .subcommand(Command::new("pause").about("Stop recording copies until `resume`"))
+        .subcommand(Command::new("resume").about("Record copies again after `pause`"))
+        .subcommand(Command::new("reclassify").about(
+            "Re-run content-type classification (built-ins and [[classifier_rules]]) over the whole history",
+        ))
+}
+
+/// Options of `show` and `toggle`, which also apply without a subcommand.
+fn overlay_args(command: Command) -> Command {
+    command
+        .arg(
+            Arg::new("at")
+                .long("at")
+                .value_name("X,Y")
+                .value_parser(parse_position)
+                .conflicts_with("anchor")
+                .help("Open the overlay at a fixed position (logical pixels from the top-left of the monitor) instead of at the pointer"),
+        )
+        .arg(
+            Arg::new("anchor")
+                .long("anchor")
+                .value_name("POSITION")
+                .value_parser(frontend::gtk_overlay::AnchorPreset::NAMES)
+                .help("Open the overlay pinned to a screen corner or the center instead of at the pointer"),
+        )
         .arg(
-            Arg::new("daemon")
-                .long("daemon")
-                .help("Run as background daemon")
+            Arg::new("keyboard")
+                .long("keyboard")
+                .help("Open the overlay without the pointer-capture layer: at the pointer where the compositor reports it (Hyprland), otherwise centered on the focused monitor. Set summon_mode = \"keyboard\" in config.toml to make this the default")
+                .conflicts_with_all(["at", "anchor"])
                 .action(clap::ArgAction::SetTrue),
         )
+        .arg(renderer_arg())
+        .arg(demo_arg())
+}
+
+/// Options of `daemon`; without a subcommand they need `--daemon`.
+fn daemon_args(command: Command) -> Command {
+    command
         .arg(
             Arg::new("monitor-only")
                 .long("monitor-only")
                 .help("Do not take ownership of a newly received external selection; just record it. This does not automatically ensure clipboard persistence if the original application is closed. You can still paste the selection by choosing it in the GUI. If unsure, you probably want to keep the default behaviour and don't use this flag.")
                 .action(clap::ArgAction::SetTrue),
         )
-        .get_matches();
+        .arg(
+            Arg::new("persist-on-exit")
+                .long("persist-on-exit")
+                .help("Like --monitor-only, but when the app that owns the clipboard exits, offer its last copy from the history so pasting keeps working")
+                .action(clap::ArgAction::SetTrue),
+        )
+        .arg(demo_arg())
+}
+
+fn demo_arg() -> Arg {
+    Arg::new("demo")
+        .long("demo")
+        .help("Use a deterministic set of fake clipboard items (text, URLs, code, images, files) instead of real history. Meant for screenshots, UI development and manual QA; the daemon never touches the persistent history database in this mode.")
+        .action(clap::ArgAction::SetTrue)
+}
 
-    let monitor_only = matches.get_flag("monitor-only");
-    let run_daemon = matches.get_flag("daemon");
+fn renderer_arg() -> Arg {
+    Arg::new("renderer")
+        .long("renderer")
+        .value_name("RENDERER")
+        .value_parser(frontend::renderer::RendererChoice::NAMES)
+        .help("GSK renderer for the overlay: gl or cairo, or auto to use GTK's default and fall back to gl if it shows nothing (default; also `renderer` in config.toml). GSK_RENDERER overrides this")
+}
 
-    if monitor_only && !run_daemon {
-        error!("--monitor-only can only be used together with --daemon");
-        std::process::exit(1);
-    }
+fn view_arg() -> Arg {
+    Arg::new("view")
+        .long("view")
+        .value_parser(["all", "pinned", "images", "files"])
+        .default_value("all")
+        .help("Only this part of the history, like the overlay's tabs")
+}
+
+fn id_arg() -> Arg {
+    Arg::new("id")
+        .value_name("ID")
+        .required(true)
+        .value_parser(clap::value_parser!(u64))
+        .help("Item ID, as printed by `list` or `search`")
+}
 
-    if run_daemon {
-        info!("Starting clipboard backend daemon...");
-        backend::run_backend(monitor_only).await?;
+fn hidden_flag(name: &'static str, help: &'static str) -> Arg {
+    Arg::new(name)
+        .long(name)
+        .hide(true)
+        .help(help)
+        .action(clap::ArgAction::SetTrue)
+}
+
+fn renderer_choice(args: &ArgMatches) -> Option<frontend::renderer::RendererChoice> {
+    args.get_one::<String>("renderer")
+        .and_then(|name| frontend::renderer::RendererChoice::from_name(name))
+}
+
+/// Where `show` or `toggle` opens the overlay.
+fn overlay_position(args: &ArgMatches) -> frontend::OverlayPosition {
+    if let Some((x, y)) = args.get_one::<(f64, f64)>("at") {
+        frontend::OverlayPosition::At(*x, *y)
+    } else if let Some(anchor) = args
+        .get_one::<String>("anchor")
+        .and_then(|name| frontend::gtk_overlay::AnchorPreset::from_name(name))
+    {
+        frontend::OverlayPosition::Anchor(anchor)
+    } else if args.get_flag("keyboard")
+        || frontend::summon::load_mode() == frontend::summon::SummonMode::Keyboard
+    {
+        frontend::OverlayPosition::Keyboard
     } else {
-        info!("Starting clipboard frontend...");
-        frontend::run_frontend().await?;
+        frontend::OverlayPosition::Pointer
     }
+}
+
+/// Parse `--at X,Y`.
+fn parse_position(value: &str) -> Result<(f64, f64), String> {
+    let invalid = || format!("expected X,Y (e.g. 200,150), got {value:?}");
+    let (x, y) = value.split_once(',').ok_or_else(invalid)?;
+    let x: f64 = x.trim().parse().map_err(|_| invalid())?;
+    let y: f64 = y.trim().parse().map_err(|_| invalid())?;
+    if x < 0.0 || y < 0.0 {
+        return Err(invalid());
+    }
+    Ok((x, y))
+}
 
+fn connect_to_daemon() -> Result<frontend::ipc_client::FrontendClient, String> {
+    frontend::ipc_client::FrontendClient::new().map_err(|e| match e {
+        shared::CursorClipError::DaemonNotRunning => {
+            format!("{e}; start it with `cursor-clip daemon`")
+        }
+        e => e.to_string(),
+    })
+}
+
+/// The daemon resolves paths itself, from its own working directory.
+fn absolute_path(path: &str) -> Result<String, Box<dyn std::error::Error>> {
+    Ok(std::path::absolute(path)?.to_string_lossy().into_owned())
+}
+
+/// Show or update the daemon settings over IPC (`GetConfig` / `SetConfig`).
+fn run_config_command(assignments: &[&String]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut client = connect_to_daemon()?;
+    let mut settings = client.get_config()?;
+    if !assignments.is_empty() {
+        for assignment in assignments {
+            let (key, value) = assignment
+                .split_once('=')
+                .ok_or_else(|| format!("Expected KEY=VALUE, got {assignment:?}"))?;
+            settings.set_from_str(key.trim(), value)?;
+        }
+        settings = client.set_config(settings)?;
+    }
+    println!("{settings}");
+    Ok(())
+}
+
+/// Report the pending history database migrations and, unless `dry_run`, run them
+/// (`config migrate`). The database is opened directly, so the daemon must not hold it.
+fn run_migrate_command(dry_run: bool) -> Result<(), Box<dyn std::error::Error>> {
+    // Even a dry run opens the database, which the daemon holds open; a running daemon
+    // migrated it when it opened it anyway
+    if frontend::ipc_client::FrontendClient::new().is_ok() {
+        return Err("The daemon has the history database open (and migrated it on startup); stop it first to inspect or migrate the database".into());
+    }
+    let Some(plan) = backend::history_migration::plan_default()? else {
+        println!(
+            "No history database at {}",
+            backend::persistence::history_db_path().display()
+        );
+        return Ok(());
+    };
+    println!("{plan}");
+    if dry_run || plan.is_current() {
+        return Ok(());
+    }
+    let password = backend::persistence::read_db_password_from_keyring_once()?
+        .ok_or("No history database password in the keyring")?;
+    backend::persistence::ClipboardPersistence::open_default(&password)?;
+    println!(
+        "Migrated to schema version {}",
+        backend::history_migration::SCHEMA_VERSION
+    );
+    Ok(())
+}
+
+/// Have the daemon write its history to a file (`ExportHistory`).
+fn run_export_command(args: &clap::ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let path = args.get_one::<String>("path").expect("path is required");
+    let format = match args.get_one::<String>("format") {
+        Some(format) => format.parse::<shared::ExportFormat>()?,
+        None => shared::ExportFormat::from_path(path),
+    };
+    let path = absolute_path(path)?;
+    let count = connect_to_daemon()?.export_history(
+        path.clone(),
+        format,
+        args.get_flag("include-sensitive"),
+    )?;
+    println!("Exported {count} items to {path}");
+    Ok(())
+}
+
+/// Merge an export into the daemon's history (`ImportHistory`).
+fn run_import_command(args: &clap::ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let path = absolute_path(args.get_one::<String>("path").expect("path is required"))?;
+    let (imported, skipped) = connect_to_daemon()?.import_history(path)?;
+    println!("Imported {imported} items ({skipped} already present or skipped)");
+    Ok(())
+}
+
+/// Print the daemon's ranked matches for a query (`SearchHistory`), one per line.
+fn run_search_command(args: &clap::ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let query = args.get_one::<String>("query").expect("query is required");
+    let view_name = args.get_one::<String>("view").expect("view has a default");
+    let view = shared::HistoryView::ALL
+        .into_iter()
+        .find(|view| view.name() == view_name)
+        .unwrap_or_default();
+    let limit = *args.get_one::<usize>("limit").expect("limit has a default");
+
+    let results = connect_to_daemon()?.search_history(query.clone(), view, limit)?;
+    for result in results {
+        let preview = result
+            .item
+            .content_preview
+            .lines()
+            .next()
+            .unwrap_or_default();
+        println!(
+            "{}\t{}\t{}\t{preview}",
+            result.item.item_id,
+            result.score,
+            result.item.content_type.as_str()
+        );
+    }
     Ok(())
 }
+
+/// Print the daemon's history (`GetHistoryPage`), one item per line.
+fn run_list_command(args: &clap::ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let view_name = args.get_one::<String>("view").expect("view has a default");
+    let view = shared::HistoryView::ALL
+        .into_iter()
+        .find(|view| view.name() == view_name)
+        .unwrap_or_default();
+    let limit = match *args.get_one::<usize>("limit").expect("limit has a default") {
+        0 => usize::MAX,
+        limit => limit,
+    };
+
+    let mut client = connect_to_daemon()?;
+    let mut printed = 0;
+    while printed < limit {
+        let page_size = shared::HISTORY_PAGE_SIZE.min(limit - printed);
+        let page =
+            client.get_history_page(printed, page_size, view, shared::HistoryOrder::default())?;
+        for item in &page.items {
+            let preview = item.content_preview.lines().next().unwrap_or_default();
+            println!(
+                "{}\t{}\t{preview}",
+                item.item_id,
+                item.content_type.as_str()
+            );
+        }
+        printed += page.items.len();
+        if page.is_last() || page.items.is_empty() {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Write an item's full text (`GetItemDetails`), or its data in one MIME type
+/// (`GetItemData`), to stdout.
+fn run_get_command(args: &clap::ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let id = *args.get_one::<u64>("id").expect("id is required");
+    let mut client = connect_to_daemon()?;
+    if let Some(mime_type) = args.get_one::<String>("mime-type") {
+        let data = client.get_item_data(id, mime_type)?;
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(&data)?;
+        stdout.flush()?;
+        return Ok(());
+    }
+    let details = client.get_item_details(id)?;
+    let text = details.full_text.ok_or_else(|| {
+        let mime_types: Vec<&str> = details
+            .mime_types
+            .iter()
+            .map(|(mime_type, _)| mime_type.as_str())
+            .collect();
+        format!(
+            "Item {id} has no text; choose one of its formats with --mime-type: {}",
+            mime_types.join(", ")
+        )
+    })?;
+    println!("{text}");
+    Ok(())
+}
+
+/// Set an item as the clipboard (`SetClipboardById`), pasting it with `instant_paste`.
+fn run_copy_command(
+    args: &clap::ArgMatches,
+    instant_paste: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let id = *args.get_one::<u64>("id").expect("id is required");
+    connect_to_daemon()?.set_clipboard_by_id(id, instant_paste, None, None)?;
+    Ok(())
+}
+
+/// Delete the history (`ClearHistory`).
+fn run_clear_command() -> Result<(), Box<dyn std::error::Error>> {
+    connect_to_daemon()?.clear_history()?;
+    println!("History cleared");
+    Ok(())
+}
+
+/// Bring back what the last delete or clear removed (`UndoLastOperation`).
+fn run_undo_command() -> Result<(), Box<dyn std::error::Error>> {
+    let count = connect_to_daemon()?.undo_last_operation()?;
+    println!("Restored {count} items");
+    Ok(())
+}
+
+/// dmenu bridge: list the history, or set the item of a chosen line. Returns false if
+/// nothing was chosen.
+fn run_menu_command(args: &clap::ArgMatches) -> Result<bool, Box<dyn std::error::Error>> {
+    let mut client = connect_to_daemon()?;
+    let instant_paste = args.get_flag("paste");
+    if let Some(launcher) = args.get_one::<String>("launcher") {
+        return Ok(frontend::dmenu::run_launcher(
+            &mut client,
+            launcher,
+            instant_paste,
+        )?);
+    }
+    if !args.get_flag("dmenu") {
+        return Err("Use --dmenu (with --select to read the choice) or --launcher COMMAND".into());
+    }
+    if args.get_flag("select") {
+        return Ok(frontend::dmenu::select_from_stdin(
+            &mut client,
+            instant_paste,
+        )?);
+    }
+    frontend::dmenu::print_menu(&mut client)?;
+    Ok(true)
+}
+
+/// Push content into the daemon's history (`AddItem`) and print the new item's ID.
+fn run_add_command(args: &clap::ArgMatches) -> Result<(), Box<dyn std::error::Error>> {
+    let payload = match args.get_one::<String>("text") {
+        Some(text) => text.clone().into_bytes(),
+        None => {
+            let mut data = Vec::new();
+            std::io::stdin().read_to_end(&mut data)?;
+            data
+        }
+    };
+    if payload.is_empty() {
+        return Err("Nothing to add".into());
+    }
+    let payload = bytes::Bytes::from(payload);
+    let mut mime_data = indexmap::IndexMap::new();
+    match args.get_one::<String>("mime-type") {
+        Some(mime_type) => {
+            mime_data.insert(mime_type.clone(), payload);
+        }
+        None => {
+            for mime_type in ["text/plain;charset=utf-8", "text/plain"] {
+                mime_data.insert(mime_type.to_string(), payload.clone());
+            }
+        }
+    }
+    let id =
+        connect_to_daemon()?.add_item(mime_data, args.get_flag("pin"), args.get_flag("select"))?;
+    println!("{id}");
+    Ok(())
+}
+
+/// Pause or resume recording (`SetRecordingEnabled`).
+fn run_recording_command(enabled: bool) -> Result<(), Box<dyn std::error::Error>> {
+    connect_to_daemon()?.set_recording_enabled(enabled)?;
+    println!(
+        "{}",
+        if enabled {
+            "Recording resumed"
+        } else {
+            "Recording paused; copies are not saved until `cursor-clip resume`"
+        }
+    );
+    Ok(())
+}
+
+/// Classify the daemon's history again (`ReclassifyHistory`).
+fn run_reclassify_command() -> Result<(), Box<dyn std::error::Error>> {
+    let changed = connect_to_daemon()?.reclassify_history()?;
+    println!("Reclassified {changed} items");
+    Ok(())
+}
+
+/// Print the daemon's health snapshot. Returns the process exit code: 0 when the
+/// daemon runs and records copies, 1 otherwise.
+fn run_status_command() -> i32 {
+    let status =
+        frontend::ipc_client::FrontendClient::new().and_then(|mut client| client.get_status());
+    match status {
+        Ok(status) => {
+            println!("{status}");
+            if status.monitor_state == shared::MonitorState::Degraded {
+                println!(
+                    "\nClipboard changes are not being recorded. Run `cursor-clip doctor` to check which protocols the compositor offers."
+                );
+                return 1;
+            }
+            0
+        }
+        Err(shared::CursorClipError::DaemonNotRunning) => {
+            println!(
+                "Daemon:       not running\n\nStart it with `cursor-clip daemon` (or enable it in your compositor's autostart)."
+            );
+            1
+        }
+        Err(e) => {
+            println!("Daemon:       not reachable ({e})");
+            1
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! Where `config.toml` and the history database live.
+
+use std::ffi::{CStr, OsStr};
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+
+/// `$XDG_CONFIG_HOME/cursor-clip`, or `~/.config/cursor-clip` when XDG_CONFIG_HOME is
+/// unset. A `~/.config/cursor-clip` from before XDG_CONFIG_HOME was honoured keeps being
+/// used until the XDG location exists, so its history and settings are not left behind.
+pub fn config_dir() -> PathBuf {
+    let default_dir = home_dir().join(".config").join("cursor-clip");
+    let Some(xdg_dir) = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        // The base directory spec says to ignore relative paths
+        .filter(|dir| dir.is_absolute())
+        .map(|dir| dir.join("cursor-clip"))
+    else {
+        return default_dir;
+    };
+    if !xdg_dir.exists() && default_dir.exists() {
+        default_dir
+    } else {
+        xdg_dir
+    }
+}
+
+pub fn config_path() -> PathBuf {
+    config_dir().join("config.toml")
+}
+
+/// `$HOME`, or the home directory in the user database when HOME is unset (e.g. in a
+/// systemd unit without `User=` environment).
+pub fn home_dir() -> PathBuf {
+    std::env::var_os("HOME")
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+        .or_else(passwd_home)
+        // Nowhere sensible is left; writes fail loudly here instead of landing in the
+        // working directory
+        .unwrap_or_else(|| PathBuf::from("/"))
+}
+
+fn passwd_home() -> Option<PathBuf> {
+    // SAFETY: passwd is plain data; getpwuid_r fills it in and points its strings into buf
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buf = vec![0 as libc::c_char; 4096];
+    let mut result = std::ptr::null_mut();
+    // SAFETY: every pointer refers to a live buffer of the size passed alongside it
+    let status = unsafe {
+        libc::getpwuid_r(
+            libc::getuid(),
+            &mut passwd,
+            buf.as_mut_ptr(),
+            buf.len(),
+            &mut result,
+        )
+    };
+    if status != 0 || result.is_null() || passwd.pw_dir.is_null() {
+        return None;
+    }
+    // SAFETY: pw_dir is a NUL-terminated string inside buf, which is still alive
+    let dir = unsafe { CStr::from_ptr(passwd.pw_dir) };
+    Some(PathBuf::from(OsStr::from_bytes(dir.to_bytes())))
+}
@@ -1,6 +1,10 @@
+use crate::shared::CursorClipError;
 use bytes::Bytes;
 use indexmap::IndexMap;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::LazyLock;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClipboardItem {
@@ -12,6 +16,32 @@ pub struct ClipboardItem {
     pub pinned: bool,
     pub mime_data: IndexMap<String, Bytes>, // content type -> payload bytes
     pub thumbnail: Option<Bytes>,
+    #[serde(default)]
+    pub seat: Option<String>, // name of the wl_seat the item was copied on
+    #[serde(default)]
+    pub image_info: Option<ImageInfo>,
+    #[serde(default)]
+    pub derived_from: Option<u64>, // item this one was produced from by a text transform
+    #[serde(default)]
+    pub files: Vec<String>, // local paths of a file copy (text/uri-list)
+    #[serde(default)]
+    pub paste_count: u32, // times it was picked from the history
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_pasted: Option<u64>, // Unix timestamp of the latest of those picks
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub compressed: IndexMap<String, u64>, // MIME types held zstd-compressed -> original size
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>, // detected language of Code, as a file extension (e.g. "rs")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_app: Option<String>, // app id focused when it was copied (foreign-toplevel)
+    #[serde(default = "first_copy")]
+    pub times_copied: u32, // copies of the same content merged into this entry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_copied: Option<u64>, // Unix timestamp of the latest of those copies
+}
+
+fn first_copy() -> u32 {
+    1
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +53,568 @@ pub struct ClipboardItemPreview {
     #[serde(default)]
     pub pinned: bool,
     pub thumbnail: Option<Bytes>,
+    #[serde(default)]
+    pub seat: Option<String>,
+    #[serde(default)]
+    pub image_info: Option<ImageInfo>,
+    #[serde(default)]
+    pub derived_from: Option<u64>,
+    #[serde(default)]
+    pub files: Vec<String>,
+    #[serde(default)]
+    pub language: Option<String>,
+    #[serde(default)]
+    pub source_app: Option<String>,
+    #[serde(default = "first_copy")]
+    pub times_copied: u32,
+    #[serde(default)]
+    pub last_copied: Option<u64>,
+    #[serde(default)]
+    pub paste_count: u32,
+    #[serde(default)]
+    pub last_pasted: Option<u64>,
+}
+
+/// Dimensions and size of the stored (canonical) image payload
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ImageInfo {
+    pub width: u32,
+    pub height: u32,
+    pub format: String, // e.g. "PNG"
+    pub byte_size: u64,
+}
+
+impl ImageInfo {
+    /// Human readable summary, e.g. "1920×1080 PNG, 2.4 MB"
+    pub fn describe(&self) -> String {
+        format!(
+            "{}×{} {}, {}",
+            self.width,
+            self.height,
+            self.format,
+            format_byte_size(self.byte_size)
+        )
+    }
+}
+
+pub fn format_byte_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1000 {
+        return format!("{bytes} B");
+    }
+    let mut value = bytes as f64;
+    let mut unit = "B";
+    for next in UNITS {
+        if value < 1000.0 {
+            break;
+        }
+        value /= 1000.0;
+        unit = next;
+    }
+    format!("{value:.1} {unit}")
+}
+
+#[cfg(test)]
+impl ClipboardItem {
+    /// Plain-text item copied at `1_700_000_000 + item_id`, for tests
+    pub(crate) fn plain_text(item_id: u64, text: &str) -> Self {
+        Self {
+            item_id,
+            content_preview: text.chars().take(200).collect(),
+            content_type: ClipboardContentType::Text,
+            timestamp: 1_700_000_000 + item_id,
+            pinned: false,
+            mime_data: IndexMap::from([("text/plain".to_string(), Bytes::from(text.to_string()))]),
+            thumbnail: None,
+            seat: None,
+            image_info: None,
+            derived_from: None,
+            files: Vec::new(),
+            paste_count: 0,
+            last_pasted: None,
+            compressed: IndexMap::new(),
+            language: None,
+            source_app: None,
+            times_copied: 1,
+            last_copied: None,
+        }
+    }
+}
+
+impl ClipboardItem {
+    /// When this content was last copied; its place in the history follows this
+    pub fn last_copied_at(&self) -> u64 {
+        self.last_copied.unwrap_or(self.timestamp)
+    }
+
+    /// When this item was last copied or picked from the history
+    pub fn last_used_at(&self) -> u64 {
+        self.last_copied_at().max(self.last_pasted.unwrap_or(0))
+    }
+
+    /// Total size of all stored MIME payloads
+    pub fn payload_size(&self) -> u64 {
+        self.mime_data
+            .values()
+            .map(|bytes| bytes.len() as u64)
+            .sum()
+    }
+
+    /// Total size of all MIME payloads before in-memory compression
+    pub fn original_payload_size(&self) -> u64 {
+        self.mime_data
+            .iter()
+            .map(|(mime, bytes)| {
+                self.compressed
+                    .get(mime)
+                    .copied()
+                    .unwrap_or(bytes.len() as u64)
+            })
+            .sum()
+    }
+}
+
+/// Everything the overlay's detail view shows about one history entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemDetails {
+    pub item: ClipboardItemPreview,
+    /// Full text content (plain text, or HTML converted to text), if any
+    pub full_text: Option<String>,
+    /// Offered MIME types with their payload sizes in bytes
+    pub mime_types: Vec<(String, u64)>,
+}
+
+/// Number of items the overlay requests per `GetHistoryPage`
+pub const HISTORY_PAGE_SIZE: usize = 50;
+/// Text returned by `GetItemFullPreview` without a `max_bytes`
+pub const FULL_PREVIEW_BYTES: usize = 4 * 1024;
+/// Largest `max_bytes` a `GetItemFullPreview` is answered with
+pub const MAX_FULL_PREVIEW_BYTES: usize = 64 * 1024;
+
+/// Changes to the history between a client's revision and `revision`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistoryDelta {
+    pub revision: u64,
+    /// Items added or changed since, each with its index in the full history (pinned
+    /// first, then newest first) after the change; by ascending index
+    pub changed: Vec<(usize, ClipboardItemPreview)>,
+    /// IDs of the items removed since
+    pub removed: Vec<u64>,
+}
+
+/// A slice of the history, newest first
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistoryPage {
+    pub items: Vec<ClipboardItemPreview>,
+    pub offset: usize,
+    /// Number of items in the requested view
+    pub total: usize,
+}
+
+impl HistoryPage {
+    /// Whether this page reaches the end of the history.
+    pub fn is_last(&self) -> bool {
+        self.offset + self.items.len() >= self.total
+    }
+}
+
+/// Aggregate numbers about the current history
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct HistoryStats {
+    pub items: usize,
+    pub images: usize,
+    pub pinned: usize,
+    pub total_bytes: u64,
+    /// Item count per content type, most common first
+    #[serde(default)]
+    pub by_type: Vec<(ClipboardContentType, usize)>,
+    /// Items copied in each local hour of the day (index 0 is 00:00–00:59)
+    #[serde(default)]
+    pub copies_by_hour: [usize; 24],
+    /// Items picked from the history most often, most pasted first
+    #[serde(default)]
+    pub most_pasted: Vec<PastedItem>,
+    /// Picks from the history (`SetClipboardById`, `SetPrimaryById`) over all items
+    #[serde(default)]
+    pub total_pastes: u64,
+    /// Copy time of the oldest unpinned item, to check the age limit against
+    #[serde(default)]
+    pub oldest_timestamp: Option<u64>,
+    /// Payload size before in-memory compression; `total_bytes` is what is held
+    #[serde(default)]
+    pub original_bytes: u64,
+    /// Items with compressed payloads, largest first
+    #[serde(default)]
+    pub compressed_items: Vec<CompressedItem>,
+}
+
+/// A history item holding compressed payloads in `HistoryStats`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CompressedItem {
+    pub item_id: u64,
+    pub original_bytes: u64,
+    pub stored_bytes: u64,
+}
+
+/// A frequently pasted history item in `HistoryStats`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PastedItem {
+    pub item_id: u64,
+    pub content_preview: String,
+    pub content_type: ClipboardContentType,
+    pub paste_count: u32,
+    #[serde(default)]
+    pub last_pasted: Option<u64>,
+}
+
+/// Health snapshot of the daemon, printed by `cursor-clip status`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DaemonStatus {
+    pub version: String,
+    pub pid: u32,
+    pub uptime_secs: u64,
+    /// Bound data-control protocol, e.g. `ext-data-control-v1`; None while the compositor offers none
+    pub protocol: Option<String>,
+    /// Version the data-control manager was bound at; None for X11 or without a manager
+    #[serde(default)]
+    pub protocol_version: Option<u32>,
+    pub monitor_state: MonitorState,
+    pub monitor_only: bool,
+    /// False while recording is paused (`SetRecordingEnabled`)
+    pub recording_enabled: bool,
+    pub persistence_enabled: bool,
+    pub seats: Vec<String>,
+    pub stats: HistoryStats,
+    /// Most recent failure as (Unix timestamp, message)
+    pub last_error: Option<(u64, String)>,
+    /// Unknown (all defaults) from daemons of earlier releases
+    #[serde(default)]
+    pub power: PowerStatus,
+}
+
+/// Power source and profile the daemon follows, and whether it reduces background work
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PowerStatus {
+    pub on_battery: bool,
+    /// Active power profile, e.g. "balanced" or "power-saver"
+    pub profile: Option<String>,
+    pub saving: bool,
+}
+
+impl fmt::Display for DaemonStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let yes_no = |flag: bool| if flag { "yes" } else { "no" };
+        writeln!(
+            f,
+            "Daemon:       running (pid {}, version {})",
+            self.pid, self.version
+        )?;
+        writeln!(f, "Uptime:       {}", format_duration(self.uptime_secs))?;
+        match (&self.protocol, self.protocol_version) {
+            (Some(protocol), Some(version)) => {
+                writeln!(f, "Protocol:     {protocol} (v{version})")?
+            }
+            (Some(protocol), None) => writeln!(f, "Protocol:     {protocol}")?,
+            (None, _) => writeln!(f, "Protocol:     none (no data-control manager)")?,
+        }
+        writeln!(
+            f,
+            "Monitoring:   {}",
+            match self.monitor_state {
+                MonitorState::Active => "active",
+                MonitorState::Degraded => "degraded",
+            }
+        )?;
+        writeln!(f, "Monitor only: {}", yes_no(self.monitor_only))?;
+        writeln!(
+            f,
+            "Recording:    {}",
+            if self.recording_enabled {
+                "on"
+            } else {
+                "paused"
+            }
+        )?;
+        writeln!(f, "Persistence:  {}", yes_no(self.persistence_enabled))?;
+        writeln!(
+            f,
+            "Power:        {}, profile {}{}",
+            if self.power.on_battery {
+                "battery"
+            } else {
+                "AC"
+            },
+            self.power.profile.as_deref().unwrap_or("unknown"),
+            if self.power.saving {
+                " (reducing background work)"
+            } else {
+                ""
+            }
+        )?;
+        writeln!(
+            f,
+            "Seats:        {}",
+            if self.seats.is_empty() {
+                "none".to_string()
+            } else {
+                self.seats.join(", ")
+            }
+        )?;
+        writeln!(
+            f,
+            "History:      {} ({} pinned)",
+            self.stats.describe(),
+            self.stats.pinned
+        )?;
+        if !self.stats.compressed_items.is_empty() {
+            writeln!(
+                f,
+                "Compression:  {} items; {} of payloads held in {}",
+                self.stats.compressed_items.len(),
+                format_byte_size(self.stats.original_bytes),
+                format_byte_size(self.stats.total_bytes)
+            )?;
+            for item in self.stats.compressed_items.iter().take(5) {
+                writeln!(
+                    f,
+                    "              #{}: {} -> {}",
+                    item.item_id,
+                    format_byte_size(item.original_bytes),
+                    format_byte_size(item.stored_bytes)
+                )?;
+            }
+        }
+        match &self.last_error {
+            Some((timestamp, message)) => {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs();
+                write!(
+                    f,
+                    "Last error:   {message} ({} ago)",
+                    format_duration(now.saturating_sub(*timestamp))
+                )
+            }
+            None => write!(f, "Last error:   none"),
+        }
+    }
+}
+
+/// Coarse human-readable duration, e.g. "3d 4h", "5m 12s"
+pub fn format_duration(secs: u64) -> String {
+    let (days, hours, minutes) = (secs / 86400, secs % 86400 / 3600, secs % 3600 / 60);
+    if days > 0 {
+        format!("{days}d {hours}h")
+    } else if hours > 0 {
+        format!("{hours}h {minutes}m")
+    } else if minutes > 0 {
+        format!("{minutes}m {}s", secs % 60)
+    } else {
+        format!("{secs}s")
+    }
+}
+
+impl HistoryStats {
+    const MOST_PASTED: usize = 5;
+
+    /// Everything but `copies_by_hour`, which needs the local time zone.
+    pub fn from_history(history: &[ClipboardItem]) -> Self {
+        let mut by_type: Vec<(ClipboardContentType, usize)> = Vec::new();
+        for item in history {
+            match by_type
+                .iter_mut()
+                .find(|(content_type, _)| *content_type == item.content_type)
+            {
+                Some((_, count)) => *count += 1,
+                None => by_type.push((item.content_type, 1)),
+            }
+        }
+        by_type.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+
+        let mut most_pasted: Vec<&ClipboardItem> =
+            history.iter().filter(|item| item.paste_count > 0).collect();
+        most_pasted.sort_by_key(|item| std::cmp::Reverse(item.paste_count));
+        let most_pasted = most_pasted
+            .into_iter()
+            .take(Self::MOST_PASTED)
+            .map(|item| PastedItem {
+                item_id: item.item_id,
+                content_preview: item.content_preview.chars().take(80).collect(),
+                content_type: item.content_type,
+                paste_count: item.paste_count,
+                last_pasted: item.last_pasted,
+            })
+            .collect();
+
+        let mut compressed_items: Vec<CompressedItem> = history
+            .iter()
+            .filter(|item| !item.compressed.is_empty())
+            .map(|item| CompressedItem {
+                item_id: item.item_id,
+                original_bytes: item.original_payload_size(),
+                stored_bytes: item.payload_size(),
+            })
+            .collect();
+        compressed_items.sort_by_key(|item| std::cmp::Reverse(item.original_bytes));
+
+        Self {
+            items: history.len(),
+            images: history
+                .iter()
+                .filter(|item| item.content_type == ClipboardContentType::Image)
+                .count(),
+            pinned: history.iter().filter(|item| item.pinned).count(),
+            total_bytes: history.iter().map(ClipboardItem::payload_size).sum(),
+            by_type,
+            copies_by_hour: [0; 24],
+            most_pasted,
+            total_pastes: history.iter().map(|item| u64::from(item.paste_count)).sum(),
+            oldest_timestamp: history
+                .iter()
+                .filter(|item| !item.pinned)
+                .map(ClipboardItem::last_copied_at)
+                .min(),
+            original_bytes: history
+                .iter()
+                .map(ClipboardItem::original_payload_size)
+                .sum(),
+            compressed_items,
+        }
+    }
+
+    /// Hours of the day with the most copies, busiest first; hours without copies are left out.
+    pub fn busiest_hours(&self, count: usize) -> Vec<(usize, usize)> {
+        let mut hours: Vec<(usize, usize)> = self
+            .copies_by_hour
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|(_, copies)| *copies > 0)
+            .collect();
+        hours.sort_by_key(|(_, copies)| std::cmp::Reverse(*copies));
+        hours.truncate(count);
+        hours
+    }
+
+    /// Summary line, e.g. "84 items · 3 images · 12.0 MB"
+    pub fn describe(&self) -> String {
+        let plural = |count: usize, noun: &str| {
+            format!("{count} {noun}{}", if count == 1 { "" } else { "s" })
+        };
+        let mut parts = vec![plural(self.items, "item")];
+        if self.images > 0 {
+            parts.push(plural(self.images, "image"));
+        }
+        parts.push(format_byte_size(self.total_bytes));
+        parts.join(" · ")
+    }
+}
+
+/// Limits applied to the history by the daemon. A value of 0 disables that limit.
+/// Pinned items are never pruned.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    pub max_items: usize,
+    pub max_total_bytes: u64,
+    pub max_item_bytes: u64,
+    pub max_age_days: u64,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_items: 100,
+            max_total_bytes: 0,
+            max_item_bytes: 0,
+            max_age_days: 0,
+        }
+    }
+}
+
+/// A permanent text template from `[[snippets]]` in config.toml. Snippets are addressed
+/// by their position in that list.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Snippet {
+    pub name: String,
+    /// May contain `{date}`, `{time}`, `{datetime}` and `{clipboard}` placeholders
+    pub text: String,
+}
+
+/// Daemon settings that can be read and replaced at runtime (`GetConfig` / `SetConfig`).
+/// Field names match the keys in `config.toml`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct DaemonSettings {
+    pub monitor_only: bool,
+    pub persist_on_exit: bool,
+    #[serde(flatten)]
+    pub retention: RetentionPolicy,
+    pub exclude_apps: Vec<String>,
+}
+
+impl DaemonSettings {
+    pub const KEYS: [&'static str; 7] = [
+        "monitor_only",
+        "persist_on_exit",
+        "max_items",
+        "max_total_bytes",
+        "max_item_bytes",
+        "max_age_days",
+        "exclude_apps",
+    ];
+
+    /// Set one setting from its textual form, e.g. `max_items` = `"50"`.
+    /// `exclude_apps` takes a comma-separated list.
+    pub fn set_from_str(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        let parse_bool = |value: &str| {
+            value
+                .parse::<bool>()
+                .map_err(|_| format!("{key} expects true or false, got {value:?}"))
+        };
+        let parse_u64 = |value: &str| {
+            value
+                .parse::<u64>()
+                .map_err(|_| format!("{key} expects a non-negative number, got {value:?}"))
+        };
+        match key {
+            "monitor_only" => self.monitor_only = parse_bool(value)?,
+            "persist_on_exit" => self.persist_on_exit = parse_bool(value)?,
+            "max_items" => self.retention.max_items = parse_u64(value)? as usize,
+            "max_total_bytes" => self.retention.max_total_bytes = parse_u64(value)?,
+            "max_item_bytes" => self.retention.max_item_bytes = parse_u64(value)?,
+            "max_age_days" => self.retention.max_age_days = parse_u64(value)?,
+            "exclude_apps" => {
+                self.exclude_apps = value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|app| !app.is_empty())
+                    .map(str::to_string)
+                    .collect();
+            }
+            _ => {
+                return Err(format!(
+                    "Unknown setting {key:?} (expected one of {})",
+                    Self::KEYS.join(", ")
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DaemonSettings {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "monitor_only = {}", self.monitor_only)?;
+        writeln!(f, "persist_on_exit = {}", self.persist_on_exit)?;
+        writeln!(f, "max_items = {}", self.retention.max_items)?;
+        writeln!(f, "max_total_bytes = {}", self.retention.max_total_bytes)?;
+        writeln!(f, "max_item_bytes = {}", self.retention.max_item_bytes)?;
+        writeln!(f, "max_age_days = {}", self.retention.max_age_days)?;
+        write!(f, "exclude_apps = {:?}", self.exclude_apps)
+    }
 }
 
 impl From<&ClipboardItem> for ClipboardItemPreview {
@@ -34,6 +626,16 @@ impl From<&ClipboardItem> for ClipboardItemPreview {
             timestamp: full.timestamp,
             pinned: full.pinned,
             thumbnail: full.thumbnail.clone(),
+            seat: full.seat.clone(),
+            image_info: full.image_info.clone(),
+            derived_from: full.derived_from,
+            files: full.files.clone(),
+            language: full.language.clone(),
+            source_app: full.source_app.clone(),
+            times_copied: full.times_copied,
+            last_copied: full.last_copied,
+            paste_count: full.paste_count,
+            last_pasted: full.last_pasted,
         }
     }
 }
@@ -47,17 +649,62 @@ pub enum ClipboardContentType {
     File,
     Image,
     Other,
+    Email,
+    IpAddress,
+    Uuid,
 }
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum FrontendMessage {
     /// Request clipboard history
     GetHistory,
+    /// Request the changes to the history since `since_revision` (a revision the
+    /// client saw), or the whole history if the daemon no longer knows them
+    GetHistorySince { since_revision: u64 },
+    /// Request `limit` items of `view` starting at `offset`
+    GetHistoryPage {
+        offset: usize,
+        limit: usize,
+        #[serde(default)]
+        view: HistoryView,
+        #[serde(default)]
+        order: HistoryOrder,
+    },
+    /// Request aggregate numbers about the history
+    GetStats,
+    /// Request full text and MIME overview of one item
+    GetItemDetails { id: u64 },
+    /// Request the raw payload of one item in one MIME type
+    GetItemData { id: u64, mime_type: String },
+    /// Request the start of an item's text, longer than its preview: up to `max_bytes`
+    /// (`FULL_PREVIEW_BYTES` by default, at most `MAX_FULL_PREVIEW_BYTES`)
+    GetItemFullPreview {
+        id: u64,
+        #[serde(default)]
+        max_bytes: Option<usize>,
+    },
+    /// Ask whether a selection was recovered from a crash backup at startup
+    GetRecoveredItem,
+    /// Stop offering the recovered selection
+    DismissRecoveredItem,
     /// Set clipboard content by ID
     SetClipboardById {
         id: u64,
         #[serde(default)]
         instant_paste: bool,
+        /// Seat to set the selection on; defaults to the item's originating seat
+        #[serde(default)]
+        seat: Option<String>,
+        /// Offer a reduced set of MIME types instead of everything that was copied
+        #[serde(default)]
+        mime_filter: Option<MimeFilter>,
+    },
+    /// Offer an item as the primary selection, pasted with a middle click (Wayland only)
+    SetPrimaryById {
+        id: u64,
+        /// Defaults to the item's originating seat
+        #[serde(default)]
+        seat: Option<String>,
     },
     /// Set pinned state by ID
     SetPinned { id: u64, pinned: bool },
@@ -65,47 +712,596 @@ pub enum FrontendMessage {
     DeleteItemById { id: u64 },
     /// Clear all clipboard history
     ClearHistory,
+    /// Restore the items removed by the most recent `DeleteItemById` or `ClearHistory`,
+    /// while they are still in the trash
+    UndoLastOperation,
     /// Enable or disable persistent history storage
     SetHistoryPersistence { enabled: bool },
+    /// Replace the retention limits of the running daemon (not written to config.toml)
+    SetRetentionPolicy { policy: RetentionPolicy },
+    /// Request a health snapshot of the daemon
+    Status,
+    /// Request the runtime-changeable daemon settings
+    GetConfig,
+    /// Replace the runtime-changeable daemon settings and write them to config.toml
+    SetConfig { settings: DaemonSettings },
+    /// Record copies without taking ownership of the selection (like `--monitor-only`)
+    SetMonitorOnly { enabled: bool },
+    /// Pause (false) or resume (true) recording; while paused, copies are dropped
+    SetRecordingEnabled { enabled: bool },
+    /// Request whether recording is paused
+    GetRecordingState,
+    /// Replace the `exclude_apps` deny list of the running daemon
+    SetExcludedApps { apps: Vec<String> },
+    /// Transform an item's text into a new history entry and set it as the selection
+    TransformAndSet {
+        id: u64,
+        transform: TextTransform,
+        #[serde(default)]
+        instant_paste: bool,
+        #[serde(default)]
+        seat: Option<String>,
+    },
+    /// Join the text of several items (in the given order) into a new history entry and
+    /// set it as the selection
+    MergeAndSet {
+        ids: Vec<u64>,
+        #[serde(default)]
+        separator: MergeSeparator,
+        #[serde(default)]
+        instant_paste: bool,
+        #[serde(default)]
+        seat: Option<String>,
+    },
+    /// Append items to the paste queue (stack mode); each paste of the queue's front
+    /// selects the next one
+    PushPasteQueue {
+        ids: Vec<u64>,
+        #[serde(default)]
+        seat: Option<String>,
+    },
+    /// Skip the queue's front and select the next item
+    PopPasteQueue {
+        #[serde(default)]
+        instant_paste: bool,
+        #[serde(default)]
+        seat: Option<String>,
+    },
+    /// Request the paste queue
+    GetPasteQueue,
+    /// Empty the paste queue
+    ClearPasteQueue,
+    /// Run content-type classification (built-ins and `[[classifier_rules]]`) over the history
+    ReclassifyHistory,
+    /// Write the history to `path` (absolute, written by the daemon)
+    ExportHistory {
+        path: String,
+        format: ExportFormat,
+        /// Also export items classified as passwords
+        #[serde(default)]
+        include_sensitive: bool,
+    },
+    /// Merge an export written by `ExportHistory` into the history
+    ImportHistory { path: String },
+    /// Fuzzy-search `view`; up to `limit` results (0 for all), best first. The query may
+    /// contain `type:`, `mime:`, `re:` and `since:` filter words, `"phrases"` and `prefix*` words
+    SearchHistory {
+        query: String,
+        #[serde(default)]
+        view: HistoryView,
+        #[serde(default)]
+        limit: usize,
+    },
+    /// Request the snippet library
+    GetSnippets,
+    /// Append a snippet to the library (written to config.toml)
+    AddSnippet { snippet: Snippet },
+    /// Remove the snippet at position `id`
+    RemoveSnippet { id: usize },
+    /// Expand the snippet at position `id`, record it as a history entry and set it as
+    /// the selection
+    PasteSnippet {
+        id: usize,
+        #[serde(default)]
+        instant_paste: bool,
+        #[serde(default)]
+        seat: Option<String>,
+    },
+    /// Turn the connection into an event stream: after `Subscribed`, every backend event
+    /// (`NewItem`, `ItemDeleted`, `ItemPinned`, `HistoryCleared`, `RecordingState`, ...)
+    /// is sent as it happens, and no further requests are read
+    Subscribe,
+    /// Request the recently used emoji, most recent first
+    GetRecentEmoji,
+    /// Record a picked emoji as a history entry and set it as the selection
+    PasteEmoji {
+        emoji: String,
+        #[serde(default)]
+        instant_paste: bool,
+        #[serde(default)]
+        seat: Option<String>,
+    },
+    /// Show the resident overlay (`cursor-clip resident`) with its top-left corner at
+    /// `x`/`y`, in logical pixels from the top-left of the focused monitor; moves it if it
+    /// is already shown. The daemon relays this to the resident frontend's socket
+    ShowAt { x: f64, y: f64 },
+    /// Hide the resident overlay; relayed like `ShowAt`
+    Close,
+    /// Record new content as a history item (e.g. dropped onto the overlay or pushed
+    /// by a script), even while recording is paused; optionally pin it and set it as
+    /// the selection in the same request
+    AddItem {
+        mime_data: IndexMap<String, Bytes>,
+        #[serde(default)]
+        pin: bool,
+        #[serde(default)]
+        select: bool,
+    },
+}
+
+impl FrontendMessage {
+    /// Stable command name, used for audit logging.
+    pub fn name(&self) -> &'static str {
+        match self {
+            FrontendMessage::GetHistory => "GetHistory",
+            FrontendMessage::GetHistorySince { .. } => "GetHistorySince",
+            FrontendMessage::GetHistoryPage { .. } => "GetHistoryPage",
+            FrontendMessage::GetStats => "GetStats",
+            FrontendMessage::GetItemDetails { .. } => "GetItemDetails",
+            FrontendMessage::GetItemData { .. } => "GetItemData",
+            FrontendMessage::GetItemFullPreview { .. } => "GetItemFullPreview",
+            FrontendMessage::GetRecoveredItem => "GetRecoveredItem",
+            FrontendMessage::DismissRecoveredItem => "DismissRecoveredItem",
+            FrontendMessage::SetClipboardById { .. } => "SetClipboardById",
+            FrontendMessage::SetPrimaryById { .. } => "SetPrimaryById",
+            FrontendMessage::SetPinned { .. } => "SetPinned",
+            FrontendMessage::DeleteItemById { .. } => "DeleteItemById",
+            FrontendMessage::ClearHistory => "ClearHistory",
+            FrontendMessage::UndoLastOperation => "UndoLastOperation",
+            FrontendMessage::SetHistoryPersistence { .. } => "SetHistoryPersistence",
+            FrontendMessage::SetRetentionPolicy { .. } => "SetRetentionPolicy",
+            FrontendMessage::Status => "Status",
+            FrontendMessage::GetConfig => "GetConfig",
+            FrontendMessage::SetConfig { .. } => "SetConfig",
+            FrontendMessage::SetMonitorOnly { .. } => "SetMonitorOnly",
+            FrontendMessage::SetRecordingEnabled { .. } => "SetRecordingEnabled",
+            FrontendMessage::GetRecordingState => "GetRecordingState",
+            FrontendMessage::SetExcludedApps { .. } => "SetExcludedApps",
+            FrontendMessage::TransformAndSet { .. } => "TransformAndSet",
+            FrontendMessage::MergeAndSet { .. } => "MergeAndSet",
+            FrontendMessage::PushPasteQueue { .. } => "PushPasteQueue",
+            FrontendMessage::PopPasteQueue { .. } => "PopPasteQueue",
+            FrontendMessage::GetPasteQueue => "GetPasteQueue",
+            FrontendMessage::ClearPasteQueue => "ClearPasteQueue",
+            FrontendMessage::ReclassifyHistory => "ReclassifyHistory",
+            FrontendMessage::ExportHistory { .. } => "ExportHistory",
+            FrontendMessage::ImportHistory { .. } => "ImportHistory",
+            FrontendMessage::SearchHistory { .. } => "SearchHistory",
+            FrontendMessage::GetSnippets => "GetSnippets",
+            FrontendMessage::AddSnippet { .. } => "AddSnippet",
+            FrontendMessage::RemoveSnippet { .. } => "RemoveSnippet",
+            FrontendMessage::PasteSnippet { .. } => "PasteSnippet",
+            FrontendMessage::Subscribe => "Subscribe",
+            FrontendMessage::GetRecentEmoji => "GetRecentEmoji",
+            FrontendMessage::PasteEmoji { .. } => "PasteEmoji",
+            FrontendMessage::ShowAt { .. } => "ShowAt",
+            FrontendMessage::Close => "Close",
+            FrontendMessage::AddItem { .. } => "AddItem",
+        }
+    }
+
+    /// The history item this command targets, if any.
+    pub fn item_id(&self) -> Option<u64> {
+        match self {
+            FrontendMessage::SetClipboardById { id, .. }
+            | FrontendMessage::SetPrimaryById { id, .. }
+            | FrontendMessage::SetPinned { id, .. }
+            | FrontendMessage::DeleteItemById { id }
+            | FrontendMessage::TransformAndSet { id, .. }
+            | FrontendMessage::GetItemDetails { id }
+            | FrontendMessage::GetItemData { id, .. }
+            | FrontendMessage::GetItemFullPreview { id, .. } => Some(*id),
+            FrontendMessage::GetHistory
+            | FrontendMessage::GetHistorySince { .. }
+            | FrontendMessage::GetHistoryPage { .. }
+            | FrontendMessage::GetStats
+            | FrontendMessage::GetRecoveredItem
+            | FrontendMessage::DismissRecoveredItem
+            | FrontendMessage::ClearHistory
+            | FrontendMessage::UndoLastOperation
+            | FrontendMessage::SetHistoryPersistence { .. }
+            | FrontendMessage::SetRetentionPolicy { .. }
+            | FrontendMessage::Status
+            | FrontendMessage::GetConfig
+            | FrontendMessage::SetConfig { .. }
+            | FrontendMessage::SetMonitorOnly { .. }
+            | FrontendMessage::SetRecordingEnabled { .. }
+            | FrontendMessage::GetRecordingState
+            | FrontendMessage::SetExcludedApps { .. }
+            | FrontendMessage::MergeAndSet { .. }
+            | FrontendMessage::PushPasteQueue { .. }
+            | FrontendMessage::PopPasteQueue { .. }
+            | FrontendMessage::GetPasteQueue
+            | FrontendMessage::ClearPasteQueue
+            | FrontendMessage::ReclassifyHistory
+            | FrontendMessage::ExportHistory { .. }
+            | FrontendMessage::ImportHistory { .. }
+            | FrontendMessage::SearchHistory { .. }
+            | FrontendMessage::GetSnippets
+            | FrontendMessage::AddSnippet { .. }
+            | FrontendMessage::RemoveSnippet { .. }
+            | FrontendMessage::PasteSnippet { .. }
+            | FrontendMessage::Subscribe
+            | FrontendMessage::GetRecentEmoji
+            | FrontendMessage::PasteEmoji { .. }
+            | FrontendMessage::ShowAt { .. }
+            | FrontendMessage::Close
+            | FrontendMessage::AddItem { .. } => None,
+        }
+    }
+
+    /// Whether running the command twice has the same effect as running it once, so it
+    /// may be sent again when the connection broke before its reply arrived. Commands
+    /// that add, paste, clear or undo are not.
+    pub fn is_idempotent(&self) -> bool {
+        matches!(
+            self,
+            FrontendMessage::GetHistory
+                | FrontendMessage::GetHistorySince { .. }
+                | FrontendMessage::GetHistoryPage { .. }
+                | FrontendMessage::GetStats
+                | FrontendMessage::GetItemDetails { .. }
+                | FrontendMessage::GetItemData { .. }
+                | FrontendMessage::GetItemFullPreview { .. }
+                | FrontendMessage::GetRecoveredItem
+                | FrontendMessage::DismissRecoveredItem
+                | FrontendMessage::Status
+                | FrontendMessage::GetConfig
+                | FrontendMessage::GetRecordingState
+                | FrontendMessage::GetPasteQueue
+                | FrontendMessage::SearchHistory { .. }
+                | FrontendMessage::GetSnippets
+                | FrontendMessage::GetRecentEmoji
+                | FrontendMessage::SetPinned { .. }
+                | FrontendMessage::SetHistoryPersistence { .. }
+                | FrontendMessage::SetRetentionPolicy { .. }
+                | FrontendMessage::SetMonitorOnly { .. }
+                | FrontendMessage::SetRecordingEnabled { .. }
+                | FrontendMessage::SetExcludedApps { .. }
+        )
+    }
+}
+
+/// Restricts what a re-owned selection offers
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum MimeFilter {
+    /// Only `text/plain;charset=utf-8`; HTML-only entries are converted to text
+    PlainText,
+    /// The paths of a file copy as plain text instead of the files themselves
+    PathsAsText,
+}
+
+/// One `SearchHistory` hit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub item: ClipboardItemPreview,
+    /// Higher is better; results arrive sorted by it
+    pub score: i64,
+    /// Byte ranges of `item.content_preview` that matched the query
+    pub highlights: Vec<(usize, usize)>,
+}
+
+/// Subset of the history shown by an overlay tab
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum HistoryView {
+    #[default]
+    All,
+    Pinned,
+    Images,
+    Files,
+}
+
+impl HistoryView {
+    pub const ALL: [Self; 4] = [Self::All, Self::Pinned, Self::Images, Self::Files];
+
+    /// Stable name, e.g. for stack pages
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::All => "all",
+            Self::Pinned => "pinned",
+            Self::Images => "images",
+            Self::Files => "files",
+        }
+    }
+
+    pub const fn title(self) -> &'static str {
+        match self {
+            Self::All => "All",
+            Self::Pinned => "Pinned",
+            Self::Images => "Images",
+            Self::Files => "Files",
+        }
+    }
+
+    pub const fn icon_name(self) -> &'static str {
+        match self {
+            Self::All => "edit-paste-symbolic",
+            Self::Pinned => "view-pin-symbolic",
+            Self::Images => "image-x-generic-symbolic",
+            Self::Files => "folder-documents-symbolic",
+        }
+    }
+
+    pub fn includes(self, content_type: ClipboardContentType, pinned: bool) -> bool {
+        match self {
+            Self::All => true,
+            Self::Pinned => pinned,
+            Self::Images => content_type == ClipboardContentType::Image,
+            Self::Files => content_type == ClipboardContentType::File,
+        }
+    }
+}
+
+/// Order of the unpinned items in a `GetHistoryPage`; pinned items always come first
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum HistoryOrder {
+    /// Most recently copied first
+    #[default]
+    Recent,
+    /// Items that are pasted or copied often and recently first
+    Frecency,
+}
+
+/// File format of `ExportHistory`
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// One JSON document with base64 payloads
+    Json,
+    /// A tar archive with a JSON manifest and the payloads as separate files
+    Tar,
+}
+
+impl ExportFormat {
+    /// Guess the format from a file name: `.tar` is a tarball, anything else JSON.
+    pub fn from_path(path: &str) -> Self {
+        if path.ends_with(".tar") {
+            Self::Tar
+        } else {
+            Self::Json
+        }
+    }
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "json" => Ok(Self::Json),
+            "tar" => Ok(Self::Tar),
+            other => Err(format!(
+                "Unknown export format {other:?} (expected json or tar)"
+            )),
+        }
+    }
+}
+
+/// Text transforms offered per history item
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum TextTransform {
+    Uppercase,
+    Lowercase,
+    TrimWhitespace,
+    StripNewlines,
+    UrlDecode,
+    UrlEncode,
+    JsonPretty,
+}
+
+impl TextTransform {
+    pub const ALL: [Self; 7] = [
+        Self::Uppercase,
+        Self::Lowercase,
+        Self::TrimWhitespace,
+        Self::StripNewlines,
+        Self::UrlDecode,
+        Self::UrlEncode,
+        Self::JsonPretty,
+    ];
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Uppercase => "UPPERCASE",
+            Self::Lowercase => "lowercase",
+            Self::TrimWhitespace => "Trim whitespace",
+            Self::StripNewlines => "Strip newlines",
+            Self::UrlDecode => "URL-decode",
+            Self::UrlEncode => "URL-encode",
+            Self::JsonPretty => "Pretty-print JSON",
+        }
+    }
+}
+
+/// What goes between the items joined by `MergeAndSet`
+#[derive(Debug, Copy, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeSeparator {
+    #[default]
+    Newline,
+    Space,
+    Comma,
+}
+
+impl MergeSeparator {
+    pub const ALL: [Self; 3] = [Self::Newline, Self::Space, Self::Comma];
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Newline => "\n",
+            Self::Space => " ",
+            Self::Comma => ", ",
+        }
+    }
+
+    pub const fn label(self) -> &'static str {
+        match self {
+            Self::Newline => "New line",
+            Self::Space => "Space",
+            Self::Comma => "Comma",
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum BackendMessage {
     /// Response with clipboard history (previews only, no mime payloads)
     History { items: Vec<ClipboardItemPreview> },
+    /// Response to `GetHistorySince`: the changes since that revision
+    HistoryDelta { delta: HistoryDelta },
+    /// Response with one page of the history
+    HistoryPage { page: HistoryPage },
+    /// Response with aggregate history numbers
+    Stats { stats: HistoryStats },
+    /// Response to `AddItem` with the ID of the new item
+    ItemAdded { id: u64 },
+    /// Response with the details of one item
+    ItemDetails { details: ItemDetails },
+    /// Response with the payload of one item in the requested MIME type
+    ItemData { mime_type: String, data: Bytes },
+    /// Response with the start of an item's text; `truncated` if there is more
+    ItemFullPreview {
+        id: u64,
+        text: String,
+        truncated: bool,
+    },
+    /// History item restored from a crash backup that is still on offer, if any
+    RecoveredItem { id: Option<u64> },
     /// New clipboard item added (preview only)
     NewItem { item: ClipboardItemPreview },
     /// Clipboard content set successfully
     ClipboardSet,
+    /// A transformed copy of `source_id` was added as `item` and set as the selection
+    ItemTransformed {
+        source_id: u64,
+        item: ClipboardItemPreview,
+    },
+    /// `source_ids` were joined into `item`, which was set as the selection
+    ItemsMerged {
+        source_ids: Vec<u64>,
+        item: ClipboardItemPreview,
+    },
+    /// Item IDs in the paste queue, next first
+    PasteQueue { ids: Vec<u64> },
+    /// History reclassified; `changed` items got a different type
+    HistoryReclassified { changed: usize },
     /// Clipboard item deleted
     ItemDeleted { id: u64 },
     /// Clipboard item pinned state updated
     ItemPinned { id: u64, pinned: bool },
     /// History cleared
     HistoryCleared,
+    /// `UndoLastOperation` put `count` items back into the history
+    ItemsRestored { count: usize },
+    /// The connection now carries backend events (response to `Subscribe`)
+    Subscribed,
     /// Persistence state was updated
     PersistenceState { enabled: bool },
+    /// Retention policy was updated; `pruned` items were removed by it
+    RetentionPolicyUpdated {
+        policy: RetentionPolicy,
+        pruned: usize,
+    },
+    /// Health snapshot of the daemon
+    Status { status: DaemonStatus },
+    /// Current daemon settings (response to `GetConfig` and `SetConfig`)
+    Config { settings: DaemonSettings },
+    /// Monitor-only mode was updated
+    MonitorOnlyState { enabled: bool },
+    /// Whether copies are recorded (false while paused)
+    RecordingState { enabled: bool },
+    /// The `exclude_apps` deny list was replaced
+    ExcludedAppsUpdated { apps: Vec<String> },
+    /// `count` items were written to `path`
+    HistoryExported { path: String, count: usize },
+    /// Items from an export were merged; `skipped` were already present or unusable
+    HistoryImported { imported: usize, skipped: usize },
+    /// Ranked `SearchHistory` hits
+    SearchResults { results: Vec<SearchResult> },
+    /// The snippet library (response to `GetSnippets`, `AddSnippet` and `RemoveSnippet`)
+    Snippets { snippets: Vec<Snippet> },
+    /// Recently used emoji, most recent first
+    RecentEmoji { emoji: Vec<String> },
+    /// Whether the resident overlay is shown (response to `ShowAt` and `Close`)
+    OverlayState { visible: bool },
+    /// Clipboard monitoring became degraded or recovered (e.g. compositor restarted data-control)
+    MonitorStateChanged { state: MonitorState, reason: String },
+    /// Event: the history changed (items added, removed, pinned, reordered or
+    /// reclassified) and is now at `revision`; clients showing it should reload
+    HistoryChanged { revision: u64 },
     /// Error occurred
-    Error { message: String },
+    Error { error: CursorClipError },
+}
+
+/// A daemon reply as sent over the socket: the message together with the history
+/// revision after the request was handled, so a client can tell whether what it shows
+/// is still current. Events of a subscribed connection are sent as bare messages.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackendResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<u64>,
+    #[serde(flatten)]
+    pub message: BackendMessage,
+}
+
+impl BackendResponse {
+    /// Parse a reply line; daemons of earlier releases send the bare message.
+    pub fn from_json(line: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(line).or_else(|e| {
+            serde_json::from_str(line)
+                .map(|message| Self {
+                    revision: None,
+                    message,
+                })
+                .map_err(|_| e)
+        })
+    }
+}
+
+#[derive(Debug, Copy, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub enum MonitorState {
+    /// Clipboard changes are being recorded
+    Active,
+    /// A required global (data-control manager or seat) is gone; history is frozen
+    Degraded,
 }
 
 impl ClipboardContentType {
+    /// Built-in classification of an item's text by its preview. User rules from
+    /// `[[classifier_rules]]` are applied on top of this by the daemon.
     pub fn type_from_preview(content: &str) -> Self {
-        const PASSWORD_SPECIALS: &str = "!@#$%^&*()-_=+[]{};:,.<>?/\\|`~";
-        if content.starts_with("http://") || content.starts_with("https://") {
+        let trimmed = content.trim();
+        let single_token = !trimmed.is_empty() && !trimmed.contains(char::is_whitespace);
+        if trimmed.starts_with("http://") || trimmed.starts_with("https://") {
             Self::Url
+        } else if single_token && EMAIL_PATTERN.is_match(trimmed) {
+            Self::Email
+        } else if single_token
+            && (trimmed.parse::<std::net::IpAddr>().is_ok()
+                || trimmed.parse::<std::net::SocketAddr>().is_ok())
+        {
+            Self::IpAddress
+        } else if single_token && UUID_PATTERN.is_match(trimmed) {
+            Self::Uuid
         } else if content.contains("fn ")
             || content.contains("impl ")
             || content.contains("struct ")
         {
             Self::Code
-        } else if content.contains('/') && !content.contains(' ') && content.len() < 256 {
+        } else if single_token && trimmed.len() < 256 && looks_like_path(trimmed) {
             Self::File
-        } else if !content.is_empty()
-            && content.len() < 50
-            && !content.contains(' ')
-            && content.chars().any(|c| PASSWORD_SPECIALS.contains(c))
-        {
+        } else if single_token && looks_like_password(trimmed) {
             Self::Password
         } else {
             Self::Text
@@ -123,6 +1319,9 @@ impl ClipboardContentType {
             Self::File => "File",
             Self::Image => "Image",
             Self::Other => "Other",
+            Self::Email => "Email",
+            Self::IpAddress => "IP address",
+            Self::Uuid => "UUID",
         }
     }
 
@@ -135,6 +1334,45 @@ impl ClipboardContentType {
             Self::File => "📁",
             Self::Image => "🖼️",
             Self::Other => "📄",
+            Self::Email => "📧",
+            Self::IpAddress => "🌐",
+            Self::Uuid => "🆔",
         }
     }
 }
+
+static EMAIL_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}$").unwrap()
+});
+static UUID_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^\{?[0-9A-Fa-f]{8}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{4}-[0-9A-Fa-f]{12}\}?$",
+    )
+    .unwrap()
+});
+
+/// Absolute, home-relative or explicitly relative paths, or any path that exists.
+/// Fractions and "and/or" are not paths.
+fn looks_like_path(token: &str) -> bool {
+    let explicit = ["/", "~/", "./", "../"]
+        .iter()
+        .any(|prefix| token.starts_with(prefix))
+        && token.len() > 1;
+    explicit || (token.contains('/') && std::path::Path::new(token).exists())
+}
+
+/// 8 to 64 characters mixing at least three of lowercase, uppercase, digits and symbols,
+/// like generated passwords and API tokens; plain words with punctuation don't qualify.
+fn looks_like_password(token: &str) -> bool {
+    let length = token.chars().count();
+    if !(8..=64).contains(&length) {
+        return false;
+    }
+    let classes = [
+        token.chars().any(|c| c.is_lowercase()),
+        token.chars().any(|c| c.is_uppercase()),
+        token.chars().any(|c| c.is_ascii_digit()),
+        token.chars().any(|c| c.is_ascii_punctuation()),
+    ];
+    classes.into_iter().filter(|present| *present).count() >= 3
+}
@@ -0,0 +1,137 @@
+use crate::shared::quirks::{Compositor, DataControlProtocol, active_quirks};
+use crate::shared::sandbox::flatpak_app_id;
+use wayland_client::globals::{GlobalListContents, registry_queue_init};
+use wayland_client::protocol::wl_registry;
+use wayland_client::{Connection, Dispatch, QueueHandle};
+
+/// Wayland globals cursor-clip relies on and what stops working without them.
+const PROTOCOL_CHECKS: &[(&str, &str)] = &[
+    ("zwlr_layer_shell_v1", "overlay window and pointer capture"),
+    ("wl_seat", "clipboard access"),
+    ("zwp_virtual_keyboard_manager_v1", "instant paste"),
+    (
+        "zwlr_foreign_toplevel_manager_v1",
+        "exclude_apps (copies are recorded from every app)",
+    ),
+    (
+        "wp_single_pixel_buffer_manager_v1",
+        "none (falls back to a wl_shm buffer for the capture surface)",
+    ),
+];
+
+const EXT_DATA_CONTROL: &str = "ext_data_control_manager_v1";
+const WLR_DATA_CONTROL: &str = "zwlr_data_control_manager_v1";
+
+struct DoctorState;
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for DoctorState {
+    fn event(
+        _state: &mut Self,
+        _proxy: &wl_registry::WlRegistry,
+        _event: wl_registry::Event,
+        _data: &GlobalListContents,
+        _conn: &Connection,
+        _qhandle: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+/// Interface names and versions advertised by the compositor.
+fn advertised_globals() -> Result<Vec<(String, u32)>, String> {
+    let connection =
+        Connection::connect_to_env().map_err(|e| format!("Wayland connection failed: {e}"))?;
+    let (globals, _queue) =
+        registry_queue_init::<DoctorState>(&connection).map_err(|e| e.to_string())?;
+    Ok(globals
+        .contents()
+        .clone_list()
+        .into_iter()
+        .map(|global| (global.interface, global.version))
+        .collect())
+}
+
+/// Human readable report for `cursor-clip doctor`: detected compositor, active quirks, protocol
+/// availability and a keybinding snippet for the compositor's config.
+pub fn doctor_report() -> String {
+    let (compositor, quirks) = active_quirks();
+    let detected = Compositor::detect();
+    let mut report = format!("Compositor: {compositor}");
+    if detected != compositor {
+        report.push_str(&format!(" (overridden; detected {detected})"));
+    }
+    if let Some(app_id) = flatpak_app_id() {
+        report.push_str(&format!("\nSandbox: Flatpak ({app_id})"));
+    }
+
+    report.push_str("\n\nActive quirks:");
+    report.push_str(&format!(
+        "\n  preferred_data_control: {:?}",
+        quirks.preferred_data_control
+    ));
+    report.push_str(&format!(
+        "\n  ownership_takeover_delay_ms: {} (unless set in config.toml)",
+        quirks.ownership_takeover_delay_ms
+    ));
+    report.push_str(&format!("\n  capture_layer: {:?}", quirks.capture_layer));
+    report.push_str(&format!(
+        "\n  gsk_renderer: {}",
+        quirks.gsk_renderer.as_deref().unwrap_or("default")
+    ));
+
+    report.push_str("\n\nProtocols:");
+    match advertised_globals() {
+        Ok(globals) => {
+            let version_of = |interface: &str| {
+                globals
+                    .iter()
+                    .find(|(name, _)| name == interface)
+                    .map(|(_, version)| *version)
+            };
+
+            let ext = version_of(EXT_DATA_CONTROL);
+            let wlr = version_of(WLR_DATA_CONTROL);
+            for (interface, version) in [(EXT_DATA_CONTROL, ext), (WLR_DATA_CONTROL, wlr)] {
+                report.push_str(&protocol_line(interface, version, None));
+            }
+            let preferred_present = match quirks.preferred_data_control {
+                DataControlProtocol::Ext => ext.is_some(),
+                DataControlProtocol::Wlr => wlr.is_some(),
+            };
+            if ext.is_none() && wlr.is_none() && flatpak_app_id().is_some() {
+                report.push_str(
+                    "\n  ! No data-control protocol for this sandbox: the daemon falls back to the clipboard portal",
+                );
+            } else if ext.is_none() && wlr.is_none() {
+                report.push_str(
+                    "\n  ! No data-control protocol: the daemon cannot monitor the clipboard",
+                );
+            } else if !preferred_present {
+                report.push_str(&format!(
+                    "\n  ! Preferred data-control ({:?}) is missing; the other one will be used",
+                    quirks.preferred_data_control
+                ));
+            }
+
+            for (interface, affects) in PROTOCOL_CHECKS {
+                report.push_str(&protocol_line(
+                    interface,
+                    version_of(interface),
+                    Some(affects),
+                ));
+            }
+        }
+        Err(e) => report.push_str(&format!("\n  unavailable: {e}")),
+    }
+
+    report.push_str("\n\nSuggested keybinding:\n");
+    report.push_str(compositor.keybinding_snippet());
+    report
+}
+
+fn protocol_line(interface: &str, version: Option<u32>, affects: Option<&str>) -> String {
+    match (version, affects) {
+        (Some(version), _) => format!("\n  [ok]      {interface} v{version}"),
+        (None, Some(affects)) => format!("\n  [missing] {interface} - affects: {affects}"),
+        (None, None) => format!("\n  [missing] {interface}"),
+    }
+}
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Errors shared by the daemon and its clients. Sent over IPC as
+/// `{"code": "item_not_found", "detail": {...}}` so clients can react per code,
+/// e.g. start the daemon on `daemon_not_running` but show `unsupported_content`.
+#[derive(Debug, Clone, Error, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "code", content = "detail", rename_all = "snake_case")]
+pub enum CursorClipError {
+    /// No daemon is listening on the IPC socket
+    #[error("the cursor-clip daemon is not running")]
+    DaemonNotRunning,
+    /// The daemon runs, but the compositor offers no clipboard protocol it can use
+    #[error("{0}")]
+    ClipboardUnavailable(String),
+    #[error("no clipboard item found with ID {id}")]
+    ItemNotFound { id: u64 },
+    /// The item has no data in the requested form (e.g. no text for a transform)
+    #[error("{0}")]
+    UnsupportedContent(String),
+    /// No usable Wayland seat or data-control device for the selection
+    #[error("{0}")]
+    SeatUnavailable(String),
+    /// Reading or writing the persistent history or its key failed
+    #[error("persistent history: {0}")]
+    Persistence(String),
+    #[error("configuration: {0}")]
+    Config(String),
+    /// Malformed request, or arguments that make no sense for the command
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+    /// The other side sent something that does not fit the protocol
+    #[error("protocol error: {0}")]
+    Protocol(String),
+    /// Writing or reading a history export failed
+    #[error("history import/export: {0}")]
+    ImportExport(String),
+    #[error("I/O error: {0}")]
+    Io(String),
+}
+
+impl CursorClipError {
+    /// Stable machine-readable code, identical to the `code` field on the wire.
+    pub const fn code(&self) -> &'static str {
+        match self {
+            Self::DaemonNotRunning => "daemon_not_running",
+            Self::ClipboardUnavailable(_) => "clipboard_unavailable",
+            Self::ItemNotFound { .. } => "item_not_found",
+            Self::UnsupportedContent(_) => "unsupported_content",
+            Self::SeatUnavailable(_) => "seat_unavailable",
+            Self::Persistence(_) => "persistence",
+            Self::Config(_) => "config",
+            Self::InvalidRequest(_) => "invalid_request",
+            Self::Protocol(_) => "protocol",
+            Self::ImportExport(_) => "import_export",
+            Self::Io(_) => "io",
+        }
+    }
+
+    /// D-Bus error name, e.g. `org.cursorclip.Daemon.Error.ItemNotFound`.
+    pub const fn dbus_name(&self) -> &'static str {
+        match self {
+            Self::DaemonNotRunning => "org.cursorclip.Daemon.Error.DaemonNotRunning",
+            Self::ClipboardUnavailable(_) => "org.cursorclip.Daemon.Error.ClipboardUnavailable",
+            Self::ItemNotFound { .. } => "org.cursorclip.Daemon.Error.ItemNotFound",
+            Self::UnsupportedContent(_) => "org.cursorclip.Daemon.Error.UnsupportedContent",
+            Self::SeatUnavailable(_) => "org.cursorclip.Daemon.Error.SeatUnavailable",
+            Self::Persistence(_) => "org.cursorclip.Daemon.Error.Persistence",
+            Self::Config(_) => "org.cursorclip.Daemon.Error.Config",
+            Self::InvalidRequest(_) => "org.cursorclip.Daemon.Error.InvalidRequest",
+            Self::Protocol(_) => "org.cursorclip.Daemon.Error.Protocol",
+            Self::ImportExport(_) => "org.cursorclip.Daemon.Error.ImportExport",
+            Self::Io(_) => "org.cursorclip.Daemon.Error.Io",
+        }
+    }
+
+    /// Worth retrying after (re)starting the daemon.
+    pub const fn is_connection_error(&self) -> bool {
+        matches!(self, Self::DaemonNotRunning | Self::Io(_))
+    }
+}
+
+impl From<std::io::Error> for CursorClipError {
+    fn from(error: std::io::Error) -> Self {
+        Self::Io(error.to_string())
+    }
+}
+
+impl From<serde_json::Error> for CursorClipError {
+    fn from(error: serde_json::Error) -> Self {
+        Self::Protocol(error.to_string())
+    }
+}
+
+pub type CursorClipResult<T> = Result<T, CursorClipError>;
@@ -0,0 +1,151 @@
+use log::warn;
+use serde::Deserialize;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::shared::config_dir::config_path;
+
+/// Size at which the log file is rotated
+const MAX_LOG_FILE_BYTES: u64 = 5 * 1024 * 1024;
+/// Rotated files kept next to the log file (`cursor-clip.log.1` is the newest)
+const ROTATED_LOG_FILES: usize = 2;
+
+/// `log_file` and `log_filter` from config.toml. The filter uses `RUST_LOG` syntax,
+/// e.g. `"info,cursor_clip::backend::wayland_clipboard=debug"`.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ConfigWithLogging {
+    log_file: Option<PathBuf>,
+    log_filter: Option<String>,
+}
+
+/// Set up the logger. The filter is `RUST_LOG` if set, else the one `verbosity`
+/// (the number of `-v` flags) maps to, else `log_filter` from config.toml, else `info`.
+/// Records also go to `log_file` (the `--log-file` flag wins over config.toml).
+pub fn init(verbosity: u8, log_file: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    let (config, config_error) = load_logging_config();
+    let filter = match verbosity {
+        0 => config.log_filter.unwrap_or_else(|| "info".to_string()),
+        1 => "info,cursor_clip=debug".to_string(),
+        2 => "info,cursor_clip=trace".to_string(),
+        _ => "trace".to_string(),
+    };
+    let mut builder =
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(filter));
+    builder.format_timestamp_secs();
+
+    let log_file = log_file.or(config.log_file);
+    let mut file_error = None;
+    if let Some(path) = &log_file {
+        match RotatingFile::open(path) {
+            Ok(file) => {
+                builder
+                    .write_style(env_logger::WriteStyle::Never)
+                    .target(env_logger::Target::Pipe(Box::new(StderrAndFile(file))));
+            }
+            Err(e) => file_error = Some(e),
+        }
+    }
+    builder.try_init()?;
+
+    if let Some(e) = config_error {
+        warn!("Failed to parse logging settings from config, using defaults: {e}");
+    }
+    if let (Some(path), Some(e)) = (log_file, file_error) {
+        warn!(
+            "Failed to open log file {}, logging to stderr only: {e}",
+            path.display()
+        );
+    }
+    Ok(())
+}
+
+/// The logging settings, plus the parse error to report once the logger runs.
+fn load_logging_config() -> (ConfigWithLogging, Option<toml::de::Error>) {
+    let Ok(contents) = fs::read_to_string(config_path()) else {
+        return (ConfigWithLogging::default(), None);
+    };
+    match toml::from_str::<ConfigWithLogging>(&contents) {
+        Ok(config) => (config, None),
+        Err(e) => (ConfigWithLogging::default(), Some(e)),
+    }
+}
+
+/// Append-only log file that moves itself to `<name>.1` (shifting older ones up)
+/// once it grows past `MAX_LOG_FILE_BYTES`.
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    written: u64,
+}
+
+impl RotatingFile {
+    fn open(path: &Path) -> io::Result<Self> {
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+            written,
+        })
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        for index in (1..ROTATED_LOG_FILES).rev() {
+            let _ = fs::rename(self.rotated_path(index), self.rotated_path(index + 1));
+        }
+        fs::rename(&self.path, self.rotated_path(1))?;
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{index}"));
+        PathBuf::from(name)
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > MAX_LOG_FILE_BYTES {
+            // Keep logging into the full file rather than losing records
+            let _ = self.rotate();
+        }
+        let written = self.file.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Log target that keeps the usual stderr output and copies it to the log file.
+struct StderrAndFile(RotatingFile);
+
+impl Write for StderrAndFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // A closed stderr (e.g. started from a desktop file) must not stop the file log
+        let _ = io::stderr().write_all(buf);
+        self.0.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let _ = io::stderr().flush();
+        self.0.flush()
+    }
+}
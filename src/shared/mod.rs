@@ -1,3 +1,11 @@
+pub mod config_dir;
 pub mod data_structures;
+pub mod doctor;
+pub mod error;
+pub mod logging;
+pub mod quirks;
+pub mod runtime_dir;
+pub mod sandbox;
 
 pub use data_structures::*;
+pub use error::{CursorClipError, CursorClipResult};
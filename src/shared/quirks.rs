@@ -0,0 +1,222 @@
+use log::{debug, warn};
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+
+use crate::shared::config_dir::config_path;
+
+/// Compositors with known behavioural differences, detected from the session environment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compositor {
+    Hyprland,
+    Sway,
+    KWin,
+    River,
+    Niri,
+    Unknown,
+}
+
+impl Compositor {
+    pub fn detect() -> Self {
+        let has_var = |name: &str| std::env::var_os(name).is_some();
+        if has_var("HYPRLAND_INSTANCE_SIGNATURE") {
+            return Self::Hyprland;
+        }
+        if has_var("SWAYSOCK") {
+            return Self::Sway;
+        }
+        if has_var("NIRI_SOCKET") {
+            return Self::Niri;
+        }
+
+        let desktop = std::env::var("XDG_CURRENT_DESKTOP")
+            .unwrap_or_default()
+            .to_lowercase();
+        if desktop.split(':').any(|entry| entry == "kde") || has_var("KDE_FULL_SESSION") {
+            Self::KWin
+        } else if desktop.split(':').any(|entry| entry == "river") {
+            Self::River
+        } else {
+            Self::Unknown
+        }
+    }
+
+    /// Config snippet binding Super+V to the overlay and starting the daemon with the session.
+    pub fn keybinding_snippet(self) -> &'static str {
+        match self {
+            Self::Hyprland => {
+                "# ~/.config/hypr/hyprland.conf\n\
+                 exec-once = cursor-clip daemon\n\
+                 bind = SUPER, V, exec, cursor-clip"
+            }
+            Self::Sway => {
+                "# ~/.config/sway/config\n\
+                 exec cursor-clip daemon\n\
+                 bindsym $mod+v exec cursor-clip"
+            }
+            Self::River => {
+                "# ~/.config/river/init\n\
+                 riverctl spawn 'cursor-clip daemon'\n\
+                 riverctl map normal Super V spawn cursor-clip"
+            }
+            Self::Niri => {
+                "// ~/.config/niri/config.kdl\n\
+                 spawn-at-startup \"cursor-clip\" \"daemon\"\n\
+                 binds {\n    Mod+V { spawn \"cursor-clip\"; }\n}"
+            }
+            Self::KWin => {
+                "System Settings > Keyboard > Shortcuts > Add New > Command or Script:\n\
+                 cursor-clip (bind to Meta+V), and add `cursor-clip daemon` to Autostart"
+            }
+            Self::Unknown => {
+                "Bind a key of your choice to `cursor-clip` and start `cursor-clip daemon` with your session"
+            }
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "hyprland" => Some(Self::Hyprland),
+            "sway" => Some(Self::Sway),
+            "kwin" | "kde" => Some(Self::KWin),
+            "river" => Some(Self::River),
+            "niri" => Some(Self::Niri),
+            "unknown" => Some(Self::Unknown),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Compositor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Hyprland => "Hyprland",
+            Self::Sway => "Sway",
+            Self::KWin => "KWin",
+            Self::River => "river",
+            Self::Niri => "niri",
+            Self::Unknown => "unknown",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataControlProtocol {
+    Ext,
+    Wlr,
+}
+
+/// Layer used for the pointer-capture surface of the frontend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureLayer {
+    Overlay,
+    Top,
+}
+
+/// Compositor-dependent defaults. Explicit settings in `config.toml` always win.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Quirks {
+    /// Data-control flavour to bind when the compositor offers both
+    pub preferred_data_control: DataControlProtocol,
+    /// Default for `ownership_takeover_delay_ms`
+    pub ownership_takeover_delay_ms: u64,
+    pub capture_layer: CaptureLayer,
+    /// Value for `GSK_RENDERER` unless the user already set one
+    pub gsk_renderer: Option<String>,
+}
+
+impl Default for Quirks {
+    fn default() -> Self {
+        Self {
+            preferred_data_control: DataControlProtocol::Ext,
+            ownership_takeover_delay_ms: 0,
+            capture_layer: CaptureLayer::Overlay,
+            gsk_renderer: None,
+        }
+    }
+}
+
+/// `[quirks]` table of `config.toml`; every field left out keeps the table value.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct QuirkOverrides {
+    /// Treat the session as this compositor instead of auto-detecting it
+    pub compositor: Option<String>,
+    pub preferred_data_control: Option<DataControlProtocol>,
+    pub capture_layer: Option<CaptureLayer>,
+    pub gsk_renderer: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ConfigWithQuirks {
+    quirks: QuirkOverrides,
+}
+
+impl Quirks {
+    /// The built-in quirks table.
+    pub fn for_compositor(compositor: Compositor) -> Self {
+        match compositor {
+            // Klipper re-owns selections as well; holding off briefly avoids both
+            // clipboard managers fighting over a freshly set selection
+            Compositor::KWin => Self {
+                ownership_takeover_delay_ms: 100,
+                ..Self::default()
+            },
+            // Older river releases only implement wlr-data-control reliably; placement
+            // uses the defaults (see niri below)
+            Compositor::River => Self {
+                preferred_data_control: DataControlProtocol::Wlr,
+                ..Self::default()
+            },
+            // niri and river place layer-shell surfaces on the overlay layer and route
+            // pointer input to them like Hyprland and Sway, so placement needs nothing
+            // beyond the defaults
+            Compositor::Niri => Self::default(),
+            Compositor::Hyprland | Compositor::Sway | Compositor::Unknown => Self::default(),
+        }
+    }
+
+    fn with_overrides(mut self, overrides: &QuirkOverrides) -> Self {
+        if let Some(protocol) = overrides.preferred_data_control {
+            self.preferred_data_control = protocol;
+        }
+        if let Some(layer) = overrides.capture_layer {
+            self.capture_layer = layer;
+        }
+        if let Some(renderer) = &overrides.gsk_renderer {
+            self.gsk_renderer = Some(renderer.clone());
+        }
+        self
+    }
+}
+
+/// Detect the compositor and resolve its quirks, applying overrides from `config.toml`.
+pub fn active_quirks() -> (Compositor, Quirks) {
+    let overrides = load_quirk_overrides();
+    let compositor = match overrides.compositor.as_deref() {
+        Some(name) => Compositor::from_name(name).unwrap_or_else(|| {
+            warn!("Unknown compositor {name:?} in [quirks]; auto-detecting instead");
+            Compositor::detect()
+        }),
+        None => Compositor::detect(),
+    };
+    let quirks = Quirks::for_compositor(compositor).with_overrides(&overrides);
+    debug!("Compositor {compositor}: {quirks:?}");
+    (compositor, quirks)
+}
+
+fn load_quirk_overrides() -> QuirkOverrides {
+    let Ok(contents) = fs::read_to_string(config_path()) else {
+        return QuirkOverrides::default();
+    };
+    toml::from_str::<ConfigWithQuirks>(&contents)
+        .map(|config| config.quirks)
+        .unwrap_or_else(|e| {
+            warn!("Failed to parse [quirks] from config, using defaults: {e}");
+            QuirkOverrides::default()
+        })
+}
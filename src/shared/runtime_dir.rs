@@ -0,0 +1,70 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::path::PathBuf;
+
+use crate::shared::sandbox::flatpak_app_id;
+
+/// IPC socket of the daemon inside the runtime directory
+pub const SOCKET_NAME: &str = "ipc.sock";
+/// Socket name used by earlier releases; only served when `legacy_socket_path` is enabled
+pub const LEGACY_SOCKET_NAME: &str = "cursor-clip.sock";
+/// Socket of a resident frontend (`cursor-clip resident`)
+pub const FRONTEND_SOCKET_NAME: &str = "frontend.sock";
+
+/// `$XDG_RUNTIME_DIR/cursor-clip`, if the runtime dir is known. Inside a Flatpak sandbox
+/// `$XDG_RUNTIME_DIR/app/<app id>/cursor-clip`, the part of it shared with the host.
+pub fn runtime_dir() -> Option<PathBuf> {
+    let dir = PathBuf::from(std::env::var_os("XDG_RUNTIME_DIR")?);
+    Some(match flatpak_app_id() {
+        Some(app_id) => dir.join("app").join(app_id).join("cursor-clip"),
+        None => dir.join("cursor-clip"),
+    })
+}
+
+pub fn socket_path() -> Option<PathBuf> {
+    runtime_dir().map(|dir| dir.join(SOCKET_NAME))
+}
+
+pub fn legacy_socket_path() -> Option<PathBuf> {
+    runtime_dir().map(|dir| dir.join(LEGACY_SOCKET_NAME))
+}
+
+pub fn frontend_socket_path() -> Option<PathBuf> {
+    runtime_dir().map(|dir| dir.join(FRONTEND_SOCKET_NAME))
+}
+
+/// Create the runtime directory with mode 0700, or tighten an existing one.
+/// Refuses a directory owned by another user, since whoever owns it could
+/// intercept the socket and the selection backups.
+pub fn ensure_private_runtime_dir() -> Result<PathBuf, String> {
+    let dir = runtime_dir().ok_or("XDG_RUNTIME_DIR is not set")?;
+    match fs::DirBuilder::new().mode(0o700).create(&dir) {
+        Ok(()) => return Ok(dir),
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(format!("Failed to create {}: {e}", dir.display())),
+    }
+
+    let metadata = fs::symlink_metadata(&dir)
+        .map_err(|e| format!("Failed to inspect {}: {e}", dir.display()))?;
+    if !metadata.is_dir() {
+        return Err(format!("{} exists but is not a directory", dir.display()));
+    }
+    if metadata.uid() != current_uid() {
+        return Err(format!(
+            "{} is owned by uid {}, not by the current user",
+            dir.display(),
+            metadata.uid()
+        ));
+    }
+    if metadata.mode() & 0o077 != 0 {
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))
+            .map_err(|e| format!("Failed to restrict {}: {e}", dir.display()))?;
+    }
+    Ok(dir)
+}
+
+pub fn current_uid() -> u32 {
+    // SAFETY: getuid has no preconditions and cannot fail
+    unsafe { libc::getuid() }
+}
@@ -0,0 +1,44 @@
+//! Detection of the Flatpak sandbox. Inside it the host's runtime directory is private,
+//! only `$XDG_RUNTIME_DIR/app/<app id>` is shared with the host, and the compositor may
+//! hide data-control from sandboxed clients (wp_security_context_v1), leaving the
+//! clipboard portal as the way to reach the clipboard.
+
+use std::fs;
+use std::sync::OnceLock;
+
+/// Written by Flatpak into every sandbox
+const FLATPAK_INFO_PATH: &str = "/.flatpak-info";
+
+/// App id of the Flatpak sandbox we run in, or None outside one.
+pub fn flatpak_app_id() -> Option<&'static str> {
+    static APP_ID: OnceLock<Option<String>> = OnceLock::new();
+    APP_ID
+        .get_or_init(|| {
+            std::env::var("FLATPAK_ID")
+                .ok()
+                .filter(|id| !id.is_empty())
+                .or_else(|| {
+                    fs::read_to_string(FLATPAK_INFO_PATH)
+                        .ok()
+                        .and_then(|info| app_id_from_flatpak_info(&info))
+                })
+        })
+        .as_deref()
+}
+
+pub fn in_flatpak() -> bool {
+    flatpak_app_id().is_some()
+}
+
+/// `name` of the `[Application]` group of a `.flatpak-info` keyfile.
+fn app_id_from_flatpak_info(info: &str) -> Option<String> {
+    let mut in_application = false;
+    for line in info.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_application = line == "[Application]";
+        } else if in_application && let Some(name) = line.strip_prefix("name=") {
+            return Some(name.trim().to_string()).filter(|name| !name.is_empty());
+        }
+    }
+    None
+}